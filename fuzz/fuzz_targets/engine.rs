@@ -0,0 +1,6 @@
+#![no_main]
+
+// run with `cargo fuzz run engine`, see src/fuzz.rs for how the bytes are read
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| tetris::fuzz::run(data));
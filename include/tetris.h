@@ -0,0 +1,50 @@
+/*
+ * c interface to the tetris engine, built with `cargo build --release --features ffi` into
+ * target/release/libtetris.so (.dylib, .dll). keep in sync with src/ffi.rs, a test there checks
+ * that everything it exports is declared here.
+ *
+ * a game starts with tetris_engine_new and is given back with tetris_engine_free. inputs go in with
+ * tetris_engine_apply, time passes with tetris_engine_advance in fixed steps of 1/120 s; the same seed,
+ * inputs and time always play out the same game. functions returning int32_t return TETRIS_OK or one of
+ * the negative TETRIS_ERR_* codes, the getters return their value or an error code. nothing panics
+ * across the boundary, after TETRIS_ERR_PANIC the game should only be freed.
+ */
+#ifndef TETRIS_H
+#define TETRIS_H
+
+#include <stdint.h>
+
+#define TETRIS_OK 0
+#define TETRIS_ERR_NULL -1
+#define TETRIS_ERR_ACTION -2
+#define TETRIS_ERR_PANIC -3
+
+#define TETRIS_ACTION_MOVE_LEFT 0
+#define TETRIS_ACTION_MOVE_RIGHT 1
+#define TETRIS_ACTION_ROTATE 2
+#define TETRIS_ACTION_SOFT_DROP_START 3
+#define TETRIS_ACTION_SOFT_DROP_STOP 4
+#define TETRIS_ACTION_HARD_DROP 5
+#define TETRIS_ACTION_HOLD 6
+#define TETRIS_ACTION_PAUSE 7
+#define TETRIS_ACTION_RESUME 8
+
+/* 10 columns by 20 rows, row by row from the bottom left. 0 is an empty cell, 1 to 7 are the
+ * tetrimino colors (O I T L J S Z) and 8 is garbage */
+#define TETRIS_BOARD_SIZE 200
+
+typedef struct FfiGame FfiGame;
+
+/* null if the game couldn't be made */
+FfiGame *tetris_engine_new(uint64_t seed);
+int32_t tetris_engine_apply(FfiGame *game, int32_t action);
+int32_t tetris_engine_advance(FfiGame *game, uint32_t millis);
+/* the board with the falling tetrimino on it, into TETRIS_BOARD_SIZE bytes at out */
+int32_t tetris_engine_board(FfiGame *game, uint8_t *out);
+int64_t tetris_engine_score(FfiGame *game);
+int32_t tetris_engine_level(FfiGame *game);
+/* 1 once the game is over, 0 before that */
+int32_t tetris_engine_game_over(FfiGame *game);
+void tetris_engine_free(FfiGame *game);
+
+#endif
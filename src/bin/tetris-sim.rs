@@ -0,0 +1,53 @@
+// runs a game without a window and prints a json summary, for bot evaluation and smoke tests
+//
+// tetris-sim [--seed <n>] [--mode marathon|sprint] [--pieces <n>] (--bot | --script <file>)
+
+use std::io::Write;
+use std::process::ExitCode;
+use tetris::{
+    cli,
+    sim::{self, Driver, SimMode, SimOptions},
+};
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().collect();
+
+    match options_from(&args) {
+        Ok(options) => {
+            // the summary is the output, so it goes to stdout rather than the log
+            let mut stdout = std::io::stdout().lock();
+            match writeln!(stdout, "{}", sim::run(&options).to_json()) {
+                Ok(()) => ExitCode::SUCCESS,
+                Err(_) => ExitCode::FAILURE,
+            }
+        }
+        Err(error) => {
+            eprintln!("{error}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn options_from(args: &[String]) -> Result<SimOptions, String> {
+    let mode = match cli::value(args, "--mode") {
+        Some(value) => SimMode::parse(value).ok_or(format!("Unknown mode: {value}"))?,
+        None => SimMode::Marathon,
+    };
+
+    let driver = match (cli::flag(args, "--bot"), cli::value(args, "--script")) {
+        (true, None) => Driver::Bot,
+        (false, Some(path)) => {
+            let source = std::fs::read_to_string(path)
+                .map_err(|error| format!("Failed to read {path}: {error}"))?;
+            Driver::Script(sim::parse_script(&source)?)
+        }
+        _ => return Err("Pass either --bot or --script <file>".to_string()),
+    };
+
+    Ok(SimOptions {
+        seed: cli::parsed(args, "--seed")?.unwrap_or(0),
+        mode,
+        driver,
+        max_pieces: cli::parsed(args, "--pieces")?.unwrap_or(SimOptions::DEFAULT_MAX_PIECES),
+    })
+}
@@ -0,0 +1,182 @@
+// heuristic ai: tries every rotation and column for the current tetrimino and picks the landing
+// spot that leaves the best looking matrix behind
+
+use crate::engine::{move_kind::MoveKind, piece_rotation::Rotation, Engine};
+
+// weights for how the matrix looks after a placement; more cleared lines is good,
+// a tall stack, holes and a bumpy surface are bad
+const LINES_WEIGHT: f32 = 0.76;
+const HEIGHT_WEIGHT: f32 = -0.51;
+const HOLES_WEIGHT: f32 = -0.36;
+const BUMPINESS_WEIGHT: f32 = -0.18;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Placement {
+    pub rotation: Rotation,
+    pub shift: isize, // columns to move away from the spawn position, negative is to the left
+}
+
+// a placement that can be made, with what's needed to rank it against the others
+#[derive(Clone, Copy)]
+struct Candidate {
+    placement: Placement,
+    score: f32,
+    column: usize, // the leftmost one the tetrimino lands in
+    turns: usize, // clockwise quarter turns from the cursor's rotation, the way the rotate key turns it
+}
+
+impl Candidate {
+    // a better score wins; a tie goes to the one further left, then to the one with fewer turns, so the
+    // same board always gets the same placement whatever order they were tried in
+    fn beats(&self, other: &Self) -> bool {
+        self.score
+            .total_cmp(&other.score)
+            .then(other.column.cmp(&self.column))
+            .then(other.turns.cmp(&self.turns))
+            .is_gt()
+    }
+}
+
+// the best spot for the current cursor, None when there's no cursor or nowhere to put it
+pub fn best_placement(engine: &Engine) -> Option<Placement> {
+    let mut best: Option<Candidate> = None;
+    let width = Engine::MATRIX_WIDTH as isize;
+
+    for rotation in Rotation::ALL {
+        for shift in -width..=width {
+            let placement = Placement { rotation, shift };
+            let Some(candidate) = evaluate(engine, placement) else {
+                continue;
+            };
+
+            if best.is_none_or(|best| candidate.beats(&best)) {
+                best = Some(candidate);
+            }
+        }
+    }
+
+    best.map(|candidate| candidate.placement)
+}
+
+fn clockwise_turns(from: Rotation, to: Rotation) -> usize {
+    let index = |rotation| {
+        Rotation::ALL
+            .iter()
+            .position(|&r| r == rotation)
+            .unwrap_or(0)
+    };
+    (index(to) + Rotation::ALL.len() - index(from)) % Rotation::ALL.len()
+}
+
+// rotate and move the cursor into place; false if something was in the way
+pub fn apply_placement(engine: &mut Engine, placement: Placement) -> bool {
+    let Some(cursor) = engine.cursor() else {
+        return false;
+    };
+
+    if cursor.rotation != placement.rotation
+        && engine
+            .rotate_and_adjust_cursor(placement.rotation)
+            .is_none()
+    {
+        return false;
+    }
+
+    let kind = if placement.shift < 0 {
+        MoveKind::Left
+    } else {
+        MoveKind::Right
+    };
+
+    for _ in 0..placement.shift.unsigned_abs() {
+        let before = engine.cursor();
+        engine.move_cursor(kind);
+
+        // move_cursor quietly stays put when blocked
+        if engine.cursor() == before {
+            return false;
+        }
+    }
+
+    true
+}
+
+// how good the matrix looks once the placement is hard dropped, None if it can't be made
+fn evaluate(engine: &Engine, placement: Placement) -> Option<Candidate> {
+    let mut engine = engine.clone();
+    let turns = clockwise_turns(engine.cursor()?.rotation, placement.rotation);
+
+    if !apply_placement(&mut engine, placement) {
+        return None;
+    }
+    let (cells, _, _) = engine.cursor_info()?;
+    let column = cells.iter().map(|cell| cell.x).min()?;
+
+    engine.hard_drop();
+    if engine.try_place_cursor() != Ok(true) {
+        return None;
+    }
+
+    let lines = engine.matrix.full_lines();
+    engine.matrix.clear_lines(&lines);
+
+    let heights = engine.matrix.column_heights();
+    let aggregate_height: usize = heights.iter().sum();
+    let bumpiness: usize = heights
+        .windows(2)
+        .map(|pair| pair[0].abs_diff(pair[1]))
+        .sum();
+    let holes = engine.matrix.count_holes();
+
+    let score = LINES_WEIGHT * lines.len() as f32
+        + HEIGHT_WEIGHT * aggregate_height as f32
+        + HOLES_WEIGHT * holes as f32
+        + BUMPINESS_WEIGHT * bumpiness as f32;
+
+    Some(Candidate {
+        placement,
+        score,
+        column,
+        turns,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::engine::piece_kind::PieceKind;
+
+    fn landing_column(engine: &Engine, placement: Placement) -> usize {
+        let mut engine = engine.clone();
+        assert!(apply_placement(&mut engine, placement));
+        let (cells, _, _) = engine.cursor_info().unwrap();
+        cells.iter().map(|cell| cell.x).min().unwrap()
+    }
+
+    #[test]
+    fn ties_on_a_symmetric_board_go_left_with_the_fewest_turns() {
+        // an empty matrix is the same mirrored, an o against either wall scores the same in any rotation
+        let mut engine = Engine::new_seeded(1);
+        engine.create_top_cursor(Some(PieceKind::O));
+
+        let placement = best_placement(&engine).unwrap();
+        assert_eq!(placement.rotation, Rotation::N);
+        assert_eq!(landing_column(&engine, placement), 0);
+
+        // and again for a t, every time and from a copy as well
+        engine.create_top_cursor(Some(PieceKind::T));
+        let placement = best_placement(&engine).unwrap();
+        for _ in 0..10 {
+            assert_eq!(best_placement(&engine.clone()), Some(placement));
+        }
+        assert_eq!(landing_column(&engine, placement), 0);
+    }
+
+    #[test]
+    fn turns_are_counted_the_way_the_rotate_key_goes() {
+        assert_eq!(clockwise_turns(Rotation::N, Rotation::N), 0);
+        assert_eq!(clockwise_turns(Rotation::N, Rotation::W), 3);
+        assert_eq!(clockwise_turns(Rotation::W, Rotation::N), 1);
+        assert_eq!(clockwise_turns(Rotation::E, Rotation::S), 1);
+    }
+}
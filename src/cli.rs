@@ -0,0 +1,61 @@
+// command line helpers shared by the binaries; arguments are `--name value` pairs and plain `--flag`s
+
+// the value following `name`, if it was given
+pub fn value<'a>(args: &'a [String], name: &str) -> Option<&'a str> {
+    args.windows(2)
+        .find(|pair| pair[0] == name)
+        .map(|pair| pair[1].as_str())
+}
+
+pub fn flag(args: &[String], name: &str) -> bool {
+    args.iter().any(|arg| arg == name)
+}
+
+// the value following `name` parsed into T, with an error message naming the argument if it doesn't parse
+pub fn parsed<T: std::str::FromStr>(args: &[String], name: &str) -> Result<Option<T>, String> {
+    value(args, name)
+        .map(|value| {
+            value
+                .parse()
+                .map_err(|_| format!("Invalid value for {name}: {value}"))
+        })
+        .transpose()
+}
+
+// the value following `name` turned into T by `parse`, with the same error as parsed if it can't be
+pub fn parsed_with<T>(
+    args: &[String],
+    name: &str,
+    parse: impl FnOnce(&str) -> Option<T>,
+) -> Result<Option<T>, String> {
+    value(args, name)
+        .map(|value| parse(value).ok_or_else(|| format!("Invalid value for {name}: {value}")))
+        .transpose()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn bad_values_are_errors_naming_the_argument() {
+        let args: Vec<String> = ["tetris", "--previews", "many", "--mode", "zen"]
+            .into_iter()
+            .map(String::from)
+            .collect();
+
+        assert_eq!(
+            parsed::<usize>(&args, "--previews"),
+            Err("Invalid value for --previews: many".to_string())
+        );
+        assert_eq!(
+            parsed_with(&args, "--mode", |_| None::<u8>),
+            Err("Invalid value for --mode: zen".to_string())
+        );
+        assert_eq!(
+            parsed_with(&args, "--mode", |value| Some(value.len())),
+            Ok(Some(3))
+        );
+        assert_eq!(parsed::<u16>(&args, "--host"), Ok(None));
+    }
+}
@@ -0,0 +1,416 @@
+// a game as it's played, without a window: the engine plus the states it goes through in between inputs
+// and updates (soft dropping, lockdown, pausing, game over). the interface draws one, replays play one
+// back and tests drive one with inputs and made up time
+use crate::engine::{advance::Advance, config::Config, move_kind::MoveKind, piece::Piece, Engine};
+use crate::replay::Replay;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+// the inputs that move a game along, whatever keys they came from
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub enum GameInput {
+    MoveLeft,
+    MoveRight,
+    Rotate,
+    RotateCounterclockwise,
+    SoftDropStart, // the soft drop key went down
+    SoftDropStop,  // and came back up
+    HardDrop,
+    Hold,
+    Pause,
+    Resume,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum GameState {
+    TickingDown,
+    SoftDropping,
+    LockingDown, // resting on a surface while the lock delay runs
+    LockedDown,  // the line clear happens on the next update
+    Spawning, // the next tetrimino forms above the matrix while the spawn delay runs, it's not in play yet
+    Paused,
+    GameOver,
+}
+
+// how a game came to be over
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum TopOut {
+    LockOut, // a tetrimino couldn't be placed where it locked
+    Garbage, // garbage from an opponent pushed the stack over the top
+}
+
+#[derive(Clone)]
+pub struct GameController {
+    pub engine: Engine,
+    pub state: GameState,
+    pub pieces: u32,                 // tetriminos locked so far
+    pub replay: Option<Replay>,      // the inputs so far, None when the game is a replay itself
+    pub top_out: Option<TopOut>,     // set along with the game over
+    pub completed: bool, // the goal of the mode was reached, which is a game over without a top out
+    pub play_time: Duration, // game time so far, pauses aside; an ultra is over once it's used up
+    locked: Option<Piece>, // the tetrimino that locked last, until it's taken
+    clear_elapsed: Duration, // since the lock, while full lines wait out the clear delay
+    spawn_elapsed: Option<Duration>, // since the spawn, while the new tetrimino waits out the spawn delay
+    soft_drop_held: bool,            // between a soft drop start and its stop, whatever the state
+    soft_drop_blocked: bool, // held through a lock, see Config::soft_drop_repress; until it's let go
+}
+
+impl GameController {
+    // a new game that records itself, updated in steps of the given length
+    pub fn new(seed: u64, config: Config, step: Duration) -> Self {
+        Self {
+            replay: Some(Replay::new(seed, config, step)),
+            ..Self::unrecorded(seed, config)
+        }
+    }
+
+    // the start of the game a replay was recorded from
+    pub fn for_replay(replay: &Replay) -> Self {
+        Self::unrecorded(replay.seed, replay.config)
+    }
+
+    fn unrecorded(seed: u64, config: Config) -> Self {
+        let mut engine = Engine::new_seeded(seed);
+        engine.config = config;
+        engine.create_top_cursor(None);
+
+        Self {
+            engine,
+            state: GameState::TickingDown,
+            pieces: 0,
+            replay: None,
+            top_out: None,
+            completed: false,
+            play_time: Duration::ZERO,
+            locked: None,
+            clear_elapsed: Duration::ZERO,
+            spawn_elapsed: None,
+            soft_drop_held: false,
+            soft_drop_blocked: false,
+        }
+    }
+
+    // throw the game away and start over with the same config
+    pub fn restart(&mut self, seed: u64) {
+        let step = self.replay.as_ref().map(|replay| replay.step);
+        let config = self.engine.config;
+        let frame_step = self.engine.frame_step;
+        let high_scores = self.engine.high_scores.take();
+
+        *self = match step {
+            Some(step) => Self::new(seed, config, step),
+            None => Self::unrecorded(seed, config),
+        };
+        self.engine.frame_step = frame_step;
+        self.engine.high_scores = high_scores;
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.state == GameState::Paused
+    }
+
+    pub fn is_game_over(&self) -> bool {
+        self.state == GameState::GameOver
+    }
+
+    // the tetrimino that locked since this was last asked, e.g. to flash it
+    pub fn take_locked(&mut self) -> Option<Piece> {
+        self.locked.take()
+    }
+
+    // the tetrimino waiting out the spawn delay and how far it has formed, from 0 to 1; it's drawn forming
+    // at the top of the matrix and nothing moves it until it's in play
+    pub fn pending(&self) -> Option<(Piece, f32)> {
+        let elapsed = self.spawn_elapsed?;
+        let formed = elapsed.as_secs_f32() / self.engine.config.spawn_delay.as_secs_f32();
+        Some((self.engine.cursor()?, formed.min(1.0)))
+    }
+
+    // how far the falling tetrimino is on its way to the next row, see Engine::fall_progress
+    pub fn fall_progress(&self) -> f32 {
+        match self.state {
+            GameState::TickingDown | GameState::SoftDropping => self
+                .engine
+                .fall_progress(self.state == GameState::SoftDropping),
+            _ => 0.0,
+        }
+    }
+
+    // how much game time until the next update changes something, None if nothing is coming
+    pub fn time_until_update(&self) -> Option<Duration> {
+        let due = match self.state {
+            GameState::Paused | GameState::GameOver => return None,
+            GameState::LockedDown if self.is_clear_delayed() => Some(
+                self.engine
+                    .config
+                    .clear_delay
+                    .saturating_sub(self.clear_elapsed),
+            ),
+            GameState::LockedDown => Some(Duration::ZERO),
+            GameState::Spawning => Some(
+                self.engine
+                    .config
+                    .spawn_delay
+                    .saturating_sub(self.spawn_elapsed.unwrap_or_default()),
+            ),
+            GameState::TickingDown | GameState::SoftDropping | GameState::LockingDown => self
+                .engine
+                .time_until_advance(self.state == GameState::SoftDropping),
+        };
+
+        // an ultra ends on time, whatever else is going on
+        match self.engine.config.mode.time_left(self.play_time) {
+            Some(left) => Some(due.map_or(left, |due| due.min(left))),
+            None => due,
+        }
+    }
+
+    pub fn apply(&mut self, input: GameInput) {
+        if self.is_game_over() {
+            return;
+        }
+
+        if let Some(replay) = self.replay.as_mut() {
+            replay.record(input);
+        }
+
+        // whether the key is down, even while the input itself doesn't do anything
+        match input {
+            GameInput::SoftDropStart => self.soft_drop_held = true,
+            GameInput::SoftDropStop => {
+                self.soft_drop_held = false;
+                self.soft_drop_blocked = false;
+            }
+            _ => {}
+        }
+
+        // while full lines hang on the board nothing moves, pausing skips the rest of the delay
+        if self.is_clear_delayed() && input != GameInput::Pause {
+            return;
+        }
+
+        // a hard drop's line clear is still due, it happens before anything else does
+        if self.state == GameState::LockedDown {
+            self.clear_lines();
+        }
+
+        // neither is the next tetrimino before the spawn delay is over
+        if self.spawn_elapsed.is_some() && !matches!(input, GameInput::Pause | GameInput::Resume) {
+            return;
+        }
+
+        match input {
+            GameInput::Pause => self.state = GameState::Paused,
+            // the lock delay stood still while paused, a grounded tetrimino only gets what was left of it;
+            // the same goes for the spawn delay
+            GameInput::Resume if self.is_paused() => {
+                self.state = if self.spawn_elapsed.is_some() {
+                    GameState::Spawning
+                } else if self.engine.lock_delay_remaining().is_some() {
+                    GameState::LockingDown
+                } else {
+                    GameState::TickingDown
+                };
+            }
+            GameInput::Resume => {}
+            _ if self.is_paused() => {}
+            GameInput::MoveLeft | GameInput::MoveRight => {
+                let kind = if input == GameInput::MoveLeft {
+                    MoveKind::Left
+                } else {
+                    MoveKind::Right
+                };
+
+                // the lock delay restarts when the tetrimino is moved
+                self.engine.move_cursor(kind);
+                self.engine.reset_lock_delay();
+            }
+            GameInput::Rotate | GameInput::RotateCounterclockwise => {
+                let rotation = if input == GameInput::Rotate {
+                    self.engine.next_cursor_rotation()
+                } else {
+                    self.engine.prev_cursor_rotation()
+                };
+                if let Some(rotation) = rotation {
+                    self.engine.rotate_and_adjust_cursor(rotation);
+                }
+                self.engine.reset_lock_delay();
+            }
+            // held down since before the last lock, it has to be let go first
+            GameInput::SoftDropStart if self.soft_drop_blocked => {}
+            // sonic drop: straight onto the surface, then the usual lock delay
+            GameInput::SoftDropStart if self.engine.config.sonic_drop => {
+                self.engine.sonic_drop();
+                self.state = GameState::LockingDown;
+            }
+            GameInput::SoftDropStart => {
+                if self.state != GameState::SoftDropping && self.state != GameState::LockingDown {
+                    // the faster drop starts counting from the press
+                    self.state = GameState::SoftDropping;
+                    self.engine.gravity_elapsed = Duration::ZERO;
+                }
+            }
+            GameInput::SoftDropStop => {
+                if self.state == GameState::SoftDropping {
+                    self.state = GameState::TickingDown;
+                }
+            }
+            GameInput::HardDrop => {
+                self.engine.hard_drop();
+                let cursor = self.engine.cursor();
+                if !self.engine.lock_cursor() {
+                    self.state = GameState::GameOver;
+                    self.top_out = Some(TopOut::LockOut);
+                    return;
+                }
+
+                self.lock(cursor);
+            }
+            GameInput::Hold => {
+                self.engine.try_hold();
+            }
+        }
+    }
+
+    // one fixed step of the game: gravity and lock delay, nothing moves while paused.
+    // returns whether anything changed that needs a redraw
+    pub fn update(&mut self, dt: Duration) -> bool {
+        // a game over ends the recording, paused steps are part of it
+        if self.is_game_over() {
+            return false;
+        }
+        if let Some(replay) = self.replay.as_mut() {
+            replay.record_step();
+        }
+
+        if !self.is_paused() {
+            self.play_time += dt;
+            if self.is_mode_complete() {
+                self.complete();
+                return true;
+            }
+        }
+
+        // the new tetrimino forms a bit more every step until it's in play
+        if self.state == GameState::Spawning {
+            let elapsed = self.spawn_elapsed.unwrap_or_default() + dt;
+            if elapsed >= self.engine.config.spawn_delay {
+                self.spawn_elapsed = None;
+                self.state = GameState::TickingDown;
+            } else {
+                self.spawn_elapsed = Some(elapsed);
+            }
+            return true;
+        }
+
+        let cursor = self.engine.cursor();
+        let was_locked_down = self.state == GameState::LockedDown;
+        if let GameState::TickingDown | GameState::SoftDropping | GameState::LockingDown =
+            self.state
+        {
+            match self
+                .engine
+                .advance(dt, self.state == GameState::SoftDropping)
+            {
+                Advance::Falling if self.state == GameState::LockingDown => {
+                    self.state = GameState::TickingDown
+                }
+                Advance::Falling => {}
+                Advance::Grounded => self.state = GameState::LockingDown,
+                Advance::Locked => self.lock(cursor),
+                Advance::GameOver => {
+                    self.state = GameState::GameOver;
+                    self.top_out = Some(TopOut::LockOut);
+                    return true;
+                }
+            }
+        }
+
+        if self.state == GameState::LockedDown {
+            // the full lines stay for the clear delay, counted from the step of the lock
+            if self.is_clear_delayed() {
+                self.clear_elapsed += dt;
+                return !was_locked_down;
+            }
+
+            self.clear_lines();
+            if self.is_mode_complete() {
+                self.complete();
+            }
+            return true;
+        }
+
+        self.engine.cursor() != cursor
+    }
+
+    fn is_mode_complete(&self) -> bool {
+        self.engine
+            .config
+            .mode
+            .is_complete(self.engine.stats().lines(), self.play_time)
+    }
+
+    // the goal of a sprint or ultra is reached: the game is over, but it wasn't lost
+    fn complete(&mut self) {
+        self.state = GameState::GameOver;
+        self.completed = true;
+    }
+
+    // garbage from an opponent, pushing the stack up; the game is over if it goes over the top
+    pub fn receive_garbage(&mut self, lines: usize, hole: usize) {
+        if self.is_game_over() {
+            return;
+        }
+
+        if !self.engine.add_garbage(lines, hole) {
+            self.state = GameState::GameOver;
+            self.top_out = Some(TopOut::Garbage);
+        }
+    }
+
+    // the engine's invariants plus the ones between it and the state
+    pub fn validate_invariants(&self) -> Result<(), String> {
+        self.engine.validate_invariants()?;
+
+        let has_full_line =
+            (0..Engine::MATRIX_HEIGHT).any(|row| self.engine.matrix.is_line_full(row));
+        // a game over leaves the board as it was when the tetrimino couldn't go anywhere
+        let clear_due = matches!(self.state, GameState::LockedDown | GameState::GameOver);
+        if has_full_line && !clear_due {
+            return Err(format!("full lines left over while {:?}", self.state));
+        }
+
+        if !self.is_game_over() && self.engine.cursor().is_none() {
+            return Err(format!("no cursor while {:?}", self.state));
+        }
+
+        Ok(())
+    }
+
+    // whether the stack is still waiting to collapse onto lines that were filled by the last lock
+    fn is_clear_delayed(&self) -> bool {
+        self.state == GameState::LockedDown
+            && self.clear_elapsed < self.engine.config.clear_delay
+            && (0..Engine::MATRIX_HEIGHT).any(|row| self.engine.matrix.is_line_full(row))
+    }
+
+    // scan the board, see what lines need to be cleared; the next tetrimino is in play after that, unless
+    // there's a spawn delay for it to wait out first
+    fn clear_lines(&mut self) {
+        self.engine.line_clear(self.engine.placed_tspin(), |_| ());
+        if self.engine.config.spawn_delay.is_zero() {
+            self.state = GameState::TickingDown;
+        } else {
+            self.spawn_elapsed = Some(Duration::ZERO);
+            self.state = GameState::Spawning;
+        }
+    }
+
+    fn lock(&mut self, cursor: Option<Piece>) {
+        self.soft_drop_blocked = self.soft_drop_held && self.engine.config.soft_drop_repress;
+        self.locked = cursor;
+        self.pieces += 1;
+        self.clear_elapsed = Duration::ZERO;
+        self.state = GameState::LockedDown;
+    }
+}
@@ -0,0 +1,8 @@
+// what happened to the cursor when time was let pass with Engine::advance
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Advance {
+    Falling,  // still in the air (or no cursor at all)
+    Grounded, // resting on a surface, the lock delay is running
+    Locked, // placed into the matrix and the next tetrimino spawned; full lines are left for the caller to clear
+    GameOver, // locked out above the matrix
+}
@@ -1,4 +1,6 @@
-#[derive(Clone, Copy, PartialEq, Debug)]
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
 pub enum TetriminoColor {
     Yellow,
     Cyan,
@@ -7,4 +9,51 @@ pub enum TetriminoColor {
     Blue,
     Green,
     Red,
+    Gray, // garbage, not part of any tetrimino
+}
+
+impl TetriminoColor {
+    // every tetrimino kind has its own color, so a cell can be written down as the letter of its kind
+    pub fn letter(&self) -> char {
+        match self {
+            Self::Yellow => 'O',
+            Self::Cyan => 'I',
+            Self::Purple => 'T',
+            Self::Orange => 'L',
+            Self::Blue => 'J',
+            Self::Green => 'S',
+            Self::Red => 'Z',
+            Self::Gray => 'G',
+        }
+    }
+
+    // a cell as a single number for frontends across a language boundary (wasm, c): 0 is an empty cell,
+    // 1 to 7 are the tetrimino colors and 8 is garbage
+    pub fn cell_code(cell: Option<Self>) -> u8 {
+        match cell {
+            None => 0,
+            Some(Self::Yellow) => 1,
+            Some(Self::Cyan) => 2,
+            Some(Self::Purple) => 3,
+            Some(Self::Orange) => 4,
+            Some(Self::Blue) => 5,
+            Some(Self::Green) => 6,
+            Some(Self::Red) => 7,
+            Some(Self::Gray) => 8,
+        }
+    }
+
+    pub fn from_letter(letter: char) -> Option<Self> {
+        match letter {
+            'O' => Some(Self::Yellow),
+            'I' => Some(Self::Cyan),
+            'T' => Some(Self::Purple),
+            'L' => Some(Self::Orange),
+            'J' => Some(Self::Blue),
+            'S' => Some(Self::Green),
+            'Z' => Some(Self::Red),
+            'G' => Some(Self::Gray),
+            _ => None,
+        }
+    }
 }
@@ -0,0 +1,59 @@
+use super::mode::GameMode;
+use super::Engine;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+// gameplay options that change how the engine behaves; they survive a reset
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub struct Config {
+    pub leveling: bool, // when off, the level (and with it the drop speed) never goes up
+    pub sonic_drop: bool, // soft drop sends the tetrimino straight to the surface, without locking it like a hard drop
+    pub danger_heights: [usize; 3], // stack heights from which the danger level is 1, 2 and 3
+    pub min_drop_time: Option<Duration>, // gravity never gets faster than a line per this, None lets it
+    pub previews: usize, // tetriminos shown in the queue below the up next one, at most Engine::MAX_PREVIEWS
+    pub clear_delay: Duration, // full lines stay on the board this long before the stack above them drops
+    pub spawn_delay: Duration, // ARE: a new tetrimino forms above the matrix this long before it's in play
+    pub hold_undo: bool, // holding again right after a hold swaps back, as long as nothing else happened in between
+    pub first_hold: bool, // the first tetrimino of a game can be held; some games only allow it once one has locked
+    pub hold_mode: HoldMode,
+    pub soft_drop_repress: bool, // a soft drop held down when a tetrimino locks doesn't go on with the next one
+    // the bonuses line_clear gives for clears in a row and for difficult clears in a row; with both off
+    // every clear scores the same whatever came before it
+    pub combo: bool,
+    pub back_to_back: bool,
+    pub mode: GameMode,
+}
+
+// what holding does with what's already on hold, see Engine::try_hold
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Serialize, Deserialize)]
+pub enum HoldMode {
+    #[default]
+    Swap, // one slot, the tetrimino on it comes back out in exchange
+    Queue, // Engine::HOLD_QUEUE_LEN slots that fill up first, then the one held longest comes back out
+}
+
+impl Config {
+    // a line a frame at 60 fps, about as fast as a falling tetrimino can still be followed
+    pub const ONE_G: Duration = Duration::from_nanos(16_666_667);
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            leveling: true,
+            sonic_drop: false,
+            danger_heights: [10, 14, 17],
+            min_drop_time: Some(Self::ONE_G),
+            previews: Engine::MAX_PREVIEWS,
+            clear_delay: Duration::ZERO,
+            spawn_delay: Duration::ZERO,
+            hold_undo: false,
+            first_hold: true,
+            hold_mode: HoldMode::Swap,
+            soft_drop_repress: false,
+            combo: true,
+            back_to_back: true,
+            mode: GameMode::Marathon,
+        }
+    }
+}
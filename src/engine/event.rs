@@ -0,0 +1,29 @@
+use super::piece_kind::PieceKind;
+use super::tspin::TSpinKind;
+
+// things that happened inside the engine which the outside may want to react to (sounds, announcing
+// tetriminos, stats); they pile up until Engine::take_events hands them over
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum EngineEvent {
+    Spawned {
+        kind: PieceKind,
+        source: SpawnSource,
+    },
+    LinesCleared {
+        lines: usize, // only for clears of at least one line
+        tspin: TSpinKind,
+    },
+    LevelUp {
+        level: u8, // the one that was just reached
+    },
+    ModeComplete, // the clear that reached the goal of a sprint; an ultra's time is up outside the engine
+}
+
+// where a new cursor came from
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum SpawnSource {
+    Queue,    // the up next tetrimino
+    Hold,     // swapped in from hold
+    HoldUndo, // back where it was before it went on hold, see Config::hold_undo
+    Forced,   // put there directly with create_top_cursor(Some(kind)), e.g. to set up a test
+}
@@ -0,0 +1,179 @@
+// the engine driven in a plain loop, without a window and without the timing GameController keeps:
+// tick is a row of gravity and apply_input is a key press. a cursor that can't go down any further locks
+// on the next tick, there's no lock delay, so tests and bots don't have to wait on one
+use serde::{Deserialize, Serialize};
+
+use super::move_kind::MoveKind;
+use super::Engine;
+
+// what a player can do to the tetrimino, the keys of Input without the ones for the game around it
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub enum EngineInput {
+    Move(MoveKind),
+    Rotate,
+    RotateCounterclockwise,
+    SoftDrop, // a row down, scored
+    HardDrop, // straight down and locked
+    Hold,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum TickResult {
+    Continuing,
+    LineClear(Vec<usize>), // the rows that were cleared, bottom first
+    ModeComplete, // a clear that reached the goal of a sprint, which ends it like a game over
+    GameOver,
+}
+
+impl Engine {
+    // the cursor falls a row, or locks if it can't; the first tick of a new engine spawns the first one
+    pub fn tick(&mut self) -> TickResult {
+        let locks = self.cursor.is_some() && self.ticked_down_cursor().is_none();
+        if let Some(recorder) = self.recording.as_mut() {
+            recorder.tick(locks);
+        }
+
+        if self.cursor.is_none() {
+            self.create_top_cursor(None);
+            return TickResult::Continuing;
+        }
+
+        if self.ticked_down_cursor().is_some() {
+            self.try_tick_down();
+            return TickResult::Continuing;
+        }
+
+        self.lock_and_clear()
+    }
+
+    // the input done to the cursor right away; only a hard drop locks it, with what that led to
+    pub fn apply_input(&mut self, input: EngineInput) -> TickResult {
+        if let Some(recorder) = self.recording.as_mut() {
+            recorder.input(input);
+        }
+
+        match input {
+            EngineInput::Move(kind) => self.move_cursor(kind),
+            EngineInput::Rotate => {
+                if let Some(rotation) = self.next_cursor_rotation() {
+                    self.rotate_and_adjust_cursor(rotation);
+                }
+            }
+            EngineInput::RotateCounterclockwise => {
+                if let Some(rotation) = self.prev_cursor_rotation() {
+                    self.rotate_and_adjust_cursor(rotation);
+                }
+            }
+            EngineInput::SoftDrop => {
+                if self.ticked_down_cursor().is_some() {
+                    self.soft_drop_step();
+                }
+            }
+            EngineInput::HardDrop if self.cursor.is_some() => {
+                self.hard_drop();
+                return self.lock_and_clear();
+            }
+            EngineInput::HardDrop => {}
+            EngineInput::Hold => {
+                self.try_hold();
+            }
+        }
+
+        TickResult::Continuing
+    }
+
+    fn lock_and_clear(&mut self) -> TickResult {
+        if !self.lock_cursor() {
+            return TickResult::GameOver;
+        }
+
+        let was_complete = self.is_sprint_complete();
+        let mut cleared = Vec::new();
+        self.line_clear(self.placed_tspin(), |lines| cleared = lines.to_vec());
+        if self.is_sprint_complete() && !was_complete {
+            TickResult::ModeComplete
+        } else if cleared.is_empty() {
+            TickResult::Continuing
+        } else {
+            TickResult::LineClear(cleared)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::engine::color::TetriminoColor;
+    use crate::engine::event::EngineEvent;
+    use crate::engine::mode::GameMode;
+    use crate::engine::piece_kind::PieceKind;
+    use crate::engine::Coordinate;
+
+    #[test]
+    fn ticks_fall_a_row_and_lock_at_the_bottom() {
+        let mut engine = Engine::new();
+        assert_eq!(engine.tick(), TickResult::Continuing);
+        let top = engine.cursor().unwrap().position.y;
+
+        assert_eq!(engine.tick(), TickResult::Continuing);
+        assert_eq!(engine.cursor().unwrap().position.y, top - 1);
+
+        // down to the floor, where the next tick locks it and brings the next one in
+        let placed = |engine: &Engine| {
+            PieceKind::ALL
+                .into_iter()
+                .map(|kind| engine.stats().placed(kind))
+                .sum::<u32>()
+        };
+        while engine.ticked_down_cursor().is_some() {
+            assert_eq!(engine.tick(), TickResult::Continuing);
+        }
+        assert_eq!(engine.tick(), TickResult::Continuing);
+        assert_eq!(placed(&engine), 1);
+        assert!(engine.ticked_down_cursor().is_some()); // the next one, falling again
+    }
+
+    #[test]
+    fn inputs_play_a_game_to_its_end() {
+        let mut engine = Engine::new();
+        for x in 0..Engine::MATRIX_WIDTH - 4 {
+            engine.matrix[Coordinate::new(x, 0)] = Some(TetriminoColor::Red);
+        }
+        engine.create_top_cursor(Some(PieceKind::I));
+        for _ in 0..3 {
+            engine.apply_input(EngineInput::Move(MoveKind::Right));
+        }
+        let score = engine.score;
+        assert_eq!(
+            engine.apply_input(EngineInput::HardDrop),
+            TickResult::LineClear(vec![0])
+        );
+        assert!(engine.score > score);
+
+        // dropping everything where it spawns stacks up to a game over soon enough
+        let mut results = Vec::new();
+        while results.last() != Some(&TickResult::GameOver) {
+            results.push(engine.apply_input(EngineInput::HardDrop));
+            assert!(results.len() < 100);
+        }
+    }
+
+    #[test]
+    fn the_clear_that_reaches_the_sprint_goal_completes_it() {
+        let mut engine = Engine::new_with_mode(GameMode::Sprint { lines_goal: 1 });
+        for x in 0..Engine::MATRIX_WIDTH - 4 {
+            engine.matrix[Coordinate::new(x, 0)] = Some(TetriminoColor::Red);
+        }
+        engine.create_top_cursor(Some(PieceKind::I));
+        for _ in 0..3 {
+            engine.apply_input(EngineInput::Move(MoveKind::Right));
+        }
+
+        assert_eq!(
+            engine.apply_input(EngineInput::HardDrop),
+            TickResult::ModeComplete
+        );
+        assert!(engine.is_sprint_complete());
+        assert!(engine.take_events().contains(&EngineEvent::ModeComplete));
+    }
+}
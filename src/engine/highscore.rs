@@ -0,0 +1,161 @@
+// the best scores ever made on this machine, kept in a json file in the config directory. the table only
+// ever holds the top LEN, best first
+use serde::{Deserialize, Serialize};
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+pub const FILE_NAME: &str = "scores.json";
+
+#[derive(Clone, PartialEq, Debug, Default, Serialize, Deserialize)]
+pub struct HighScoreTable {
+    entries: [Option<(String, u32)>; HighScoreTable::LEN], // name and score, the empty slots last
+}
+
+impl HighScoreTable {
+    pub const LEN: usize = 10;
+
+    // a file that isn't there is an error like any other, it's up to the caller to start a new table
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let text = fs::read_to_string(path)?;
+        serde_json::from_str(&text)
+            .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))
+    }
+
+    // the table in the file, a new one if there's no file yet; None for a file that can't be read, which is
+    // then left alone instead of being written over
+    pub fn load_or_new(path: &Path) -> Option<Self> {
+        match Self::load(path) {
+            Ok(table) => Some(table),
+            Err(error) if error.kind() == io::ErrorKind::NotFound => Some(Self::default()),
+            Err(error) => {
+                log::warn!(
+                    "Failed to read the high scores in {}: {error}",
+                    path.display()
+                );
+                None
+            }
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let text = serde_json::to_string_pretty(self).map_err(io::Error::other)?;
+        fs::write(path, text)
+    }
+
+    // the names and scores in the table, best first
+    pub fn entries(&self) -> impl Iterator<Item = (&str, u32)> {
+        self.entries
+            .iter()
+            .flatten()
+            .map(|(name, score)| (name.as_str(), *score))
+    }
+
+    // whether the score makes it into the table; nothing for a game that didn't score at all
+    pub fn qualifies(&self, score: u32) -> bool {
+        score > 0 && self.rank(score).is_some()
+    }
+
+    // puts the score in its place, the last one drops out of the table; returns the place it went to,
+    // counting from 0, None if it didn't make it in
+    pub fn insert(&mut self, name: &str, score: u32) -> Option<usize> {
+        if score == 0 {
+            return None;
+        }
+
+        let rank = self.rank(score)?;
+        self.entries[rank..].rotate_right(1);
+        self.entries[rank] = Some((name.to_string(), score));
+        Some(rank)
+    }
+
+    // where the score would go, below the ones it doesn't beat; a tie goes after the older score
+    fn rank(&self, score: u32) -> Option<usize> {
+        self.entries
+            .iter()
+            .position(|entry| entry.as_ref().is_none_or(|(_, best)| score > *best))
+    }
+
+    // "1  NAME  1200" for every score in the table, for the game over screen
+    pub fn lines(&self) -> Vec<String> {
+        self.entries()
+            .enumerate()
+            .map(|(index, (name, score))| format!("{}  {name}  {score}", index + 1))
+            .collect()
+    }
+}
+
+// $XDG_CONFIG_HOME/tetris-rust/scores.json, or ~/.config/tetris-rust/scores.json without it;
+// %APPDATA%\tetris-rust\scores.json on windows
+pub fn default_path() -> PathBuf {
+    let base = env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("APPDATA").map(PathBuf::from))
+        .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(".config")))
+        .unwrap_or_default();
+    base.join("tetris-rust").join(FILE_NAME)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn scores_go_in_their_place_and_the_last_drops_out() {
+        let mut table = HighScoreTable::default();
+        assert!(!table.qualifies(0));
+        assert_eq!(table.insert("ANNA", 500), Some(0));
+        assert_eq!(table.insert("BEN", 900), Some(0));
+        assert_eq!(table.insert("CLEO", 500), Some(2)); // after the older 500
+        assert_eq!(
+            table.lines(),
+            ["1  BEN  900", "2  ANNA  500", "3  CLEO  500"]
+        );
+
+        for score in 1..=7 {
+            table.insert("DAN", 1000 + score);
+        }
+        assert_eq!(table.entries().count(), HighScoreTable::LEN);
+        assert_eq!(table.entries().next(), Some(("DAN", 1007)));
+        assert_eq!(table.entries().last(), Some(("CLEO", 500)));
+
+        // a full table takes only what beats its last score, and the last one drops out
+        assert!(!table.qualifies(500));
+        assert_eq!(table.insert("EVE", 500), None);
+        assert_eq!(table.insert("EVE", 501), Some(8));
+        assert_eq!(table.entries().last(), Some(("ANNA", 500)));
+    }
+
+    #[test]
+    fn the_table_survives_a_round_trip() {
+        let path = env::temp_dir()
+            .join(format!("tetris-highscores-{}", std::process::id()))
+            .join(FILE_NAME);
+        let _ = fs::remove_dir_all(path.parent().unwrap());
+
+        let error = HighScoreTable::load(&path).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::NotFound);
+
+        let mut table = HighScoreTable::default();
+        table.insert("ANNA", 1200);
+        table.save(&path).unwrap();
+        assert_eq!(HighScoreTable::load(&path).unwrap(), table);
+
+        fs::write(&path, "{ not json").unwrap();
+        let error = HighScoreTable::load(&path).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(HighScoreTable::load_or_new(&path), None);
+
+        fs::remove_file(&path).unwrap();
+        assert_eq!(
+            HighScoreTable::load_or_new(&path),
+            Some(HighScoreTable::default())
+        );
+
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+}
@@ -0,0 +1,110 @@
+// the super rotation system's wall kicks: when a rotation doesn't fit where it is, the tetrimino is
+// tried at these offsets in turn and goes to the first one where it fits. x goes right and y goes up,
+// like everywhere else in the engine. see Rotation::wall_kick_offsets for looking them up
+use super::piece_kind::PieceKind;
+use super::piece_rotation::Rotation;
+use super::Offset;
+
+pub type Table = [[Offset; 5]; 8];
+
+const fn o(x: isize, y: isize) -> Offset {
+    Offset::new(x, y)
+}
+
+// in the order of the turns in index below
+const JLSTZ: Table = [
+    [o(0, 0), o(-1, 0), o(-1, 1), o(0, -2), o(-1, -2)], // n -> e
+    [o(0, 0), o(1, 0), o(1, -1), o(0, 2), o(1, 2)],     // e -> n
+    [o(0, 0), o(1, 0), o(1, -1), o(0, 2), o(1, 2)],     // e -> s
+    [o(0, 0), o(-1, 0), o(-1, 1), o(0, -2), o(-1, -2)], // s -> e
+    [o(0, 0), o(1, 0), o(1, 1), o(0, -2), o(1, -2)],    // s -> w
+    [o(0, 0), o(-1, 0), o(-1, -1), o(0, 2), o(-1, 2)],  // w -> s
+    [o(0, 0), o(-1, 0), o(-1, -1), o(0, 2), o(-1, 2)],  // w -> n
+    [o(0, 0), o(1, 0), o(1, 1), o(0, -2), o(1, -2)],    // n -> w
+];
+
+const I: Table = [
+    [o(0, 0), o(-2, 0), o(1, 0), o(-2, -1), o(1, 2)], // n -> e
+    [o(0, 0), o(2, 0), o(-1, 0), o(2, 1), o(-1, -2)], // e -> n
+    [o(0, 0), o(-1, 0), o(2, 0), o(-1, 2), o(2, -1)], // e -> s
+    [o(0, 0), o(1, 0), o(-2, 0), o(1, -2), o(-2, 1)], // s -> e
+    [o(0, 0), o(2, 0), o(-1, 0), o(2, 1), o(-1, -2)], // s -> w
+    [o(0, 0), o(-2, 0), o(1, 0), o(-2, -1), o(1, 2)], // w -> s
+    [o(0, 0), o(1, 0), o(-2, 0), o(1, -2), o(-2, 1)], // w -> n
+    [o(0, 0), o(-1, 0), o(2, 0), o(-1, 2), o(2, -1)], // n -> w
+];
+
+pub const NONE: [Offset; 1] = [o(0, 0)];
+
+// the table of the kind, None for the o which doesn't need one
+pub fn table(kind: PieceKind) -> Option<&'static Table> {
+    match kind {
+        PieceKind::O => None,
+        PieceKind::I => Some(&I),
+        _ => Some(&JLSTZ),
+    }
+}
+
+// where a turn goes in the tables, None for the ones that aren't a quarter turn
+pub fn index(from: Rotation, to: Rotation) -> Option<usize> {
+    use Rotation::*;
+
+    match (from, to) {
+        (N, E) => Some(0),
+        (E, N) => Some(1),
+        (E, S) => Some(2),
+        (S, E) => Some(3),
+        (S, W) => Some(4),
+        (W, S) => Some(5),
+        (W, N) => Some(6),
+        (N, W) => Some(7),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn turning_back_kicks_the_opposite_way() {
+        let kicks = Rotation::wall_kick_offsets;
+        for kind in PieceKind::ALL {
+            for from in Rotation::ALL {
+                let to = from.next_rotation();
+                let there = kicks(kind, from, to);
+                let back = kicks(kind, to, from);
+                assert_eq!(there.len(), back.len());
+                for (there, back) in there.iter().zip(back) {
+                    assert_eq!(*there, -*back, "{kind:?} {from:?} -> {to:?}");
+                }
+            }
+        }
+
+        assert_eq!(kicks(PieceKind::T, Rotation::N, Rotation::S), [o(0, 0)]);
+    }
+
+    #[test]
+    fn counterclockwise_kicks_mirror_the_clockwise_ones() {
+        // from north or south the two ways are left to right of each other; the i's grid has no middle
+        // column to mirror around, so only the three wide ones are mirrored exactly
+        for from in [Rotation::N, Rotation::S] {
+            let clockwise = Rotation::wall_kick_offsets(PieceKind::T, from, from.next_rotation());
+            let counterclockwise =
+                Rotation::wall_kick_offsets(PieceKind::T, from, from.prev_rotation());
+            for (clockwise, counterclockwise) in clockwise.iter().zip(counterclockwise) {
+                assert_eq!(o(-clockwise.x, clockwise.y), *counterclockwise, "{from:?}");
+            }
+        }
+
+        // every turn either way has all five tests, for the i as well
+        for kind in [PieceKind::T, PieceKind::I] {
+            for from in Rotation::ALL {
+                assert_eq!(
+                    Rotation::wall_kick_offsets(kind, from, from.prev_rotation()).len(),
+                    5
+                );
+            }
+        }
+    }
+}
@@ -1,12 +1,16 @@
-use super::{color::TetriminoColor, piece::Piece, Coordinate};
+use super::{color::TetriminoColor, piece::Piece, Coordinate, Offset};
 use crate::engine::geometry::GridIncrement;
 use cgmath::EuclideanSpace;
+use serde::{Deserialize, Serialize};
 use std::{
+    fmt,
     ops::{Index, IndexMut},
     slice::ArrayChunks,
+    str::FromStr,
 };
 
 // represents the tetris matrix
+#[derive(Clone)]
 pub struct Matrix<const WIDTH: usize, const HEIGHT: usize>
 where
     [usize; WIDTH * HEIGHT]:,
@@ -14,6 +18,14 @@ where
     pub matrix: [Option<TetriminoColor>; WIDTH * HEIGHT],
 }
 
+// a cell that's different in another matrix of the same size, see Matrix::diff
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub struct CellChange {
+    pub x: usize,
+    pub y: usize,
+    pub cell: Option<TetriminoColor>,
+}
+
 // zero is at bottom left
 impl<const WIDTH: usize, const HEIGHT: usize> Matrix<WIDTH, HEIGHT>
 where
@@ -101,18 +113,108 @@ where
         self.matrix.array_chunks()
     }
 
-    pub fn full_lines(&mut self) -> Vec<usize> {
+    // the rows that are full, bottom first
+    pub fn full_lines(&self) -> Vec<usize> {
+        (0..HEIGHT).filter(|&row| self.is_line_full(row)).collect()
+    }
+
+    // whether every cell of the row is filled, rows count from the bottom
+    pub fn is_line_full(&self, row: usize) -> bool {
+        assert!(row < HEIGHT, "row {row} is outside of the matrix");
+
+        self.matrix[row * WIDTH..(row + 1) * WIDTH]
+            .iter()
+            .all(Option::is_some)
+    }
+
+    // how high each column is stacked, i.e. one above its topmost filled cell, 0 for an empty column
+    pub fn column_heights(&self) -> [usize; WIDTH] {
+        let mut heights = [0; WIDTH];
+
+        for (y, line) in self.lines().enumerate() {
+            for (x, cell) in line.iter().enumerate() {
+                if cell.is_some() {
+                    heights[x] = y + 1;
+                }
+            }
+        }
+
+        heights
+    }
+
+    // empty cells with a filled cell somewhere above them in the same column
+    pub fn count_holes(&self) -> usize {
+        let heights = self.column_heights();
+
         self.lines()
             .enumerate()
-            .filter(|(_, line)| line.iter().all(Option::is_some)) // where every cell is full
-            .map(|(i, _)| i) // take the indices
-            .collect() // collect into the return type
+            .map(|(y, line)| {
+                line.iter()
+                    .enumerate()
+                    .filter(|&(x, cell)| cell.is_none() && y < heights[x])
+                    .count()
+            })
+            .sum()
     }
 
     pub fn clear(&mut self) {
         self.matrix[0..].fill(None)
     }
 
+    // a hash of the cells that's the same on every machine and build (fnv-1a over one byte per cell), for
+    // telling whether two games ended up with the same board without keeping the boards around
+    pub fn content_hash(&self) -> u64 {
+        const OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+        const PRIME: u64 = 0x0100_0000_01b3;
+
+        self.matrix.iter().fold(OFFSET, |hash, cell| {
+            let byte = cell.map_or(0, |color| color as u8 + 1);
+            (hash ^ byte as u64).wrapping_mul(PRIME)
+        })
+    }
+
+    // the cells which have to change to turn this matrix into `newer`
+    pub fn diff(&self, newer: &Self) -> Vec<CellChange> {
+        self.matrix
+            .iter()
+            .zip(newer.matrix.iter())
+            .enumerate()
+            .filter(|(_, (old, new))| old != new)
+            .map(|(index, (_, &cell))| CellChange {
+                x: index % WIDTH,
+                y: index / WIDTH,
+                cell,
+            })
+            .collect()
+    }
+
+    // the other end of diff; changes outside of the matrix are skipped, they can come from anywhere
+    pub fn apply_changes(&mut self, changes: &[CellChange]) {
+        for change in changes {
+            let coord = Coordinate::new(change.x, change.y);
+            if self.on_matrix(coord) {
+                self[coord] = change.cell;
+            }
+        }
+    }
+
+    // push everything up and fill the bottom rows with garbage, leaving the hole column empty;
+    // returns false if that pushed filled cells off the top
+    pub fn insert_garbage(&mut self, lines: usize, hole: usize) -> bool {
+        let lines = lines.min(HEIGHT);
+        let shift = lines * WIDTH;
+        let fits = self.matrix[Self::SIZE - shift..]
+            .iter()
+            .all(Option::is_none);
+
+        self.matrix.copy_within(..Self::SIZE - shift, shift);
+        for (index, cell) in self.matrix[..shift].iter_mut().enumerate() {
+            *cell = (index % WIDTH != hole).then_some(TetriminoColor::Gray);
+        }
+
+        fits
+    }
+
     // place all of the squares of the piece into the matrix
     pub fn place_piece(&mut self, piece: Piece) {
         let color: TetriminoColor = piece.kind.color();
@@ -123,22 +225,25 @@ where
     }
 
     pub fn has_piece_out_of_bounds_coords(&self, piece: &Piece) -> bool {
-        piece.matrix_offsets().into_iter().any(|coord| {
-            let is_invalid = coord[0] < 0 || coord[1] < 0 || coord[0] >= WIDTH as isize;
-            if is_invalid {
-                return true;
-            }
+        piece
+            .matrix_offsets()
+            .into_iter()
+            .any(|coord| self.is_blocked(coord))
+    }
 
-            // cast to something we can index the matrix with
-            let positive_offset = coord.cast::<usize>().unwrap(); // the question mark denotes that if this returns none, the whole thing will return none
-            let coord = Coordinate::from_vec(positive_offset);
-            if self.on_matrix(coord) && self[coord].is_some() {
-                // it's on the matrix and overlapping
-                return true;
-            }
+    // whether a cell is past a wall or the floor, or filled; above the matrix there's always room
+    pub fn is_blocked(&self, coord: Offset) -> bool {
+        let is_invalid = coord[0] < 0 || coord[1] < 0 || coord[0] >= WIDTH as isize;
+        if is_invalid {
+            return true;
+        }
 
-            false
-        })
+        // cast to something we can index the matrix with
+        let positive_offset = coord.cast::<usize>().unwrap();
+        let coord = Coordinate::from_vec(positive_offset);
+
+        // it's on the matrix and overlapping
+        self.on_matrix(coord) && self[coord].is_some()
     }
 
     // returns coordinates of piece; None on an invalid cursor position;
@@ -191,6 +296,86 @@ where
     }
 }
 
+// one line of text per row from the top down, '.' for an empty cell and the tetrimino letter otherwise
+impl<const WIDTH: usize, const HEIGHT: usize> fmt::Display for Matrix<WIDTH, HEIGHT>
+where
+    [usize; WIDTH * HEIGHT]:,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (index, line) in self.lines().rev().enumerate() {
+            if index > 0 {
+                writeln!(f)?;
+            }
+
+            for cell in line {
+                write!(f, "{}", cell.map_or('.', |color| color.letter()))?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ParseMatrixError {
+    RowCount { expected: usize, found: usize },
+    RowWidth { row: usize, width: usize }, // a row, from the top, that isn't as wide as the matrix
+    Cell(char),                            // neither '.' nor a tetrimino letter
+}
+
+impl fmt::Display for ParseMatrixError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::RowCount { expected, found } => {
+                write!(f, "expected {expected} rows, got {found}")
+            }
+            Self::RowWidth { row, width } => write!(f, "row {row} is {width} cells wide"),
+            Self::Cell(cell) => write!(f, "'{cell}' isn't a cell"),
+        }
+    }
+}
+
+impl std::error::Error for ParseMatrixError {}
+
+// the other way around from Display, so boards can be written down in tests; surrounding whitespace
+// of the whole string and of each row is ignored
+impl<const WIDTH: usize, const HEIGHT: usize> FromStr for Matrix<WIDTH, HEIGHT>
+where
+    [usize; WIDTH * HEIGHT]:,
+{
+    type Err = ParseMatrixError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let rows: Vec<&str> = s.trim().lines().map(str::trim).collect();
+        if rows.len() != HEIGHT {
+            return Err(ParseMatrixError::RowCount {
+                expected: HEIGHT,
+                found: rows.len(),
+            });
+        }
+
+        let mut matrix = Self::blank();
+        for (index, row) in rows.into_iter().enumerate() {
+            let width = row.chars().count();
+            if width != WIDTH {
+                return Err(ParseMatrixError::RowWidth { row: index, width });
+            }
+
+            let y = HEIGHT - 1 - index;
+            for (x, cell) in row.chars().enumerate() {
+                matrix[Coordinate::new(x, y)] = match cell {
+                    '.' => None,
+                    _ => Some(
+                        TetriminoColor::from_letter(cell).ok_or(ParseMatrixError::Cell(cell))?,
+                    ),
+                };
+            }
+        }
+
+        Ok(matrix)
+    }
+}
+
 // 'matrix is a lifetime parameter
 pub struct CellIter<'matrix, const WIDTH: usize, const HEIGHT: usize> {
     pub position: Coordinate, // starts at the bottom and goes up, tracks where we are in the iteration
@@ -1,28 +1,71 @@
+use std::collections::{BTreeSet, HashSet};
 use std::time::Duration;
 
+use advance::Advance;
 use cgmath::{EuclideanSpace, Point2, Vector2};
 use color::TetriminoColor;
+use config::{Config, HoldMode};
+use event::{EngineEvent, SpawnSource};
+use highscore::HighScoreTable;
 use matrix::Matrix;
+use mode::GameMode;
 use move_kind::MoveKind;
 use piece::Piece;
 use piece_kind::PieceKind;
 use piece_rotation::Rotation;
 use rand::prelude::SliceRandom;
-use rand::rngs::ThreadRng;
-use rand::thread_rng;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use recording::Recorder;
+use stats::Stats;
+use tspin::TSpinKind;
 
+pub mod advance;
 pub mod color;
+pub mod config;
+pub mod event;
 mod geometry;
+pub mod headless;
+pub mod highscore;
+pub mod kicks;
 pub mod matrix;
+pub mod mode;
 pub mod move_kind;
 pub mod piece;
-mod piece_kind;
+pub mod piece_kind;
 pub mod piece_rotation;
+pub mod recording;
+pub mod scoring;
+pub mod stats;
+pub mod tspin;
 
 pub type Coordinate = Point2<usize>;
 type Offset = Vector2<isize>;
 
+// there was no cursor to place, see Engine::try_place_cursor
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct NoCursor;
+
+// what a hold changed, to put back if it's undone (see Config::hold_undo)
+#[derive(Clone)]
+struct HoldUndo {
+    cursor: Piece,
+    last_kick: Option<usize>,
+    hold: Vec<PieceKind>,
+    hold_matrix: Matrix<
+        { Engine::SINGLE_TETRIMINO_MATRIX_WIDTH },
+        { Engine::SINGLE_TETRIMINO_MATRIX_HEIGHT },
+    >,
+    next: Vec<PieceKind>,
+    bag: Vec<PieceKind>,
+    rng: StdRng,
+    gravity_elapsed: Duration,
+    lock_elapsed: Option<Duration>,
+    lock_resets: u32,
+}
+
 // represents the game engine
+#[derive(Clone)]
 pub struct Engine {
     pub matrix: Matrix<{ Self::MATRIX_WIDTH }, { Self::MATRIX_HEIGHT }>,
     pub up_next_matrix:
@@ -31,14 +74,32 @@ pub struct Engine {
         Matrix<{ Self::SINGLE_TETRIMINO_MATRIX_WIDTH }, { Self::SINGLE_TETRIMINO_MATRIX_HEIGHT }>,
     pub queue_matrix:
         Matrix<{ Self::REMAINING_NEXT_MATRIX_WIDTH }, { Self::REMAINING_NEXT_MATRIX_HEIGHT }>,
-    next: Vec<PieceKind>, // next up, these are also visible on the screen (7), they are filled from the bag or randomly
+    next: Vec<PieceKind>, // next up, these are also visible on the screen (7), they are filled from the bag
     bag: Vec<PieceKind>, // this is from where tetris piece types are taken from during gameplay (7 are shuffled, taken out one by one, then process repeats)
-    hold: Option<PieceKind>,
-    rng: ThreadRng,        // random number generator instance
+    hold: Vec<PieceKind>, // oldest first, one at most unless Config::hold_mode is a queue
+    hold_used: bool, // hold was used for the cursor, holding again has to wait for the next tetrimino
+    hold_undo: Option<Box<HoldUndo>>, // the last hold, until the cursor it brought in moves, rotates or drops
+    rng: StdRng, // random number generator instance, seeded so piece sequences can be reproduced
+    seed: u64,   // what rng was seeded with, for replays
+    garbage_rng: StdRng, // where the holes in garbage go, seeded on its own so they don't depend on the pieces
     cursor: Option<Piece>, // current active piece (the one falling down), optional
-    pub level: u8,         // fixed goal System requires 10 lines each level through level 15
+    last_kick: Option<usize>, // the wall kick the cursor last rotated with, None once it moved or fell since
+    tspin: TSpinKind,         // the one the last placed tetrimino made, for the line clear after it
+    pub level: u8,            // fixed goal System requires 10 lines each level through level 15
     pub lines_reached: u32,
-    pub score: u32, // will equal an acumulation of lines reached for the simple scoring
+    pub score: u32,         // by the guideline's table, see scoring::score_lines
+    pub combo: i32, // clears in a row after the first, -1 once a tetrimino locks without one
+    pub back_to_back: bool, // the last clear was a difficult one, see scoring::is_difficult
+    pub config: Config,
+    pub gravity_elapsed: Duration, // time since the cursor last fell a row
+    pub lock_elapsed: Option<Duration>, // time the cursor has rested on a surface, None while it's falling
+    pub stats: Stats,                   // see stats(), reset along with the matrix
+    pub lock_resets: u32, // how often moving or rotating restarted the lock delay of the current cursor
+    events: Vec<EngineEvent>, // since they were last taken
+    pub frame_step: bool, // for debugging: gravity and locks stand still until step is called
+    step_pending: bool,
+    pub high_scores: Option<HighScoreTable>, // loaded by the interface, kept through restarts; None without one
+    recording: Option<Recorder>,             // the ticks and inputs so far, see start_recording
 }
 
 impl Engine {
@@ -53,12 +114,41 @@ impl Engine {
 
     pub const LINES_PER_LEVEL: u32 = 10;
 
+    pub const NEXT_COUNT: usize = 7; // the up next tetrimino and the 6 in the queue
+    pub const PREVIEW_ROWS: usize = 4; // of the queue matrix for each tetrimino in it
+    pub const MAX_PREVIEWS: usize = Self::REMAINING_NEXT_MATRIX_HEIGHT / Self::PREVIEW_ROWS;
+
+    // a tetrimino that falls or soft drops onto a surface gets this long before it locks down
+    pub const LOCK_DELAY: Duration = Duration::from_millis(500);
+    pub const MAX_LOCK_RESETS: u32 = 15; // so a tetrimino can't be kept from locking forever
+    pub const HOLD_QUEUE_LEN: usize = 3; // see HoldMode::Queue
+
+    // see is_overstacked
+    pub const OVERSTACKED_HOLES: usize = 6;
+    pub const OVERSTACKED_HEIGHT: usize = 12;
+
     pub fn new() -> Self {
-        let mut rng = thread_rng();
-        let mut up_next = Vec::from(PieceKind::ALL.as_slice());
-        up_next.shuffle(&mut rng);
+        Self::new_seeded(rand::random())
+    }
+
+    pub fn new_with_mode(mode: GameMode) -> Self {
+        let mut engine = Self::new();
+        engine.config.mode = mode;
+        engine
+    }
 
-        Engine {
+    // the same seed always deals the same sequence of tetriminos; the garbage holes get a seed of their
+    // own out of it, see seed_garbage for picking that one as well
+    pub fn new_seeded(seed: u64) -> Self {
+        Self::with_rng(
+            seed,
+            StdRng::seed_from_u64(seed),
+            StdRng::seed_from_u64(seed.wrapping_add(1)),
+        )
+    }
+
+    fn with_rng(seed: u64, rng: StdRng, garbage_rng: StdRng) -> Self {
+        let mut engine = Engine {
             matrix: Matrix::<{ Self::MATRIX_WIDTH }, { Self::MATRIX_HEIGHT }>::blank(),
             up_next_matrix: Matrix::<
                 { Self::SINGLE_TETRIMINO_MATRIX_WIDTH },
@@ -73,14 +163,40 @@ impl Engine {
                 { Self::REMAINING_NEXT_MATRIX_HEIGHT },
             >::blank(),
             bag: Vec::new(),
-            next: up_next,
+            next: Vec::new(),
             rng,
+            seed,
+            garbage_rng,
             cursor: None,
-            hold: None,
+            hold: Vec::with_capacity(Self::HOLD_QUEUE_LEN),
+            hold_used: false,
+            hold_undo: None,
             level: 1,
             lines_reached: 0,
             score: 0,
-        }
+            combo: -1,
+            back_to_back: false,
+            last_kick: None,
+            tspin: TSpinKind::None,
+            config: Config::default(),
+            gravity_elapsed: Duration::ZERO,
+            lock_elapsed: None,
+            lock_resets: 0,
+            stats: Stats::default(),
+            events: Vec::new(),
+            frame_step: false,
+            step_pending: false,
+            high_scores: None,
+            recording: None,
+        };
+
+        // the up next and queue tetriminos come out of the bag as well
+        let next = (0..Self::NEXT_COUNT)
+            .map(|_| engine.draw_from_bag())
+            .collect();
+        engine.next = next;
+
+        engine
     }
 
     // once bag where we pick new pieces from is empty, we need to refill it
@@ -95,6 +211,75 @@ impl Engine {
         self.bag.shuffle(&mut self.rng)
     }
 
+    // take the next tetrimino out of the bag, starting a new bag when it runs out
+    fn draw_from_bag(&mut self) -> PieceKind {
+        if self.bag.is_empty() {
+            self.refill_bag();
+        }
+
+        self.bag.pop().unwrap()
+    }
+
+    // tetriminos of the current bag which haven't been drawn yet (not counting the ones already in the next queue)
+    pub fn remaining_bag(&self) -> &[PieceKind] {
+        &self.bag
+    }
+
+    // the tetriminos coming next in order, the up next one first
+    pub fn upcoming(&self) -> &[PieceKind] {
+        &self.next
+    }
+
+    // where in upcoming() the tetriminos of the next bag start, which is also how many of the current
+    // bag are still to come; the queue is never longer than a bag, so there's only one such place
+    pub fn bag_divider(&self) -> usize {
+        let drawn = PieceKind::ALL.len() - self.bag.len();
+        self.next.len().saturating_sub(drawn)
+    }
+
+    // the tetrimino shown on hold, the next one to come back out of it
+    pub fn held(&self) -> Option<PieceKind> {
+        self.hold.first().copied()
+    }
+
+    // what the next hold takes off hold in exchange for the cursor, if anything
+    fn leaving_hold(&self) -> Option<PieceKind> {
+        match self.config.hold_mode {
+            HoldMode::Swap => self.held(),
+            HoldMode::Queue => self
+                .held()
+                .filter(|_| self.hold.len() == Self::HOLD_QUEUE_LEN),
+        }
+    }
+
+    // whether try_hold would swap anything: hold can be used once per tetrimino, not at all for the first
+    // one of the game unless Config::first_hold allows it, and a tetrimino can't be swapped for itself
+    pub fn can_hold(&self) -> bool {
+        let first = self.stats.placed.iter().sum::<u32>() == 0;
+        !self.hold_used
+            && (self.config.first_hold || !first)
+            && self
+                .cursor
+                .is_some_and(|cursor| self.leaving_hold() != Some(cursor.kind))
+    }
+
+    // whether try_hold would take the last hold back instead, see Config::hold_undo
+    pub fn can_undo_hold(&self) -> bool {
+        self.hold_undo.is_some()
+    }
+
+    // the t-spin the cursor would make if it locked where it is now, before it's placed into the matrix
+    pub fn detect_t_spin(&self) -> TSpinKind {
+        self.cursor.map_or(TSpinKind::None, |cursor| {
+            tspin::detect(&cursor, self.last_kick, |cell| self.matrix.is_blocked(cell))
+        })
+    }
+
+    // the t-spin the last placed tetrimino made, for passing on to line_clear
+    pub fn placed_tspin(&self) -> TSpinKind {
+        self.tspin
+    }
+
     // place the cursor into the matrix onto the position it's currently at;
     // if that's not possible, it's game over
     pub fn place_cursor(&mut self) -> bool {
@@ -104,23 +289,25 @@ impl Engine {
             return false;
         }
 
+        self.tspin = self.detect_t_spin();
         self.matrix.place_piece(cursor);
+        self.stats.record_placement(cursor.kind);
         true
     }
 
-    // place the cursor into the matrix onto the position it's currently at; if it returns false, it's game over
-    pub fn try_place_cursor(&mut self) -> bool {
-        if let Some(cursor) = self.cursor {
-            return self.place_cursor();
-        } else {
-            println!("Tried placing a nonexistant cursor")
+    // place the cursor into the matrix onto the position it's currently at; Ok(false) if it doesn't fit there,
+    // which is game over, and Err(NoCursor) if there's no cursor to place at all
+    pub fn try_place_cursor(&mut self) -> Result<bool, NoCursor> {
+        if self.cursor.is_none() {
+            return Err(NoCursor);
         }
 
-        true
+        Ok(self.place_cursor())
     }
 
     // returns Ok(()), Err(()) of unit, represented in memory same as a bool
     pub fn move_cursor(&mut self, kind: MoveKind) {
+        self.hold_undo = None;
         let Some(cursor) = self.cursor.as_mut() else {
             return; // because it's OK to move a cursor that isn't there, it would just do nothing
         };
@@ -133,9 +320,11 @@ impl Engine {
         }
 
         self.cursor = Some(new);
+        self.last_kick = None;
     }
 
     pub fn rotate_cursor(&mut self, kind: Rotation) {
+        self.hold_undo = None;
         let Some(cursor) = self.cursor.as_mut() else {
             return; // because it's OK to move a cursor that isn't there, it would just do nothing
         };
@@ -143,21 +332,24 @@ impl Engine {
         cursor.rotation = kind;
     }
 
-    pub fn rotate_and_adjust_cursor(&mut self, kind: Rotation) -> Option<()> {
-        // check if any position is out of bounds
-        let mut cursor_clone = self.cursor?.clone();
-
-        cursor_clone.rotation = kind;
-
-        // if cursor has out of bounds coordinates, do not rotate
-        if self.matrix.has_piece_out_of_bounds_coords(&cursor_clone) {
-            return None;
-        }
+    // rotates in place if it fits there, otherwise at the first of the wall kicks where it does, which
+    // is returned; if it fits nowhere, it doesn't rotate
+    pub fn rotate_and_adjust_cursor(&mut self, kind: Rotation) -> Option<Offset> {
+        self.hold_undo = None;
+        let cursor = self.cursor?;
+        let rotated = Piece {
+            rotation: kind,
+            ..cursor
+        };
 
-        // otherwise perform the rotation
-        self.cursor = Some(cursor_clone);
+        let (kick, &offset) = Rotation::wall_kick_offsets(cursor.kind, cursor.rotation, kind)
+            .iter()
+            .enumerate()
+            .find(|(_, &offset)| !self.matrix.is_clipping(&rotated.moved_by(offset)))?;
+        self.cursor = Some(rotated.moved_by(offset));
+        self.last_kick = Some(kick);
 
-        Some(())
+        Some(offset)
     }
 
     pub fn cursor_info(
@@ -169,6 +361,26 @@ impl Engine {
         Some((cells, cursor.kind.color(), cursor.rotation))
     }
 
+    // snapshot of the matrix as it should appear on screen - settled cells with the cursor placed on top
+    pub fn render_state(&self) -> Matrix<{ Self::MATRIX_WIDTH }, { Self::MATRIX_HEIGHT }> {
+        let mut matrix = self.matrix.clone();
+
+        if let Some((cursor_cells, cursor_color, _)) = self.cursor_info() {
+            for coord in cursor_cells {
+                // the cursor can be partially above the visible matrix
+                if coord.y < Self::MATRIX_HEIGHT {
+                    matrix[coord] = Some(cursor_color);
+                }
+            }
+        }
+
+        matrix
+    }
+
+    pub fn cursor(&self) -> Option<Piece> {
+        self.cursor
+    }
+
     // current cursor rotation
     pub fn next_cursor_rotation(&self) -> Option<Rotation> {
         let cursor = self.cursor?; // early return a None if it was None
@@ -176,45 +388,59 @@ impl Engine {
         Some(cursor.rotation.next_rotation())
     }
 
-    // creates a random tetrimino and places it above the matrix
+    // the rotation a counterclockwise turn would go to
+    pub fn prev_cursor_rotation(&self) -> Option<Rotation> {
+        Some(self.cursor?.rotation.prev_rotation())
+    }
+
+    // takes the up next tetrimino, which came out of the bag, and places it above the matrix
     pub fn create_top_cursor(&mut self, force_kind: Option<PieceKind>) {
-        let kind: PieceKind;
-        if force_kind.is_some() {
-            // force the kind (e.g. from hold) and skip the next & queue tetrimino manipulations
-            kind = force_kind.unwrap();
-        } else {
-            kind = self.next.remove(0);
-
-            // add a new one since we removed one
-            let new_tetrimino: PieceKind = rand::random(); // we can do this because we implemented the distribution trait for this enum!
-            self.next.push(new_tetrimino);
-
-            // readd cells in up next matrix
-            self.up_next_matrix.clear();
-            self.queue_matrix.clear();
-
-            for (index, next_up) in self.next.iter().rev().enumerate() {
-                let mut piece = Piece {
-                    kind: *next_up,
-                    position: (0, 0).into(),
-                    rotation: Rotation::N,
-                };
+        if let Some(kind) = force_kind {
+            // force the kind and skip the next & queue tetrimino manipulations
+            self.spawn_cursor(kind, SpawnSource::Forced);
+            return;
+        }
 
-                // the up next tetrimino
-                if index == self.next.len() - 1 {
-                    self.up_next_matrix.place_piece(piece);
-                } else {
-                    // the queue tetriminos
-                    let inside_index = index;
-                    piece.position = (0, ((inside_index) * 4) as isize).into();
-
-                    for coord in self.matrix.piece_cells(&piece).unwrap() {
-                        // add to y so we get a top-to-bottom queue
-                        self.queue_matrix[(coord.x, coord.y).into()] = Some(piece.kind.color());
-                    }
-                }
+        let kind = self.next.remove(0);
+
+        // add a new one since we removed one
+        let new_tetrimino = self.draw_from_bag();
+        self.next.push(new_tetrimino);
+
+        self.fill_previews();
+        self.spawn_cursor(kind, SpawnSource::Queue);
+    }
+
+    // readd cells in the up next matrix and the queue; only config.previews tetriminos go into the queue,
+    // from its bottom row up, so the nearest one is on top and the rows above the last one stay empty
+    fn fill_previews(&mut self) {
+        self.up_next_matrix.clear();
+        self.queue_matrix.clear();
+
+        let previews = self.config.previews.min(Self::MAX_PREVIEWS);
+        for (index, next_up) in self.next.iter().take(previews + 1).enumerate() {
+            let mut piece = Piece {
+                kind: *next_up,
+                position: (0, 0).into(),
+                rotation: Rotation::N,
+            };
+
+            // the up next tetrimino
+            if index == 0 {
+                self.up_next_matrix.place_piece(piece);
+                continue;
+            }
+
+            // the queue tetriminos, add to y so we get a top-to-bottom queue
+            piece.position = (0, ((previews - index) * Self::PREVIEW_ROWS) as isize).into();
+            for coord in self.matrix.piece_cells(&piece).unwrap() {
+                self.queue_matrix[(coord.x, coord.y).into()] = Some(piece.kind.color());
             }
         }
+    }
+
+    // places a tetrimino of the given kind above the matrix
+    fn spawn_cursor(&mut self, kind: PieceKind, source: SpawnSource) {
         // tetriminos are all generated north facing (just as they appear in the next Queue)
         let rotation = Rotation::N;
 
@@ -227,22 +453,17 @@ impl Engine {
            the o-tetrimino is generated on the 5th and  6th cell.
         */
 
-        let (mut x, mut y) = (0, 0);
-
-        // the I-tetrimino should start lower than the rest because of its north height being smaller
-        match kind.north_height() {
-            2 => y = 19,
-            1 => y = 18,
-            _ => y = 19,
-        }
-
-        // try to center them as best we can;
-        match kind.north_width() {
-            2 => x = 4,
-            3 => x = 3,
-            4 => x = 3,
-            _ => todo!(),
-        }
+        // the grids of their cells all start on the 4th cell across, which centers them; the I-tetrimino
+        // starts lower than the rest because of its north height being smaller
+        let (x, y) = match kind {
+            PieceKind::I => (3, 18),
+            PieceKind::O
+            | PieceKind::T
+            | PieceKind::L
+            | PieceKind::J
+            | PieceKind::S
+            | PieceKind::Z => (3, 19),
+        };
 
         let position = (x, y).into();
 
@@ -251,7 +472,23 @@ impl Engine {
             rotation,
             position,
         };
-        self.cursor = Some(piece)
+        self.cursor = Some(piece);
+        self.last_kick = None;
+
+        // the new tetrimino starts falling from scratch, whether it came from the queue or from hold;
+        // try_hold takes hold away again for the one it brings in
+        self.gravity_elapsed = Duration::ZERO;
+        self.lock_elapsed = None;
+        self.lock_resets = 0;
+        self.hold_used = false;
+        self.hold_undo = None;
+
+        self.events.push(EngineEvent::Spawned { kind, source });
+    }
+
+    // what happened since the last time they were taken, oldest first
+    pub fn take_events(&mut self) -> Vec<EngineEvent> {
+        std::mem::take(&mut self.events)
     }
 
     // ticks down the cursor for one spot and if it can't, returns an error and allow extended placement
@@ -268,6 +505,15 @@ impl Engine {
 
         // unwrap to catch errors
         self.cursor = Some(self.ticked_down_cursor().unwrap());
+        self.hold_undo = None;
+        self.last_kick = None;
+    }
+
+    // a row of soft drop: the cursor ticks down like it would with gravity, and the player gets a point
+    // for bringing it down themselves
+    pub fn soft_drop_step(&mut self) {
+        self.try_tick_down();
+        self.score += 1;
     }
 
     pub fn cursor_has_hit_bottom(&self) -> bool {
@@ -282,48 +528,406 @@ impl Engine {
         (!self.matrix.is_clipping(&new)).then_some(new)
     }
 
-    // moves cursor down and places it (series of tick downs), always succeeds
-    pub fn hard_drop(&mut self) {
+    // the cursor straight down onto whatever is below it, two points a row; returns how many rows it fell
+    pub fn hard_drop(&mut self) -> usize {
+        let distance = self.drop_to_surface();
+        self.score += 2 * distance as u32;
+        distance
+    }
+
+    // the cursor down onto the surface below it, scoring nothing; how many rows it fell
+    fn drop_to_surface(&mut self) -> usize {
+        self.hold_undo = None;
+        let mut distance = 0;
         // while we have a ticked down cursor, move it down
         while let Some(new) = self.ticked_down_cursor() {
             self.cursor = Some(new);
+            self.last_kick = None;
+            distance += 1;
+        }
+
+        distance
+    }
+
+    // the cursor where a hard drop would take it, moved down a copy of it so the cursor stays where it is
+    fn landed_cursor(&self) -> Option<Piece> {
+        let mut ghost = self.cursor?;
+        loop {
+            let lower = ghost.moved_by(Offset::new(0, -1));
+            if self.matrix.is_clipping(&lower) {
+                return Some(ghost);
+            }
+            ghost = lower;
+        }
+    }
+
+    // the cells the cursor would land on with a hard drop, where it is already if it's resting on
+    // something; None without a cursor. for landing previews and for weighing up placements
+    pub fn ghost_piece(&self) -> Option<[Coordinate; Piece::CELL_COUNT]> {
+        self.matrix.piece_cells(&self.landed_cursor()?)
+    }
+
+    // the cells the cursor would land on with a hard drop, and its color; None without a cursor or when
+    // it's already resting on a surface, where the ghost would only hide behind it
+    pub fn ghost_cursor_info(&self) -> Option<([Coordinate; Piece::CELL_COUNT], TetriminoColor)> {
+        self.ticked_down_cursor()?;
+        let ghost = self.landed_cursor()?;
+
+        Some((self.matrix.piece_cells(&ghost)?, ghost.kind.color()))
+    }
+
+    // how many rows the cursor would fall on a hard drop, None without a cursor
+    pub fn drop_distance(&self) -> Option<usize> {
+        let mut cursor = self.cursor?;
+        let mut distance = 0;
+
+        loop {
+            cursor = cursor.moved_by(Offset::new(0, -1));
+            if self.matrix.is_clipping(&cursor) {
+                return Some(distance);
+            }
+            distance += 1;
+        }
+    }
+
+    // columns the cursor can still be brought to from where it is, moving sideways and down without rotating;
+    // a column is where the leftmost cell of the cursor ends up. walls and overhangs on the way block it
+    pub fn reachable_columns(&self) -> Vec<usize> {
+        let Some(cursor) = self.cursor else {
+            return Vec::new();
+        };
+
+        let mut visited = HashSet::from([(cursor.position.x, cursor.position.y)]);
+        let mut pending = vec![cursor];
+        let mut columns = BTreeSet::new();
+
+        while let Some(piece) = pending.pop() {
+            if let Some(cells) = self.matrix.piece_cells(&piece) {
+                columns.insert(cells.iter().map(|coord| coord.x).min().unwrap());
+            }
+
+            for offset in [
+                MoveKind::Left.offset(),
+                MoveKind::Right.offset(),
+                Offset::new(0, -1),
+            ] {
+                let moved = piece.moved_by(offset);
+                if !self.matrix.is_clipping(&moved)
+                    && visited.insert((moved.position.x, moved.position.y))
+                {
+                    pending.push(moved);
+                }
+            }
+        }
+
+        columns.into_iter().collect()
+    }
+
+    // moves the cursor onto the surface below it without placing it, so the lock delay still applies;
+    // it isn't a hard drop, so it isn't scored like one. returns whether the cursor moved at all
+    pub fn sonic_drop(&mut self) -> bool {
+        self.drop_to_surface() > 0
+    }
+
+    // the same seed always puts the holes in the same columns, whatever the tetriminos are
+    pub fn seed_garbage(&mut self, seed: u64) {
+        self.garbage_rng = StdRng::seed_from_u64(seed);
+    }
+
+    // a random column for the hole of the next garbage
+    pub fn garbage_hole(&mut self) -> usize {
+        self.garbage_rng.gen_range(0..Self::MATRIX_WIDTH)
+    }
+
+    // garbage rises from the bottom with a hole in the given column; the cursor goes up along with it if
+    // it would overlap otherwise. returns false if the stack got pushed over the top, which is game over
+    pub fn add_garbage(&mut self, lines: usize, hole: usize) -> bool {
+        let fits = self.matrix.insert_garbage(lines, hole);
+
+        if let Some(mut cursor) = self.cursor {
+            for _ in 0..lines {
+                if !self.matrix.is_clipping(&cursor) {
+                    break;
+                }
+                cursor = cursor.moved_by(Offset::new(0, 1));
+            }
+            self.cursor = Some(cursor);
         }
+
+        fits
     }
 
+    // puts the cursor on hold. with HoldMode::Swap, whatever was on hold comes out as the new cursor; with
+    // HoldMode::Queue, the cursor goes to the back of the queue and the one at the front only comes out
+    // once the queue is full, until then the new cursor comes from up next like it does for the first
+    // hold of a swap. either way it's once per tetrimino (see can_hold)
     pub fn try_hold(&mut self) -> Option<bool> {
-        let mut cursor: Piece = self.cursor?; // early return a None if it was None
+        let cursor: Piece = self.cursor?; // early return a None if it was None
+
+        if let Some(undo) = self.hold_undo.take() {
+            self.undo_hold(*undo);
+            return Some(true);
+        }
 
         // if we don't have a hold or the hold is not the same as the current cursor
-        if self.hold.is_none() || (self.hold.is_some() && self.hold.unwrap() != cursor.kind) {
-            self.hold_matrix.clear();
+        if self.can_hold() {
+            let undo = self.config.hold_undo.then(|| HoldUndo {
+                cursor,
+                last_kick: self.last_kick,
+                hold: self.hold.clone(),
+                hold_matrix: self.hold_matrix.clone(),
+                next: self.next.clone(),
+                bag: self.bag.clone(),
+                rng: self.rng.clone(),
+                gravity_elapsed: self.gravity_elapsed,
+                lock_elapsed: self.lock_elapsed,
+                lock_resets: self.lock_resets,
+            });
+
+            let old_hold = self.leaving_hold();
+            if old_hold.is_some() {
+                self.hold.remove(0);
+            }
+            self.hold.push(cursor.kind);
 
-            let old_hold = self.hold;
-            self.hold = Some(cursor.kind);
-            cursor.position = (0, 0).into();
-            self.hold_matrix.place_piece(cursor);
+            // the hold matrix shows the front of the queue, north facing like up next
+            self.hold_matrix.clear();
+            self.hold_matrix.place_piece(Piece {
+                kind: self.hold[0],
+                position: (0, 0).into(),
+                rotation: Rotation::N,
+            });
 
             self.cursor = None;
 
-            // create top cursor from whatever was on hold if there was anything
-            self.create_top_cursor(old_hold);
+            // create top cursor from whatever came off hold if there was anything
+            match old_hold {
+                Some(kind) => self.spawn_cursor(kind, SpawnSource::Hold),
+                None => self.create_top_cursor(None),
+            }
+            self.hold_used = true;
+            self.hold_undo = undo.map(Box::new);
         }
 
         Some(true)
     }
 
+    // everything back to how it was before the hold, the tetrimino that went on hold where it was and
+    // free to be held again
+    fn undo_hold(&mut self, undo: HoldUndo) {
+        self.hold = undo.hold;
+        self.hold_matrix = undo.hold_matrix;
+        self.next = undo.next;
+        self.bag = undo.bag;
+        self.rng = undo.rng;
+        self.fill_previews();
+
+        self.cursor = Some(undo.cursor);
+        self.last_kick = undo.last_kick;
+        self.gravity_elapsed = undo.gravity_elapsed;
+        self.lock_elapsed = undo.lock_elapsed;
+        self.lock_resets = undo.lock_resets;
+        self.hold_used = false;
+
+        self.events.push(EngineEvent::Spawned {
+            kind: undo.cursor.kind,
+            source: SpawnSource::HoldUndo,
+        });
+    }
+
+    // let time pass: the cursor falls a row every drop time and once it has rested on a surface
+    // for the lock delay, it locks down
+    pub fn advance(&mut self, mut dt: Duration, is_soft_drop: bool) -> Advance {
+        if self.cursor.is_none() {
+            return Advance::Falling;
+        }
+
+        // stepping by hand, the time until the next thing happens passes all at once
+        if self.frame_step {
+            if !self.step_pending {
+                return match self.lock_elapsed {
+                    Some(_) => Advance::Grounded,
+                    None => Advance::Falling,
+                };
+            }
+            dt = dt.max(self.time_until_advance(is_soft_drop).unwrap_or_default());
+            self.step_pending = false;
+        }
+
+        // moved off the surface during the lock delay, so it falls again
+        if self.lock_elapsed.is_some() && !self.cursor_has_hit_bottom() {
+            self.lock_elapsed = None;
+            self.gravity_elapsed = Duration::ZERO;
+        }
+
+        // landed without falling, e.g. hard dropped or moved onto a ledge
+        if self.lock_elapsed.is_none() && self.cursor_has_hit_bottom() {
+            self.lock_elapsed = Some(Duration::ZERO);
+        }
+
+        if let Some(elapsed) = self.lock_elapsed {
+            let elapsed = elapsed + dt;
+            if elapsed < Self::LOCK_DELAY {
+                self.lock_elapsed = Some(elapsed);
+                return Advance::Grounded;
+            }
+
+            return if self.lock_cursor() {
+                Advance::Locked
+            } else {
+                Advance::GameOver
+            };
+        }
+
+        self.gravity_elapsed += dt;
+        let drop_time = self.drop_time(is_soft_drop);
+
+        while self.gravity_elapsed >= drop_time {
+            self.gravity_elapsed -= drop_time;
+
+            if self.ticked_down_cursor().is_some() {
+                if is_soft_drop {
+                    self.soft_drop_step();
+                } else {
+                    self.try_tick_down();
+                }
+            }
+
+            if self.cursor_has_hit_bottom() {
+                self.lock_elapsed = Some(Duration::ZERO);
+                self.gravity_elapsed = Duration::ZERO;
+                return Advance::Grounded;
+            }
+        }
+
+        Advance::Falling
+    }
+
+    // how close the stack is to the top, from 0 (safe) to 3 (critical), see Config::danger_heights;
+    // meant for things like the music picking up pace
+    pub fn danger_level(&self) -> u8 {
+        let height = self.matrix.column_heights().into_iter().max().unwrap_or(0);
+
+        self.config
+            .danger_heights
+            .iter()
+            .filter(|&&threshold| height >= threshold)
+            .count() as u8
+    }
+
+    // a hint for learning players, nothing in the game goes by it: the stack is tall and has so many holes
+    // under it that digging them out before topping out is unlikely
+    pub fn is_overstacked(&self) -> bool {
+        let height = self.matrix.column_heights().into_iter().max().unwrap_or(0);
+
+        height >= Self::OVERSTACKED_HEIGHT && self.matrix.count_holes() >= Self::OVERSTACKED_HOLES
+    }
+
+    // how long until advance would change something: the cursor falling a row or the lock delay running out
+    pub fn time_until_advance(&self, is_soft_drop: bool) -> Option<Duration> {
+        self.cursor?;
+        if self.frame_step && !self.step_pending {
+            return None;
+        }
+
+        Some(match self.lock_delay_remaining() {
+            Some(remaining) => remaining,
+            None => self
+                .drop_time(is_soft_drop)
+                .saturating_sub(self.gravity_elapsed),
+        })
+    }
+
+    // in frame_step mode, let the next advance go on to whatever happens next: the cursor falling a row,
+    // landing or locking down
+    pub fn step(&mut self) {
+        self.step_pending = true;
+    }
+
+    // what's left of the lock delay of a grounded cursor, None while it's falling
+    pub fn lock_delay_remaining(&self) -> Option<Duration> {
+        self.lock_elapsed
+            .map(|elapsed| Self::LOCK_DELAY.saturating_sub(elapsed))
+    }
+
+    // moving or rotating the cursor while it's grounded gives it the full lock delay again, a limited number of times
+    pub fn reset_lock_delay(&mut self) {
+        if self.lock_elapsed.is_some() && self.lock_resets < Self::MAX_LOCK_RESETS {
+            self.lock_elapsed = Some(Duration::ZERO);
+            self.lock_resets += 1;
+        }
+    }
+
+    // place the cursor where it is and spawn the next one; false if it couldn't be placed, which is game over
+    pub fn lock_cursor(&mut self) -> bool {
+        self.lock_elapsed = None;
+
+        match self.try_place_cursor() {
+            Ok(true) => {}
+            Ok(false) => return false,
+            // nothing to place, the next one still spawns
+            Err(NoCursor) => log::warn!("Tried placing a nonexistent cursor"),
+        }
+
+        self.create_top_cursor(None);
+        true
+    }
+
+    // how far the cursor is on its way down to the next row, from 0 right after it fell a row to almost 1
+    // just before it falls again; 0 on a surface, where it isn't going anywhere. the cursor itself only
+    // ever moves a whole row, this is for drawing it in between
+    pub fn fall_progress(&self, is_soft_drop: bool) -> f32 {
+        if self.cursor.is_none() || self.cursor_has_hit_bottom() {
+            return 0.0;
+        }
+
+        let progress =
+            self.gravity_elapsed.as_secs_f32() / self.drop_time(is_soft_drop).as_secs_f32();
+        progress.min(1.0)
+    }
+
+    // the drop speed as rows per frame at 60 fps, the way other games give it (1G is Config::ONE_G)
+    pub fn gravity(&self) -> f32 {
+        Config::ONE_G.as_secs_f32() / self.drop_time(false).as_secs_f32()
+    }
+
     // how long the tetrimino should drop for a certain level
     pub fn drop_time(&self, is_soft_drop: bool) -> Duration {
         // equation from the docs: (0.8 - ((level - 1) * 0.007))^(level-1)
-        let level_index = self.level + 1;
-        let mut seconds_per_line = (0.8 - ((level_index) as f32 * 0.007)).powi(level_index as i32);
+        let level_index = self.level as i32 + 1;
+        // past level 113 the base goes below zero, it stays at zero instead of flipping sign with every level
+        let mut seconds_per_line = (0.8 - ((level_index) as f32 * 0.007))
+            .max(0.0)
+            .powi(level_index);
         if is_soft_drop {
             seconds_per_line /= 20.0;
         }
-        Duration::from_secs_f32(seconds_per_line)
+        // the formula keeps getting faster until it's down to nothing, the clamp keeps it playable
+        let drop_time = Duration::from_secs_f32(seconds_per_line);
+        match self.config.min_drop_time {
+            Some(min_drop_time) => drop_time.max(min_drop_time),
+            None => drop_time,
+        }
     }
 
-    // when a line is full, it needs to be removed from the screen
-    pub fn line_clear(&mut self, mut animation: impl FnMut(&[usize])) {
+    // the full lines, bottom first, with the color of each of their cells; for clear effects that show
+    // the cells going away as they were, before line_clear takes them
+    pub fn full_line_colors(&self) -> Vec<(usize, [TetriminoColor; Self::MATRIX_WIDTH])> {
+        self.matrix
+            .full_lines()
+            .into_iter()
+            .map(|row| {
+                let colors = std::array::from_fn(|x| {
+                    self.matrix[Coordinate::new(x, row)].expect("a full line has every cell filled")
+                });
+                (row, colors)
+            })
+            .collect()
+    }
+
+    // when a line is full, it needs to be removed from the screen; tspin is what the tetrimino that filled
+    // them made, see placed_tspin. returns the points the clear scored, for showing them
+    pub fn line_clear(&mut self, tspin: TSpinKind, mut animation: impl FnMut(&[usize])) -> u32 {
         // identify full lines
         let lines: Vec<usize> = self.matrix.full_lines();
 
@@ -331,22 +935,172 @@ impl Engine {
         animation(lines.as_slice());
 
         self.matrix.clear_lines(lines.as_slice());
+        if !lines.is_empty() {
+            log::debug!("Cleared lines {lines:?}");
+            self.events.push(EngineEvent::LinesCleared {
+                lines: lines.len(),
+                tspin,
+            });
+        }
 
+        let was_complete = self.is_sprint_complete();
+        self.stats.record_clear(lines.len());
         self.lines_reached += lines.len() as u32;
-        self.score += lines.len() as u32;
+        if self.is_sprint_complete() && !was_complete {
+            log::info!("Cleared the {} lines of the sprint", self.stats.lines());
+            self.events.push(EngineEvent::ModeComplete);
+        }
+        if tspin != TSpinKind::None {
+            self.stats.tspins += 1;
+        }
+        // at the level the clear was made at, before it levels up
+        let points = self.clear_points(lines.len(), tspin);
+        self.score += points;
+        self.tspin = TSpinKind::None;
 
-        if self.lines_reached >= Self::LINES_PER_LEVEL {
+        if self.config.leveling && self.lines_reached >= Self::LINES_PER_LEVEL {
             self.level += 1;
             self.lines_reached = 0;
+            log::info!("Reached level {}", self.level);
+            self.events.push(EngineEvent::LevelUp { level: self.level });
+        }
+
+        points
+    }
+
+    // the lines of a sprint have all been cleared; false in the other modes
+    pub fn is_sprint_complete(&self) -> bool {
+        self.config.mode.lines_left(self.stats.lines()) == Some(0)
+    }
+
+    // puts a cell straight onto the matrix, for tools that set up boards (editors, puzzles) without
+    // tetriminos locking
+    pub fn place_at(&mut self, coord: Coordinate, color: TetriminoColor) {
+        self.matrix[coord] = Some(color);
+    }
+
+    // the points for a clear of count lines, with the bonuses for the clears before it; keeps the combo and
+    // back-to-back going or breaks them. each only goes on while its Config switch is on
+    fn clear_points(&mut self, count: usize, tspin: TSpinKind) -> u32 {
+        let mut points = scoring::score_lines(count, tspin, self.level);
+        if count == 0 {
+            // a t-spin without lines doesn't break the back-to-back either
+            self.combo = -1;
+            return points;
+        }
+
+        if self.config.back_to_back {
+            let difficult = scoring::is_difficult(count, tspin);
+            if difficult && self.back_to_back {
+                points = points * 3 / 2;
+            }
+            self.back_to_back = difficult;
         }
+        if self.config.combo {
+            self.combo += 1;
+            points += scoring::combo_bonus(self.combo, self.level);
+        }
+        points
+    }
+
+    // the line clear that otherwise only follows a lock, for boards set up with place_at; returns how
+    // many lines it cleared
+    pub fn force_line_clear(&mut self) -> usize {
+        let lines = self.matrix.full_lines().len();
+        self.line_clear(TSpinKind::None, |_| ());
+        lines
     }
 
+    // back to how a new game starts: a fresh bag deals what's up next, nothing is held; the rng goes on
+    // where it was, so the tetriminos are new ones
     pub fn reset(&mut self) {
         self.cursor = None;
+        self.hold.clear();
+        self.hold_matrix.clear();
+        self.hold_undo = None;
+        self.last_kick = None;
+        self.tspin = TSpinKind::None;
+        self.gravity_elapsed = Duration::ZERO;
+        self.lock_elapsed = None;
+        self.lock_resets = 0;
+        self.hold_used = false;
         self.matrix.clear();
         self.level = 1;
         self.score = 0;
+        self.combo = -1;
+        self.back_to_back = false;
         self.lines_reached = 0;
+        self.stats = Stats::default();
+        self.events.clear();
+
+        self.bag.clear();
+        self.next.clear();
+        for _ in 0..Self::NEXT_COUNT {
+            let kind = self.draw_from_bag();
+            self.next.push(kind);
+        }
+        self.fill_previews();
+    }
+
+    // empty the matrix and take the cursor away, for moving on to a new board within the same game;
+    // unlike reset, the level, score, lines, stats, hold, bag and what's up next all stay
+    pub fn clear_board(&mut self) {
+        self.cursor = None;
+        self.hold_undo = None;
+        self.tspin = TSpinKind::None;
+        self.gravity_elapsed = Duration::ZERO;
+        self.lock_elapsed = None;
+        self.lock_resets = 0;
+        self.hold_used = false;
+        self.matrix.clear();
+    }
+
+    pub fn stats(&self) -> Stats {
+        self.stats
+    }
+
+    // clears in a row after the first, -1 until there's a first one; see Config::combo
+    pub fn combo(&self) -> i32 {
+        self.combo
+    }
+
+    // things that have to hold no matter what was done to the engine, for tests and fuzzing;
+    // the error says which one didn't
+    pub fn validate_invariants(&self) -> Result<(), String> {
+        if let Some(cursor) = self.cursor {
+            if self.matrix.is_clipping(&cursor) {
+                return Err(format!("cursor {cursor:?} overlaps the stack or a wall"));
+            }
+        }
+
+        if self.next.len() != Self::NEXT_COUNT {
+            return Err(format!("{} tetriminos up next", self.next.len()));
+        }
+
+        if self.bag.len() >= PieceKind::ALL.len()
+            || PieceKind::ALL
+                .iter()
+                .any(|kind| self.bag.iter().filter(|&bagged| bagged == kind).count() > 1)
+        {
+            return Err(format!("bag {:?} isn't part of a single 7-bag", self.bag));
+        }
+
+        if self
+            .lock_elapsed
+            .is_some_and(|elapsed| elapsed >= Self::LOCK_DELAY)
+        {
+            return Err(format!("lock delay overran at {:?}", self.lock_elapsed));
+        }
+
+        if self.lock_resets > Self::MAX_LOCK_RESETS {
+            return Err(format!("{} lock resets", self.lock_resets));
+        }
+
+        if self.level == 0 {
+            return Err("level 0".to_string());
+        }
+
+        Ok(())
     }
 }
 
@@ -387,4 +1141,1276 @@ mod test {
 
         assert!(iter.all(|(_, contents)| contents.is_none()));
     }
+
+    #[test]
+    fn clear_stacked_lines() {
+        let mut matrix = Matrix::<10, 20>::blank();
+        let colors = [
+            TetriminoColor::Red,
+            TetriminoColor::Green,
+            TetriminoColor::Blue,
+        ];
+
+        // three full rows at the bottom, each in its own color
+        for (y, color) in colors.into_iter().enumerate() {
+            for x in 0..10 {
+                matrix[Coordinate::new(x, y)] = Some(color);
+            }
+        }
+
+        // cells above the full rows that should fall down by three
+        matrix[Coordinate::new(0, 3)] = Some(TetriminoColor::Yellow);
+        matrix[Coordinate::new(5, 19)] = Some(TetriminoColor::Purple);
+
+        let lines = matrix.full_lines();
+        assert_eq!(lines, [0, 1, 2]);
+        matrix.clear_lines(&lines);
+
+        assert_eq!(matrix[Coordinate::new(0, 0)], Some(TetriminoColor::Yellow));
+        assert_eq!(matrix[Coordinate::new(5, 16)], Some(TetriminoColor::Purple));
+
+        // nothing else is left over, in particular the top three rows are blank
+        let occupied = matrix.matrix.iter().filter(|cell| cell.is_some()).count();
+        assert_eq!(occupied, 2);
+        for y in 17..20 {
+            for x in 0..10 {
+                assert_eq!(matrix[Coordinate::new(x, y)], None);
+            }
+        }
+    }
+
+    #[test]
+    fn fewer_previews_fill_fewer_queue_rows() {
+        let mut all = Engine::new_seeded(8);
+        let mut three = Engine::new_seeded(8);
+        three.config.previews = 3;
+        all.create_top_cursor(None);
+        three.create_top_cursor(None);
+
+        let rows = 3 * Engine::PREVIEW_ROWS;
+        for y in 0..Engine::REMAINING_NEXT_MATRIX_HEIGHT {
+            for x in 0..Engine::REMAINING_NEXT_MATRIX_WIDTH {
+                let coord = Coordinate::new(x, y);
+                if y >= rows {
+                    assert_eq!(three.queue_matrix[coord], None);
+                } else {
+                    // the nearest three, in the same order as with all of them shown
+                    let shifted =
+                        Coordinate::new(x, y + Engine::REMAINING_NEXT_MATRIX_HEIGHT - rows);
+                    assert_eq!(three.queue_matrix[coord], all.queue_matrix[shifted]);
+                }
+            }
+        }
+        assert_eq!(
+            three.up_next_matrix.to_string(),
+            all.up_next_matrix.to_string()
+        );
+    }
+
+    #[test]
+    fn remaining_bag_lists_undrawn_kinds() {
+        let mut engine = Engine::new();
+
+        // start from a fresh bag and draw three of its tetriminos
+        engine.bag.clear();
+        let drawn: Vec<PieceKind> = (0..3).map(|_| engine.draw_from_bag()).collect();
+
+        let remaining = engine.remaining_bag();
+        assert_eq!(remaining.len(), 4);
+        for kind in PieceKind::ALL {
+            assert_ne!(drawn.contains(&kind), remaining.contains(&kind));
+        }
+    }
+
+    #[test]
+    fn a_seed_deals_the_same_sequence_every_time() {
+        let dealt = |seed| {
+            let mut engine = Engine::new_seeded(seed);
+            (0..50)
+                .map(|_| {
+                    engine.create_top_cursor(None);
+                    engine.cursor().unwrap().kind
+                })
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(dealt(42), dealt(42));
+        assert_ne!(dealt(42), dealt(43));
+    }
+
+    #[test]
+    fn two_bags_deal_every_kind_twice() {
+        for seed in 0..20 {
+            let mut engine = Engine::new_seeded(seed);
+            let mut counts = [0; 7];
+            for _ in 0..14 {
+                engine.create_top_cursor(None);
+                counts[engine.cursor().unwrap().kind as usize] += 1;
+            }
+            assert_eq!(counts, [2; 7], "seed {seed}");
+        }
+    }
+
+    #[test]
+    fn every_bag_of_seven_deals_each_kind_once() {
+        let mut engine = Engine::new();
+        let dealt: Vec<_> = (0..70)
+            .map(|_| {
+                engine.create_top_cursor(None);
+                engine.cursor().unwrap().kind
+            })
+            .collect();
+
+        // no droughts or repeats within a bag, however the bags were shuffled
+        for (index, bag) in dealt.chunks(PieceKind::ALL.len()).enumerate() {
+            for kind in PieceKind::ALL {
+                assert_eq!(
+                    bag.iter().filter(|&&dealt| dealt == kind).count(),
+                    1,
+                    "{kind:?} in bag {index}: {bag:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn bag_divider_is_where_the_current_bag_runs_out() {
+        let mut engine = Engine::new_seeded(5);
+        let mut spawned = Vec::new();
+
+        for _ in 0..3 * PieceKind::ALL.len() {
+            engine.create_top_cursor(None);
+            spawned.push(engine.cursor().unwrap().kind);
+
+            let divider = engine.bag_divider();
+            let left_in_bag = (7 - spawned.len() % 7) % 7;
+            assert_eq!(divider, left_in_bag, "after {} tetriminos", spawned.len());
+
+            // the ones since the current bag started and the ones before the divider make up a whole bag
+            let bag_start = (spawned.len() - 1) / 7 * 7;
+            let mut bag = spawned[bag_start..].to_vec();
+            bag.extend(&engine.upcoming()[..divider]);
+            bag.sort_by_key(|kind| *kind as u8);
+            assert_eq!(bag, PieceKind::ALL);
+        }
+    }
+
+    #[test]
+    fn leveling_disabled_keeps_level() {
+        let mut engine = Engine::new();
+        engine.config.leveling = false;
+        let drop_time = engine.drop_time(false);
+
+        for _ in 0..30 {
+            for x in 0..Engine::MATRIX_WIDTH {
+                engine.matrix[Coordinate::new(x, 0)] = Some(TetriminoColor::Cyan);
+            }
+            engine.line_clear(TSpinKind::None, |_| ());
+        }
+
+        assert_eq!(engine.level, 1);
+        // thirty singles in a row, each one combo more than the last
+        assert_eq!(engine.score, 30 * 100 + 50 * (0..30).sum::<u32>());
+        assert_eq!(engine.drop_time(false), drop_time);
+    }
+
+    #[test]
+    fn without_bonuses_tetrises_in_a_row_score_the_same() {
+        let mut engine = Engine::new();
+        engine.config.combo = false;
+        engine.config.back_to_back = false;
+
+        let mut tetris = || {
+            let before = engine.score;
+            for y in 0..4 {
+                for x in 0..Engine::MATRIX_WIDTH {
+                    engine.matrix[Coordinate::new(x, y)] = Some(TetriminoColor::Cyan);
+                }
+            }
+            engine.line_clear(TSpinKind::None, |_| ());
+            engine.score - before
+        };
+
+        let first = tetris();
+        assert!(first > 0);
+        assert_eq!(tetris(), first);
+        assert_eq!(engine.score, 2 * first);
+        assert_eq!((engine.combo, engine.back_to_back), (-1, false));
+    }
+
+    #[test]
+    fn clears_score_by_the_level_they_are_made_at() {
+        let mut engine = Engine::new();
+        engine.level = 3;
+        for y in 0..2 {
+            for x in 0..Engine::MATRIX_WIDTH {
+                engine.matrix[Coordinate::new(x, y)] = Some(TetriminoColor::Cyan);
+            }
+        }
+
+        // a double is 300 a level
+        assert_eq!(engine.line_clear(TSpinKind::None, |_| ()), 900);
+        assert_eq!(engine.score, 900);
+        assert_eq!(engine.lines_reached, 2); // the lines themselves are what levels up
+
+        // nothing to clear, nothing scored
+        assert_eq!(engine.line_clear(TSpinKind::None, |_| ()), 0);
+        assert_eq!(engine.score, 900);
+    }
+
+    #[test]
+    fn singles_in_a_row_grow_the_combo_bonus() {
+        let mut engine = Engine::new();
+        let mut single = || {
+            let before = engine.score;
+            for x in 0..Engine::MATRIX_WIDTH {
+                engine.matrix[Coordinate::new(x, 0)] = Some(TetriminoColor::Cyan);
+            }
+            engine.line_clear(TSpinKind::None, |_| ());
+            (engine.score - before, engine.combo())
+        };
+
+        assert_eq!(single(), (100, 0));
+        assert_eq!(single(), (150, 1));
+        assert_eq!(single(), (200, 2));
+
+        // a lock that clears nothing ends it
+        engine.line_clear(TSpinKind::None, |_| ());
+        assert_eq!(engine.combo(), -1);
+    }
+
+    #[test]
+    fn clears_in_a_row_get_combo_and_back_to_back_bonuses() {
+        let mut engine = Engine::new();
+        engine.config.leveling = false; // all at level 1
+        let mut clear = |lines: usize| {
+            let before = engine.score;
+            for y in 0..lines {
+                for x in 0..Engine::MATRIX_WIDTH {
+                    engine.matrix[Coordinate::new(x, y)] = Some(TetriminoColor::Cyan);
+                }
+            }
+            engine.line_clear(TSpinKind::None, |_| ());
+            (engine.score - before, engine.combo, engine.back_to_back)
+        };
+
+        assert_eq!(clear(4), (800, 0, true));
+        assert_eq!(clear(4), (1200 + 50, 1, true)); // back to back, and the first of the combo
+        assert_eq!(clear(1), (100 + 100, 2, false)); // a single breaks the back-to-back
+        assert_eq!(clear(4), (800 + 150, 3, true)); // and doesn't start a new one
+
+        // a lock without lines breaks the combo but not the back-to-back
+        assert_eq!(clear(0), (0, -1, true));
+        assert_eq!(clear(4), (1200, 0, true));
+    }
+
+    #[test]
+    fn level_ups_are_reported_and_speed_up_gravity() {
+        let mut engine = Engine::new();
+        let gravity = engine.gravity();
+        assert!(
+            (gravity - Config::ONE_G.as_secs_f32() / engine.drop_time(false).as_secs_f32()).abs()
+                < 1e-6
+        );
+
+        for _ in 0..Engine::LINES_PER_LEVEL {
+            for x in 0..Engine::MATRIX_WIDTH {
+                engine.matrix[Coordinate::new(x, 0)] = Some(TetriminoColor::Cyan);
+            }
+            engine.line_clear(TSpinKind::None, |_| ());
+        }
+
+        let events = engine.take_events();
+        assert_eq!(events.last(), Some(&EngineEvent::LevelUp { level: 2 }));
+        assert_eq!(
+            events
+                .iter()
+                .filter(|event| matches!(event, EngineEvent::LevelUp { .. }))
+                .count(),
+            1
+        );
+        assert!(engine.gravity() > gravity);
+    }
+
+    #[test]
+    fn every_kind_spawns_centered_on_the_21st_row() {
+        for kind in PieceKind::ALL {
+            let mut engine = Engine::new();
+            engine.create_top_cursor(Some(kind));
+            let (cells, _, _) = engine.cursor_info().unwrap();
+
+            let columns = cells.map(|cell| cell.x);
+            let (left, right) = (
+                *columns.iter().min().unwrap(),
+                *columns.iter().max().unwrap(),
+            );
+            let expected = match kind {
+                PieceKind::I => (3, 6),
+                PieceKind::O => (4, 5),
+                _ => (3, 5),
+            };
+            assert_eq!((left, right), expected, "{kind:?}");
+            assert_eq!(cells.iter().map(|cell| cell.y).min(), Some(20), "{kind:?}");
+        }
+    }
+
+    #[test]
+    fn spawns_from_the_queue_and_hold_are_reported() {
+        let mut engine = Engine::new_seeded(1);
+        let spawned = |engine: &mut Engine, source| {
+            let kind = engine.cursor().unwrap().kind;
+            assert_eq!(
+                engine.take_events(),
+                [EngineEvent::Spawned { kind, source }]
+            );
+            kind
+        };
+
+        engine.create_top_cursor(None);
+        let first = spawned(&mut engine, SpawnSource::Queue);
+
+        // nothing on hold yet, the next one comes from the queue
+        engine.try_hold();
+        spawned(&mut engine, SpawnSource::Queue);
+
+        // hold is used up until the next tetrimino
+        engine.hard_drop();
+        engine.lock_cursor();
+        spawned(&mut engine, SpawnSource::Queue);
+
+        engine.try_hold();
+        assert_eq!(spawned(&mut engine, SpawnSource::Hold), first);
+        assert!(engine.take_events().is_empty());
+
+        engine.create_top_cursor(Some(PieceKind::I));
+        assert_eq!(spawned(&mut engine, SpawnSource::Forced), PieceKind::I);
+    }
+
+    #[test]
+    fn the_ghost_is_where_a_hard_drop_lands() {
+        let mut engine = Engine::new();
+        assert_eq!(engine.ghost_cursor_info(), None);
+        assert_eq!(engine.ghost_piece(), None);
+
+        engine.create_top_cursor(Some(PieceKind::J));
+        engine.matrix[Coordinate::new(3, 4)] = Some(TetriminoColor::Red);
+        let cursor = engine.cursor();
+        let (cells, color) = engine.ghost_cursor_info().unwrap();
+        assert_eq!(color, TetriminoColor::Blue);
+        assert_eq!(engine.ghost_piece(), Some(cells));
+        assert_eq!(engine.cursor(), cursor); // only looked
+
+        engine.hard_drop();
+        assert_eq!(engine.cursor_info().map(|(cells, ..)| cells), Some(cells));
+        assert!(cells.iter().all(|cell| cell.y >= 5)); // on top of the red cell
+
+        // resting on it, there's nothing to show, but it still lands where it is
+        assert_eq!(engine.ghost_cursor_info(), None);
+        assert_eq!(engine.ghost_piece(), Some(cells));
+    }
+
+    #[test]
+    fn drop_distance_is_how_far_a_hard_drop_goes() {
+        let mut engine = Engine::new();
+        assert_eq!(engine.drop_distance(), None);
+
+        let hard_dropped = |engine: &Engine| {
+            let mut dropped = engine.clone();
+            dropped.hard_drop();
+            (engine.cursor().unwrap().position.y - dropped.cursor().unwrap().position.y) as usize
+        };
+
+        engine.create_top_cursor(Some(PieceKind::O));
+        let distance = engine.drop_distance().unwrap();
+        assert_eq!(distance, hard_dropped(&engine));
+
+        // a cell in the way, six rows up
+        engine.matrix[Coordinate::new(4, 5)] = Some(TetriminoColor::Red);
+        assert_eq!(engine.drop_distance(), Some(distance - 6));
+        assert_eq!(engine.drop_distance(), Some(hard_dropped(&engine)));
+
+        engine.hard_drop();
+        assert_eq!(engine.drop_distance(), Some(0));
+    }
+
+    #[test]
+    fn hard_dropped_rows_score_two_points_each() {
+        let mut engine = Engine::new();
+        engine.create_top_cursor(Some(PieceKind::T));
+        assert_eq!(engine.cursor().unwrap().position.y, 19);
+        let distance = engine.drop_distance().unwrap();
+        assert!(distance > 0);
+
+        assert_eq!(engine.hard_drop(), distance);
+        assert_eq!(engine.score, 2 * distance as u32);
+
+        // already down, a second one goes nowhere and scores nothing
+        assert_eq!(engine.hard_drop(), 0);
+        assert_eq!(engine.score, 2 * distance as u32);
+    }
+
+    #[test]
+    fn an_i_hard_dropped_from_spawn_falls_the_whole_matrix() {
+        let mut engine = Engine::new();
+        engine.create_top_cursor(Some(PieceKind::I));
+
+        // flat on the 21st row, right above the matrix, and all the way down to the floor
+        assert_eq!(engine.hard_drop(), Engine::MATRIX_HEIGHT);
+        assert_eq!(engine.score, 40);
+    }
+
+    #[test]
+    fn soft_dropped_rows_score_a_point_each() {
+        let mut engine = Engine::new();
+        engine.create_top_cursor(Some(PieceKind::O));
+        let top = engine.cursor().unwrap().position.y;
+
+        for _ in 0..5 {
+            engine.soft_drop_step();
+        }
+        assert_eq!(engine.cursor().unwrap().position.y, top - 5);
+        assert_eq!(engine.score, 5);
+
+        // the same through advance, the rows soft drop brings it down by
+        let soft_drop = engine.drop_time(true);
+        engine.advance(soft_drop * 5, true);
+        assert_eq!(engine.cursor().unwrap().position.y, top - 10);
+        assert_eq!(engine.score, 10);
+
+        // and gravity doesn't score anything
+        engine.advance(engine.drop_time(false) * 3, false);
+        assert_eq!(engine.cursor().unwrap().position.y, top - 13);
+        assert_eq!(engine.score, 10);
+    }
+
+    #[test]
+    fn drop_time_stops_at_the_clamp() {
+        let mut engine = Engine::new();
+        let mut previous = engine.drop_time(false);
+
+        for level in 1..=u8::MAX {
+            engine.level = level;
+            let drop_time = engine.drop_time(false);
+            assert!(
+                drop_time <= previous,
+                "level {level} is slower than the one before"
+            );
+            if level >= 15 {
+                assert_eq!(drop_time, Config::ONE_G, "level {level}");
+                assert_eq!(engine.drop_time(true), Config::ONE_G);
+            }
+            previous = drop_time;
+        }
+
+        // without it, gravity goes on getting faster
+        engine.level = 15;
+        engine.config.min_drop_time = None;
+        assert!(engine.drop_time(false) < Config::ONE_G);
+    }
+
+    #[test]
+    fn render_state_matches_board() {
+        let mut engine = Engine::new();
+        engine.matrix[Coordinate::new(0, 0)] = Some(TetriminoColor::Red);
+        engine.matrix[Coordinate::new(9, 3)] = Some(TetriminoColor::Cyan);
+        engine.create_top_cursor(Some(PieceKind::T));
+        engine.hard_drop();
+
+        let render_state = engine.render_state();
+        let (cursor_cells, cursor_color, _) = engine.cursor_info().unwrap();
+
+        for y in 0..Engine::MATRIX_HEIGHT {
+            for x in 0..Engine::MATRIX_WIDTH {
+                let coord = Coordinate::new(x, y);
+                let expected = if cursor_cells.contains(&coord) {
+                    Some(cursor_color)
+                } else {
+                    engine.matrix[coord]
+                };
+
+                assert_eq!(render_state[coord], expected);
+            }
+        }
+    }
+
+    #[test]
+    fn reachable_columns_blocked_by_overhang() {
+        let mut engine = Engine::new();
+        engine.create_top_cursor(Some(PieceKind::O));
+
+        // above the stack the o tetrimino can go anywhere its two columns fit
+        assert_eq!(engine.reachable_columns(), (0..=8).collect::<Vec<_>>());
+
+        // a wall in the middle with a roof reaching left over the bottom rows
+        for y in 0..10 {
+            engine.matrix[Coordinate::new(5, y)] = Some(TetriminoColor::Red);
+        }
+        for x in 2..5 {
+            engine.matrix[Coordinate::new(x, 9)] = Some(TetriminoColor::Red);
+        }
+
+        // tuck the tetrimino under the roof; it can still slide left but nothing gets it past the wall
+        engine.move_cursor(MoveKind::Left);
+        engine.move_cursor(MoveKind::Left);
+        engine.move_cursor(MoveKind::Left);
+        engine.move_cursor(MoveKind::Left);
+        engine.hard_drop();
+        engine.move_cursor(MoveKind::Right);
+        engine.move_cursor(MoveKind::Right);
+        engine.move_cursor(MoveKind::Right);
+
+        let (cells, _, _) = engine.cursor_info().unwrap();
+        assert!(cells.iter().all(|coord| coord.y < 9 && coord.x < 5));
+        assert_eq!(engine.reachable_columns(), [0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn placing_the_cursor() {
+        let mut engine = Engine::new();
+        engine.create_top_cursor(Some(PieceKind::O));
+        engine.hard_drop(); // it spawns above the matrix, where it can't be placed yet
+        let cells = engine.cursor_info().unwrap().0;
+
+        assert_eq!(engine.try_place_cursor(), Ok(true));
+        assert!(cells.iter().all(|&coord| engine.matrix[coord].is_some()));
+
+        // the same spot again is taken now
+        assert_eq!(engine.try_place_cursor(), Ok(false));
+    }
+
+    #[test]
+    fn placing_a_missing_cursor() {
+        let mut engine = Engine::new();
+        assert!(engine.cursor().is_none());
+
+        assert_eq!(engine.try_place_cursor(), Err(NoCursor));
+        assert!(engine.matrix.matrix.iter().all(Option::is_none));
+    }
+
+    // a tetrimino of the kind turned to `from` at the top and brought as far as it goes the given way
+    fn against(kind: PieceKind, from: Rotation, way: Option<MoveKind>) -> Engine {
+        let mut engine = Engine::new();
+        engine.create_top_cursor(Some(kind));
+        engine.rotate_and_adjust_cursor(from);
+        match way {
+            Some(way) => (0..Engine::MATRIX_WIDTH).for_each(|_| engine.move_cursor(way)),
+            None => {
+                engine.hard_drop();
+            }
+        }
+        engine
+    }
+
+    // turns the cursor to `to`, which doesn't fit in place, and returns the kick it took
+    fn kicked(engine: &mut Engine, to: Rotation) -> Offset {
+        let before = engine.cursor().unwrap();
+        assert!(engine.matrix.is_clipping(&Piece {
+            rotation: to,
+            ..before
+        }));
+
+        let kick = engine.rotate_and_adjust_cursor(to).unwrap();
+        let cursor = engine.cursor().unwrap();
+        assert_eq!(cursor.rotation, to);
+        assert_eq!(cursor.position, before.position + kick);
+        assert!(!engine.matrix.is_clipping(&cursor));
+        kick
+    }
+
+    #[test]
+    fn every_kind_kicks_off_the_walls_and_the_floor() {
+        for kind in PieceKind::ALL
+            .into_iter()
+            .filter(|&kind| kind != PieceKind::O)
+        {
+            // standing up against a wall, lying back down would stick out of it
+            let mut engine = against(kind, Rotation::E, Some(MoveKind::Left));
+            assert!(kicked(&mut engine, Rotation::N).x > 0, "{kind:?}");
+
+            let mut engine = against(kind, Rotation::W, Some(MoveKind::Right));
+            assert!(kicked(&mut engine, Rotation::N).x < 0, "{kind:?}");
+
+            // lying on the floor, standing up would go through it
+            let mut engine = against(kind, Rotation::N, None);
+            assert!(kicked(&mut engine, Rotation::E).y > 0, "{kind:?}");
+        }
+
+        // the o turns in place wherever it is
+        let mut engine = against(PieceKind::O, Rotation::N, Some(MoveKind::Left));
+        assert_eq!(
+            engine.rotate_and_adjust_cursor(Rotation::E),
+            Some(Offset::new(0, 0))
+        );
+    }
+
+    #[test]
+    fn vertical_i_kicks_off_the_right_wall_and_the_stack() {
+        let mut engine = Engine::new();
+        engine.create_top_cursor(Some(PieceKind::I));
+        engine.rotate_and_adjust_cursor(Rotation::E);
+        for _ in 0..Engine::MATRIX_WIDTH {
+            engine.move_cursor(MoveKind::Right);
+        }
+        let against_the_wall = engine.cursor().unwrap();
+        assert_eq!(against_the_wall.position.x, 7); // in the third column of its grid
+
+        // lying down in the lower middle row it goes one to the left
+        assert_eq!(
+            engine.rotate_and_adjust_cursor(Rotation::S),
+            Some(Offset::new(-1, 0))
+        );
+        let cursor = engine.cursor().unwrap();
+        assert_eq!(cursor.rotation, Rotation::S);
+        assert_eq!(
+            cursor.position,
+            against_the_wall.position + Offset::new(-1, 0)
+        );
+
+        // stood up in a well with a full column on either side, there's nowhere to lie down
+        let mut engine = Engine::new();
+        for y in 0..4 {
+            for x in (0..Engine::MATRIX_WIDTH).filter(|&x| x != 4) {
+                engine.matrix[Coordinate::new(x, y)] = Some(TetriminoColor::Red);
+            }
+        }
+        engine.create_top_cursor(Some(PieceKind::I));
+        engine.cursor = Some(Piece {
+            kind: PieceKind::I,
+            position: Offset::new(2, 0),
+            rotation: Rotation::E,
+        });
+        assert!(!engine.matrix.is_clipping(&engine.cursor().unwrap()));
+        let in_the_well = engine.cursor();
+        assert_eq!(engine.rotate_and_adjust_cursor(Rotation::S), None);
+        assert_eq!(engine.rotate_and_adjust_cursor(Rotation::N), None);
+        assert_eq!(engine.cursor(), in_the_well);
+    }
+
+    // an engine with the rows filled where they have an x, bottom row first
+    fn with_rows(rows: &[&str]) -> Engine {
+        let mut engine = Engine::new();
+        for (y, row) in rows.iter().enumerate() {
+            for (x, cell) in row.chars().enumerate() {
+                if cell == 'x' {
+                    engine.matrix[Coordinate::new(x, y)] = Some(TetriminoColor::Red);
+                }
+            }
+        }
+        engine
+    }
+
+    // a t brought down facing `from` in the middle column, turned to `to` at the bottom and locked;
+    // returns what the clear after it scored
+    fn spun(engine: &mut Engine, from: Rotation, to: Rotation) -> u32 {
+        engine.create_top_cursor(Some(PieceKind::T));
+        engine.rotate_and_adjust_cursor(from);
+        engine.hard_drop();
+        assert!(engine.rotate_and_adjust_cursor(to).is_some());
+        engine.lock_cursor();
+        engine.take_events();
+        engine.line_clear(engine.placed_tspin(), |_| ())
+    }
+
+    #[test]
+    fn a_t_spin_with_both_front_corners_blocked_is_a_full_one() {
+        let mut engine = with_rows(&["xxxx.xxxxx", "xxx...xxxx", "...x......"]);
+        let points = spun(&mut engine, Rotation::E, Rotation::S);
+
+        assert_eq!(
+            engine.take_events(),
+            [EngineEvent::LinesCleared {
+                lines: 2,
+                tspin: TSpinKind::Full
+            }]
+        );
+        assert_eq!(points, 1200);
+        assert_eq!(engine.stats.tspins, 1);
+    }
+
+    #[test]
+    fn a_t_spin_with_a_front_corner_open_is_a_mini() {
+        let mut engine = with_rows(&["xxxx.xxxxx", "xxx...xxxx", ".....x...."]);
+        let points = spun(&mut engine, Rotation::W, Rotation::N);
+
+        assert_eq!(
+            engine.take_events(),
+            [EngineEvent::LinesCleared {
+                lines: 1,
+                tspin: TSpinKind::Mini
+            }]
+        );
+        assert_eq!(points, 200);
+
+        // the same single without the spin scores a plain single
+        let mut engine = with_rows(&["xxxx.xxxxx", "xxx...xxxx"]);
+        engine.create_top_cursor(Some(PieceKind::T));
+        engine.hard_drop();
+        engine.lock_cursor();
+        assert_eq!(engine.placed_tspin(), TSpinKind::None);
+        assert_eq!(engine.line_clear(engine.placed_tspin(), |_| ()), 100);
+        assert_eq!(engine.stats.tspins, 0);
+    }
+
+    #[test]
+    fn held_in_tetrimino_starts_fresh() {
+        let mut engine = Engine::new();
+        engine.create_top_cursor(Some(PieceKind::T));
+        engine.try_hold();
+        assert!(!engine.can_hold());
+
+        // give the next tetrimino some state of its own
+        engine.create_top_cursor(Some(PieceKind::I));
+        assert!(engine.can_hold());
+        engine.rotate_cursor(Rotation::E);
+        engine.move_cursor(MoveKind::Left);
+        engine.hard_drop();
+        engine.advance(Duration::from_millis(100), false);
+        engine.reset_lock_delay();
+        assert!(engine.lock_elapsed.is_some() && engine.lock_resets == 1);
+
+        engine.try_hold();
+        let cursor = engine.cursor().unwrap();
+        assert_eq!(cursor.kind, PieceKind::T);
+        assert_eq!(cursor.rotation, Rotation::N);
+        assert_eq!(engine.gravity_elapsed, Duration::ZERO);
+        assert_eq!(engine.lock_elapsed, None);
+        assert_eq!(engine.lock_resets, 0);
+
+        // once per tetrimino, the next one from the queue can be held again
+        assert!(!engine.can_hold());
+        engine.try_hold();
+        assert_eq!(engine.cursor().unwrap(), cursor);
+
+        engine.hard_drop();
+        engine.lock_cursor();
+        assert!(!engine.hold_used); // can_hold could still be false, if it's another t
+    }
+
+    #[test]
+    fn hold_undo_until_the_held_in_tetrimino_moves() {
+        let mut engine = Engine::new_seeded(9);
+        engine.config.hold_undo = true;
+        engine.create_top_cursor(None);
+        engine.move_cursor(MoveKind::Left);
+        engine.rotate_and_adjust_cursor(Rotation::E);
+        engine.gravity_elapsed = Duration::from_millis(300);
+
+        let cursor = engine.cursor().unwrap();
+        let last_kick = engine.last_kick;
+        assert!(last_kick.is_some());
+        let upcoming = engine.upcoming().to_vec();
+        let queue = engine.queue_matrix.clone();
+        engine.take_events();
+
+        // held right away and taken back: as if the hold never happened
+        engine.try_hold();
+        assert_ne!(engine.cursor(), Some(cursor));
+        assert!(engine.can_undo_hold());
+        assert_eq!(
+            engine.take_events(),
+            [EngineEvent::Spawned {
+                kind: upcoming[0],
+                source: SpawnSource::Queue
+            }]
+        );
+        engine.try_hold();
+        assert_eq!(engine.cursor(), Some(cursor));
+        assert_eq!(engine.last_kick, last_kick);
+        // the tetrimino that comes back isn't a new one out of hold
+        assert_eq!(
+            engine.take_events(),
+            [EngineEvent::Spawned {
+                kind: cursor.kind,
+                source: SpawnSource::HoldUndo
+            }]
+        );
+        assert_eq!(engine.held(), None);
+        assert_eq!(engine.upcoming(), upcoming);
+        assert_eq!(engine.queue_matrix.matrix, queue.matrix);
+        assert_eq!(engine.gravity_elapsed, Duration::from_millis(300));
+        assert!(engine.can_hold());
+        assert!(!engine.can_undo_hold());
+
+        // the bag comes out the same way after the undo
+        let mut without_undo = Engine::new_seeded(9);
+        without_undo.create_top_cursor(None);
+        engine.try_hold();
+        without_undo.try_hold();
+        assert_eq!(engine.upcoming(), without_undo.upcoming());
+
+        // once the held in tetrimino moved, the hold stays
+        engine.move_cursor(MoveKind::Right);
+        assert!(!engine.can_undo_hold());
+        let moved = engine.cursor();
+        engine.try_hold();
+        assert_eq!(engine.cursor(), moved);
+        assert_eq!(engine.held(), Some(cursor.kind));
+
+        // and it's off by default
+        without_undo.hard_drop();
+        without_undo.lock_cursor();
+        without_undo.try_hold();
+        assert!(!without_undo.can_undo_hold());
+    }
+
+    #[test]
+    fn the_first_tetrimino_can_only_be_held_if_allowed() {
+        for first_hold in [true, false] {
+            let mut engine = Engine::new_seeded(2);
+            engine.config.first_hold = first_hold;
+            engine.create_top_cursor(None);
+            let first = engine.cursor().unwrap();
+
+            assert_eq!(engine.can_hold(), first_hold);
+            engine.try_hold();
+            assert_eq!(engine.cursor() == Some(first), !first_hold);
+            assert_eq!(engine.held(), first_hold.then_some(first.kind));
+
+            // from the second one on it makes no difference
+            engine.hard_drop();
+            engine.lock_cursor();
+            let second = engine.cursor().unwrap();
+            assert!(engine.can_hold());
+            engine.try_hold();
+            assert_ne!(engine.cursor(), Some(second));
+        }
+    }
+
+    #[test]
+    fn swap_hold_trades_with_the_one_on_hold() {
+        let mut engine = Engine::new();
+        engine.create_top_cursor(Some(PieceKind::T));
+
+        // nothing on hold yet, the next one comes from up next
+        let up_next = engine.upcoming()[0];
+        engine.try_hold();
+        assert_eq!(engine.held(), Some(PieceKind::T));
+        assert_eq!(engine.cursor().unwrap().kind, up_next);
+
+        engine.create_top_cursor(Some(PieceKind::I));
+        engine.try_hold();
+        assert_eq!(engine.cursor().unwrap().kind, PieceKind::T);
+        assert_eq!(engine.held(), Some(PieceKind::I));
+        assert_eq!(engine.hold, [PieceKind::I]);
+    }
+
+    #[test]
+    fn queued_holds_cycle_once_the_queue_is_full() {
+        let mut engine = Engine::new();
+        engine.config.hold_mode = HoldMode::Queue;
+
+        // the queue fills up first, every new cursor comes from up next
+        for kind in [PieceKind::T, PieceKind::I, PieceKind::O] {
+            engine.create_top_cursor(Some(kind));
+            let up_next = engine.upcoming()[0];
+            engine.try_hold();
+            assert_eq!(engine.cursor().unwrap().kind, up_next);
+        }
+        assert_eq!(engine.hold, [PieceKind::T, PieceKind::I, PieceKind::O]);
+        assert_eq!(engine.held(), Some(PieceKind::T));
+
+        // then the one held longest comes out and the cursor goes to the back
+        engine.create_top_cursor(Some(PieceKind::S));
+        engine.try_hold();
+        assert_eq!(engine.cursor().unwrap().kind, PieceKind::T);
+        assert_eq!(engine.hold, [PieceKind::I, PieceKind::O, PieceKind::S]);
+        assert!(!engine.can_hold());
+
+        // a tetrimino can't be swapped for itself, but it can queue up behind one of its kind
+        engine.create_top_cursor(Some(PieceKind::I));
+        assert!(!engine.can_hold());
+        engine.hold.truncate(2);
+        assert!(engine.can_hold());
+        engine.try_hold();
+        assert_eq!(engine.hold, [PieceKind::I, PieceKind::O, PieceKind::I]);
+    }
+
+    #[test]
+    fn garbage_holes_only_depend_on_the_garbage_seed() {
+        let holes = |piece_seed, garbage_seed| {
+            let mut engine = Engine::new_seeded(piece_seed);
+            engine.seed_garbage(garbage_seed);
+            engine.create_top_cursor(None);
+            for _ in 0..8 {
+                let hole = engine.garbage_hole();
+                engine.add_garbage(1, hole);
+            }
+            engine.matrix.to_string()
+        };
+
+        assert_eq!(holes(1, 7), holes(2, 7));
+        assert_ne!(holes(1, 7), holes(1, 8));
+
+        // and drawing holes doesn't change what tetriminos come
+        let (mut engine, mut without) = (Engine::new_seeded(1), Engine::new_seeded(1));
+        engine.garbage_hole();
+        engine.create_top_cursor(None);
+        without.create_top_cursor(None);
+        assert_eq!(engine.upcoming(), without.upcoming());
+    }
+
+    #[test]
+    fn clearing_the_board_keeps_the_score() {
+        let mut engine = Engine::new();
+        engine.create_top_cursor(None);
+        for _ in 0..5 {
+            engine.hard_drop();
+            engine.lock_cursor();
+        }
+        engine.score = 1200;
+        engine.level = 3;
+        let next = engine.next.clone();
+
+        engine.clear_board();
+
+        assert!(engine.matrix.matrix.iter().all(Option::is_none));
+        assert!(engine.cursor().is_none());
+        assert_eq!(engine.score, 1200);
+        assert_eq!(engine.level, 3);
+        assert_eq!(engine.next, next);
+        assert_eq!(engine.validate_invariants(), Ok(()));
+    }
+
+    #[test]
+    fn resetting_starts_over_with_a_fresh_bag_and_nothing_held() {
+        let mut engine = Engine::new_seeded(4);
+        engine.create_top_cursor(None);
+        engine.try_hold();
+        for _ in 0..3 {
+            engine.hard_drop();
+            engine.lock_cursor();
+        }
+        engine.score = 1200;
+        assert!(engine.held().is_some());
+
+        engine.reset();
+
+        assert_eq!(engine.held(), None);
+        assert!(engine.hold_matrix.matrix.iter().all(Option::is_none));
+        assert!(!engine.hold_used);
+        assert_eq!(engine.score, 0);
+        // all seven of one bag are up next, none of the old one is left over
+        let mut upcoming = engine.upcoming().to_vec();
+        upcoming.sort_by_key(|kind| PieceKind::ALL.iter().position(|k| k == kind));
+        assert_eq!(upcoming, PieceKind::ALL);
+        assert!(engine.remaining_bag().is_empty());
+        assert!(engine.up_next_matrix.matrix.iter().any(Option::is_some));
+        assert_eq!(engine.validate_invariants(), Ok(()));
+    }
+
+    #[test]
+    fn sonic_drop_grounds_without_locking() {
+        let mut engine = Engine::new();
+        engine.config.sonic_drop = true;
+        engine.create_top_cursor(Some(PieceKind::T));
+
+        assert!(engine.sonic_drop());
+
+        // on the surface but still the cursor, nothing has been placed into the matrix
+        assert!(engine.cursor_has_hit_bottom());
+        assert!(engine.cursor().is_some());
+        assert!(engine.matrix.matrix.iter().all(Option::is_none));
+        assert_eq!(engine.score, 0); // no hard drop points for it
+
+        // already grounded, so another one doesn't move it
+        assert!(!engine.sonic_drop());
+    }
+
+    #[test]
+    fn fall_progress_goes_from_zero_to_almost_a_row() {
+        let mut engine = Engine::new();
+        engine.create_top_cursor(Some(PieceKind::T));
+        let drop_time = engine.drop_time(false);
+        assert_eq!(engine.fall_progress(false), 0.0);
+
+        // right after a tick it starts over
+        engine.advance(drop_time, false);
+        assert_eq!(engine.fall_progress(false), 0.0);
+
+        engine.advance(drop_time / 2, false);
+        assert!((engine.fall_progress(false) - 0.5).abs() < 0.01);
+        engine.advance(drop_time / 2 - Duration::from_millis(1), false);
+        let progress = engine.fall_progress(false);
+        assert!(progress > 0.99 && progress < 1.0, "{progress}");
+
+        // soft dropping covers the same time in a twentieth of it
+        assert_eq!(engine.fall_progress(true), 1.0);
+
+        // on the floor there's nowhere to go
+        engine.hard_drop();
+        assert_eq!(engine.fall_progress(false), 0.0);
+    }
+
+    #[test]
+    fn advance_falls_per_drop_time_and_locks_after_delay() {
+        let mut engine = Engine::new();
+        engine.create_top_cursor(Some(PieceKind::O));
+        let drop_time = engine.drop_time(false);
+        let start = engine.cursor().unwrap().position;
+
+        // nothing happens before a whole drop time has passed
+        assert_eq!(engine.advance(drop_time / 2, false), Advance::Falling);
+        assert_eq!(engine.cursor().unwrap().position, start);
+
+        assert_eq!(engine.advance(drop_time / 2, false), Advance::Falling);
+        assert_eq!(engine.cursor().unwrap().position.y, start.y - 1);
+
+        // soft drop falls 20 times as fast
+        engine.advance(engine.drop_time(true) * 3, true);
+        assert_eq!(engine.cursor().unwrap().position.y, start.y - 4);
+
+        // fall the rest of the way, then the lock delay runs
+        let mut result = Advance::Falling;
+        while result == Advance::Falling {
+            result = engine.advance(drop_time, false);
+        }
+        assert_eq!(result, Advance::Grounded);
+        assert_eq!(
+            engine.advance(Engine::LOCK_DELAY / 2, false),
+            Advance::Grounded
+        );
+
+        // moving restarts the delay
+        engine.move_cursor(MoveKind::Left);
+        engine.reset_lock_delay();
+        assert_eq!(
+            engine.advance(Engine::LOCK_DELAY * 3 / 4, false),
+            Advance::Grounded
+        );
+        assert_eq!(
+            engine.advance(Engine::LOCK_DELAY / 4, false),
+            Advance::Locked
+        );
+
+        // placed, and the next tetrimino is falling from the top
+        assert_eq!(engine.matrix.matrix.iter().flatten().count(), 4);
+        assert_eq!(engine.lock_elapsed, None);
+        assert!(engine.cursor().unwrap().position.y > 10);
+    }
+
+    #[test]
+    fn lock_delay_resets_are_limited() {
+        let mut engine = Engine::new();
+        engine.create_top_cursor(Some(PieceKind::T));
+        engine.hard_drop();
+        assert_eq!(engine.advance(Duration::ZERO, false), Advance::Grounded);
+
+        for _ in 0..Engine::MAX_LOCK_RESETS {
+            engine.advance(Engine::LOCK_DELAY / 2, false);
+            engine.reset_lock_delay();
+        }
+        assert_eq!(engine.lock_resets, Engine::MAX_LOCK_RESETS);
+
+        // out of resets, so moving no longer buys any time
+        assert_eq!(
+            engine.advance(Engine::LOCK_DELAY / 2, false),
+            Advance::Grounded
+        );
+        engine.reset_lock_delay();
+        assert_eq!(
+            engine.advance(Engine::LOCK_DELAY / 2, false),
+            Advance::Locked
+        );
+    }
+
+    #[test]
+    fn is_line_full() {
+        let mut matrix = Matrix::<10, 20>::blank();
+        for x in 0..10 {
+            matrix[Coordinate::new(x, 0)] = Some(TetriminoColor::Red);
+        }
+        for x in 1..10 {
+            matrix[Coordinate::new(x, 5)] = Some(TetriminoColor::Blue);
+        }
+
+        assert!(matrix.is_line_full(0));
+        assert!(!matrix.is_line_full(5)); // one gap on the left
+        assert!(!matrix.is_line_full(19));
+        assert_eq!(matrix.full_lines(), [0]);
+    }
+
+    #[test]
+    fn a_row_filled_by_hand_can_be_cleared() {
+        let mut engine = Engine::new();
+        for x in 0..Engine::MATRIX_WIDTH {
+            engine.place_at(Coordinate::new(x, 0), TetriminoColor::Gray);
+        }
+        engine.place_at(Coordinate::new(2, 1), TetriminoColor::Red);
+
+        assert_eq!(engine.force_line_clear(), 1);
+        assert_eq!(engine.stats.singles, 1);
+        assert_eq!(
+            engine.matrix[Coordinate::new(2, 0)],
+            Some(TetriminoColor::Red)
+        );
+        assert_eq!(engine.matrix.matrix.iter().flatten().count(), 1);
+
+        // nothing full, nothing cleared
+        assert_eq!(engine.force_line_clear(), 0);
+    }
+
+    #[test]
+    fn full_lines_come_with_their_colors() {
+        let mut engine = Engine::new();
+        let colors = [
+            TetriminoColor::Red,
+            TetriminoColor::Cyan,
+            TetriminoColor::Gray,
+            TetriminoColor::Yellow,
+            TetriminoColor::Purple,
+            TetriminoColor::Orange,
+            TetriminoColor::Blue,
+            TetriminoColor::Green,
+            TetriminoColor::Red,
+            TetriminoColor::Cyan,
+        ];
+        for (x, &color) in colors.iter().enumerate() {
+            engine.matrix[Coordinate::new(x, 2)] = Some(color);
+        }
+        engine.matrix[Coordinate::new(0, 3)] = Some(TetriminoColor::Blue); // not full, left out
+
+        assert_eq!(engine.full_line_colors(), [(2, colors)]);
+
+        // only looking, the line is still there to be cleared
+        assert!(engine.matrix.is_line_full(2));
+        engine.line_clear(TSpinKind::None, |_| ());
+        assert!(engine.full_line_colors().is_empty());
+    }
+
+    #[test]
+    #[should_panic]
+    fn is_line_full_outside_matrix() {
+        Matrix::<10, 20>::blank().is_line_full(20);
+    }
+
+    #[test]
+    fn time_until_advance() {
+        let mut engine = Engine::new();
+        assert_eq!(engine.time_until_advance(false), None);
+
+        engine.create_top_cursor(Some(PieceKind::T));
+        let drop_time = engine.drop_time(false);
+        assert_eq!(engine.time_until_advance(false), Some(drop_time));
+
+        engine.advance(drop_time / 4, false);
+        assert_eq!(
+            engine.time_until_advance(false),
+            Some(drop_time - drop_time / 4)
+        );
+
+        // grounded, so it's the lock delay that's counting down
+        engine.hard_drop();
+        engine.advance(Duration::from_millis(100), false);
+        assert_eq!(
+            engine.time_until_advance(false),
+            Some(Engine::LOCK_DELAY - Duration::from_millis(100))
+        );
+    }
+
+    #[test]
+    fn danger_level_follows_stack_height() {
+        let mut engine = Engine::new();
+        engine.config.danger_heights = [5, 10, 15];
+
+        for (height, danger_level) in [
+            (0, 0),
+            (4, 0),
+            (5, 1),
+            (9, 1),
+            (10, 2),
+            (14, 2),
+            (15, 3),
+            (20, 3),
+        ] {
+            engine.matrix.clear();
+            for y in 0..height {
+                engine.matrix[Coordinate::new(3, y)] = Some(TetriminoColor::Green);
+            }
+
+            assert_eq!(engine.danger_level(), danger_level, "height {height}");
+        }
+    }
+
+    #[test]
+    fn overstacked_needs_both_holes_and_height() {
+        let mut engine = Engine::new();
+        assert!(!engine.is_overstacked());
+
+        // a clean stack with a well, as tall as it gets
+        for y in 0..18 {
+            for x in 0..9 {
+                engine.matrix[Coordinate::new(x, y)] = Some(TetriminoColor::Gray);
+            }
+        }
+        assert_eq!(engine.matrix.count_holes(), 0);
+        assert!(!engine.is_overstacked());
+
+        // every row with a gap somewhere else, covered by the rows above
+        engine.matrix.clear();
+        for y in 0..14 {
+            for x in (0..10).filter(|x| x != &(y * 3 % 10)) {
+                engine.matrix[Coordinate::new(x, y)] = Some(TetriminoColor::Gray);
+            }
+        }
+        assert!(engine.matrix.count_holes() >= Engine::OVERSTACKED_HOLES);
+        assert!(engine.is_overstacked());
+
+        // just as holed but low, there's still time to dig
+        engine.matrix.clear_lines(&(8..14).collect::<Vec<_>>());
+        assert!(engine.matrix.count_holes() >= Engine::OVERSTACKED_HOLES);
+        assert!(!engine.is_overstacked());
+    }
+
+    #[test]
+    fn tetris_streak() {
+        let mut engine = Engine::new();
+        let clear = |engine: &mut Engine, lines: usize| {
+            for y in 0..lines {
+                for x in 0..Engine::MATRIX_WIDTH {
+                    engine.matrix[Coordinate::new(x, y)] = Some(TetriminoColor::Cyan);
+                }
+            }
+            engine.line_clear(TSpinKind::None, |_| ());
+        };
+
+        for _ in 0..3 {
+            clear(&mut engine, 4);
+        }
+        assert_eq!(engine.stats().tetris_streak, 3);
+
+        // a lock without a line clear doesn't count either way
+        clear(&mut engine, 0);
+        assert_eq!(engine.stats().tetris_streak, 3);
+
+        clear(&mut engine, 1);
+        assert_eq!(engine.stats().tetris_streak, 0);
+        assert_eq!(engine.stats().longest_tetris_streak, 3);
+
+        clear(&mut engine, 4);
+        assert_eq!(engine.stats().tetris_streak, 1);
+        assert_eq!(engine.stats().longest_tetris_streak, 3);
+    }
+
+    #[test]
+    fn placements_are_counted_by_kind() {
+        let mut engine = Engine::new();
+        for kind in [PieceKind::L, PieceKind::I, PieceKind::L] {
+            engine.create_top_cursor(Some(kind));
+            engine.hard_drop();
+            assert!(engine.lock_cursor());
+        }
+
+        let stats = engine.stats();
+        assert_eq!(stats.placed(PieceKind::L), 2);
+        assert_eq!(stats.placed(PieceKind::I), 1);
+        for kind in [
+            PieceKind::O,
+            PieceKind::T,
+            PieceKind::J,
+            PieceKind::S,
+            PieceKind::Z,
+        ] {
+            assert_eq!(stats.placed(kind), 0);
+        }
+    }
 }
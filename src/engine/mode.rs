@@ -0,0 +1,99 @@
+// what a game is played for: a marathon goes on until it's lost, a sprint is over once enough lines are
+// cleared and an ultra once its time is up. the time is game time, see GameController::play_time
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Serialize, Deserialize)]
+pub enum GameMode {
+    #[default]
+    Marathon,
+    Sprint {
+        lines_goal: u32,
+    },
+    Ultra {
+        duration: Duration,
+    },
+}
+
+impl GameMode {
+    pub const SPRINT: Self = Self::Sprint { lines_goal: 40 };
+    pub const ULTRA: Self = Self::Ultra {
+        duration: Duration::from_secs(2 * 60),
+    };
+    pub const ALL: [Self; 3] = [Self::Marathon, Self::SPRINT, Self::ULTRA]; // in the order they're picked from
+
+    // as it goes in the stats and on the command line
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Marathon => "marathon",
+            Self::Sprint { .. } => "sprint",
+            Self::Ultra { .. } => "ultra",
+        }
+    }
+
+    // the standard sprint and ultra, by name
+    pub fn parse(value: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|mode| mode.name() == value)
+    }
+
+    // the one after it in ALL, around to the first; for picking one with a single key
+    pub fn next(self) -> Self {
+        let index = Self::ALL.iter().position(|&mode| mode == self).unwrap_or(0);
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+
+    pub fn prev(self) -> Self {
+        let index = Self::ALL.iter().position(|&mode| mode == self).unwrap_or(0);
+        Self::ALL[(index + Self::ALL.len() - 1) % Self::ALL.len()]
+    }
+
+    // lines still to clear in a sprint, None for the other modes
+    pub fn lines_left(self, lines: u32) -> Option<u32> {
+        match self {
+            Self::Sprint { lines_goal } => Some(lines_goal.saturating_sub(lines)),
+            _ => None,
+        }
+    }
+
+    // time still left of an ultra, None for the other modes
+    pub fn time_left(self, elapsed: Duration) -> Option<Duration> {
+        match self {
+            Self::Ultra { duration } => Some(duration.saturating_sub(elapsed)),
+            _ => None,
+        }
+    }
+
+    // the goal is reached, the game is over without having been lost; never for a marathon
+    pub fn is_complete(self, lines: u32, elapsed: Duration) -> bool {
+        self.lines_left(lines) == Some(0) || self.time_left(elapsed) == Some(Duration::ZERO)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn sprints_end_on_lines_and_ultras_on_time() {
+        let minute = Duration::from_secs(60);
+        assert!(!GameMode::Marathon.is_complete(1000, minute * 1000));
+
+        assert_eq!(GameMode::SPRINT.lines_left(38), Some(2));
+        assert!(!GameMode::SPRINT.is_complete(39, minute * 1000));
+        assert!(GameMode::SPRINT.is_complete(41, Duration::ZERO)); // a tetris can go past the goal
+
+        assert_eq!(GameMode::ULTRA.time_left(minute / 2), Some(minute * 3 / 2));
+        assert!(!GameMode::ULTRA.is_complete(1000, minute));
+        assert!(GameMode::ULTRA.is_complete(0, minute * 3));
+    }
+
+    #[test]
+    fn modes_go_round_by_name() {
+        for mode in GameMode::ALL {
+            assert_eq!(GameMode::parse(mode.name()), Some(mode));
+            assert_eq!(mode.next().prev(), mode);
+        }
+        assert_eq!(GameMode::ULTRA.next(), GameMode::Marathon);
+        assert_eq!(GameMode::parse("zen"), None);
+    }
+}
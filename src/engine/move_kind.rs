@@ -1,6 +1,8 @@
+use serde::{Deserialize, Serialize};
+
 use super::Offset;
 
-#[derive(Clone, Copy, PartialEq, Debug)]
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
 pub enum MoveKind {
     Left,
     Right,
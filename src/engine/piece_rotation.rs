@@ -1,3 +1,5 @@
+use super::kicks;
+use super::piece_kind::PieceKind;
 use super::Offset;
 use cgmath::Zero;
 
@@ -11,6 +13,8 @@ pub enum Rotation {
 }
 
 impl Rotation {
+    pub const ALL: [Self; 4] = [Self::N, Self::E, Self::S, Self::W];
+
     pub fn intrinsic_offset(&self) -> Offset {
         // this we need to then multiply by grid size
         match self {
@@ -21,6 +25,16 @@ impl Rotation {
         }
     }
 
+    // the offsets to try, in order, for turning a tetrimino of the kind from one rotation to the other, see
+    // kicks; the first is always where it already is. the o doesn't need any, and neither does anything
+    // other than a quarter turn, those are only tried in place
+    pub fn wall_kick_offsets(kind: PieceKind, from: Self, to: Self) -> &'static [Offset] {
+        match (kicks::table(kind), kicks::index(from, to)) {
+            (Some(table), Some(index)) => &table[index],
+            _ => &kicks::NONE,
+        }
+    }
+
     pub fn next_rotation(&self) -> Self {
         match self {
             Self::N => Self::E,
@@ -29,6 +43,16 @@ impl Rotation {
             Self::W => Self::N,
         }
     }
+
+    // counterclockwise, the other way around from next_rotation
+    pub fn prev_rotation(&self) -> Self {
+        match self {
+            Self::N => Self::W,
+            Self::W => Self::S,
+            Self::S => Self::E,
+            Self::E => Self::N,
+        }
+    }
 }
 
 // multiply vector by a rotation -> for rotating relative coordinates of a piece
@@ -44,3 +68,16 @@ impl std::ops::Mul<Rotation> for Offset {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn prev_rotation_turns_counterclockwise() {
+        assert_eq!(Rotation::N.prev_rotation(), Rotation::W);
+        for rotation in Rotation::ALL {
+            assert_eq!(rotation.next_rotation().prev_rotation(), rotation);
+        }
+    }
+}
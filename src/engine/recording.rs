@@ -0,0 +1,231 @@
+// the headless engine's own replays: every tick and input that went into it, in order, and the seed its
+// tetriminos came from. playing those into a new engine with the same seed goes exactly the same way, so
+// a bot's or a test's game can be kept and looked at again. crate::replay::Replay is the one for games
+// played through GameController, timed in steps instead of ticks; both are saved through crate::save
+use serde::{Deserialize, Serialize};
+
+use super::config::Config;
+use super::headless::{EngineInput, TickResult};
+use super::Engine;
+
+// frame is the number of ticks before the event, inputs in between ticks share the frame of the next one
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub enum HeadlessEvent {
+    Input { frame: u32, input: EngineInput },
+    Tick { frame: u32 },         // the cursor fell a row, or a new one came in
+    LockdownTick { frame: u32 }, // the cursor couldn't fall and locked
+}
+
+impl HeadlessEvent {
+    pub fn frame(&self) -> u32 {
+        match *self {
+            Self::Input { frame, .. } | Self::Tick { frame } | Self::LockdownTick { frame } => {
+                frame
+            }
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct HeadlessReplay {
+    pub seed: u64,
+    pub config: Config, // as it was when the recording started
+    pub events: Vec<HeadlessEvent>,
+}
+
+impl HeadlessReplay {
+    // how many ticks there are in it, the length of it when it's watched
+    pub fn ticks(&self) -> u32 {
+        self.events
+            .iter()
+            .filter(|event| !matches!(event, HeadlessEvent::Input { .. }))
+            .count() as u32
+    }
+}
+
+// a headless replay played into a new engine with its seed, a tick at a time
+#[derive(Clone)]
+pub struct HeadlessPlayback {
+    pub engine: Engine,
+    pub ticks: u32,    // ticks played so far
+    next_event: usize, // index of the first event that hasn't been played yet
+}
+
+impl HeadlessPlayback {
+    pub fn new(replay: &HeadlessReplay) -> Self {
+        let mut engine = Engine::new_seeded(replay.seed);
+        engine.config = replay.config;
+        Self {
+            engine,
+            ticks: 0,
+            next_event: 0,
+        }
+    }
+
+    pub fn is_finished(&self, replay: &HeadlessReplay) -> bool {
+        self.next_event >= replay.events.len()
+    }
+
+    // the next event, and what it led to
+    fn play(&mut self, replay: &HeadlessReplay) -> Option<(HeadlessEvent, TickResult)> {
+        let event = *replay.events.get(self.next_event)?;
+        self.next_event += 1;
+        let result = match event {
+            HeadlessEvent::Input { input, .. } => self.engine.apply_input(input),
+            HeadlessEvent::Tick { .. } | HeadlessEvent::LockdownTick { .. } => {
+                self.ticks += 1;
+                self.engine.tick()
+            }
+        };
+        Some((event, result))
+    }
+
+    // run the next tick, with the inputs recorded right before it
+    pub fn step(&mut self, replay: &HeadlessReplay) {
+        while let Some((event, _)) = self.play(replay) {
+            if !matches!(event, HeadlessEvent::Input { .. }) {
+                break;
+            }
+        }
+    }
+}
+
+// a recording in progress, see Engine::start_recording
+#[derive(Clone, Debug)]
+pub(super) struct Recorder {
+    replay: HeadlessReplay,
+    frame: u32,
+}
+
+impl Recorder {
+    pub(super) fn input(&mut self, input: EngineInput) {
+        self.replay.events.push(HeadlessEvent::Input {
+            frame: self.frame,
+            input,
+        });
+    }
+
+    pub(super) fn tick(&mut self, locked: bool) {
+        let frame = self.frame;
+        self.replay.events.push(if locked {
+            HeadlessEvent::LockdownTick { frame }
+        } else {
+            HeadlessEvent::Tick { frame }
+        });
+        self.frame += 1;
+    }
+}
+
+impl Engine {
+    // every tick and input from here on goes into a replay, until finish_recording hands it over; the
+    // replay starts from a new engine with the same seed, so it only plays back the same when the
+    // recording starts before the first tick
+    pub fn start_recording(&mut self) {
+        self.recording = Some(Recorder {
+            replay: HeadlessReplay {
+                seed: self.seed,
+                config: self.config,
+                events: Vec::new(),
+            },
+            frame: 0,
+        });
+    }
+
+    // an empty replay if nothing was being recorded
+    pub fn finish_recording(&mut self) -> HeadlessReplay {
+        match self.recording.take() {
+            Some(recorder) => recorder.replay,
+            None => HeadlessReplay {
+                seed: self.seed,
+                config: self.config,
+                events: Vec::new(),
+            },
+        }
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.recording.is_some()
+    }
+
+    // what each event of the replay led to, one at a time as it's played into a new engine
+    pub fn play_replay(replay: &HeadlessReplay) -> impl Iterator<Item = TickResult> + '_ {
+        let mut playback = HeadlessPlayback::new(replay);
+        std::iter::from_fn(move || playback.play(replay).map(|(_, result)| result))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::engine::move_kind::MoveKind;
+
+    #[test]
+    fn a_recorded_game_plays_back_the_same() {
+        let mut engine = Engine::new_seeded(11);
+        engine.config.leveling = false;
+        engine.start_recording();
+
+        // falling and locking on their own mostly, moving and turning on the way, hard dropped every so
+        // often, until the stack reaches the top
+        let inputs = [
+            EngineInput::Move(MoveKind::Left),
+            EngineInput::Rotate,
+            EngineInput::Hold,
+            EngineInput::Move(MoveKind::Right),
+            EngineInput::SoftDrop,
+            EngineInput::RotateCounterclockwise,
+        ];
+        let mut results = Vec::new();
+        for turn in 0.. {
+            let result = if turn % 30 == 29 {
+                engine.apply_input(EngineInput::HardDrop)
+            } else {
+                results.push(engine.apply_input(inputs[turn % inputs.len()]));
+                engine.tick()
+            };
+            results.push(result.clone());
+            if result == TickResult::GameOver {
+                break;
+            }
+            assert!(turn < 10_000);
+        }
+
+        let replay = engine.finish_recording();
+        assert!(!engine.is_recording());
+        assert!(replay
+            .events
+            .iter()
+            .any(|event| matches!(event, HeadlessEvent::LockdownTick { .. })));
+        assert!(replay
+            .events
+            .windows(2)
+            .all(|pair| pair[0].frame() <= pair[1].frame()));
+
+        assert_eq!(Engine::play_replay(&replay).collect::<Vec<_>>(), results);
+
+        // and a tick at a time, the way it's watched
+        let mut playback = HeadlessPlayback::new(&replay);
+        while !playback.is_finished(&replay) {
+            playback.step(&replay);
+        }
+        assert_eq!(playback.ticks, replay.ticks());
+        assert_eq!(playback.engine.matrix.matrix, engine.matrix.matrix);
+        assert_eq!(playback.engine.score, engine.score);
+
+        // and the same once it's been through json
+        let json = serde_json::to_string(&replay).unwrap();
+        assert_eq!(
+            serde_json::from_str::<HeadlessReplay>(&json).unwrap(),
+            replay
+        );
+    }
+
+    #[test]
+    fn finishing_without_recording_is_an_empty_replay() {
+        let mut engine = Engine::new_seeded(7);
+        engine.tick();
+        let replay = engine.finish_recording();
+        assert_eq!(replay.seed, 7);
+        assert!(replay.events.is_empty());
+    }
+}
@@ -0,0 +1,74 @@
+// the guideline's points for a line clear, by how many lines it took and whether a t spun into place;
+// the level it was made at multiplies them. on top of those come the bonuses for clears in a row, see
+// Engine::line_clear
+use super::tspin::TSpinKind;
+
+pub fn score_lines(count: usize, tspin: TSpinKind, level: u8) -> u32 {
+    let base = match (tspin, count) {
+        (TSpinKind::None, 0) => 0,
+        (TSpinKind::None, 1) => 100,
+        (TSpinKind::None, 2) => 300,
+        (TSpinKind::None, 3) => 500,
+        (TSpinKind::None, _) => 800,
+        (TSpinKind::Mini, 0) => 100,
+        (TSpinKind::Mini, 1) => 200,
+        (TSpinKind::Mini, _) => 400, // a mini can't take more than two
+        (TSpinKind::Full, 0) => 400,
+        (TSpinKind::Full, 1) => 800,
+        (TSpinKind::Full, 2) => 1200,
+        (TSpinKind::Full, _) => 1600, // a t can't take more than three
+    };
+
+    base * level as u32
+}
+
+// tetrises and t-spins that clear lines; one after the other, each after the first is worth half again
+pub fn is_difficult(count: usize, tspin: TSpinKind) -> bool {
+    count > 0 && (count >= 4 || tspin != TSpinKind::None)
+}
+
+// what a clear adds for the combo it's at: nothing for the first clear, 50 a level for each one after
+pub fn combo_bonus(combo: i32, level: u8) -> u32 {
+    50 * combo.max(0) as u32 * level as u32
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn the_guideline_table_times_the_level() {
+        let table = [
+            (TSpinKind::None, [0, 100, 300, 500, 800]),
+            (TSpinKind::Mini, [100, 200, 400, 400, 400]),
+            (TSpinKind::Full, [400, 800, 1200, 1600, 1600]),
+        ];
+
+        for (tspin, points) in table {
+            for (count, points) in points.into_iter().enumerate() {
+                assert_eq!(score_lines(count, tspin, 1), points, "{tspin:?} {count}");
+                assert_eq!(
+                    score_lines(count, tspin, 7),
+                    7 * points,
+                    "{tspin:?} {count}"
+                );
+            }
+        }
+
+        // the level never scores anything on its own
+        assert_eq!(score_lines(0, TSpinKind::None, 20), 0);
+    }
+
+    #[test]
+    fn only_tetrises_and_t_spins_that_clear_are_difficult() {
+        assert!(is_difficult(4, TSpinKind::None));
+        assert!(is_difficult(1, TSpinKind::Mini));
+        assert!(is_difficult(2, TSpinKind::Full));
+        assert!(!is_difficult(3, TSpinKind::None));
+        assert!(!is_difficult(0, TSpinKind::Full));
+
+        assert_eq!(combo_bonus(-1, 3), 0);
+        assert_eq!(combo_bonus(0, 3), 0);
+        assert_eq!(combo_bonus(2, 3), 300);
+    }
+}
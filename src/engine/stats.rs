@@ -0,0 +1,49 @@
+use super::piece_kind::PieceKind;
+
+// running numbers about the current game, for things like achievements; they start over on a reset
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub struct Stats {
+    pub tetris_streak: u32, // tetrises in a row so far, any other line clear ends the streak
+    pub longest_tetris_streak: u32, // the longest streak of the game
+    pub singles: u32,       // line clears by how many lines they took
+    pub doubles: u32,
+    pub triples: u32,
+    pub tetrises: u32,
+    pub tspins: u32,                         // minis or not, with or without lines
+    pub placed: [u32; PieceKind::ALL.len()], // tetriminos placed on the matrix, by kind in the order of ALL
+}
+
+impl Stats {
+    pub fn record_placement(&mut self, kind: PieceKind) {
+        self.placed[kind as usize] += 1;
+    }
+
+    pub fn placed(&self, kind: PieceKind) -> u32 {
+        self.placed[kind as usize]
+    }
+
+    // lines cleared in the whole game, unlike Engine::lines_reached which starts over every level
+    pub fn lines(&self) -> u32 {
+        self.singles + self.doubles * 2 + self.triples * 3 + self.tetrises * 4
+    }
+
+    // count a line clear of the given number of lines; locks that clear nothing don't affect streaks
+    pub fn record_clear(&mut self, lines: usize) {
+        match lines {
+            1 => self.singles += 1,
+            2 => self.doubles += 1,
+            3 => self.triples += 1,
+            4 => self.tetrises += 1,
+            _ => {}
+        }
+
+        match lines {
+            0 => {}
+            4 => {
+                self.tetris_streak += 1;
+                self.longest_tetris_streak = self.longest_tetris_streak.max(self.tetris_streak);
+            }
+            _ => self.tetris_streak = 0,
+        }
+    }
+}
@@ -0,0 +1,54 @@
+// t-spins by the guideline's three corner rule: a t that got where it is by rotating, with at least three
+// of the four corners around its center blocked. it's a full one when both corners on the side it points
+// to are blocked, or when it got there with the last of the wall kicks; otherwise it's a mini
+use super::piece::Piece;
+use super::piece_kind::PieceKind;
+use super::piece_rotation::Rotation;
+use super::Offset;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum TSpinKind {
+    #[default]
+    None,
+    Mini,
+    Full,
+}
+
+const LAST_KICK: usize = 4; // see kicks::kicks
+
+// the corners of the 3x3 grid of a t, in its grid
+const CORNERS: [Offset; 4] = [
+    Offset::new(0, 0),
+    Offset::new(2, 0),
+    Offset::new(0, 2),
+    Offset::new(2, 2),
+];
+
+// the two corners on the side a t in the rotation points to
+fn front_corners(rotation: Rotation) -> [Offset; 2] {
+    match rotation {
+        Rotation::N => [CORNERS[2], CORNERS[3]],
+        Rotation::E => [CORNERS[1], CORNERS[3]],
+        Rotation::S => [CORNERS[0], CORNERS[1]],
+        Rotation::W => [CORNERS[0], CORNERS[2]],
+    }
+}
+
+// the t-spin a tetrimino locking where it is makes; kick is the wall kick its last rotation used, None if it
+// moved or fell since (or never rotated). blocked tells walls, the floor and filled cells apart from room
+pub fn detect(piece: &Piece, kick: Option<usize>, blocked: impl Fn(Offset) -> bool) -> TSpinKind {
+    let Some(kick) = kick.filter(|_| piece.kind == PieceKind::T) else {
+        return TSpinKind::None;
+    };
+
+    let is_blocked = |corner: &Offset| blocked(piece.position + *corner);
+    if CORNERS.iter().filter(|corner| is_blocked(corner)).count() < 3 {
+        return TSpinKind::None;
+    }
+
+    if kick == LAST_KICK || front_corners(piece.rotation).iter().all(is_blocked) {
+        TSpinKind::Full
+    } else {
+        TSpinKind::Mini
+    }
+}
@@ -0,0 +1,297 @@
+// c abi for driving a game from other languages, e.g. for research. include/tetris.h declares all of
+// it for c callers. a game is an opaque pointer from tetris_engine_new that goes back to
+// tetris_engine_free; nothing here unwinds into the caller, a panic comes back as TETRIS_ERR_PANIC
+use crate::controller::{GameController, GameInput};
+use crate::engine::{color::TetriminoColor, config::Config, Engine};
+use crate::timestep::FixedTimestep;
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+use std::time::Duration;
+
+pub const TETRIS_OK: i32 = 0;
+pub const TETRIS_ERR_NULL: i32 = -1; // a null game or buffer
+pub const TETRIS_ERR_ACTION: i32 = -2; // not one of the action numbers
+pub const TETRIS_ERR_PANIC: i32 = -3; // a bug on this side, the game shouldn't be used any further
+
+pub const TETRIS_BOARD_SIZE: usize = Engine::MATRIX_WIDTH * Engine::MATRIX_HEIGHT;
+
+// a game as the c side holds it; time comes in as milliseconds and goes out in fixed steps
+pub struct FfiGame {
+    game: GameController,
+    step: Duration,
+    accumulated: Duration,
+}
+
+// the inputs by number, in the order of TETRIS_ACTION_* in the header
+fn action(code: i32) -> Option<GameInput> {
+    match code {
+        0 => Some(GameInput::MoveLeft),
+        1 => Some(GameInput::MoveRight),
+        2 => Some(GameInput::Rotate),
+        3 => Some(GameInput::SoftDropStart),
+        4 => Some(GameInput::SoftDropStop),
+        5 => Some(GameInput::HardDrop),
+        6 => Some(GameInput::Hold),
+        7 => Some(GameInput::Pause),
+        8 => Some(GameInput::Resume),
+        _ => None,
+    }
+}
+
+fn guarded<T>(on_panic: T, call: impl FnOnce() -> T) -> T {
+    panic::catch_unwind(AssertUnwindSafe(call)).unwrap_or(on_panic)
+}
+
+// the game behind a pointer from tetris_engine_new, None for a null one
+//
+// safety: the pointer is null or came from tetris_engine_new and hasn't been freed
+unsafe fn game_at<'a>(game: *mut FfiGame) -> Option<&'a mut FfiGame> {
+    game.as_mut()
+}
+
+#[no_mangle]
+pub extern "C" fn tetris_engine_new(seed: u64) -> *mut FfiGame {
+    guarded(ptr::null_mut(), || {
+        let step = FixedTimestep::default().step;
+        Box::into_raw(Box::new(FfiGame {
+            game: GameController::new(seed, Config::default(), step),
+            step,
+            accumulated: Duration::ZERO,
+        }))
+    })
+}
+
+/// # Safety
+/// `game` is null or a live pointer from `tetris_engine_new`
+#[no_mangle]
+pub unsafe extern "C" fn tetris_engine_apply(game: *mut FfiGame, action_code: i32) -> i32 {
+    guarded(TETRIS_ERR_PANIC, || {
+        let Some(ffi) = game_at(game) else {
+            return TETRIS_ERR_NULL;
+        };
+        let Some(input) = action(action_code) else {
+            return TETRIS_ERR_ACTION;
+        };
+
+        ffi.game.apply(input);
+        TETRIS_OK
+    })
+}
+
+/// # Safety
+/// `game` is null or a live pointer from `tetris_engine_new`
+#[no_mangle]
+pub unsafe extern "C" fn tetris_engine_advance(game: *mut FfiGame, millis: u32) -> i32 {
+    guarded(TETRIS_ERR_PANIC, || {
+        let Some(ffi) = game_at(game) else {
+            return TETRIS_ERR_NULL;
+        };
+
+        // unlike a frame, a call can cover any amount of time, none of it is dropped
+        ffi.accumulated += Duration::from_millis(millis as u64);
+        while ffi.accumulated >= ffi.step {
+            ffi.accumulated -= ffi.step;
+            ffi.game.update(ffi.step);
+        }
+        TETRIS_OK
+    })
+}
+
+/// # Safety
+/// `game` is null or a live pointer from `tetris_engine_new`, `out` is null or points to
+/// `TETRIS_BOARD_SIZE` writable bytes
+#[no_mangle]
+pub unsafe extern "C" fn tetris_engine_board(game: *mut FfiGame, out: *mut u8) -> i32 {
+    guarded(TETRIS_ERR_PANIC, || {
+        let Some(ffi) = game_at(game) else {
+            return TETRIS_ERR_NULL;
+        };
+        if out.is_null() {
+            return TETRIS_ERR_NULL;
+        }
+
+        let out = std::slice::from_raw_parts_mut(out, TETRIS_BOARD_SIZE);
+        let board = ffi.game.engine.render_state();
+        for (byte, &cell) in out.iter_mut().zip(board.matrix.iter()) {
+            *byte = TetriminoColor::cell_code(cell);
+        }
+        TETRIS_OK
+    })
+}
+
+/// # Safety
+/// `game` is null or a live pointer from `tetris_engine_new`
+#[no_mangle]
+pub unsafe extern "C" fn tetris_engine_score(game: *mut FfiGame) -> i64 {
+    guarded(TETRIS_ERR_PANIC as i64, || match game_at(game) {
+        Some(ffi) => ffi.game.engine.score as i64,
+        None => TETRIS_ERR_NULL as i64,
+    })
+}
+
+/// # Safety
+/// `game` is null or a live pointer from `tetris_engine_new`
+#[no_mangle]
+pub unsafe extern "C" fn tetris_engine_level(game: *mut FfiGame) -> i32 {
+    guarded(TETRIS_ERR_PANIC, || match game_at(game) {
+        Some(ffi) => ffi.game.engine.level as i32,
+        None => TETRIS_ERR_NULL,
+    })
+}
+
+/// # Safety
+/// `game` is null or a live pointer from `tetris_engine_new`
+#[no_mangle]
+pub unsafe extern "C" fn tetris_engine_game_over(game: *mut FfiGame) -> i32 {
+    guarded(TETRIS_ERR_PANIC, || match game_at(game) {
+        Some(ffi) => ffi.game.is_game_over() as i32,
+        None => TETRIS_ERR_NULL,
+    })
+}
+
+/// # Safety
+/// `game` is null or a pointer from `tetris_engine_new` that isn't used again afterwards
+#[no_mangle]
+pub unsafe extern "C" fn tetris_engine_free(game: *mut FfiGame) {
+    guarded((), || {
+        if !game.is_null() {
+            drop(Box::from_raw(game));
+        }
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const HEADER: &str = include_str!("../include/tetris.h");
+
+    fn board(game: *mut FfiGame) -> [u8; TETRIS_BOARD_SIZE] {
+        let mut out = [0xff; TETRIS_BOARD_SIZE];
+        assert_eq!(
+            unsafe { tetris_engine_board(game, out.as_mut_ptr()) },
+            TETRIS_OK
+        );
+        out
+    }
+
+    #[test]
+    fn a_game_through_the_c_abi() {
+        let game = tetris_engine_new(7);
+        assert!(!game.is_null());
+
+        unsafe {
+            // the tetrimino spawns above the visible part of the matrix, nothing is on it yet
+            assert!(board(game).iter().all(|&cell| cell == 0));
+            assert_eq!(tetris_engine_level(game), 1);
+            assert_eq!(tetris_engine_game_over(game), 0);
+
+            assert_eq!(tetris_engine_apply(game, 5), TETRIS_OK);
+            assert_eq!(tetris_engine_advance(game, 100), TETRIS_OK);
+            let locked = board(game);
+            assert!(locked.iter().all(|&cell| cell <= 8));
+            assert!(locked.iter().filter(|&&cell| cell != 0).count() >= 4);
+
+            assert_eq!(tetris_engine_apply(game, 99), TETRIS_ERR_ACTION);
+
+            // hard drops until the stack reaches the top
+            for _ in 0..100 {
+                tetris_engine_apply(game, 5);
+                tetris_engine_advance(game, 10);
+            }
+            assert_eq!(tetris_engine_game_over(game), 1);
+            assert!(tetris_engine_score(game) >= 0);
+
+            tetris_engine_free(game);
+        }
+    }
+
+    #[test]
+    fn time_adds_up_across_calls() {
+        let (one_go, in_bits) = (tetris_engine_new(3), tetris_engine_new(3));
+
+        unsafe {
+            tetris_engine_advance(one_go, 5000);
+            for _ in 0..1000 {
+                tetris_engine_advance(in_bits, 5);
+            }
+            assert_eq!(board(one_go), board(in_bits));
+            assert!(board(one_go).iter().any(|&cell| cell != 0));
+
+            tetris_engine_free(one_go);
+            tetris_engine_free(in_bits);
+        }
+    }
+
+    #[test]
+    fn null_is_refused() {
+        let null = ptr::null_mut();
+        let mut out = [0; TETRIS_BOARD_SIZE];
+
+        unsafe {
+            assert_eq!(tetris_engine_apply(null, 0), TETRIS_ERR_NULL);
+            assert_eq!(tetris_engine_advance(null, 16), TETRIS_ERR_NULL);
+            assert_eq!(tetris_engine_board(null, out.as_mut_ptr()), TETRIS_ERR_NULL);
+            assert_eq!(tetris_engine_score(null), TETRIS_ERR_NULL as i64);
+            assert_eq!(tetris_engine_level(null), TETRIS_ERR_NULL);
+            assert_eq!(tetris_engine_game_over(null), TETRIS_ERR_NULL);
+            tetris_engine_free(null);
+
+            let game = tetris_engine_new(1);
+            assert_eq!(tetris_engine_board(game, ptr::null_mut()), TETRIS_ERR_NULL);
+            tetris_engine_free(game);
+        }
+    }
+
+    #[test]
+    fn header_declares_everything() {
+        for function in [
+            "tetris_engine_new",
+            "tetris_engine_apply",
+            "tetris_engine_advance",
+            "tetris_engine_board",
+            "tetris_engine_score",
+            "tetris_engine_level",
+            "tetris_engine_game_over",
+            "tetris_engine_free",
+        ] {
+            assert!(HEADER.contains(&format!("{function}(")), "{function}");
+        }
+
+        for (name, value) in [
+            ("TETRIS_OK", TETRIS_OK),
+            ("TETRIS_ERR_NULL", TETRIS_ERR_NULL),
+            ("TETRIS_ERR_ACTION", TETRIS_ERR_ACTION),
+            ("TETRIS_ERR_PANIC", TETRIS_ERR_PANIC),
+            ("TETRIS_BOARD_SIZE", TETRIS_BOARD_SIZE as i32),
+        ] {
+            assert!(
+                HEADER.contains(&format!("#define {name} {value}\n")),
+                "{name}"
+            );
+        }
+
+        // the actions are numbered like the header has them
+        for (code, name) in [
+            "MOVE_LEFT",
+            "MOVE_RIGHT",
+            "ROTATE",
+            "SOFT_DROP_START",
+            "SOFT_DROP_STOP",
+            "HARD_DROP",
+            "HOLD",
+            "PAUSE",
+            "RESUME",
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            assert!(action(code as i32).is_some());
+            assert!(
+                HEADER.contains(&format!("#define TETRIS_ACTION_{name} {code}\n")),
+                "{name}"
+            );
+        }
+        assert!(action(9).is_none());
+    }
+}
@@ -0,0 +1,52 @@
+// the fuzzing harness (see fuzz/ for the cargo-fuzz target): arbitrary bytes are read as a game of
+// inputs and passing time, and the game has to hold up to all of it without panicking or breaking an
+// invariant. it's plain code, so tests/fuzz_regressions.rs runs the corpus and found crashes through it
+use crate::controller::{GameController, GameInput};
+use crate::engine::config::Config;
+use std::time::Duration;
+
+const FRAME: Duration = Duration::from_micros(16_667);
+
+// what a single byte stands for: the low four bits pick an input, or anything past the inputs lets
+// time pass, as many frames as the high four bits say plus one
+pub fn action(byte: u8) -> Result<GameInput, Duration> {
+    match byte & 0x0f {
+        0 => Ok(GameInput::MoveLeft),
+        1 => Ok(GameInput::MoveRight),
+        2 => Ok(GameInput::Rotate),
+        3 => Ok(GameInput::SoftDropStart),
+        4 => Ok(GameInput::SoftDropStop),
+        5 => Ok(GameInput::HardDrop),
+        6 => Ok(GameInput::Hold),
+        7 => Ok(GameInput::Pause),
+        8 => Ok(GameInput::Resume),
+        _ => Err(FRAME * (byte as u32 >> 4) + FRAME),
+    }
+}
+
+// the first byte is the seed, each one after it an action; a lost game starts over so the rest of the
+// input still gets played. panics on the first broken invariant
+pub fn run(data: &[u8]) {
+    let Some((&seed, actions)) = data.split_first() else {
+        return;
+    };
+
+    let mut game = GameController::new(seed as u64, Config::default(), FRAME);
+
+    for (index, &byte) in actions.iter().enumerate() {
+        match action(byte) {
+            Ok(input) => game.apply(input),
+            Err(dt) => {
+                game.update(dt);
+            }
+        }
+
+        if let Err(broken) = game.validate_invariants() {
+            panic!("after action {index} ({:?}): {broken}", action(byte));
+        }
+
+        if game.is_game_over() {
+            game.restart(seed as u64 + index as u64);
+        }
+    }
+}
@@ -0,0 +1,145 @@
+// a csv file with a row for every finished game, for players keeping track of how they're doing.
+// rows are only ever appended, the header goes in when the file is new
+use crate::controller::GameController;
+use crate::engine::stats::Stats;
+use std::fs::OpenOptions;
+use std::io::{self, Write};
+use std::path::Path;
+use std::time::Duration;
+
+pub const HEADER: &str =
+    "finished_at,mode,score,level,lines,duration_s,pieces,pps,singles,doubles,triples,tetrises";
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct GameRecord {
+    pub finished_at: u64, // unix time in seconds
+    pub mode: &'static str,
+    pub score: u32,
+    pub level: u8,
+    pub lines: u32,
+    pub duration: Duration, // game time, paused or not
+    pub pieces: u32,
+    pub stats: Stats,
+}
+
+impl GameRecord {
+    // the game as it ended; the duration comes from its replay, a game that isn't recorded took no time
+    pub fn of(game: &GameController, mode: &'static str, finished_at: u64) -> Self {
+        let duration = game
+            .replay
+            .as_ref()
+            .map_or(Duration::ZERO, |replay| replay.step * replay.steps);
+
+        Self {
+            finished_at,
+            mode,
+            score: game.engine.score,
+            level: game.engine.level,
+            lines: game.engine.lines_reached,
+            duration,
+            pieces: game.pieces,
+            stats: game.engine.stats(),
+        }
+    }
+
+    // tetriminos per second
+    pub fn pps(&self) -> f64 {
+        if self.duration.is_zero() {
+            return 0.0;
+        }
+        self.pieces as f64 / self.duration.as_secs_f64()
+    }
+
+    // in the columns of HEADER, without the line break
+    pub fn row(&self) -> String {
+        format!(
+            "{},{},{},{},{},{:.3},{},{:.3},{},{},{},{}",
+            self.finished_at,
+            self.mode,
+            self.score,
+            self.level,
+            self.lines,
+            self.duration.as_secs_f64(),
+            self.pieces,
+            self.pps(),
+            self.stats.singles,
+            self.stats.doubles,
+            self.stats.triples,
+            self.stats.tetrises,
+        )
+    }
+}
+
+pub fn append(path: &Path, record: &GameRecord) -> io::Result<()> {
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    if file.metadata()?.len() == 0 {
+        writeln!(file, "{HEADER}")?;
+    }
+    writeln!(file, "{}", record.row())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::controller::GameInput;
+    use crate::engine::config::Config;
+    use std::{env, fs};
+
+    #[test]
+    fn finished_games_are_appended_as_rows() {
+        let path = env::temp_dir().join(format!("tetris-history-{}.csv", std::process::id()));
+        let _ = fs::remove_file(&path);
+
+        // hard drops straight down until the stack reaches the top
+        let step = Duration::from_millis(10);
+        let mut game = GameController::new(3, Config::default(), step);
+        while !game.is_game_over() {
+            game.apply(GameInput::HardDrop);
+            game.update(step);
+        }
+        let record = GameRecord::of(&game, "marathon", 1_700_000_000);
+        assert_eq!(record.duration, step * game.replay.as_ref().unwrap().steps);
+
+        append(&path, &record).unwrap();
+        append(&path, &record).unwrap();
+
+        let written = fs::read_to_string(&path).unwrap();
+        let lines: Vec<&str> = written.lines().collect();
+        assert_eq!(lines.len(), 3, "the header only goes in once");
+        assert_eq!(lines[0], HEADER);
+        assert_eq!(lines[1], lines[2]);
+
+        let columns: Vec<&str> = lines[1].split(',').collect();
+        assert_eq!(columns.len(), HEADER.split(',').count());
+        assert_eq!(columns[0], "1700000000");
+        assert_eq!(columns[1], "marathon");
+        assert_eq!(columns[2], game.engine.score.to_string());
+        assert_eq!(columns[6], game.pieces.to_string());
+        assert_eq!(
+            columns[7],
+            format!("{:.3}", game.pieces as f64 / record.duration.as_secs_f64())
+        );
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn clears_are_broken_down_by_kind() {
+        let mut stats = Stats::default();
+        for lines in [1, 4, 2, 4, 0, 3] {
+            stats.record_clear(lines);
+        }
+        let record = GameRecord {
+            finished_at: 0,
+            mode: "sprint",
+            score: 0,
+            level: 1,
+            lines: 14,
+            duration: Duration::ZERO,
+            pieces: 0,
+            stats,
+        };
+
+        assert!(record.row().ends_with(",0.000,0,0.000,1,1,1,2"));
+    }
+}
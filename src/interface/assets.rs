@@ -0,0 +1,96 @@
+use std::env;
+use std::path::{Path, PathBuf};
+
+use super::error::check_asset;
+
+// where optional assets are looked for, in order: a directory given with `--assets`, the assets directory
+// next to the executable, then the one in the working directory. nothing the game needs to start is
+// among them, a missing asset only leaves something out
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct AssetDirs {
+    dirs: Vec<PathBuf>,
+}
+
+impl AssetDirs {
+    pub const DIR: &str = "assets";
+
+    pub fn new(cli: Option<PathBuf>) -> Self {
+        let exe_dir = env::current_exe()
+            .ok()
+            .and_then(|exe| exe.parent().map(Path::to_path_buf));
+        Self::from(cli, exe_dir, env::current_dir().ok())
+    }
+
+    fn from(cli: Option<PathBuf>, exe_dir: Option<PathBuf>, cwd: Option<PathBuf>) -> Self {
+        let mut dirs: Vec<PathBuf> = cli.into_iter().collect();
+        for base in [exe_dir, cwd].into_iter().flatten() {
+            let dir = base.join(Self::DIR);
+            if !dirs.contains(&dir) {
+                dirs.push(dir);
+            }
+        }
+
+        Self { dirs }
+    }
+
+    // the first file called `name` in any of the directories
+    pub fn find(&self, name: &str) -> Option<PathBuf> {
+        let found = self
+            .dirs
+            .iter()
+            .find_map(|dir| check_asset(&dir.join(name)).ok().map(Path::to_path_buf));
+
+        match &found {
+            Some(path) => log::info!("Using {}", path.display()),
+            None => log::debug!("No {name} among the assets"),
+        }
+        found
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn assets_are_looked_for_in_order() {
+        let root = env::temp_dir().join(format!("tetris-assets-{}", std::process::id()));
+        let (cli, exe_dir, cwd) = (root.join("cli"), root.join("exe"), root.join("cwd"));
+        for dir in [
+            &cli,
+            &exe_dir.join(AssetDirs::DIR),
+            &cwd.join(AssetDirs::DIR),
+        ] {
+            fs::create_dir_all(dir).unwrap();
+        }
+
+        fs::write(cwd.join(AssetDirs::DIR).join("skin.png"), "").unwrap();
+        let assets = AssetDirs::from(Some(cli.clone()), Some(exe_dir.clone()), Some(cwd.clone()));
+        assert_eq!(
+            assets.find("skin.png"),
+            Some(cwd.join(AssetDirs::DIR).join("skin.png"))
+        );
+
+        // next to the executable wins over the working directory
+        fs::write(exe_dir.join(AssetDirs::DIR).join("skin.png"), "").unwrap();
+        assert_eq!(
+            assets.find("skin.png"),
+            Some(exe_dir.join(AssetDirs::DIR).join("skin.png"))
+        );
+
+        // and the command line over both
+        fs::write(cli.join("skin.png"), "").unwrap();
+        assert_eq!(assets.find("skin.png"), Some(cli.join("skin.png")));
+
+        assert_eq!(assets.find("sound.ogg"), None);
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn running_from_the_assets_parent_looks_once() {
+        let dir = PathBuf::from("/games/tetris");
+        let assets = AssetDirs::from(None, Some(dir.clone()), Some(dir.clone()));
+        assert_eq!(assets.dirs, [dir.join(AssetDirs::DIR)]);
+    }
+}
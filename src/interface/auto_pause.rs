@@ -0,0 +1,150 @@
+use std::time::Duration;
+
+use super::settings::Settings;
+use tetris::controller::{GameController, GameInput};
+
+// pausing the game when the window loses focus, and with auto resume on, picking it back up after a
+// countdown once the window has focus again; resuming from the pause menu goes through the countdown too
+#[derive(Clone, Copy, PartialEq, Default, Debug)]
+pub struct AutoPause {
+    paused_by_focus: bool, // the pause came from losing focus, not from the player
+    pub countdown: Option<Duration>, // time left until the game resumes by itself
+}
+
+impl AutoPause {
+    pub const COUNTDOWN: Duration = Duration::from_secs(3);
+
+    // returns whether the game got paused
+    pub fn focus_lost(&mut self, game: &mut GameController, settings: &Settings) -> bool {
+        // going away again in the middle of a countdown keeps the game paused
+        self.countdown = None;
+
+        if !settings.auto_pause || game.is_paused() || game.is_game_over() {
+            return false;
+        }
+
+        game.apply(GameInput::Pause);
+        self.paused_by_focus = true;
+        true
+    }
+
+    // returns whether a countdown started
+    pub fn focus_gained(&mut self, settings: &Settings) -> bool {
+        if !self.paused_by_focus || !settings.auto_resume {
+            return false;
+        }
+
+        self.countdown = Some(Self::COUNTDOWN);
+        true
+    }
+
+    // the player resumed from the pause menu, the game picks back up after the same countdown
+    pub fn resume(&mut self) {
+        self.countdown = Some(Self::COUNTDOWN);
+    }
+
+    // let the countdown run; returns whether anything changed that needs a redraw
+    pub fn advance(&mut self, dt: Duration, game: &mut GameController) -> bool {
+        let Some(left) = self.countdown else {
+            return false;
+        };
+
+        // the player resumed, or quit to another game, before the countdown ran out
+        if !game.is_paused() {
+            *self = Self::default();
+            return true;
+        }
+
+        if left > dt {
+            self.countdown = Some(left - dt);
+        } else {
+            *self = Self::default();
+            game.apply(GameInput::Resume);
+        }
+        true
+    }
+
+    // how long until the countdown shows a different number, None without a countdown
+    pub fn time_until_change(&self) -> Option<Duration> {
+        let left = self.countdown?;
+
+        Some(match left.subsec_nanos() {
+            0 => left.min(Duration::from_secs(1)),
+            nanos => Duration::from_nanos(nanos as u64),
+        })
+    }
+
+    // the number the countdown shows, None without a countdown
+    pub fn seconds_left(&self) -> Option<u64> {
+        let left = self.countdown?;
+
+        Some(left.as_secs_f32().ceil() as u64)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use tetris::controller::GameState;
+    use tetris::engine::config::Config;
+
+    fn game() -> GameController {
+        GameController::new(1, Config::default(), Duration::from_millis(10))
+    }
+
+    #[test]
+    fn focus_lost_pauses_when_enabled() {
+        let mut settings = Settings::default();
+        let mut game = game();
+        let mut auto_pause = AutoPause::default();
+
+        settings.auto_pause = false;
+        assert!(!auto_pause.focus_lost(&mut game, &settings));
+        assert_eq!(game.state, GameState::TickingDown);
+
+        settings.auto_pause = true;
+        assert!(auto_pause.focus_lost(&mut game, &settings));
+        assert_eq!(game.state, GameState::Paused);
+    }
+
+    #[test]
+    fn focus_gained_resumes_after_the_countdown() {
+        let settings = Settings {
+            auto_pause: true,
+            auto_resume: true,
+            ..Settings::default()
+        };
+        let mut game = game();
+        let mut auto_pause = AutoPause::default();
+
+        auto_pause.focus_lost(&mut game, &settings);
+        assert!(auto_pause.focus_gained(&settings));
+        assert_eq!(auto_pause.seconds_left(), Some(3));
+
+        auto_pause.advance(Duration::from_millis(2500), &mut game);
+        assert_eq!(auto_pause.seconds_left(), Some(1));
+        assert!(game.is_paused());
+
+        auto_pause.advance(Duration::from_millis(500), &mut game);
+        assert_eq!(auto_pause.countdown, None);
+        assert_eq!(game.state, GameState::TickingDown);
+
+        // a pause of the player's own isn't resumed
+        game.apply(GameInput::Pause);
+        assert!(!auto_pause.focus_gained(&settings));
+    }
+
+    #[test]
+    fn resuming_from_the_menu_counts_down_too() {
+        let mut game = game();
+        let mut auto_pause = AutoPause::default();
+        game.apply(GameInput::Pause);
+
+        auto_pause.resume();
+        assert_eq!(auto_pause.seconds_left(), Some(3));
+        auto_pause.advance(Duration::from_millis(2999), &mut game);
+        assert!(game.is_paused());
+        auto_pause.advance(Duration::from_millis(1), &mut game);
+        assert_eq!(game.state, GameState::TickingDown);
+    }
+}
@@ -1,40 +1,86 @@
+use crate::interface::error::InterfaceError;
 use crate::interface::render_traits::ScreenColor;
+use crate::interface::renderer::Renderer;
 use cgmath::ElementWise;
 use cgmath::EuclideanSpace;
 use cgmath::{Point2, Vector2};
-use sdl2::{pixels::Color, rect::Rect, render::Canvas, video::Window};
+use sdl2::{
+    pixels::Color,
+    rect::{Point, Rect},
+};
 
 use crate::engine::{
     color::TetriminoColor,
     matrix::{CellIter, Matrix},
+    piece::Piece,
     Coordinate,
 };
 
+// the sides of a cell that border a cell of the same color; when drawing connected those sides aren't
+// outlined, so the cells read as one shape
+#[derive(Clone, Copy, PartialEq, Default, Debug)]
+pub struct SharedEdges {
+    pub left: bool,
+    pub right: bool,
+    pub up: bool,
+    pub down: bool,
+}
+
+impl SharedEdges {
+    // color_at gives the color of the cell at a coordinate, None if it's empty or off the matrix
+    pub fn find(
+        coord: Coordinate,
+        color: TetriminoColor,
+        color_at: impl Fn(Coordinate) -> Option<TetriminoColor>,
+    ) -> Self {
+        let same = |x: Option<usize>, y: Option<usize>| match (x, y) {
+            (Some(x), Some(y)) => color_at(Coordinate::new(x, y)) == Some(color),
+            _ => false,
+        };
+
+        Self {
+            left: same(coord.x.checked_sub(1), Some(coord.y)),
+            right: same(Some(coord.x + 1), Some(coord.y)),
+            up: same(Some(coord.x), Some(coord.y + 1)),
+            down: same(Some(coord.x), coord.y.checked_sub(1)),
+        }
+    }
+}
+
 // we need a lifetime because we have a mutable reference
-pub struct CellDrawContext<'canvas, const WIDTH: usize, const HEIGHT: usize>
+pub struct CellDrawContext<'canvas, R: Renderer, const WIDTH: usize, const HEIGHT: usize>
 where
     [usize; WIDTH * HEIGHT]:,
 {
     pub origin: Point2<i32>,
     pub dims: Vector2<u32>,
-    pub canvas: &'canvas mut Canvas<Window>,
+    pub canvas: &'canvas mut R,
     pub matrix: &'canvas Matrix<WIDTH, HEIGHT>,
+    pub connected: bool, // leave out the borders between same colored cells
+    pub ghost: Option<([Coordinate; Piece::CELL_COUNT], TetriminoColor)>, // drawn dimmed over the cells, see Engine::ghost_cursor_info
 }
 
-impl<const WIDTH: usize, const HEIGHT: usize> CellDrawContext<'_, { WIDTH }, { HEIGHT }>
+impl<R: Renderer, const WIDTH: usize, const HEIGHT: usize>
+    CellDrawContext<'_, R, { WIDTH }, { HEIGHT }>
 where
     [usize; WIDTH * HEIGHT]:,
 {
     const CELL_COUNT: Vector2<u32> = Vector2::new(WIDTH as u32, HEIGHT as u32);
 
-    pub fn draw_matrix(&mut self) {
+    pub fn draw_matrix(&mut self) -> Result<(), InterfaceError> {
+        self.draw_rows(HEIGHT)
+    }
+
+    // only the bottom `rows` rows of the matrix, the ones above aren't drawn at all (not even their borders);
+    // dims are still those of the whole matrix, so the rows drawn reach up to origin.y - dims.y * rows / HEIGHT
+    pub fn draw_rows(&mut self, rows: usize) -> Result<(), InterfaceError> {
         let cell_iter: CellIter<WIDTH, HEIGHT> = CellIter {
             position: Coordinate::origin(),
             cells: self.matrix.matrix.iter(), // iter over first element of tuple which is our matrix array
         };
 
-        for (coord, _) in cell_iter {
-            self.draw_border(coord);
+        for (coord, _) in cell_iter.filter(|(coord, _)| coord.y < rows) {
+            self.draw_border(coord)?;
         }
 
         let cell_iter1: CellIter<WIDTH, HEIGHT> = CellIter {
@@ -42,12 +88,23 @@ where
             cells: self.matrix.matrix.iter(), // iter over first element of tuple which is our matrix array
         };
 
-        for (coord, cell) in cell_iter1 {
-            self.try_draw_cell(coord, cell);
+        for (coord, cell) in cell_iter1.filter(|(coord, _)| coord.y < rows) {
+            self.try_draw_cell(coord, cell)?;
+        }
+
+        // before the cursor, which goes on top of it once it gets there
+        if let Some((cells, color)) = self.ghost {
+            let cells_drawn = cells.into_iter().filter(|coord| coord.y < rows);
+            for coord in cells_drawn {
+                let edges = self.piece_edges(&cells, coord, color);
+                self.draw_outlined_cell(coord, color.ghost_color(), edges)?;
+            }
         }
+
+        Ok(())
     }
 
-    fn get_rect(&mut self, coord: Coordinate) -> Rect {
+    fn get_rect(&self, coord: Coordinate) -> Rect {
         // // we get the width from the next cells coordinates because otherwise we end up with a rounding error
         // let this_x = (coord.x as u32 + 0) * matrix_width / Matrix::WIDTH as u32;
         // let this_y = (coord.y as u32 + 1) * matrix_height / Matrix::HEIGHT as u32;
@@ -74,24 +131,366 @@ where
         )
     }
 
-    pub fn try_draw_cell(&mut self, coord: Coordinate, cell: Option<TetriminoColor>) {
+    pub fn try_draw_cell(
+        &mut self,
+        coord: Coordinate,
+        cell: Option<TetriminoColor>,
+    ) -> Result<(), InterfaceError> {
         let Some(color) = cell else {
-            return;
+            return Ok(());
         };
 
-        let cell_rect = self.get_rect(coord);
+        let edges = if self.connected {
+            let matrix = self.matrix;
+            SharedEdges::find(coord, color, |neighbor| {
+                matrix
+                    .valid_coord(neighbor)
+                    .then(|| matrix[neighbor])
+                    .flatten()
+            })
+        } else {
+            SharedEdges::default()
+        };
 
-        self.canvas.set_draw_color(color.screen_color());
-        self.canvas.fill_rect(cell_rect).unwrap();
+        self.draw_outlined_cell(coord, color.screen_color(), edges)
+    }
 
-        self.canvas.set_draw_color(Color::WHITE);
-        self.canvas.draw_rect(cell_rect).unwrap();
+    // a tetrimino that isn't on the matrix itself (the cursor), only connected to its own cells
+    pub fn draw_piece(
+        &mut self,
+        cells: [Coordinate; Piece::CELL_COUNT],
+        color: TetriminoColor,
+    ) -> Result<(), InterfaceError> {
+        self.draw_lowered_piece(cells, color, 0.0)
     }
 
-    fn draw_border(&mut self, coord: Coordinate) {
+    // the same, drawn the given fraction of a row further down than its cells are
+    pub fn draw_lowered_piece(
+        &mut self,
+        cells: [Coordinate; Piece::CELL_COUNT],
+        color: TetriminoColor,
+        rows: f32,
+    ) -> Result<(), InterfaceError> {
+        let lowered_by = (rows * self.dims.y as f32 / HEIGHT as f32) as i32;
+
+        for coord in cells {
+            let edges = self.piece_edges(&cells, coord, color);
+            let mut cell_rect = self.get_rect(coord);
+            cell_rect.offset(0, lowered_by);
+            self.draw_outlined_rect(cell_rect, color.screen_color(), edges)?;
+        }
+
+        Ok(())
+    }
+
+    // a cell of a tetrimino is only connected to the other cells of it
+    fn piece_edges(
+        &self,
+        cells: &[Coordinate; Piece::CELL_COUNT],
+        coord: Coordinate,
+        color: TetriminoColor,
+    ) -> SharedEdges {
+        if !self.connected {
+            return SharedEdges::default();
+        }
+
+        SharedEdges::find(coord, color, |neighbor| {
+            cells.contains(&neighbor).then_some(color)
+        })
+    }
+
+    // draws a cell in any screen color, e.g. for cells that aren't part of a tetrimino
+    pub fn draw_cell(&mut self, coord: Coordinate, color: Color) -> Result<(), InterfaceError> {
+        self.draw_outlined_cell(coord, color, SharedEdges::default())
+    }
+
+    fn draw_outlined_cell(
+        &mut self,
+        coord: Coordinate,
+        color: Color,
+        edges: SharedEdges,
+    ) -> Result<(), InterfaceError> {
+        self.draw_outlined_rect(self.get_rect(coord), color, edges)
+    }
+
+    fn draw_outlined_rect(
+        &mut self,
+        cell_rect: Rect,
+        color: Color,
+        edges: SharedEdges,
+    ) -> Result<(), InterfaceError> {
+        self.canvas.set_color(color);
+        self.canvas
+            .fill_rect(cell_rect)
+            .map_err(InterfaceError::Render)?;
+
+        self.canvas.set_color(Color::WHITE);
+        if edges == SharedEdges::default() {
+            return self
+                .canvas
+                .draw_rect(cell_rect)
+                .map_err(InterfaceError::Render);
+        }
+
+        // the rects overlap by a pixel, so a shared side is covered by the neighbor's fill
+        let (left, right) = (cell_rect.left(), cell_rect.right() - 1);
+        let (top, bottom) = (cell_rect.top(), cell_rect.bottom() - 1);
+        let sides = [
+            (edges.up, (left, top), (right, top)),
+            (edges.down, (left, bottom), (right, bottom)),
+            (edges.left, (left, top), (left, bottom)),
+            (edges.right, (right, top), (right, bottom)),
+        ];
+
+        for (shared, start, end) in sides {
+            if !shared {
+                self.canvas
+                    .draw_line(Point::from(start), Point::from(end))
+                    .map_err(InterfaceError::Render)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn draw_border(&mut self, coord: Coordinate) -> Result<(), InterfaceError> {
         let cell_rect = self.get_rect(coord);
 
-        self.canvas.set_draw_color(Color::RGB(130, 130, 130));
-        self.canvas.draw_rect(cell_rect).unwrap();
+        self.canvas.set_color(Color::RGB(130, 130, 130));
+        self.canvas
+            .draw_rect(cell_rect)
+            .map_err(InterfaceError::Render)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::interface::renderer::{DrawCall, RecordingRenderer};
+
+    const BORDER_COLOR: Color = Color::RGB(130, 130, 130);
+
+    // a 4x4 matrix drawn at 10 pixels a cell, with the 40x40 panel's bottom left at (0, 40)
+    fn draw_4x4(matrix: &Matrix<4, 4>, connected: bool) -> Vec<DrawCall> {
+        let mut renderer = RecordingRenderer::default();
+        CellDrawContext {
+            origin: Point2::new(0, 40),
+            dims: Vector2::new(40, 40),
+            canvas: &mut renderer,
+            matrix,
+            connected,
+            ghost: None,
+        }
+        .draw_matrix()
+        .unwrap();
+
+        renderer.calls
+    }
+
+    fn red_pair_under_blue() -> Matrix<4, 4> {
+        let mut matrix: Matrix<4, 4> = Matrix::blank();
+        matrix[Coordinate::new(1, 0)] = Some(TetriminoColor::Red);
+        matrix[Coordinate::new(2, 0)] = Some(TetriminoColor::Red);
+        matrix[Coordinate::new(1, 2)] = Some(TetriminoColor::Blue);
+        matrix
+    }
+
+    #[test]
+    fn draw_matrix_outlines_every_cell_then_fills_the_occupied_ones() {
+        let calls = draw_4x4(&red_pair_under_blue(), false);
+
+        // first the grid, row by row from the bottom, each cell 11 pixels so neighbors share a border
+        let (grid, cells) = calls.split_at(2 * 16);
+        for (index, pair) in grid.chunks(2).enumerate() {
+            let (x, y) = (index as i32 % 4, index as i32 / 4);
+            assert_eq!(
+                pair,
+                [
+                    DrawCall::SetColor(BORDER_COLOR),
+                    DrawCall::DrawRect(Rect::new(10 * x, 29 - 10 * y, 11, 11)),
+                ]
+            );
+        }
+
+        let red = TetriminoColor::Red.screen_color();
+        let blue = TetriminoColor::Blue.screen_color();
+        assert_eq!(
+            cells,
+            [
+                DrawCall::SetColor(red),
+                DrawCall::FillRect(Rect::new(10, 29, 11, 11)),
+                DrawCall::SetColor(Color::WHITE),
+                DrawCall::DrawRect(Rect::new(10, 29, 11, 11)),
+                DrawCall::SetColor(red),
+                DrawCall::FillRect(Rect::new(20, 29, 11, 11)),
+                DrawCall::SetColor(Color::WHITE),
+                DrawCall::DrawRect(Rect::new(20, 29, 11, 11)),
+                DrawCall::SetColor(blue),
+                DrawCall::FillRect(Rect::new(10, 9, 11, 11)),
+                DrawCall::SetColor(Color::WHITE),
+                DrawCall::DrawRect(Rect::new(10, 9, 11, 11)),
+            ]
+        );
+    }
+
+    #[test]
+    fn draw_rows_leaves_out_the_rows_above() {
+        let matrix = red_pair_under_blue();
+        let mut renderer = RecordingRenderer::default();
+        CellDrawContext {
+            origin: Point2::new(0, 40),
+            dims: Vector2::new(40, 40),
+            canvas: &mut renderer,
+            matrix: &matrix,
+            connected: false,
+            ghost: None,
+        }
+        .draw_rows(2)
+        .unwrap();
+
+        // the grid of the bottom two rows, then the red pair; the blue cell in the third row is left out
+        let (grid, cells) = renderer.calls.split_at(2 * 8);
+        assert!(grid
+            .iter()
+            .all(|call| !matches!(call, DrawCall::DrawRect(rect) if rect.y() < 19)));
+        assert_eq!(cells.len(), 2 * 4);
+        assert!(!cells.contains(&DrawCall::SetColor(TetriminoColor::Blue.screen_color())));
+    }
+
+    #[test]
+    fn the_ghost_is_drawn_dimmed_after_the_cells() {
+        let matrix = red_pair_under_blue();
+        let cells = [(0, 1), (1, 1), (2, 1), (3, 1)].map(Coordinate::from);
+        let mut renderer = RecordingRenderer::default();
+        CellDrawContext {
+            origin: Point2::new(0, 40),
+            dims: Vector2::new(40, 40),
+            canvas: &mut renderer,
+            matrix: &matrix,
+            connected: false,
+            ghost: Some((cells, TetriminoColor::Cyan)),
+        }
+        .draw_matrix()
+        .unwrap();
+
+        let cyan = TetriminoColor::Cyan.screen_color();
+        let dimmed = Color::RGB(cyan.r / 2, cyan.g / 2, cyan.b / 2);
+        assert_eq!(TetriminoColor::Cyan.ghost_color(), dimmed);
+
+        // the grid and the three cells of the matrix come first, then a dimmed cell for each of the ghost's
+        let ghost = &renderer.calls[2 * 16 + 4 * 3..];
+        assert_eq!(ghost.len(), 4 * 4);
+        for (x, calls) in ghost.chunks(4).enumerate() {
+            assert_eq!(
+                calls[..2],
+                [
+                    DrawCall::SetColor(dimmed),
+                    DrawCall::FillRect(Rect::new(10 * x as i32, 19, 11, 11)),
+                ]
+            );
+        }
+    }
+
+    #[test]
+    fn connected_cells_leave_out_the_shared_side() {
+        let calls = draw_4x4(&red_pair_under_blue(), true);
+        let red = TetriminoColor::Red.screen_color();
+        let line = |from: (i32, i32), to: (i32, i32)| DrawCall::DrawLine(from.into(), to.into());
+
+        // the left red cell, everything but its right side which the other red cell's fill covers
+        assert_eq!(
+            calls[2 * 16..2 * 16 + 5],
+            [
+                DrawCall::SetColor(red),
+                DrawCall::FillRect(Rect::new(10, 29, 11, 11)),
+                DrawCall::SetColor(Color::WHITE),
+                line((10, 29), (20, 29)),
+                line((10, 39), (20, 39)),
+            ]
+        );
+        assert_eq!(calls[2 * 16 + 5], line((10, 29), (10, 39)));
+    }
+
+    // neighboring rects overlap by exactly their one pixel border, and together they cover the panel
+    // plus that border around it, whatever the rounding
+    fn assert_tiles<const WIDTH: usize, const HEIGHT: usize>(dims: Vector2<u32>)
+    where
+        [usize; WIDTH * HEIGHT]:,
+    {
+        let matrix: Matrix<WIDTH, HEIGHT> = Matrix::blank();
+        let mut renderer = RecordingRenderer::default();
+        let origin = Point2::new(7, 300);
+        let ctx = CellDrawContext {
+            origin,
+            dims,
+            canvas: &mut renderer,
+            matrix: &matrix,
+            connected: false,
+            ghost: None,
+        };
+        let rect = |x, y| ctx.get_rect(Coordinate::new(x, y));
+
+        for y in 0..HEIGHT {
+            for x in 0..WIDTH {
+                let cell = rect(x, y);
+                if x + 1 < WIDTH {
+                    let right = rect(x + 1, y);
+                    assert_eq!(cell.right() - 1, right.left(), "{dims:?} ({x}, {y})");
+                    assert_eq!((cell.top(), cell.bottom()), (right.top(), right.bottom()));
+                }
+                if y + 1 < HEIGHT {
+                    let up = rect(x, y + 1);
+                    assert_eq!(cell.top(), up.bottom() - 1, "{dims:?} ({x}, {y})");
+                    assert_eq!((cell.left(), cell.right()), (up.left(), up.right()));
+                }
+            }
+        }
+
+        let (width, height) = (dims.x as i32, dims.y as i32);
+        assert_eq!(rect(0, 0).left(), origin.x);
+        assert_eq!(rect(0, 0).bottom(), origin.y);
+        assert_eq!(rect(WIDTH - 1, 0).right(), origin.x + width + 1);
+        assert_eq!(rect(0, HEIGHT - 1).top(), origin.y - height - 1);
+    }
+
+    #[test]
+    fn get_rect_tiles_the_panel() {
+        assert_tiles::<4, 4>(Vector2::new(40, 40));
+        assert_tiles::<4, 4>(Vector2::new(13, 7));
+        assert_tiles::<10, 22>(Vector2::new(97, 203));
+        assert_tiles::<10, 22>(Vector2::new(333, 719));
+        assert_tiles::<4, 2>(Vector2::new(101, 33));
+    }
+
+    #[test]
+    fn shared_edges_between_same_colored_neighbors() {
+        let mut matrix: Matrix<4, 4> = Matrix::blank();
+        matrix[Coordinate::new(1, 1)] = Some(TetriminoColor::Red);
+        matrix[Coordinate::new(2, 1)] = Some(TetriminoColor::Red);
+        matrix[Coordinate::new(1, 2)] = Some(TetriminoColor::Blue);
+
+        let color_at = |coord| matrix.valid_coord(coord).then(|| matrix[coord]).flatten();
+
+        // only the red neighbor to the right, the blue one above is another tetrimino
+        assert_eq!(
+            SharedEdges::find(Coordinate::new(1, 1), TetriminoColor::Red, color_at),
+            SharedEdges {
+                right: true,
+                ..SharedEdges::default()
+            }
+        );
+        assert_eq!(
+            SharedEdges::find(Coordinate::new(2, 1), TetriminoColor::Red, color_at),
+            SharedEdges {
+                left: true,
+                ..SharedEdges::default()
+            }
+        );
+
+        // the edges of the matrix don't count as neighbors
+        assert_eq!(
+            SharedEdges::find(Coordinate::new(0, 0), TetriminoColor::Red, color_at),
+            SharedEdges::default()
+        );
     }
 }
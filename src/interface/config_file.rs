@@ -0,0 +1,315 @@
+use std::fs;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
+
+use sdl2::keyboard::Keycode;
+use serde::Deserialize;
+
+use super::input::{Action, ControlPreset, KeyBindings};
+use super::settings::Settings;
+
+// a file which is read again whenever it changes on disk; checked once a second, which is plenty for
+// someone editing it by hand
+pub struct FileWatch {
+    pub path: PathBuf,
+    seen: Option<(SystemTime, u64)>, // modification time and length when it was last read
+    since_check: Duration,
+}
+
+impl FileWatch {
+    pub const INTERVAL: Duration = Duration::from_secs(1);
+
+    // the first check reads the file right away
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            seen: None,
+            since_check: Self::INTERVAL,
+        }
+    }
+
+    // the contents of the file if it changed since it was last read; a file that can't be read is an error
+    // once, and read again as soon as it's back
+    pub fn poll(&mut self, dt: Duration) -> Option<Result<String, String>> {
+        self.since_check += dt;
+        if self.since_check < Self::INTERVAL {
+            return None;
+        }
+        self.since_check = Duration::ZERO;
+
+        let stamp = fs::metadata(&self.path)
+            .and_then(|metadata| Ok((metadata.modified()?, metadata.len())))
+            .ok();
+        if stamp.is_some() && stamp == self.seen {
+            return None;
+        }
+
+        let missing = stamp.is_none();
+        let already_missing = self.seen == Some((SystemTime::UNIX_EPOCH, 0));
+        if missing && already_missing {
+            return None;
+        }
+
+        self.seen = Some(stamp.unwrap_or((SystemTime::UNIX_EPOCH, 0)));
+        Some(fs::read_to_string(&self.path).map_err(|error| error.to_string()))
+    }
+}
+
+// a settings file as written: the toggles of the settings screen, a control preset and the key bindings
+// on top of it, by their sdl key names ("Left", "Space", "C"). anything left out keeps its current value
+#[derive(Deserialize, Default, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct ConfigFile {
+    game_over_animation: Option<bool>,
+    mini_map: Option<bool>,
+    bag_panel: Option<bool>,
+    bag_divider: Option<bool>,
+    piece_stats: Option<bool>,
+    connected_cells: Option<bool>,
+    smooth_fall: Option<bool>,
+    auto_pause: Option<bool>,
+    auto_resume: Option<bool>,
+    metrics: Option<bool>,
+    ghost_min_distance: Option<usize>,
+    dim_opacity: Option<u8>,
+    controls: Option<String>,
+    keys: KeysFile,
+}
+
+#[derive(Deserialize, Default, Debug)]
+#[serde(default, deny_unknown_fields)]
+struct KeysFile {
+    move_left: Option<String>,
+    move_right: Option<String>,
+    rotate: Option<String>,
+    rotate_counterclockwise: Option<String>,
+    soft_drop: Option<String>,
+    hard_drop: Option<String>,
+    pause: Option<String>,
+    hold: Option<String>,
+    continue_game: Option<String>,
+    restart: Option<String>,
+}
+
+impl ConfigFile {
+    pub fn parse(text: &str) -> Result<Self, String> {
+        serde_json::from_str(text).map_err(|error| error.to_string())
+    }
+
+    // applies the file on top of the current settings and bindings; if anything in it is wrong, nothing
+    // of it is applied and the error says what it was
+    pub fn apply(&self, settings: &mut Settings, bindings: &mut KeyBindings) -> Result<(), String> {
+        let mut settings_after = *settings;
+        let mut bindings_after = *bindings;
+
+        if let Some(name) = &self.controls {
+            let preset = ControlPreset::parse(name).ok_or(format!("'{name}' isn't a preset"))?;
+            settings_after.controls = preset;
+            if let Some(preset_bindings) = preset.bindings() {
+                bindings_after = preset_bindings;
+            }
+        }
+        if !self.keys.is_empty() {
+            bindings_after = self.keys.bind(bindings_after)?;
+            settings_after.controls = ControlPreset::Custom;
+        }
+
+        let toggles = [
+            (
+                self.game_over_animation,
+                &mut settings_after.game_over_animation,
+            ),
+            (self.mini_map, &mut settings_after.mini_map),
+            (self.bag_panel, &mut settings_after.bag_panel),
+            (self.bag_divider, &mut settings_after.bag_divider),
+            (self.piece_stats, &mut settings_after.piece_stats),
+            (self.connected_cells, &mut settings_after.connected_cells),
+            (self.smooth_fall, &mut settings_after.smooth_fall),
+            (self.auto_pause, &mut settings_after.auto_pause),
+            (self.auto_resume, &mut settings_after.auto_resume),
+            (self.metrics, &mut settings_after.metrics),
+        ];
+        for (value, setting) in toggles {
+            if let Some(value) = value {
+                *setting = value;
+            }
+        }
+        if let Some(distance) = self.ghost_min_distance {
+            settings_after.ghost_min_distance = distance;
+        }
+        if let Some(opacity) = self.dim_opacity {
+            settings_after.dim_opacity = opacity;
+        }
+
+        *settings = settings_after;
+        *bindings = bindings_after;
+        Ok(())
+    }
+}
+
+impl KeysFile {
+    fn is_empty(&self) -> bool {
+        [
+            &self.move_left,
+            &self.move_right,
+            &self.rotate,
+            &self.rotate_counterclockwise,
+            &self.soft_drop,
+            &self.hard_drop,
+            &self.pause,
+            &self.hold,
+            &self.continue_game,
+            &self.restart,
+        ]
+        .iter()
+        .all(|key| key.is_none())
+    }
+
+    // `bindings` with the keys of the file; unlike a rebind in the game, a key given to two actions is an
+    // error instead of a swap, it's hard to tell which one was meant
+    fn bind(&self, mut bindings: KeyBindings) -> Result<KeyBindings, String> {
+        let keys = [
+            (Action::MoveLeft, &self.move_left),
+            (Action::MoveRight, &self.move_right),
+            (Action::Rotate, &self.rotate),
+            (
+                Action::RotateCounterclockwise,
+                &self.rotate_counterclockwise,
+            ),
+            (Action::SoftDrop, &self.soft_drop),
+            (Action::HardDrop, &self.hard_drop),
+            (Action::Pause, &self.pause),
+            (Action::Hold, &self.hold),
+            (Action::Continue, &self.continue_game),
+            (Action::Restart, &self.restart),
+        ];
+
+        for (action, name) in keys {
+            if let Some(name) = name {
+                let key = Keycode::from_name(name).ok_or(format!("'{name}' isn't a key"))?;
+                *bindings.key_mut(action) = key;
+            }
+        }
+
+        for (index, action) in Action::ALL.into_iter().enumerate() {
+            let key = bindings.key(action);
+            if Action::ALL[index + 1..]
+                .iter()
+                .any(|&other| bindings.key(other) == key)
+            {
+                return Err(format!("{} is bound twice", key.name()));
+            }
+        }
+
+        Ok(bindings)
+    }
+}
+
+// a message over the game for a little while, e.g. whether a file that changed could be applied
+#[derive(Clone, PartialEq, Debug)]
+pub struct Popup {
+    pub text: String,
+    pub left: Duration,
+}
+
+impl Popup {
+    pub const DURATION: Duration = Duration::from_secs(3);
+
+    pub fn new(text: String) -> Self {
+        Self {
+            text,
+            left: Self::DURATION,
+        }
+    }
+
+    // returns whether it's still showing
+    pub fn advance(&mut self, dt: Duration) -> bool {
+        self.left = self.left.saturating_sub(dt);
+        !self.left.is_zero()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn apply(
+        text: &str,
+        settings: &mut Settings,
+        bindings: &mut KeyBindings,
+    ) -> Result<(), String> {
+        ConfigFile::parse(text)?.apply(settings, bindings)
+    }
+
+    #[test]
+    fn a_valid_file_applies() {
+        let mut settings = Settings::default();
+        let mut bindings = KeyBindings::default();
+
+        let text = r#"{ "mini_map": true, "ghost_min_distance": 2, "dim_opacity": 0, "keys": { "hold": "Left Shift" } }"#;
+        apply(text, &mut settings, &mut bindings).unwrap();
+
+        assert!(settings.mini_map);
+        assert_eq!(settings.ghost_min_distance, 2);
+        assert_eq!(settings.dim_opacity, 0);
+        assert_eq!(settings.bag_panel, Settings::default().bag_panel);
+        assert_eq!(bindings.hold, Keycode::LShift);
+        assert_eq!(bindings.move_left, KeyBindings::default().move_left);
+        assert_eq!(settings.controls, ControlPreset::Custom);
+
+        // a preset replaces every key, keys next to it are picked on top of it
+        apply(r#"{ "controls": "wasd" }"#, &mut settings, &mut bindings).unwrap();
+        assert_eq!(settings.controls, ControlPreset::Wasd);
+        assert_eq!(bindings, ControlPreset::Wasd.bindings().unwrap());
+    }
+
+    #[test]
+    fn an_invalid_edit_keeps_the_old_config() {
+        let mut settings = Settings::default();
+        let mut bindings = KeyBindings::default();
+        apply(r#"{ "bag_panel": true }"#, &mut settings, &mut bindings).unwrap();
+        let (settings_before, bindings_before) = (settings, bindings);
+
+        for broken in [
+            r#"{ "bag_panel": false, "mini_map": tru }"#, // not even json
+            r#"{ "bag_panel": false, "minimap": true }"#, // a setting that doesn't exist
+            r#"{ "bag_panel": false, "mini_map": 1 }"#,   // not a toggle
+            r#"{ "bag_panel": false, "controls": "nes" }"#, // a preset that doesn't exist
+            r#"{ "bag_panel": false, "keys": { "jump": "Space" } }"#, // an action that doesn't exist
+            r#"{ "bag_panel": false, "keys": { "hold": 67 } }"#,      // a key by its number
+            r#"{ "bag_panel": false, "keys": { "hold": "Nope" } }"#,  // a key that doesn't exist
+            r#"{ "bag_panel": false, "keys": { "hold": "X", "rotate": "X" } }"#, // twice the same key
+            r#"{ "bag_panel": false, "keys": { "hold": "Space" } }"#, // hard drop's key, which it keeps
+        ] {
+            assert!(
+                apply(broken, &mut settings, &mut bindings).is_err(),
+                "{broken}"
+            );
+            assert_eq!(settings, settings_before, "{broken}");
+            assert_eq!(bindings, bindings_before, "{broken}");
+        }
+    }
+
+    #[test]
+    fn changes_on_disk_are_picked_up() {
+        let path = std::env::temp_dir().join(format!("tetris-watch-{}.json", std::process::id()));
+        fs::write(&path, "{}").unwrap();
+        let mut watch = FileWatch::new(path.clone());
+
+        assert_eq!(watch.poll(Duration::ZERO), Some(Ok("{}".to_string())));
+        assert_eq!(watch.poll(FileWatch::INTERVAL), None);
+
+        // not looked at again until a second went by
+        fs::write(&path, r#"{ "mini_map": true }"#).unwrap();
+        assert_eq!(watch.poll(Duration::from_millis(10)), None);
+        assert_eq!(
+            watch.poll(FileWatch::INTERVAL),
+            Some(Ok(r#"{ "mini_map": true }"#.to_string()))
+        );
+
+        // gone is an error, once
+        fs::remove_file(&path).unwrap();
+        assert!(matches!(watch.poll(FileWatch::INTERVAL), Some(Err(_))));
+        assert_eq!(watch.poll(FileWatch::INTERVAL), None);
+    }
+}
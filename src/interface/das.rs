@@ -0,0 +1,120 @@
+use std::time::Duration;
+
+use tetris::engine::move_kind::MoveKind;
+use tetris::engine::Engine;
+
+// delayed auto shift: a direction held down moves the tetrimino once when it's pressed, then nothing for
+// DAS, then once every ARR for as long as it stays down. the key repeat of the system isn't used for it,
+// its delay and rate are whatever the desktop has them at
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub struct DasState {
+    direction: Option<MoveKind>, // the direction held down, the last one pressed if both are
+    das_elapsed: Duration,       // how long it's been held down
+    arr_elapsed: Duration,       // time since the last repeated move, once it's repeating
+}
+
+impl DasState {
+    pub const DAS: Duration = Duration::from_millis(133);
+    pub const ARR: Duration = Duration::from_millis(10);
+
+    // the direction went down; the move it makes right away is up to the caller
+    pub fn press(&mut self, direction: MoveKind) {
+        *self = Self {
+            direction: Some(direction),
+            ..Self::default()
+        };
+    }
+
+    // letting go of the other direction than the one that's repeating leaves it going
+    pub fn release(&mut self, direction: MoveKind) {
+        if self.direction == Some(direction) {
+            self.reset();
+        }
+    }
+
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+
+    // the direction held and how many moves in it are due after another dt; None while nothing is
+    pub fn advance(&mut self, dt: Duration) -> Option<(MoveKind, usize)> {
+        let direction = self.direction?;
+        let was_repeating = self.das_elapsed >= Self::DAS;
+        self.das_elapsed += dt;
+        if self.das_elapsed < Self::DAS {
+            return None;
+        }
+
+        // the first repeat is right as the delay runs out
+        let mut moves = 0;
+        if was_repeating {
+            self.arr_elapsed += dt;
+        } else {
+            moves += 1;
+            self.arr_elapsed = self.das_elapsed - Self::DAS;
+        }
+        let repeats = self.arr_elapsed.as_nanos() / Self::ARR.as_nanos();
+        self.arr_elapsed -= Self::ARR * repeats as u32;
+        // a long stall can't take it further than from one wall to the other
+        moves = (moves + repeats as usize).min(Engine::MATRIX_WIDTH);
+
+        Some((direction, moves)).filter(|_| moves > 0)
+    }
+
+    // how long until the next move is due, None while no direction is held
+    pub fn time_until_move(&self) -> Option<Duration> {
+        self.direction?;
+        Some(match Self::DAS.checked_sub(self.das_elapsed) {
+            Some(left) if !left.is_zero() => left,
+            _ => Self::ARR - self.arr_elapsed,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const MS: Duration = Duration::from_millis(1);
+
+    #[test]
+    fn moves_repeat_once_the_delay_is_over() {
+        let mut das = DasState::default();
+        assert_eq!(das.advance(MS * 500), None);
+        assert_eq!(das.time_until_move(), None);
+
+        das.press(MoveKind::Left);
+        assert_eq!(das.advance(MS * 100), None);
+        assert_eq!(das.time_until_move(), Some(MS * 33));
+        assert_eq!(das.advance(MS * 33), Some((MoveKind::Left, 1)));
+        assert_eq!(das.time_until_move(), Some(DasState::ARR));
+
+        // every ARR after that, however the time comes in
+        assert_eq!(das.advance(MS * 5), None);
+        assert_eq!(das.advance(MS * 5), Some((MoveKind::Left, 1)));
+        assert_eq!(das.advance(MS * 35), Some((MoveKind::Left, 3)));
+        assert_eq!(das.time_until_move(), Some(MS * 5));
+
+        // a step that goes past the delay repeats for the part past it too
+        das.press(MoveKind::Right);
+        assert_eq!(das.advance(MS * 153), Some((MoveKind::Right, 3)));
+
+        // and however long it was, it goes no further than across the matrix
+        assert_eq!(
+            das.advance(Duration::from_secs(5)),
+            Some((MoveKind::Right, Engine::MATRIX_WIDTH))
+        );
+    }
+
+    #[test]
+    fn releasing_the_other_direction_keeps_it_going() {
+        let mut das = DasState::default();
+        das.press(MoveKind::Left);
+        das.press(MoveKind::Right);
+        das.release(MoveKind::Left);
+        assert_eq!(das.advance(DasState::DAS), Some((MoveKind::Right, 1)));
+
+        das.release(MoveKind::Right);
+        assert_eq!(das.advance(DasState::DAS), None);
+    }
+}
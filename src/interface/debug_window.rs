@@ -0,0 +1,253 @@
+use std::collections::VecDeque;
+
+use sdl2::pixels::Color;
+use sdl2::rect::Rect;
+use sdl2::render::Canvas;
+use sdl2::video::Window;
+use sdl2::Sdl;
+
+use super::error::InterfaceError;
+use super::renderer::Renderer;
+use super::sub_rect::{Align, SubRect};
+use super::text_draw::{TextCache, TextDrawContext};
+use tetris::engine::event::EngineEvent;
+use tetris::engine::piece_kind::PieceKind;
+use tetris::engine::Engine;
+
+const SIZE: (u32, u32) = (480, 720);
+const BACKGROUND_COLOR: Color = Color::RGB(0x10, 0x10, 0x10);
+const BAR_COLOR: Color = Color::RGB(0x66, 0x77, 0x77);
+const TEXT_LINES: usize = 7; // above the events
+
+// a second window with what's going on inside the engine, for chasing timing bugs. closing it only
+// closes it, the game goes on
+pub struct DebugWindow {
+    canvas: Canvas<Window>,
+    events: VecDeque<EngineEvent>, // the most recent ones, oldest first
+}
+
+impl DebugWindow {
+    pub const RECENT_EVENTS: usize = 10;
+
+    pub fn open(sdl: &Sdl) -> Result<Self, InterfaceError> {
+        let video = sdl.video().map_err(InterfaceError::SdlInit)?;
+        let window = video
+            .window("Tetris debug", SIZE.0, SIZE.1)
+            .resizable()
+            .build()
+            .map_err(|e| InterfaceError::WindowCreation(e.to_string()))?;
+
+        // without vsync, the game window already waits for that
+        let canvas = window
+            .into_canvas()
+            .accelerated()
+            .build()
+            .map_err(|e| InterfaceError::WindowCreation(e.to_string()))?;
+
+        Ok(Self {
+            canvas,
+            events: VecDeque::with_capacity(Self::RECENT_EVENTS),
+        })
+    }
+
+    // to tell its window events apart from the game window's
+    pub fn id(&self) -> u32 {
+        self.canvas.window().id()
+    }
+
+    pub fn record(&mut self, event: EngineEvent) {
+        record(&mut self.events, event);
+    }
+
+    pub fn render(
+        &mut self,
+        engine: &Engine,
+        text_cache: &mut TextCache<'_>,
+    ) -> Result<(), InterfaceError> {
+        self.canvas.set_draw_color(BACKGROUND_COLOR);
+        self.canvas.clear();
+
+        let area = self.canvas.viewport();
+        draw(
+            &mut self.canvas,
+            area,
+            engine,
+            &self.events,
+            Some(text_cache),
+        )?;
+
+        self.canvas.present();
+        Ok(())
+    }
+}
+
+fn record(events: &mut VecDeque<EngineEvent>, event: EngineEvent) {
+    if events.len() == DebugWindow::RECENT_EVENTS {
+        events.pop_front();
+    }
+    events.push_back(event);
+}
+
+fn kinds(kinds: &[PieceKind]) -> String {
+    kinds
+        .iter()
+        .map(|kind| format!("{kind:?}"))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+// the text part, one line each
+fn lines(engine: &Engine, events: &VecDeque<EngineEvent>) -> Vec<String> {
+    let hold = match engine.held() {
+        Some(kind) => format!("{kind:?}"),
+        None => "-".to_string(),
+    };
+    let lock = match engine.lock_elapsed {
+        Some(elapsed) => format!(
+            "{:.3}S OF {:.3}S",
+            elapsed.as_secs_f32(),
+            Engine::LOCK_DELAY.as_secs_f32()
+        ),
+        None => "-".to_string(),
+    };
+
+    let mut lines = vec![
+        format!("BAG {}", kinds(engine.remaining_bag())),
+        format!("NEXT {}", kinds(engine.upcoming())),
+        format!(
+            "HOLD {hold} {}",
+            if engine.can_hold() { "FREE" } else { "LOCKED" }
+        ),
+        format!(
+            "LOCK {lock} RESETS {}/{}",
+            engine.lock_resets,
+            Engine::MAX_LOCK_RESETS
+        ),
+        format!(
+            "GRAVITY {:.3}S OF {:.3}S",
+            engine.gravity_elapsed.as_secs_f32(),
+            engine.drop_time(false).as_secs_f32()
+        ),
+        format!(
+            "DANGER {} HOLES {}",
+            engine.danger_level(),
+            engine.matrix.count_holes()
+        ),
+        "EVENTS".to_string(),
+    ];
+    lines.extend(events.iter().rev().map(|event| format!("{event:?}")));
+
+    lines
+}
+
+// the lines at the top, the column heights as bars along the bottom third
+fn draw<R: Renderer>(
+    renderer: &mut R,
+    area: Rect,
+    engine: &Engine,
+    events: &VecDeque<EngineEvent>,
+    mut text_cache: Option<&mut TextCache<'_>>,
+) -> Result<(), InterfaceError> {
+    let text_height = area.height() * 2 / 3;
+    let line_height = text_height / (TEXT_LINES + DebugWindow::RECENT_EVENTS) as u32;
+
+    for (index, line) in lines(engine, events).iter().enumerate() {
+        let line_rect = Rect::new(
+            area.x(),
+            area.y() + (index as u32 * line_height) as i32,
+            area.width(),
+            line_height,
+        );
+
+        let mut text_draw_ctx: TextDrawContext<_> = TextDrawContext {
+            canvas: renderer,
+            text_cache: text_cache.as_deref_mut(),
+            text: line,
+            rect: SubRect::of(line_rect, (1.0, 0.8), Some((Align::Near, Align::Center))),
+        };
+        text_draw_ctx.draw_text()?;
+    }
+
+    let chart_height = area.height() - text_height;
+    let bar_width = area.width() / Engine::MATRIX_WIDTH as u32;
+    renderer.set_color(BAR_COLOR);
+
+    for (column, height) in engine.matrix.column_heights().into_iter().enumerate() {
+        if height == 0 {
+            continue;
+        }
+
+        let bar_height = chart_height * height as u32 / Engine::MATRIX_HEIGHT as u32;
+        let bar = Rect::new(
+            area.x() + (column as u32 * bar_width) as i32,
+            area.bottom() - bar_height as i32,
+            bar_width.saturating_sub(2).max(1),
+            bar_height.max(1),
+        );
+        renderer.fill_rect(bar).map_err(InterfaceError::Render)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::interface::renderer::{DrawCall, RecordingRenderer};
+    use tetris::engine::tspin::TSpinKind;
+
+    #[test]
+    fn only_the_latest_events_are_kept() {
+        let mut events = VecDeque::new();
+        for lines in 1..=15 {
+            record(
+                &mut events,
+                EngineEvent::LinesCleared {
+                    lines,
+                    tspin: TSpinKind::None,
+                },
+            );
+        }
+
+        assert_eq!(events.len(), DebugWindow::RECENT_EVENTS);
+        assert_eq!(
+            events[0],
+            EngineEvent::LinesCleared {
+                lines: 6,
+                tspin: TSpinKind::None
+            }
+        );
+
+        // newest first
+        let engine = Engine::new();
+        let lines = lines(&engine, &events);
+        assert_eq!(lines.len(), TEXT_LINES + DebugWindow::RECENT_EVENTS);
+        assert_eq!(lines[TEXT_LINES], "LinesCleared { lines: 15, tspin: None }");
+    }
+
+    #[test]
+    fn a_bar_for_every_column_with_cells() {
+        let mut engine = Engine::new();
+        engine.create_top_cursor(Some(PieceKind::O));
+        engine.hard_drop();
+        engine.lock_cursor();
+
+        let mut renderer = RecordingRenderer::default();
+        let area = Rect::new(0, 0, 500, 900);
+        draw(&mut renderer, area, &engine, &VecDeque::new(), None).unwrap();
+
+        let bars: Vec<Rect> = renderer
+            .calls
+            .iter()
+            .filter_map(|call| match call {
+                DrawCall::FillRect(rect) => Some(*rect),
+                _ => None,
+            })
+            .collect();
+
+        // an o is two columns of two cells, a tenth of the matrix height
+        assert_eq!(bars.len(), 2);
+        assert!(bars.iter().all(|bar| bar.height() == 300 * 2 / 20));
+        assert!(bars.iter().all(|bar| bar.bottom() == 900));
+    }
+}
@@ -0,0 +1,52 @@
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+// what can go wrong setting up or running the window; sdl itself only ever reports strings
+#[derive(Clone, PartialEq, Debug)]
+pub enum InterfaceError {
+    SdlInit(String), // sdl or one of its subsystems (video, events, ttf) didn't start
+    WindowCreation(String), // the window or its renderer
+    FontLoad(String), // the font file is there but couldn't be used
+    AssetMissing(PathBuf),
+    Render(String),
+}
+
+impl fmt::Display for InterfaceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::SdlInit(error) => write!(f, "Failed to initialize sdl: {error}"),
+            Self::WindowCreation(error) => write!(f, "Failed to create the window: {error}"),
+            Self::FontLoad(error) => write!(f, "Failed to load the font: {error}"),
+            Self::AssetMissing(path) => write!(f, "Missing asset: {}", path.display()),
+            Self::Render(error) => write!(f, "Failed to render: {error}"),
+        }
+    }
+}
+
+impl std::error::Error for InterfaceError {}
+
+// the path back if there's a file at it, so a missing asset can be told apart from a broken one
+pub fn check_asset(path: &Path) -> Result<&Path, InterfaceError> {
+    if path.is_file() {
+        Ok(path)
+    } else {
+        Err(InterfaceError::AssetMissing(path.to_path_buf()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn missing_font_is_reported_with_its_path() {
+        let path = Path::new("assets/NoSuchFont.ttf");
+
+        let error = check_asset(path).unwrap_err();
+        assert_eq!(error, InterfaceError::AssetMissing(path.to_path_buf()));
+        assert_eq!(error.to_string(), "Missing asset: assets/NoSuchFont.ttf");
+
+        // the font that ships with the game is there
+        assert!(check_asset(Path::new("assets/NewAmsterdam-Regular.ttf")).is_ok());
+    }
+}
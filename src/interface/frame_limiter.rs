@@ -0,0 +1,80 @@
+use std::time::Duration;
+
+// how the interface keeps its frame rate in check; vsync has the lowest power usage,
+// a manual cap or no cap at all trade that for lower input latency
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum FrameRate {
+    Vsync,       // present waits for the display refresh
+    Capped(u32), // sleep after each frame so at most this many frames happen per second
+    Uncapped,    // run the loop as fast as it goes
+}
+
+impl FrameRate {
+    // parse a command line value: "vsync", "uncapped" or a frames per second number
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "vsync" => Some(Self::Vsync),
+            "uncapped" => Some(Self::Uncapped),
+            fps => fps.parse().ok().filter(|&fps| fps > 0).map(Self::Capped),
+        }
+    }
+
+    pub fn is_vsync(&self) -> bool {
+        *self == Self::Vsync
+    }
+
+    // how long to sleep after a frame that took `elapsed`, only a manual cap ever sleeps
+    pub fn sleep_duration(&self, elapsed: Duration) -> Duration {
+        match self {
+            Self::Capped(fps) => frame_sleep(*fps, elapsed),
+            Self::Vsync | Self::Uncapped => Duration::ZERO,
+        }
+    }
+}
+
+// the time left in a frame at the target fps, zero if the frame already ran over
+pub fn frame_sleep(target_fps: u32, elapsed: Duration) -> Duration {
+    if target_fps == 0 {
+        return Duration::ZERO;
+    }
+
+    let frame_duration = Duration::from_secs(1) / target_fps;
+    frame_duration.saturating_sub(elapsed)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn sleeps_for_the_rest_of_the_frame() {
+        // 50 fps gives 20ms frames
+        assert_eq!(
+            frame_sleep(50, Duration::from_millis(5)),
+            Duration::from_millis(15)
+        );
+        assert_eq!(frame_sleep(50, Duration::ZERO), Duration::from_millis(20));
+
+        // a frame that ran over doesn't sleep at all
+        assert_eq!(frame_sleep(50, Duration::from_millis(30)), Duration::ZERO);
+        assert_eq!(frame_sleep(0, Duration::ZERO), Duration::ZERO);
+
+        assert_eq!(
+            FrameRate::Vsync.sleep_duration(Duration::ZERO),
+            Duration::ZERO
+        );
+        assert_eq!(
+            FrameRate::Capped(100).sleep_duration(Duration::from_millis(4)),
+            Duration::from_millis(6)
+        );
+    }
+
+    #[test]
+    fn parse() {
+        assert_eq!(FrameRate::parse("vsync"), Some(FrameRate::Vsync));
+        assert_eq!(FrameRate::parse("uncapped"), Some(FrameRate::Uncapped));
+        assert_eq!(FrameRate::parse("144"), Some(FrameRate::Capped(144)));
+        assert_eq!(FrameRate::parse("0"), None);
+        assert_eq!(FrameRate::parse("fast"), None);
+    }
+}
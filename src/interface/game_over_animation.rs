@@ -0,0 +1,87 @@
+use std::time::Duration;
+
+// the classic game over animation - the matrix is filled with gray from the bottom up, one row per tick
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct GameOverAnimation {
+    pub filled_rows: usize, // how many rows from the bottom are already gray
+    height: usize,          // how many rows there are to fill in total
+    elapsed: Duration,      // time since the last row was filled
+}
+
+impl GameOverAnimation {
+    pub const TICK_DURATION: Duration = Duration::from_millis(40); // 20 rows take 0.8s
+
+    pub fn new(height: usize) -> Self {
+        Self {
+            filled_rows: 0,
+            height,
+            elapsed: Duration::ZERO,
+        }
+    }
+
+    // fill one more row; returns whether there is anything left to fill after this tick
+    pub fn tick(&mut self) -> bool {
+        if !self.is_complete() {
+            self.filled_rows += 1;
+        }
+
+        !self.is_complete()
+    }
+
+    // let time pass, filling a row every TICK_DURATION; returns whether any row got filled
+    pub fn advance(&mut self, dt: Duration) -> bool {
+        self.elapsed += dt;
+
+        let mut filled = false;
+        while self.elapsed >= Self::TICK_DURATION && !self.is_complete() {
+            self.elapsed -= Self::TICK_DURATION;
+            self.tick();
+            filled = true;
+        }
+
+        filled
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.filled_rows >= self.height
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn fills_one_row_per_tick() {
+        let mut animation = GameOverAnimation::new(20);
+        assert_eq!(animation.filled_rows, 0);
+
+        for row in 1..20 {
+            assert!(animation.tick());
+            assert_eq!(animation.filled_rows, row);
+            assert!(!animation.is_complete());
+        }
+
+        // the last row completes the animation, further ticks do nothing
+        assert!(!animation.tick());
+        assert!(animation.is_complete());
+        assert!(!animation.tick());
+        assert_eq!(animation.filled_rows, 20);
+    }
+
+    #[test]
+    fn advance_fills_by_elapsed_time() {
+        let mut animation = GameOverAnimation::new(20);
+
+        assert!(!animation.advance(GameOverAnimation::TICK_DURATION / 2));
+        assert!(animation.advance(GameOverAnimation::TICK_DURATION / 2));
+        assert_eq!(animation.filled_rows, 1);
+
+        // a long frame fills several rows at once, but never more than there are
+        assert!(animation.advance(GameOverAnimation::TICK_DURATION * 3));
+        assert_eq!(animation.filled_rows, 4);
+        animation.advance(GameOverAnimation::TICK_DURATION * 100);
+        assert!(animation.is_complete());
+        assert_eq!(animation.filled_rows, 20);
+    }
+}
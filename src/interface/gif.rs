@@ -0,0 +1,374 @@
+// just enough of gif89a to write the game down as an animation: frames of up to 256 colors each, looping
+// forever, no transparency
+use std::collections::HashMap;
+use std::io::{self, Write};
+
+pub type Rgb = [u8; 3];
+
+// a frame as it's kept for encoding, row by row from the top left
+#[derive(Clone, PartialEq, Debug)]
+pub struct Frame {
+    pub width: usize,
+    pub height: usize,
+    pub pixels: Vec<Rgb>,
+}
+
+impl Frame {
+    // rgb24 pixels (three bytes each, no padding between rows) shrunk by `factor` both ways; every pixel
+    // is the average of the block it stands for, what doesn't fill a whole block at the edges is left out
+    pub fn downscaled(rgb: &[u8], width: usize, height: usize, factor: usize) -> Self {
+        let (scaled_width, scaled_height) = (width / factor, height / factor);
+        let block = (factor * factor) as u32;
+        let mut pixels = Vec::with_capacity(scaled_width * scaled_height);
+
+        for y in 0..scaled_height {
+            for x in 0..scaled_width {
+                let mut sum = [0u32; 3];
+                for dy in 0..factor {
+                    let row = (y * factor + dy) * width;
+                    for dx in 0..factor {
+                        let offset = (row + x * factor + dx) * 3;
+                        for (channel, total) in sum.iter_mut().enumerate() {
+                            *total += rgb[offset + channel] as u32;
+                        }
+                    }
+                }
+                pixels.push(sum.map(|total| ((total + block / 2) / block) as u8));
+            }
+        }
+
+        Self {
+            width: scaled_width,
+            height: scaled_height,
+            pixels,
+        }
+    }
+
+    // a palette of at most 256 colors and every pixel as an index into it. the exact colors if there
+    // aren't more than that, otherwise every color goes to the nearest one of a 6x6x6 color cube
+    pub fn quantized(&self) -> (Vec<Rgb>, Vec<u8>) {
+        let mut palette: Vec<Rgb> = Vec::new();
+        let mut indices: HashMap<Rgb, u8> = HashMap::new();
+        let mut exact = Vec::with_capacity(self.pixels.len());
+
+        for &pixel in &self.pixels {
+            let index = match indices.get(&pixel) {
+                Some(&index) => index,
+                None if palette.len() < 256 => {
+                    let index = palette.len() as u8;
+                    palette.push(pixel);
+                    indices.insert(pixel, index);
+                    index
+                }
+                None => return self.cube_quantized(),
+            };
+            exact.push(index);
+        }
+
+        (palette, exact)
+    }
+
+    fn cube_quantized(&self) -> (Vec<Rgb>, Vec<u8>) {
+        let level = |channel: u8| (channel as u16 * 5 + 127) / 255;
+        let palette = (0..216)
+            .map(|index: u16| {
+                [index / 36, index / 6 % 6, index % 6].map(|level| (level * 51) as u8)
+            })
+            .collect();
+        let indices = self
+            .pixels
+            .iter()
+            .map(|&[r, g, b]| (level(r) * 36 + level(g) * 6 + level(b)) as u8)
+            .collect();
+
+        (palette, indices)
+    }
+}
+
+// codes of a variable width, packed from the least significant bit up
+#[derive(Default)]
+struct BitWriter {
+    bytes: Vec<u8>,
+    pending: u32,
+    pending_bits: u8,
+}
+
+impl BitWriter {
+    fn write(&mut self, code: u16, bits: u8) {
+        self.pending |= (code as u32) << self.pending_bits;
+        self.pending_bits += bits;
+        while self.pending_bits >= 8 {
+            self.bytes.push(self.pending as u8);
+            self.pending >>= 8;
+            self.pending_bits -= 8;
+        }
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.pending_bits > 0 {
+            self.bytes.push(self.pending as u8);
+        }
+        self.bytes
+    }
+}
+
+const MAX_CODES: u16 = 4096; // codes are at most 12 bits wide
+
+// gif's flavor of lzw; codes widen as the table grows, and the table starts over once it's full
+fn lzw(indices: &[u8], min_code_size: u8) -> Vec<u8> {
+    let clear = 1u16 << min_code_size;
+    let end = clear + 1;
+    let mut table: HashMap<(u16, u8), u16> = HashMap::new();
+    let mut next = end + 1;
+    let mut size = min_code_size + 1;
+    let mut out = BitWriter::default();
+
+    // the reader widens its codes once the table it builds reaches the current width, which is one
+    // entry behind this one
+    let write = |out: &mut BitWriter, code: u16, size: &mut u8, next: u16| {
+        out.write(code, *size);
+        if next >= 1 << *size && *size < 12 {
+            *size += 1;
+        }
+    };
+
+    out.write(clear, size);
+    let mut current: Option<u16> = None;
+    for &index in indices {
+        let Some(prefix) = current else {
+            current = Some(index as u16);
+            continue;
+        };
+        if let Some(&code) = table.get(&(prefix, index)) {
+            current = Some(code);
+            continue;
+        }
+
+        write(&mut out, prefix, &mut size, next);
+        if next < MAX_CODES {
+            table.insert((prefix, index), next);
+            next += 1;
+        } else {
+            out.write(clear, size);
+            table.clear();
+            next = end + 1;
+            size = min_code_size + 1;
+        }
+        current = Some(index as u16);
+    }
+
+    if let Some(code) = current {
+        write(&mut out, code, &mut size, next);
+    }
+    out.write(end, size);
+    out.finish()
+}
+
+// an animated gif written frame by frame, so it never has to be held in memory as a whole
+pub struct GifWriter<W: Write> {
+    out: W,
+    width: u16,
+    height: u16,
+}
+
+impl<W: Write> GifWriter<W> {
+    pub fn new(mut out: W, width: u16, height: u16) -> io::Result<Self> {
+        out.write_all(b"GIF89a")?;
+        out.write_all(&width.to_le_bytes())?;
+        out.write_all(&height.to_le_bytes())?;
+        out.write_all(&[0, 0, 0])?; // no global color table, every frame brings its own
+
+        // loop forever
+        out.write_all(&[0x21, 0xff, 0x0b])?;
+        out.write_all(b"NETSCAPE2.0")?;
+        out.write_all(&[0x03, 0x01, 0x00, 0x00, 0x00])?;
+
+        Ok(Self { out, width, height })
+    }
+
+    // `delay` is how long the frame shows, in hundredths of a second
+    pub fn frame(&mut self, frame: &Frame, delay: u16) -> io::Result<()> {
+        if (frame.width, frame.height) != (self.width as usize, self.height as usize) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "a {}x{} frame in a {}x{} gif",
+                    frame.width, frame.height, self.width, self.height
+                ),
+            ));
+        }
+
+        let (palette, indices) = frame.quantized();
+        // the color table holds a power of two colors, at least 4 for the smallest lzw code size
+        let table_bits = (palette.len().max(4) as u32)
+            .next_power_of_two()
+            .trailing_zeros() as u8;
+
+        self.out.write_all(&[0x21, 0xf9, 0x04, 0x00])?;
+        self.out.write_all(&delay.to_le_bytes())?;
+        self.out.write_all(&[0x00, 0x00])?;
+
+        self.out.write_all(&[0x2c, 0, 0, 0, 0])?;
+        self.out.write_all(&self.width.to_le_bytes())?;
+        self.out.write_all(&self.height.to_le_bytes())?;
+        self.out.write_all(&[0x80 | (table_bits - 1)])?;
+        for index in 0..1usize << table_bits {
+            self.out
+                .write_all(palette.get(index).unwrap_or(&[0, 0, 0]))?;
+        }
+
+        self.out.write_all(&[table_bits])?;
+        for block in lzw(&indices, table_bits).chunks(255) {
+            self.out.write_all(&[block.len() as u8])?;
+            self.out.write_all(block)?;
+        }
+        self.out.write_all(&[0]) // no more sub-blocks
+    }
+
+    pub fn finish(mut self) -> io::Result<W> {
+        self.out.write_all(&[0x3b])?;
+        self.out.flush()?;
+        Ok(self.out)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    // reads what lzw wrote back into indices
+    fn unlzw(bytes: &[u8], min_code_size: u8) -> Vec<u8> {
+        let clear = 1u16 << min_code_size;
+        let end = clear + 1;
+        let initial: Vec<Vec<u8>> = (0..clear).map(|index| vec![index as u8]).collect();
+        let mut table = initial.clone();
+        table.extend([vec![], vec![]]); // clear and end
+        let mut size = min_code_size + 1;
+        let mut previous: Option<Vec<u8>> = None;
+        let mut out = Vec::new();
+        let (mut bit, total_bits) = (0, bytes.len() * 8);
+
+        while bit + size as usize <= total_bits {
+            let mut code = 0u16;
+            for offset in 0..size as usize {
+                let at = bit + offset;
+                code |= (((bytes[at / 8] >> (at % 8)) & 1) as u16) << offset;
+            }
+            bit += size as usize;
+
+            if code == clear {
+                table.truncate(end as usize + 1);
+                size = min_code_size + 1;
+                previous = None;
+                continue;
+            }
+            if code == end {
+                break;
+            }
+
+            let entry = match (table.get(code as usize), &previous) {
+                (Some(entry), _) => entry.clone(),
+                (None, Some(previous)) => [previous.clone(), vec![previous[0]]].concat(),
+                (None, None) => panic!("code {code} out of nowhere"),
+            };
+            out.extend(&entry);
+            if let Some(previous) = previous {
+                if table.len() < MAX_CODES as usize {
+                    table.push([previous, vec![entry[0]]].concat());
+                }
+            }
+            if table.len() == 1 << size && size < 12 {
+                size += 1;
+            }
+            previous = Some(entry);
+        }
+
+        out
+    }
+
+    #[test]
+    fn downscaling_averages_blocks() {
+        // 4x2 pixels: a black and a white block, then a block of red and blue
+        let mut rgb = vec![0; 4 * 2 * 3];
+        for (x, y) in [(2, 0), (3, 0), (2, 1), (3, 1)] {
+            rgb[(y * 4 + x) * 3..][..3].copy_from_slice(&[255, 255, 255]);
+        }
+        let frame = Frame::downscaled(&rgb, 4, 2, 2);
+        assert_eq!((frame.width, frame.height), (2, 1));
+        assert_eq!(frame.pixels, [[0, 0, 0], [255, 255, 255]]);
+
+        let rgb = [255, 0, 0, 0, 0, 255, 255, 0, 0, 0, 0, 255];
+        let frame = Frame::downscaled(&rgb, 2, 2, 2);
+        assert_eq!(frame.pixels, [[128, 0, 128]]);
+    }
+
+    #[test]
+    fn few_colors_are_kept_exactly() {
+        let frame = Frame {
+            width: 3,
+            height: 1,
+            pixels: vec![[16, 16, 24], [102, 119, 119], [16, 16, 24]],
+        };
+        let (palette, indices) = frame.quantized();
+        assert_eq!(palette, [[16, 16, 24], [102, 119, 119]]);
+        assert_eq!(indices, [0, 1, 0]);
+    }
+
+    #[test]
+    fn many_colors_go_to_the_color_cube() {
+        let pixels: Vec<Rgb> = (0..300).map(|i| [i as u8, (i / 2) as u8, 255]).collect();
+        let frame = Frame {
+            width: 300,
+            height: 1,
+            pixels,
+        };
+        let (palette, indices) = frame.quantized();
+        assert_eq!(palette.len(), 216);
+
+        // every pixel ends up within half a cube step of its color
+        for (pixel, &index) in frame.pixels.iter().zip(&indices) {
+            let color = palette[index as usize];
+            for channel in 0..3 {
+                assert!((pixel[channel] as i32 - color[channel] as i32).abs() <= 26);
+            }
+        }
+    }
+
+    #[test]
+    fn lzw_reads_back() {
+        let short = [0, 1, 1, 1, 2, 3, 3, 0, 1, 1, 1];
+        assert_eq!(unlzw(&lzw(&short, 2), 2), short);
+
+        // long enough for the table to fill up and start over a few times
+        let long: Vec<u8> = (0..60_000u32)
+            .map(|i| (((i * 7919) % 251) ^ (i / 300)) as u8)
+            .collect();
+        assert_eq!(unlzw(&lzw(&long, 8), 8), long);
+    }
+
+    #[test]
+    fn gif_is_framed() {
+        let frame = Frame {
+            width: 2,
+            height: 2,
+            pixels: vec![[0, 0, 0], [255, 0, 0], [0, 255, 0], [0, 0, 255]],
+        };
+        let mut gif = GifWriter::new(Vec::new(), 2, 2).unwrap();
+        gif.frame(&frame, 4).unwrap();
+        gif.frame(&frame, 4).unwrap();
+        let bytes = gif.finish().unwrap();
+
+        assert!(bytes.starts_with(b"GIF89a\x02\x00\x02\x00"));
+        assert_eq!(bytes.last(), Some(&0x3b));
+        assert_eq!(
+            bytes
+                .windows(4)
+                .filter(|w| w == &[0x21, 0xf9, 0x04, 0x00])
+                .count(),
+            2
+        );
+
+        let mut gif = GifWriter::new(Vec::new(), 4, 4).unwrap();
+        assert!(gif.frame(&frame, 4).is_err());
+    }
+}
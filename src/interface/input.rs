@@ -1,3 +1,4 @@
+use sdl2::event::Event;
 use sdl2::keyboard::Keycode;
 
 use crate::engine::{move_kind::MoveKind, piece_rotation::Rotation};
@@ -11,28 +12,359 @@ pub enum Input {
     Pause,
     Hold,
     Continue,
+    Restart,
+}
+
+// the actions a key can be bound to
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Action {
+    MoveLeft,
+    MoveRight,
+    Rotate,
+    RotateCounterclockwise,
+    SoftDrop,
+    HardDrop,
+    Pause,
+    Hold,
+    Continue,
+    Restart,
+}
+
+impl Action {
+    pub const ALL: [Self; 10] = [
+        Self::MoveLeft,
+        Self::MoveRight,
+        Self::Rotate,
+        Self::RotateCounterclockwise,
+        Self::SoftDrop,
+        Self::HardDrop,
+        Self::Pause,
+        Self::Hold,
+        Self::Continue,
+        Self::Restart,
+    ];
+}
+
+// which key triggers which action; every action has exactly one key
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct KeyBindings {
+    pub move_left: Keycode,
+    pub move_right: Keycode,
+    pub rotate: Keycode,
+    pub rotate_counterclockwise: Keycode,
+    pub soft_drop: Keycode,
+    pub hard_drop: Keycode,
+    pub pause: Keycode,
+    pub hold: Keycode,
+    pub continue_game: Keycode,
+    pub restart: Keycode,
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        Self {
+            move_left: Keycode::Left,
+            move_right: Keycode::Right,
+            rotate: Keycode::Up,
+            rotate_counterclockwise: Keycode::Z,
+            soft_drop: Keycode::Down,
+            hard_drop: Keycode::Space,
+            pause: Keycode::NUM_1,
+            hold: Keycode::C,
+            continue_game: Keycode::Return,
+            restart: Keycode::R,
+        }
+    }
+}
+
+// whole sets of key bindings to pick from at once instead of rebinding key by key
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ControlPreset {
+    Guideline, // arrows, space to hard drop and c to hold
+    Classic,   // arrows with x to rotate, like on a gamepad; up hard drops
+    Wasd,      // the left hand on wasd, the right one free
+    Custom,    // keys picked one by one
+}
+
+impl ControlPreset {
+    pub const ALL: [Self; 4] = [Self::Guideline, Self::Classic, Self::Wasd, Self::Custom];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Guideline => "guideline",
+            Self::Classic => "classic",
+            Self::Wasd => "wasd",
+            Self::Custom => "custom",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|preset| preset.name().eq_ignore_ascii_case(name))
+    }
+
+    // the bindings the preset stands for, None for custom ones
+    pub fn bindings(&self) -> Option<KeyBindings> {
+        let guideline = KeyBindings::default();
+
+        match self {
+            Self::Guideline => Some(guideline),
+            Self::Classic => Some(KeyBindings {
+                rotate: Keycode::X,
+                hard_drop: Keycode::Up,
+                hold: Keycode::LShift,
+                ..guideline
+            }),
+            Self::Wasd => Some(KeyBindings {
+                move_left: Keycode::A,
+                move_right: Keycode::D,
+                rotate: Keycode::W,
+                soft_drop: Keycode::S,
+                hold: Keycode::LShift,
+                ..guideline
+            }),
+            Self::Custom => None,
+        }
+    }
+}
+
+impl KeyBindings {
+    pub fn key(&self, action: Action) -> Keycode {
+        match action {
+            Action::MoveLeft => self.move_left,
+            Action::MoveRight => self.move_right,
+            Action::Rotate => self.rotate,
+            Action::RotateCounterclockwise => self.rotate_counterclockwise,
+            Action::SoftDrop => self.soft_drop,
+            Action::HardDrop => self.hard_drop,
+            Action::Pause => self.pause,
+            Action::Hold => self.hold,
+            Action::Continue => self.continue_game,
+            Action::Restart => self.restart,
+        }
+    }
+
+    pub(super) fn key_mut(&mut self, action: Action) -> &mut Keycode {
+        match action {
+            Action::MoveLeft => &mut self.move_left,
+            Action::MoveRight => &mut self.move_right,
+            Action::Rotate => &mut self.rotate,
+            Action::RotateCounterclockwise => &mut self.rotate_counterclockwise,
+            Action::SoftDrop => &mut self.soft_drop,
+            Action::HardDrop => &mut self.hard_drop,
+            Action::Pause => &mut self.pause,
+            Action::Hold => &mut self.hold,
+            Action::Continue => &mut self.continue_game,
+            Action::Restart => &mut self.restart,
+        }
+    }
+
+    // which action the key is bound to, if any
+    pub fn action(&self, key: Keycode) -> Option<Action> {
+        Action::ALL
+            .into_iter()
+            .find(|&action| self.key(action) == key)
+    }
+
+    // binds the key to the action; if another action already had that key, the two actions swap keys
+    // so nothing is left unbound and no key triggers two actions
+    pub fn rebind(&mut self, action: Action, key: Keycode) {
+        let old_key = self.key(action);
+
+        if let Some(other) = self.action(key) {
+            *self.key_mut(other) = old_key;
+        }
+
+        *self.key_mut(action) = key;
+    }
 }
 
 // map various keyboard keys to actions within the game
 impl Input {
-    pub fn try_from(key: Keycode, next_rotation: Option<Rotation>) -> Result<Input, ()> {
-        println!("{:?}", key);
-        Ok(match key {
-            Keycode::Right => Self::Move(MoveKind::Right),
-            Keycode::Left => Self::Move(MoveKind::Left),
-            Keycode::Return => Self::Continue,
-            Keycode::Up => {
-                if let Some(rotation) = next_rotation {
-                    Self::Rotation(rotation)
+    // `rotation` is the one the cursor has now, a rotation key turns it from there
+    pub fn try_from_with_bindings(
+        key: Keycode,
+        bindings: &KeyBindings,
+        rotation: Option<Rotation>,
+    ) -> Result<Input, ()> {
+        log::trace!("Key {key:?}");
+        Ok(match bindings.action(key).ok_or(())? {
+            Action::MoveRight => Self::Move(MoveKind::Right),
+            Action::MoveLeft => Self::Move(MoveKind::Left),
+            Action::Continue => Self::Continue,
+            Action::Rotate => {
+                if let Some(rotation) = rotation {
+                    Self::Rotation(rotation.next_rotation())
                 } else {
                     Self::Rotation(Rotation::N)
                 }
             }
-            Keycode::Down => Self::SoftDrop,
-            Keycode::Space => Self::HardDrop,
-            Keycode::NUM_1 => Self::Pause,
-            Keycode::C => Self::Hold,
-            _ => return Err(()),
+            Action::RotateCounterclockwise => {
+                if let Some(rotation) = rotation {
+                    Self::Rotation(rotation.prev_rotation())
+                } else {
+                    Self::Rotation(Rotation::N)
+                }
+            }
+            Action::SoftDrop => Self::SoftDrop,
+            Action::HardDrop => Self::HardDrop,
+            Action::Pause => Self::Pause,
+            Action::Hold => Self::Hold,
+            Action::Restart => Self::Restart,
+        })
+    }
+}
+
+// the move a key down event asks for, if any
+fn pressed_move(event: &Event, bindings: &KeyBindings) -> Option<MoveKind> {
+    match event {
+        Event::KeyDown {
+            keycode: Some(key), ..
+        } => match bindings.action(*key)? {
+            Action::MoveLeft => Some(MoveKind::Left),
+            Action::MoveRight => Some(MoveKind::Right),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+// when left and right are both pressed within one poll batch, only the last pressed one moves the cursor;
+// a move is dropped if the opposite move is pressed later in the same batch
+pub fn cancel_opposite_moves(events: Vec<Event>, bindings: &KeyBindings) -> Vec<Event> {
+    let moves: Vec<Option<MoveKind>> = events
+        .iter()
+        .map(|event| pressed_move(event, bindings))
+        .collect();
+
+    events
+        .into_iter()
+        .enumerate()
+        .filter(|&(index, _)| match moves[index] {
+            Some(kind) => !moves[index + 1..]
+                .iter()
+                .any(|&later| later.is_some_and(|later| later != kind)),
+            None => true,
         })
+        .map(|(_, event)| event)
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use sdl2::keyboard::Mod;
+
+    fn key_down(key: Keycode) -> Event {
+        Event::KeyDown {
+            timestamp: 0,
+            window_id: 0,
+            keycode: Some(key),
+            scancode: None,
+            keymod: Mod::NOMOD,
+            repeat: false,
+        }
+    }
+
+    fn moves(events: &[Event], bindings: &KeyBindings) -> Vec<MoveKind> {
+        events
+            .iter()
+            .filter_map(|event| pressed_move(event, bindings))
+            .collect()
+    }
+
+    #[test]
+    fn opposite_moves_in_one_batch_last_pressed_wins() {
+        let bindings = KeyBindings::default();
+
+        let events = cancel_opposite_moves(
+            vec![key_down(Keycode::Left), key_down(Keycode::Right)],
+            &bindings,
+        );
+        assert_eq!(moves(&events, &bindings), vec![MoveKind::Right]);
+
+        let events = cancel_opposite_moves(
+            vec![
+                key_down(Keycode::Right),
+                key_down(Keycode::Space),
+                key_down(Keycode::Left),
+            ],
+            &bindings,
+        );
+        assert_eq!(moves(&events, &bindings), vec![MoveKind::Left]);
+        assert_eq!(events.len(), 2); // the hard drop is left alone
+
+        // moves in the same direction aren't opposite, both still go through
+        let events = cancel_opposite_moves(
+            vec![key_down(Keycode::Left), key_down(Keycode::Left)],
+            &bindings,
+        );
+        assert_eq!(events.len(), 2);
+    }
+
+    #[test]
+    fn rebind_swaps_conflicting_keys() {
+        let mut bindings = KeyBindings::default();
+
+        // space belongs to hard drop, so hard drop should end up on the old hold key
+        bindings.rebind(Action::Hold, Keycode::Space);
+
+        assert_eq!(bindings.hold, Keycode::Space);
+        assert_eq!(bindings.hard_drop, Keycode::C);
+
+        // every action is still bound and no key is used twice
+        for action in Action::ALL {
+            let key = bindings.key(action);
+            assert_eq!(bindings.action(key), Some(action));
+        }
+    }
+
+    #[test]
+    fn classic_preset() {
+        let bindings = ControlPreset::Classic.bindings().unwrap();
+
+        assert_eq!(bindings.rotate, Keycode::X);
+        assert_eq!(bindings.move_left, Keycode::Left);
+        assert_eq!(bindings.move_right, Keycode::Right);
+        assert_eq!(bindings.soft_drop, Keycode::Down);
+        assert_eq!(bindings.hard_drop, Keycode::Up);
+
+        // every preset binds every action to a key of its own
+        for preset in ControlPreset::ALL {
+            let Some(bindings) = preset.bindings() else {
+                continue;
+            };
+            for action in Action::ALL {
+                assert_eq!(bindings.action(bindings.key(action)), Some(action));
+            }
+        }
+
+        assert_eq!(
+            ControlPreset::parse("Classic"),
+            Some(ControlPreset::Classic)
+        );
+        assert_eq!(ControlPreset::parse("nes"), None);
+    }
+
+    #[test]
+    fn z_turns_the_other_way() {
+        let bindings = KeyBindings::default();
+        let turned = |key| match Input::try_from_with_bindings(key, &bindings, Some(Rotation::N)) {
+            Ok(Input::Rotation(rotation)) => Some(rotation),
+            _ => None,
+        };
+
+        assert_eq!(turned(Keycode::Up), Some(Rotation::E));
+        assert_eq!(turned(Keycode::Z), Some(Rotation::W));
+    }
+
+    #[test]
+    fn rebind_to_free_key() {
+        let mut bindings = KeyBindings::default();
+        bindings.rebind(Action::Hold, Keycode::LShift);
+
+        assert_eq!(bindings.hold, Keycode::LShift);
+        assert_eq!(bindings.action(Keycode::C), None);
     }
 }
@@ -0,0 +1,270 @@
+use sdl2::event::WindowEvent;
+use sdl2::rect::Rect;
+
+use super::sub_rect::{Align, SubRect};
+use tetris::engine::Engine;
+
+// every area a frame is drawn into, worked out from the size of the window. it only changes when the
+// window does, so it's kept around between frames instead of being rebuilt for each of them
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Layout {
+    pub viewport: Rect,
+    pub ui_square: SubRect, // the square into which we draw, the margins around it can be left/right or top/bottom
+    pub matrix_container: SubRect,
+    pub matrix: SubRect,
+    pub up_next: SubRect,
+    pub hold: SubRect,
+    pub queue: SubRect,
+    pub score: SubRect,
+    pub mini_map: SubRect,
+    pub up_next_text: SubRect,
+    pub hold_text: SubRect,
+    pub level_label: SubRect,
+    pub level_value: SubRect,
+    pub score_label: SubRect,
+    pub score_value: SubRect,
+    pub bonus_text: SubRect, // the combo and back-to-back going on, along the bottom of the score box
+    pub bag_text: SubRect,
+    pub piece_stats: SubRect, // a slot for each kind of tetrimino, in the order of PieceKind::ALL
+    pub menu_area: SubRect,   // pause menu lines are stacked in here
+    pub entry_area: SubRect,  // and the seed entry lines in here
+    pub review_text: SubRect,
+    pub game_over_text: SubRect,
+    pub high_scores: SubRect, // under the game over text
+}
+
+impl Layout {
+    // the widest the panels spread out to with wide_panels, as width over height; about 21:9
+    pub const MAX_WIDE_ASPECT: f32 = 2.4;
+
+    // the design is all based upon a 16x15 grid which is further divided into 4ths (see grid.png) -
+    // the system is based upon first positioning the container, then an inner rect relative to id.
+    // the queue is as high as the `previews` tetriminos in it need. with `wide_panels`, a window wider
+    // than it's high has the panels on either side spread out into the margins, each column of them in
+    // the middle of the space beside the matrix
+    pub fn new(viewport: Rect, previews: usize, wide_panels: bool) -> Self {
+        let ui_square = SubRect::absolute(viewport, (1.0, 1.0), None);
+
+        let matrix_container = ui_square.sub_rect((0.5, 1.0), None); // half of the width and full height, center alignment by default
+
+        let matrix = ui_square
+            .sub_rect((0.5, 1.0), None) // half of the width and full height, center alignment by default
+            .sub_rect((7.0 / 8.0, 7.0 / 8.0), None); // 7/8ths of the width and 7/8ths of the height, center by default
+
+        // the columns of panels to the left and right of the matrix, a quarter of the square each
+        let (left, right) = if wide_panels {
+            Self::spread_columns(viewport, ui_square, matrix_container)
+        } else {
+            (
+                ui_square.sub_rect((0.25, 1.0), Some((Align::Near, Align::Center))),
+                ui_square.sub_rect((0.25, 1.0), Some((Align::Far, Align::Center))),
+            )
+        };
+
+        // top right container for coming up tetrimino
+        let up_next = right
+            .sub_rect((1.0, 0.25), Some((Align::Far, Align::Near))) // top right container
+            .sub_rect((7.0 / 8.0, 7.0 / 8.0), Some((Align::Center, Align::Center))); // inside the top right container
+
+        // top left container for hold tetrimino
+        let hold = left
+            .sub_rect((1.0, 0.25), Some((Align::Near, Align::Near)))
+            .sub_rect((0.64, 0.64), None);
+
+        // bottom right where next tetriminos are displayed, shrinking from the bottom with fewer of them
+        let shown = previews.min(Engine::MAX_PREVIEWS) as f32 / Engine::MAX_PREVIEWS as f32;
+        let queue = right
+            .sub_rect((1.0, 0.75), Some((Align::Far, Align::Far)))
+            .sub_rect(
+                (5.0 / 10.0, 23.0 / 24.0 * shown),
+                Some((Align::Center, Align::Near)),
+            );
+
+        // bottom left score box
+        let score = left
+            .sub_rect((1.0, 11.0 / 16.0), Some((Align::Near, Align::Far)))
+            .sub_rect((7.0 / 8.0, 8.0 / 11.0), Some((Align::Center, Align::Near)));
+
+        // bottom left, below the score box, the mini-map of the whole matrix (1:2 like the matrix itself)
+        let mini_map = SubRect::absolute(
+            Rect::from(left.sub_rect((1.0, 3.0 / 16.0), Some((Align::Near, Align::Far)))),
+            (0.5, 1.0),
+            None,
+        )
+        .sub_rect((7.0 / 8.0, 7.0 / 8.0), None);
+
+        let score_top = score.sub_rect((1.0, 0.5), Some((Align::Center, Align::Near)));
+        let score_bottom = score.sub_rect((1.0, 0.5), Some((Align::Center, Align::Far)));
+
+        Self {
+            viewport,
+            ui_square,
+            matrix_container,
+            matrix,
+            up_next,
+            hold,
+            queue,
+            score,
+            mini_map,
+            up_next_text: up_next.sub_rect((0.5, 0.2), Some((Align::Center, Align::Near))),
+            hold_text: hold.sub_rect((0.5, 0.25), Some((Align::Center, Align::Near))),
+            level_label: score_top.sub_rect((0.5, 0.25), Some((Align::Center, Align::Near))),
+            level_value: score_top.sub_rect((0.8, 0.85), Some((Align::Center, Align::Far))),
+            score_label: score_bottom.sub_rect((0.5, 0.25), Some((Align::Center, Align::Near))),
+            score_value: score_bottom.sub_rect((0.8, 0.85), Some((Align::Center, Align::Far))),
+            bonus_text: score_bottom.sub_rect((0.6, 0.15), Some((Align::Center, Align::Far))),
+            // below the matrix, the tetriminos still left in the current bag
+            bag_text: matrix_container
+                .sub_rect((7.0 / 8.0, 1.0 / 16.0), Some((Align::Center, Align::Far))),
+            // above the matrix, the tetriminos placed so far
+            piece_stats: matrix_container
+                .sub_rect((7.0 / 8.0, 1.0 / 16.0), Some((Align::Center, Align::Near))),
+            menu_area: matrix_container.sub_rect((0.8, 0.5), None),
+            entry_area: matrix_container.sub_rect((0.8, 0.4), None),
+            review_text: matrix_container.sub_rect((0.8, 0.05), Some((Align::Center, Align::Near))),
+            game_over_text: matrix_container
+                .sub_rect((0.8, 0.1), Some((Align::Center, Align::Center))),
+            high_scores: matrix_container.sub_rect((0.6, 0.4), Some((Align::Center, Align::Far))),
+        }
+    }
+
+    // the panel columns in the middle of the space between the matrix and the edges of an area as wide
+    // as the window, up to MAX_WIDE_ASPECT; just the quarters of the square on a narrower window
+    fn spread_columns(viewport: Rect, ui_square: SubRect, matrix: SubRect) -> (SubRect, SubRect) {
+        let (width, height) = (viewport.width() as f32, viewport.height() as f32);
+        let aspect = (width / height).clamp(1.0, Self::MAX_WIDE_ASPECT);
+        let area = Rect::from(SubRect::absolute(viewport, (aspect, 1.0), None));
+        let (square, matrix) = (Rect::from(ui_square), Rect::from(matrix));
+
+        let column = |x: i32, right: i32| {
+            let outer = Rect::new(x, area.y(), (right - x).max(1) as u32, area.height());
+            let width = square.width() as f32 / 4.0 / outer.width() as f32;
+            SubRect::of(outer, (width.min(1.0), 1.0), None)
+        };
+
+        (
+            column(area.left(), matrix.left()),
+            column(matrix.right(), area.right()),
+        )
+    }
+
+    // whether the window changed in a way that needs the layout worked out again
+    pub fn is_outdated_by(event: &WindowEvent) -> bool {
+        matches!(
+            event,
+            WindowEvent::Resized(..) | WindowEvent::SizeChanged(..)
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn contains(outer: SubRect, inner: SubRect) -> bool {
+        Rect::from(outer).contains_rect(Rect::from(inner))
+    }
+
+    #[test]
+    fn same_window_same_layout() {
+        let viewport = Rect::new(0, 0, 1024, 1024);
+
+        assert_eq!(
+            Layout::new(viewport, Engine::MAX_PREVIEWS, false),
+            Layout::new(viewport, Engine::MAX_PREVIEWS, false)
+        );
+        assert!(!Layout::is_outdated_by(&WindowEvent::FocusLost));
+        assert!(!Layout::is_outdated_by(&WindowEvent::Moved(10, 10)));
+        assert!(!Layout::is_outdated_by(&WindowEvent::Exposed));
+    }
+
+    #[test]
+    fn resizing_changes_the_layout() {
+        let before = Layout::new(Rect::new(0, 0, 1024, 1024), Engine::MAX_PREVIEWS, false);
+        assert!(Layout::is_outdated_by(&WindowEvent::Resized(1600, 900)));
+        assert!(Layout::is_outdated_by(&WindowEvent::SizeChanged(1600, 900)));
+        let after = Layout::new(Rect::new(0, 0, 1600, 900), Engine::MAX_PREVIEWS, false);
+
+        assert_ne!(after, before);
+        assert_ne!(Rect::from(after.matrix), Rect::from(before.matrix));
+
+        // landscape: the square is as high as the window and centered across it
+        let square = Rect::from(after.ui_square);
+        assert_eq!((square.width(), square.height()), (900, 900));
+        assert_eq!(square.x(), 350);
+
+        for layout in [before, after] {
+            assert!(contains(layout.ui_square, layout.matrix_container));
+            assert!(contains(layout.matrix_container, layout.matrix));
+            assert!(contains(layout.ui_square, layout.queue));
+            assert!(contains(layout.score, layout.score_value));
+            assert!(contains(layout.matrix_container, layout.menu_area));
+        }
+    }
+
+    #[test]
+    fn queue_height_follows_the_previews() {
+        let viewport = Rect::new(0, 0, 1024, 1024);
+        let height =
+            |previews| Rect::from(Layout::new(viewport, previews, false).queue).height() as f32;
+        let full = height(Engine::MAX_PREVIEWS);
+
+        for previews in 1..Engine::MAX_PREVIEWS {
+            let expected = full * previews as f32 / Engine::MAX_PREVIEWS as f32;
+            assert!(
+                (height(previews) - expected).abs() <= 1.0,
+                "{previews} previews"
+            );
+        }
+        assert!(height(0) <= 1.0, "sdl makes an empty rect a pixel high");
+
+        // more than fit are as many as fit, and the top of the queue stays where it is
+        assert_eq!(height(Engine::MAX_PREVIEWS + 3), full);
+        let top = |previews| Rect::from(Layout::new(viewport, previews, false).queue).top();
+        assert_eq!(top(2), top(Engine::MAX_PREVIEWS));
+    }
+
+    #[test]
+    fn wide_panels_spread_out_on_an_ultrawide_window() {
+        let viewport = Rect::new(0, 0, 3440, 1440);
+        let narrow = Layout::new(viewport, Engine::MAX_PREVIEWS, false);
+        let wide = Layout::new(viewport, Engine::MAX_PREVIEWS, true);
+
+        // the matrix stays where it is, the panels move out towards the edges
+        assert_eq!(wide.matrix, narrow.matrix);
+        assert!(Rect::from(wide.hold).left() < Rect::from(narrow.hold).left());
+        assert!(Rect::from(wide.queue).left() > Rect::from(narrow.queue).left());
+        assert_eq!(Rect::from(wide.hold).size(), Rect::from(narrow.hold).size());
+
+        let panels = [
+            wide.matrix_container,
+            wide.up_next,
+            wide.hold,
+            wide.queue,
+            wide.score,
+            wide.mini_map,
+        ]
+        .map(Rect::from);
+        for (index, panel) in panels.iter().enumerate() {
+            assert!(viewport.contains_rect(*panel), "{panel:?}");
+            for other in &panels[index + 1..] {
+                assert!(!panel.has_intersection(*other), "{panel:?} and {other:?}");
+            }
+        }
+
+        // past MAX_WIDE_ASPECT they don't spread any further
+        let gap = |width| {
+            let layout = Layout::new(Rect::new(0, 0, width, 1440), Engine::MAX_PREVIEWS, true);
+            Rect::from(layout.matrix).left() - Rect::from(layout.hold).left()
+        };
+        assert!(gap(3440) > gap(2560));
+        assert!(gap(3600).abs_diff(gap(5120)) <= 1, "give or take rounding");
+
+        // and a square one has nowhere to spread them
+        let square = Rect::new(0, 0, 1024, 1024);
+        assert_eq!(
+            Rect::from(Layout::new(square, Engine::MAX_PREVIEWS, true).hold),
+            Rect::from(Layout::new(square, Engine::MAX_PREVIEWS, false).hold)
+        );
+    }
+}
@@ -0,0 +1,60 @@
+use std::time::Duration;
+
+// the level that was just reached, big over the matrix for a moment along with how fast the tetriminos
+// fall now
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct LevelUp {
+    pub level: u8,
+    pub gravity: f32, // see Engine::gravity
+    pub elapsed: Duration,
+}
+
+impl LevelUp {
+    pub fn new(level: u8, gravity: f32) -> Self {
+        Self {
+            level,
+            gravity,
+            elapsed: Duration::ZERO,
+        }
+    }
+
+    // whether it's still showing after being up for `elapsed` out of `duration`; zero turns it off
+    pub fn is_visible(&self, duration: Duration) -> bool {
+        self.elapsed < duration
+    }
+
+    // let time pass; returns whether it's still showing
+    pub fn advance(&mut self, dt: Duration, duration: Duration) -> bool {
+        self.elapsed += dt;
+        self.is_visible(duration)
+    }
+
+    pub fn lines(&self) -> [String; 2] {
+        [
+            format!("LEVEL {}", self.level),
+            format!("GRAVITY {:.3}G", self.gravity),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn shows_for_the_configured_duration() {
+        let duration = Duration::from_millis(1000);
+        let mut level_up = LevelUp::new(4, 0.05);
+        assert!(level_up.is_visible(duration));
+
+        assert!(level_up.advance(Duration::from_millis(999), duration));
+        assert!(!level_up.advance(Duration::from_millis(1), duration));
+        assert!(!level_up.is_visible(duration));
+
+        // a longer duration keeps it up longer, none doesn't show it at all
+        assert!(level_up.is_visible(Duration::from_millis(1500)));
+        assert!(!LevelUp::new(4, 0.05).is_visible(Duration::ZERO));
+
+        assert_eq!(level_up.lines(), ["LEVEL 4", "GRAVITY 0.050G"]);
+    }
+}
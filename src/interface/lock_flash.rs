@@ -0,0 +1,115 @@
+use sdl2::pixels::Color;
+use std::time::Duration;
+
+use crate::engine::{piece::Piece, Coordinate};
+
+// how the flash looks; the default white doesn't show up on light colors, hence configurable
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct LockFlashStyle {
+    pub color: Color,
+    pub duration: Duration, // zero turns the flash off
+}
+
+impl Default for LockFlashStyle {
+    fn default() -> Self {
+        Self {
+            color: Color::WHITE,
+            duration: Duration::from_millis(150),
+        }
+    }
+}
+
+impl LockFlashStyle {
+    // a color from the command line, as RRGGBB hex with or without a leading #
+    pub fn parse_color(value: &str) -> Option<Color> {
+        let hex = value.strip_prefix('#').unwrap_or(value);
+        if hex.len() != 6 {
+            return None;
+        }
+
+        let rgb = u32::from_str_radix(hex, 16).ok()?;
+        Some(Color::RGB((rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8))
+    }
+
+    // how strongly the flash shows `elapsed` after the lock: full at first, easing out to nothing
+    pub fn intensity(&self, elapsed: Duration) -> f32 {
+        if elapsed >= self.duration {
+            return 0.0;
+        }
+
+        let remaining = 1.0 - elapsed.as_secs_f32() / self.duration.as_secs_f32();
+        remaining * remaining
+    }
+}
+
+// the cells of a tetrimino that just locked light up and fade back to their own color
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct LockFlash {
+    pub cells: [Coordinate; Piece::CELL_COUNT],
+    pub elapsed: Duration,
+}
+
+impl LockFlash {
+    pub fn new(cells: [Coordinate; Piece::CELL_COUNT]) -> Self {
+        Self {
+            cells,
+            elapsed: Duration::ZERO,
+        }
+    }
+
+    // let time pass; returns whether the flash is still showing
+    pub fn advance(&mut self, dt: Duration, style: &LockFlashStyle) -> bool {
+        self.elapsed += dt;
+        self.elapsed < style.duration
+    }
+
+    // the flash color with its alpha set to the current intensity
+    pub fn color(&self, style: &LockFlashStyle) -> Color {
+        let alpha = (style.intensity(self.elapsed) * 255.0).round() as u8;
+        Color::RGBA(style.color.r, style.color.g, style.color.b, alpha)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn intensity_fades_out_over_the_duration() {
+        let style = LockFlashStyle {
+            color: Color::RED,
+            duration: Duration::from_millis(200),
+        };
+
+        assert_eq!(style.intensity(Duration::ZERO), 1.0);
+        assert_eq!(style.intensity(Duration::from_millis(100)), 0.25);
+        assert_eq!(style.intensity(Duration::from_millis(200)), 0.0);
+        assert_eq!(style.intensity(Duration::from_secs(1)), 0.0);
+
+        // it only ever goes down
+        let mut previous = f32::MAX;
+        for ms in 0..=200 {
+            let intensity = style.intensity(Duration::from_millis(ms));
+            assert!(intensity <= previous);
+            previous = intensity;
+        }
+
+        // no duration, no flash
+        let off = LockFlashStyle {
+            duration: Duration::ZERO,
+            ..style
+        };
+        assert_eq!(off.intensity(Duration::ZERO), 0.0);
+    }
+
+    #[test]
+    fn parse_color() {
+        assert_eq!(
+            LockFlashStyle::parse_color("#ff8000"),
+            Some(Color::RGB(0xff, 0x80, 0x00))
+        );
+        assert_eq!(LockFlashStyle::parse_color("000000"), Some(Color::BLACK));
+        assert_eq!(LockFlashStyle::parse_color("fff"), None);
+        assert_eq!(LockFlashStyle::parse_color("zzzzzz"), None);
+    }
+}
@@ -1,568 +1,1928 @@
-use crate::engine::Engine;
-use cancellable_timer::{Canceller, Timer as CancellableTimer};
+use crate::engine::{
+    event::EngineEvent,
+    highscore::{self, HighScoreTable},
+    mode::GameMode,
+    move_kind::MoveKind,
+    piece::Piece,
+    piece_kind::PieceKind,
+    Coordinate, Engine,
+};
+use assets::AssetDirs;
+use auto_pause::AutoPause;
 use cell_draw::CellDrawContext;
 use cgmath::Vector2;
-use input::Input;
+use config_file::{ConfigFile, FileWatch, Popup};
+use das::DasState;
+use debug_window::DebugWindow;
+pub use error::InterfaceError;
+use frame_limiter::FrameRate;
+use game_over_animation::GameOverAnimation;
+use input::{Input, KeyBindings};
+use layout::Layout;
+use level_up::LevelUp;
+use lock_flash::LockFlash;
+use online::Online;
+use overlay::Overlay;
+use pause_menu::{PauseMenu, PauseMenuText, PauseNavigation, PauseOutcome};
+use quick_restart::QuickRestart;
+use recording::Recording;
+use render_traits::ScreenColor;
+use replaying::Replaying;
+use review::{Review, ReviewNavigation};
+use sdl2::keyboard::{Keycode, Mod};
+use sdl2::mouse::MouseButton;
+use sdl2::pixels::PixelFormatEnum;
+use sdl2::render::BlendMode;
+use sdl2::rwops::RWops;
 use sdl2::ttf::Sdl2TtfContext;
-use sdl2::{event::Event, pixels::Color, rect::Rect, render::Canvas, video::Window};
-use sdl2::{EventSubsystem, Sdl};
+use sdl2::Sdl;
+use sdl2::{
+    event::Event, event::WindowEvent, pixels::Color, rect::Rect, render::Canvas, video::Window,
+};
+use seed_entry::SeedEntry;
+pub use settings::Settings;
+use spectating::Spectating;
 use state::State;
-use std::path::Path;
-use std::time::Duration;
+use std::env;
+use std::path::PathBuf;
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
 use sub_rect::{Align, SubRect};
-use text_draw::TextDrawContext;
-
+use tetris::controller::{GameController, GameInput, GameState};
+use tetris::history::{self, GameRecord};
+use tetris::logger;
+use tetris::metrics::{self, GameMetrics, Trends};
+use tetris::net::connection::Pending;
+use tetris::net::spectate::Watcher;
+use tetris::replay::{self, Replay, Scrubber};
+use tetris::save::{self, Autosave, Saved};
+use tetris::timestep::FixedTimestep;
+use text_draw::{BagText, BonusText, ClockText, NumberText, TextCache, TextDrawContext};
+use theme::Theme;
+
+pub mod assets;
+mod auto_pause;
 mod cell_draw;
-mod input;
+mod config_file;
+mod das;
+mod debug_window;
+pub mod error;
+pub mod frame_limiter;
+mod game_over_animation;
+mod gif;
+pub mod input;
+mod layout;
+mod level_up;
+pub mod lock_flash;
+mod online;
+pub mod overlay;
+mod pause_menu;
+mod quick_restart;
+mod recording;
 mod render_traits;
-mod state;
+mod renderer;
+mod replaying;
+mod review;
+mod seed_entry;
+pub mod settings;
+mod spectating;
+pub mod state;
 mod sub_rect;
 mod text_draw;
+mod theme;
 
+// the font is part of the executable so the game starts from anywhere; a font file among the assets is
+// used instead if there is one
+const FONT: &[u8] = include_bytes!("../../assets/NewAmsterdam-Regular.ttf");
+const FONT_FILE: &str = "font.ttf";
 const INIT_SIZE: Vector2<u32> = Vector2::new(1024, 1024);
-const BACKGROUND_COLOR: Color = Color::RGB(0x10, 0x10, 0x18);
-const MATRIX_COLOR: Color = Color::RGB(0x66, 0x77, 0x77);
-const MATRIX_CONTAINER_COLOR: Color = Color::RGB(0x22, 0x22, 0x22);
 const PLACEHOLDER_2: Color = Color::RGB(0x66, 0x77, 0x77);
 const PLACEHOLDER_3: Color = Color::RGB(0x77, 0x88, 0x88);
+const GAME_OVER_FILL_COLOR: Color = Color::RGB(0x55, 0x55, 0x55);
+const LOG_OVERLAY_KEY: Keycode = Keycode::F3;
+const LOG_OVERLAY_COLOR: Color = Color::RGBA(0x00, 0x00, 0x00, 0xcc);
+const RECORD_KEY: Keycode = Keycode::F9;
+const RECORD_COLOR: Color = Color::RGB(0xcc, 0x22, 0x22);
+const STEP_KEY: Keycode = Keycode::F10; // only does anything in frame-step mode
+const IDLE_WAIT: Duration = Duration::from_millis(250); // longest the loop sleeps when nothing is due
+const ANIMATION_FRAME: Duration = Duration::from_millis(16); // redraw rate while something animates continuously
+
+// what handling an event amounts to for the loop
+enum EventOutcome {
+    Ignored,
+    Redraw,
+    Quit,
+}
 
-// event structs
-struct Tick; // basically same as type Tick=()
-struct LockdownTick;
-struct SoftDropTick;
-struct Sleep(Duration);
-
-pub struct Interface {
-    pub engine: Engine,
+pub struct Interface<'ttf> {
+    pub game: GameController,
     pub sdl: Sdl,
     pub canvas: Canvas<Window>,
-    pub ttf_context: Sdl2TtfContext,
-    pub static_event_subsystem: &'static EventSubsystem,
-    pub timer_lockdown: Option<Canceller>,
-    pub timer_tick: Option<Canceller>,
+    pub text_cache: TextCache<'ttf>, // all text goes through it, rendered with the font
     pub state: State,
-    pub lockdown_timer_count: i32,
+    pub settings: Settings,
+    pub key_bindings: KeyBindings,
+    pub pause_menu: PauseMenu,
+    pub quick_restart: QuickRestart,
+    pub das: DasState, // left or right held down, moving the tetrimino on its own
+    pub game_over_animation: Option<GameOverAnimation>,
+    pub lock_flash: Option<LockFlash>, // the tetrimino that locked last, while it's still flashing
+    level_up: Option<LevelUp>,         // the level that was just reached, while it's still shown
+    pub timestep: FixedTimestep, // updates run in fixed steps, decoupled from how often frames are drawn
+    pub review: Option<Review>,
+    pub replaying: Option<Replaying>, // the saved replay being watched, see watch_replay
+    pub score_text: NumberText,
+    pub level_text: NumberText,
+    pub clock_text: ClockText, // the time of a sprint or an ultra, in place of the score or the level
+    pub sprint_time_text: ClockText, // what a finished sprint took, in place of the game over
+    pub placed_texts: [NumberText; PieceKind::ALL.len()], // for the statistics, by kind
+    pub bag_text: BagText,
+    pub bonus_text: BonusText, // the combo and back-to-back under the score
+    pub pause_menu_text: PauseMenuText,
+    pub countdown_text: NumberText, // in place of the pause menu while resuming
+    pub seed_entry: SeedEntry,
+    pub fixed_seed: Option<u64>, // the seed that was typed in, restarts deal the same sequence again
+    pub log_overlay: bool, // the most recent log lines on top of the game, toggled with LOG_OVERLAY_KEY
+    pub auto_pause: AutoPause,
+    pub layout: Layout,         // worked out again when the window is resized
+    pub online: Option<Online>, // a match against someone over the network instead of a game alone
+    pub spectating: Option<Spectating>, // someone else's match instead of a game at all
+    pub theme: Theme,
+    pub config_watch: Option<FileWatch>, // settings and key bindings, applied again whenever the file changes
+    pub theme_watch: Option<FileWatch>,
+    pub popup: Option<Popup>,
+    pub debug_window: Option<DebugWindow>, // engine internals in a second window, for development
+    pub recording: Option<Recording>,      // the game going into a gif, toggled with RECORD_KEY
+    pub stats_file: Option<PathBuf>,       // a csv file every finished game is added to
+    pub replay_file: Option<PathBuf>,      // where the replay of a finished game is saved
+    pub metrics_file: PathBuf,             // the metrics log, only written with the setting on
+    pub high_scores_file: PathBuf, // where the engine's high scores are loaded from and saved to
+    pub metrics_page: Vec<String>, // what the metrics page shows, read when it's opened
+    pub autosave: Option<Autosave>, // the game in progress, kept on disk in case of a crash
+    pub resumable: Option<Replay>, // an autosaved game offered before the seed entry
 }
 
-impl Interface {
-    pub fn new(engine: Engine) -> Self {
-        let sdl: Sdl = sdl2::init().expect("Failed to initialize sdl2");
-        let video = sdl.video().expect("Failed to acquire display");
-        let canvas = {
-            // evaluation block
-            let window = video
-                .window("Tetris", INIT_SIZE.x, INIT_SIZE.y)
-                .position_centered()
-                .resizable()
-                .build()
-                .expect("Failed to create window");
-
-            window
-                .into_canvas()
-                .accelerated()
-                .present_vsync()
-                .build()
-                .expect("Failed to get render canvas")
-        };
+impl<'ttf> Interface<'ttf> {
+    pub fn new(ttf_context: &'ttf Sdl2TtfContext, engine: Engine) -> Result<Self, InterfaceError> {
+        Self::with_settings(
+            ttf_context,
+            engine,
+            Settings::default(),
+            AssetDirs::new(None),
+        )
+    }
 
-        let ttf_context = sdl2::ttf::init()
-            .map_err(|e| e.to_string())
-            .expect("Failed to initialize ttf context");
+    // the font borrows the ttf context, so that's made by whoever makes the interface and has to
+    // outlive it
+    pub fn with_settings(
+        ttf_context: &'ttf Sdl2TtfContext,
+        engine: Engine,
+        settings: Settings,
+        assets: AssetDirs,
+    ) -> Result<Self, InterfaceError> {
+        let mut game = GameController::new(0, engine.config, FixedTimestep::default().step);
+        game.engine.frame_step = engine.frame_step;
+        let high_scores_file = highscore::default_path();
+        game.engine.high_scores = HighScoreTable::load_or_new(&high_scores_file);
+        let sdl: Sdl = sdl2::init().map_err(InterfaceError::SdlInit)?;
+        let video = sdl.video().map_err(InterfaceError::SdlInit)?;
+        let mut window = video.window("Tetris", INIT_SIZE.x, INIT_SIZE.y);
+        window.position_centered();
+        match settings.overlay {
+            Some(overlay) if overlay.pinned => window.borderless().always_on_top(),
+            _ => window.resizable(),
+        };
+        let window = window
+            .build()
+            .map_err(|e| InterfaceError::WindowCreation(e.to_string()))?;
+        let canvas = build_canvas(window, settings.frame_rate)?;
+        let layout = Layout::new(
+            canvas.viewport(),
+            game.engine.config.previews,
+            settings.wide_panels,
+        );
+
+        if let Some(overlay) = settings.overlay {
+            for color in overlay.clashes() {
+                log::warn!("{color:?} cells are close to the overlay color and may get keyed out");
+            }
+        }
 
-        let static_event_subsystem: &'static _ = Box::leak(Box::new(
-            sdl.event().expect("Failed to acquire event subsystem"),
-        ));
+        let font = match assets.find(FONT_FILE) {
+            Some(path) => ttf_context.load_font(path, 512),
+            None => RWops::from_bytes(FONT)
+                .and_then(|rwops| ttf_context.load_font_from_rwops(rwops, 512)),
+        }
+        .map_err(InterfaceError::FontLoad)?;
 
-        Self {
-            engine,
+        Ok(Self {
+            game,
             sdl,
             canvas,
-            ttf_context,
-            static_event_subsystem,
-            timer_lockdown: None,
-            timer_tick: None,
-            state: State::TickingDown,
-            lockdown_timer_count: 0,
-        }
+            text_cache: TextCache::new(font),
+            state: State::SeedEntry,
+            settings,
+            key_bindings: settings.controls.bindings().unwrap_or_default(),
+            pause_menu: PauseMenu::default(),
+            quick_restart: QuickRestart::default(),
+            das: DasState::default(),
+            game_over_animation: None,
+            lock_flash: None,
+            level_up: None,
+            review: None,
+            replaying: None,
+            seed_entry: SeedEntry::default(),
+            fixed_seed: None,
+            log_overlay: false,
+            auto_pause: AutoPause::default(),
+            layout,
+            online: None,
+            spectating: None,
+            theme: Theme::default(),
+            config_watch: None,
+            theme_watch: None,
+            popup: None,
+            debug_window: None,
+            recording: None,
+            stats_file: None,
+            replay_file: None,
+            metrics_file: metrics::data_dir().join(metrics::FILE_NAME),
+            high_scores_file,
+            metrics_page: Vec::new(),
+            autosave: None,
+            resumable: None,
+            timestep: FixedTimestep::default(),
+            score_text: NumberText::new(2),
+            level_text: NumberText::new(2),
+            clock_text: ClockText::new(1),
+            sprint_time_text: ClockText::labeled("SPRINT ", 0),
+            placed_texts: std::array::from_fn(|_| NumberText::new(0)),
+            bag_text: BagText::default(),
+            bonus_text: BonusText::default(),
+            pause_menu_text: PauseMenuText::default(),
+            countdown_text: NumberText::labeled("RESUMING IN ", 0),
+        })
+    }
+
+    // keep the game in progress in the file, and offer the game left in it by the last run
+    pub fn enable_autosave(&mut self, path: PathBuf) {
+        let autosave = Autosave::new(path);
+        self.resumable = autosave.load().map(|(mode, replay)| {
+            log::info!("Found an autosaved {mode} game {} steps in", replay.steps);
+            replay
+        });
+        self.autosave = Some(autosave);
+    }
+
+    // play a match against whoever is on the other end of `pending` instead of starting a game alone
+    pub fn go_online(&mut self, pending: Pending) {
+        // a match goes on until one side tops out
+        self.game.engine.config.mode = GameMode::Marathon;
+        self.online = Some(Online::Connecting(pending));
+        self.state = State::Connecting;
+    }
+
+    // play a saved replay back from the start instead of starting a game; leaving it goes to the seed entry
+    pub fn watch_replay(&mut self, saved: Saved) {
+        log::info!("Watching a replay with seed {}", saved.seed());
+        let replaying = Replaying::new(saved, self.game.engine.clone());
+        self.game.engine = replaying.engine().clone();
+        self.replaying = Some(replaying);
+        self.state = State::Replaying;
+        // it was recorded with its own number of previews
+        self.layout = Layout::new(
+            self.canvas.viewport(),
+            self.game.engine.config.previews,
+            self.settings.wide_panels,
+        );
+    }
+
+    // watch the match of the host on the other end of `pending`, without playing
+    pub fn spectate(&mut self, pending: Pending<Watcher>) {
+        self.spectating = Some(Spectating::Connecting(pending));
+        self.state = State::Spectating;
+    }
+
+    // take the settings and key bindings from the config file at `path`, now and whenever it changes
+    pub fn watch_config(&mut self, path: PathBuf) {
+        self.config_watch = Some(FileWatch::new(path));
+    }
+
+    // take the colors from the theme file at `path`, now and whenever it changes
+    pub fn watch_theme(&mut self, path: PathBuf) {
+        self.theme_watch = Some(FileWatch::new(path));
     }
 
-    pub fn run(&mut self) -> Result<(), String> {
+    // show the engine's internals in a second window next to the game
+    pub fn open_debug_window(&mut self) -> Result<(), InterfaceError> {
+        self.debug_window = Some(DebugWindow::open(&self.sdl)?);
+        Ok(())
+    }
+
+    pub fn run(&mut self) -> Result<(), InterfaceError> {
         /*
         A tetrimino that is Hard dropped Locks down immediately. However, if a tetrimino
         naturally falls or Soft drops onto a Surface, it is given 0.5 seconds on a Lock
         down timer before it actually Locks down.
         */
 
+        // sdl only hands out one pump, so it's taken once and lives as long as the loop
+        let mut event_pump = self.sdl.event_pump().map_err(InterfaceError::SdlInit)?;
+
         // whether we should redraw or not
         let mut dirty: bool = true;
 
-        self.static_event_subsystem
-            .register_custom_event::<Tick>()
-            .unwrap();
-        self.static_event_subsystem
-            .register_custom_event::<LockdownTick>()
-            .unwrap();
-
-        self.engine.create_top_cursor(None);
-
-        self.static_event_subsystem.push_custom_event(Tick).unwrap();
+        let mut last_frame = Instant::now();
 
         loop {
-            for event in self.sdl.event_pump().unwrap().poll_iter() {
-                match event {
-                    Event::Quit { .. } => {
+            // sleep until either input arrives or the next thing is due, instead of spinning
+            let timeout = self.wait_timeout().as_micros().div_ceil(1000) as u32;
+            let first_event = event_pump.wait_event_timeout(timeout);
+
+            let frame_started = Instant::now();
+            let dt = frame_started.duration_since(last_frame);
+            last_frame = frame_started;
+
+            // the whole batch is gathered first, so presses within it can be weighed against each other
+            let events = input::cancel_opposite_moves(
+                first_event
+                    .into_iter()
+                    .chain(event_pump.poll_iter())
+                    .collect(),
+                &self.key_bindings,
+            );
+
+            for event in events {
+                match self.handle_event(event) {
+                    EventOutcome::Ignored => {}
+                    EventOutcome::Redraw => dirty = true,
+                    EventOutcome::Quit => {
+                        self.autosave_game();
                         return Ok(());
                     }
-                    Event::User { .. } if event.as_user_event_type::<Tick>().is_some() => {
-                        println!("Timer ticky picky?{:?}", self.state);
+                }
+            }
 
-                        self.set_tick_timer();
+            // however long the frame took, the game only ever moves on in whole steps
+            for _ in 0..self.timestep.accumulate(dt) {
+                if self.update(self.timestep.step) {
+                    dirty = true;
+                }
+            }
+
+            if dirty {
+                self.render()?;
+            }
+            dirty = false;
 
-                        if self.state == State::Paused {
-                            continue;
-                        };
+            // with vsync present already waits, with a manual cap sleep out the rest of the frame
+            let sleep = self
+                .settings
+                .frame_rate
+                .sleep_duration(frame_started.elapsed());
+            if !sleep.is_zero() {
+                std::thread::sleep(sleep);
+            }
+        }
+    }
+
+    // react to a single window or keyboard event
+    fn handle_event(&mut self, event: Event) -> EventOutcome {
+        match event {
+            Event::Quit { .. } => {
+                return EventOutcome::Quit;
+            }
+            // closing the debug window leaves the game be
+            Event::Window {
+                window_id,
+                win_event,
+                ..
+            } if self
+                .debug_window
+                .as_ref()
+                .is_some_and(|debug| debug.id() == window_id) =>
+            {
+                if win_event == WindowEvent::Close {
+                    self.debug_window = None;
+                }
+                return EventOutcome::Ignored;
+            }
+            // while the debug window is open, closing the game window isn't a quit event of its own
+            Event::Window {
+                win_event: WindowEvent::Close,
+                ..
+            } => {
+                return EventOutcome::Quit;
+            }
+            Event::Window { win_event, .. } if Layout::is_outdated_by(&win_event) => {
+                self.layout = Layout::new(
+                    self.canvas.viewport(),
+                    self.game.engine.config.previews,
+                    self.settings.wide_panels,
+                );
+                return EventOutcome::Redraw;
+            }
+            // the opponent's game doesn't stop, so neither does the local one
+            Event::Window { win_event, .. }
+                if self.state == State::Playing && self.online.is_none() =>
+            {
+                let changed = match win_event {
+                    WindowEvent::FocusLost => {
+                        // the key going up goes to whatever has the focus now
+                        self.das.reset();
+                        let paused = self.auto_pause.focus_lost(&mut self.game, &self.settings);
+                        if paused {
+                            self.pause_menu = PauseMenu::default();
+                        }
+                        paused
+                    }
+                    WindowEvent::FocusGained => self.auto_pause.focus_gained(&self.settings),
+                    _ => false,
+                };
 
-                        // check if we've hit bottom without ticking down!
-                        let has_hit_bottom = self.engine.cursor_has_hit_bottom();
-                        if has_hit_bottom && self.state == State::TickingDown {
-                            println!("has hit bottom game over");
-                            self.state = State::GameOver;
+                if changed {
+                    return EventOutcome::Redraw;
+                }
+            }
+            // the pause menu can be pointed at and clicked as well
+            Event::MouseMotion { x, y, .. } if self.is_in_pause_menu() => {
+                let line = PauseMenu::line_at(Rect::from(self.layout.menu_area), x, y);
+                if line.is_some_and(|line| self.pause_menu.point(line)) {
+                    return EventOutcome::Redraw;
+                }
+            }
+            Event::MouseButtonDown {
+                mouse_btn: MouseButton::Left,
+                x,
+                y,
+                ..
+            } if self.is_in_pause_menu() => {
+                if let Some(line) = PauseMenu::line_at(Rect::from(self.layout.menu_area), x, y) {
+                    let outcome = self.pause_menu.click(line, &mut self.settings);
+                    self.apply_pause_outcome(outcome);
+                    return EventOutcome::Redraw;
+                }
+            }
+            Event::TextInput { text, .. }
+                if self.state == State::SeedEntry && self.resumable.is_none() =>
+            {
+                self.seed_entry.insert(&text);
+                return EventOutcome::Redraw;
+            }
+            Event::KeyUp {
+                keycode: Some(key), ..
+            } => {
+                if let Ok(input) = Input::try_from_with_bindings(
+                    key,
+                    &self.key_bindings,
+                    self.game.engine.cursor().map(|cursor| cursor.rotation),
+                ) {
+                    match input {
+                        // the game here is behind on what has been sent, so the release always goes out
+                        Input::SoftDrop if self.online.is_some() => {
+                            self.apply(GameInput::SoftDropStop);
                         }
+                        Input::SoftDrop if self.game.state == GameState::SoftDropping => {
+                            self.game.apply(GameInput::SoftDropStop);
+                        }
+                        Input::Move(direction) => self.das.release(direction),
+                        Input::Restart => {
+                            self.quick_restart.release();
+                            return EventOutcome::Redraw;
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            Event::KeyDown {
+                keycode: Some(key),
+                keymod,
+                repeat,
+                ..
+            } => {
+                if key == LOG_OVERLAY_KEY {
+                    self.log_overlay = !self.log_overlay;
+                    return EventOutcome::Redraw;
+                }
 
-                        // if we have a cursor to tick down, tick it down :)
-                        if self.engine.ticked_down_cursor().is_some() {
-                            self.engine.try_tick_down();
-                            let has_hit_bottom = self.engine.cursor_has_hit_bottom();
+                if key == RECORD_KEY && !repeat {
+                    self.toggle_recording();
+                    return EventOutcome::Redraw;
+                }
 
-                            println!("cursor, {:?}", self.engine.cursor_info());
-                            if has_hit_bottom {
-                                println!("has hit bottom");
-                                self.state = State::LockingDown;
+                if key == STEP_KEY && self.game.engine.frame_step {
+                    self.game.engine.step();
+                    return EventOutcome::Redraw;
+                }
 
-                                // add event after 0.5s!
-                                self.set_lockdown_timer();
-                            }
-                        }
+                if self.state == State::SeedEntry {
+                    return self.handle_seed_entry_key(key, keymod);
+                }
+
+                if matches!(self.state, State::Connecting | State::Spectating) {
+                    return EventOutcome::Ignored;
+                }
 
-                        dirty = true;
+                // a replay can only be watched, or left
+                if self.state == State::Replaying {
+                    if matches!(key, Keycode::Escape | Keycode::Backspace) {
+                        self.leave_replay();
+                        return EventOutcome::Redraw;
                     }
-                    Event::User { .. } if event.as_user_event_type::<LockdownTick>().is_some() => {
-                        println!("Lockdown tick event? {:?}", self.state);
-                        if self.state != State::LockingDown {
-                            continue;
-                        }
-                        // if we've moved the tetrimino in lockdown so it can tick down more than once, we'll need to check that
-                        if self.engine.ticked_down_cursor().is_some() {
-                            self.set_lockdown_timer();
-                            continue;
-                        }
 
-                        // the Lock down timer resets to 0.5 seconds if the player simply moves or rotates the tetrimino.
-                        let ok = self.engine.place_cursor();
-                        if !ok {
-                            println!("CURSOR COULD NOT BE PLACED");
-                            // if cursor could not be placed
-                            self.state = State::GameOver;
-                            continue;
-                        }
+                    return EventOutcome::Ignored;
+                }
+
+                // while reviewing, nothing but the review keys do anything
+                if self.state == State::Reviewing {
+                    if let Some(navigation) = ReviewNavigation::try_from(key) {
+                        self.navigate_review(navigation);
+                        return EventOutcome::Redraw;
+                    }
 
-                        self.engine.create_top_cursor(None);
-                        println!("creating top corsurp {:?}", self.engine.cursor_info());
+                    return EventOutcome::Ignored;
+                }
 
-                        dirty = true;
-                        self.state = State::LockedDown;
+                if self.game.is_game_over()
+                    && self.online.is_none()
+                    && key == ReviewNavigation::START_KEY
+                {
+                    self.start_review();
+                    return EventOutcome::Redraw;
+                }
 
-                        self.set_tick_timer();
+                // while paused, the menu takes the keys it navigates with
+                if self.is_in_pause_menu() {
+                    if let Some(navigation) = PauseNavigation::try_from(key) {
+                        let outcome = self.pause_menu.navigate(navigation, &mut self.settings);
+                        self.apply_pause_outcome(outcome);
+                        return EventOutcome::Redraw;
                     }
-                    Event::KeyUp {
-                        keycode: Some(key), ..
-                    } => {
-                        if let Ok(input) = Input::try_from(key, self.engine.next_cursor_rotation())
+                }
+
+                if let Ok(input) = Input::try_from_with_bindings(
+                    key,
+                    &self.key_bindings,
+                    self.game.engine.cursor().map(|cursor| cursor.rotation),
+                ) {
+                    let is_playing = !self.game.is_paused() && !self.game.is_game_over();
+
+                    match input {
+                        Input::Move(_)
+                        | Input::HardDrop
+                        | Input::SoftDrop
+                        | Input::Rotation(_)
+                        | Input::Hold
+                            if !is_playing =>
                         {
-                            match input {
-                                Input::SoftDrop => {
-                                    if self.state == State::SoftDropping {
-                                        self.state = State::TickingDown;
-                                    }
-                                }
-                                _ => {}
-                            }
+                            return EventOutcome::Ignored;
                         }
-                    }
-                    Event::KeyDown {
-                        keycode: Some(key), ..
-                    } => {
-                        if let Ok(input) = Input::try_from(key, self.engine.next_cursor_rotation())
+                        // a match can't be paused or started over from one side
+                        Input::Pause | Input::Continue | Input::Restart
+                            if self.online.is_some() =>
                         {
-                            match input {
-                                Input::Move(kind) => {
-                                    // restart lockdown timer
-                                    if self.state == State::LockingDown {
-                                        self.set_lockdown_timer();
-                                    }
-
-                                    self.engine.move_cursor(kind);
-                                }
-                                Input::HardDrop => {
-                                    if self.state == State::Paused || self.state == State::GameOver
-                                    {
-                                        continue;
-                                    }
-
-                                    self.engine.hard_drop(); // hard drop
-                                    let ok = self.engine.try_place_cursor(); // since we could press keyboard multiple times during one tick cycle, we need to not panic if there's no cursor
-                                    if !ok {
-                                        println!("CUrsor cuold NTO BE PLACED1");
-                                        self.state = State::GameOver;
-                                        continue;
-                                    }
-
-                                    self.engine.create_top_cursor(None);
-                                    self.state = State::LockedDown;
-                                }
-                                Input::SoftDrop => {
-                                    if self.state == State::Paused || self.state == State::GameOver
-                                    {
-                                        continue;
-                                    }
-
-                                    if self.state != State::SoftDropping
-                                        && self.state != State::LockingDown
-                                    {
-                                        self.state = State::SoftDropping;
-                                        self.set_tick_timer();
-                                    }
-                                }
-                                Input::Rotation(kind) => {
-                                    if self.state == State::Paused || self.state == State::GameOver
-                                    {
-                                        continue;
-                                    }
-
-                                    self.engine.rotate_and_adjust_cursor(kind);
-
-                                    // restart lockdown timer
-                                    if self.state == State::LockingDown {
-                                        self.set_lockdown_timer();
-                                    }
-                                }
-                                Input::Pause => {
-                                    if self.state == State::GameOver {
-                                        continue;
-                                    }
-
-                                    if self.state == State::Paused {
-                                        self.state = State::TickingDown;
-                                    } else {
-                                        self.state = State::Paused;
-                                    }
-                                }
-                                Input::Hold => {
-                                    // put a tetrimino on hold
-                                    if self.state == State::Paused || self.state == State::GameOver
-                                    {
-                                        continue;
-                                    }
-
-                                    self.engine.try_hold();
-                                }
-                                Input::Continue => {
-                                    // start new game
-                                    if self.state != State::GameOver {
-                                        continue;
-                                    }
-
-                                    self.state = State::TickingDown;
-                                    self.engine.reset();
-                                    self.engine.create_top_cursor(None);
-                                    self.set_tick_timer();
-                                }
+                            return EventOutcome::Ignored;
+                        }
+                        // held down, the moves come from the das instead of the key repeat
+                        Input::Move(_) if repeat => return EventOutcome::Ignored,
+                        Input::Move(direction) => {
+                            self.das.press(direction);
+                            self.apply_move(direction);
+                        }
+                        Input::HardDrop => self.apply(GameInput::HardDrop),
+                        Input::SoftDrop => self.apply(GameInput::SoftDropStart),
+                        Input::Rotation(rotation) => {
+                            // the game works the turn out again from the direction, like a replay does
+                            if self.game.engine.prev_cursor_rotation() == Some(rotation) {
+                                self.apply(GameInput::RotateCounterclockwise)
+                            } else {
+                                self.apply(GameInput::Rotate)
+                            }
+                        }
+                        Input::Hold => self.apply(GameInput::Hold),
+                        Input::Pause => {
+                            if self.game.is_game_over() {
+                                return EventOutcome::Ignored;
+                            }
+
+                            if self.game.is_paused() {
+                                self.game.apply(GameInput::Resume);
+                            } else {
+                                self.game.apply(GameInput::Pause);
+                                self.pause_menu = PauseMenu::default();
+                            }
+                        }
+                        Input::Continue => {
+                            // start new game
+                            if !self.game.is_game_over() {
+                                return EventOutcome::Ignored;
+                            }
+
+                            self.restart();
+                        }
+                        Input::Restart => {
+                            if repeat || !is_playing {
+                                return EventOutcome::Ignored;
+                            }
+
+                            // a double tap restarts right away, otherwise the key has to be held
+                            if self.quick_restart.press(Instant::now()) {
+                                self.restart();
                             }
-                            dirty = true
                         }
                     }
-                    _ => {}
+                    return EventOutcome::Redraw;
                 }
             }
+            _ => {}
+        }
+
+        EventOutcome::Ignored
+    }
+
+    // the menu is up while a game of one's own is paused, except while it's counting down to resuming
+    fn is_in_pause_menu(&self) -> bool {
+        self.state == State::Playing && self.game.is_paused() && self.auto_pause.countdown.is_none()
+    }
 
-            // scan the board, see what lines need to be cleared
-            if self.state == State::LockedDown {
-                self.engine.line_clear(|_| ());
-                self.state = State::TickingDown;
-                self.lockdown_timer_count = 0;
+    fn apply_pause_outcome(&mut self, outcome: PauseOutcome) {
+        match outcome {
+            PauseOutcome::Stay => {}
+            PauseOutcome::Resume => self.auto_pause.resume(),
+            PauseOutcome::Restart => self.restart(),
+            PauseOutcome::Quit => self.quit_to_menu(),
+            PauseOutcome::Metrics => {
+                let now = SystemTime::now()
+                    .duration_since(SystemTime::UNIX_EPOCH)
+                    .unwrap_or_default()
+                    .as_secs();
+                let games = metrics::read(&self.metrics_file);
+                self.metrics_page = Trends::of(&games, now).lines();
             }
-            if dirty {
-                self.draw();
+        }
+    }
+
+    fn apply_move(&mut self, direction: MoveKind) {
+        match direction {
+            MoveKind::Left => self.apply(GameInput::MoveLeft),
+            MoveKind::Right => self.apply(GameInput::MoveRight),
+        }
+    }
+
+    // an input for the game itself; a lock or a game over it leads to shows up right away. in a match
+    // it goes to the opponent first and only reaches the game some steps later
+    fn apply(&mut self, input: GameInput) {
+        if let Some(online) = self.online.as_mut() {
+            online.input(input);
+            return;
+        }
+
+        self.game.apply(input);
+        self.after_game_moved();
+    }
+
+    // typing in the seed happens through text input events, the keys here edit it and start the game
+    fn handle_seed_entry_key(&mut self, key: Keycode, keymod: Mod) -> EventOutcome {
+        // an autosaved game is offered before the seed can be typed in
+        if self.resumable.is_some() {
+            return self.handle_resume_key(key);
+        }
+
+        let is_paste = key == Keycode::V
+            && keymod.intersects(Mod::LCTRLMOD | Mod::RCTRLMOD | Mod::LGUIMOD | Mod::RGUIMOD);
+
+        match key {
+            Keycode::Return | Keycode::KpEnter => {
+                self.fixed_seed = seed_entry::parse_seed(&self.seed_entry.text);
+                self.restart();
             }
-            dirty = false;
+            Keycode::Backspace => self.seed_entry.backspace(),
+            Keycode::Escape => self.seed_entry = SeedEntry::default(),
+            // restarts keep the config, the mode along with it; it's only picked on the seed entry lines,
+            // never under the resume prompt, which would go on with the mode it was saved with
+            Keycode::Left if self.resumable.is_none() => {
+                self.game.engine.config.mode = self.game.engine.config.mode.prev();
+            }
+            Keycode::Right if self.resumable.is_none() => {
+                self.game.engine.config.mode = self.game.engine.config.mode.next();
+            }
+            _ if is_paste => match self
+                .sdl
+                .video()
+                .and_then(|video| video.clipboard().clipboard_text())
+            {
+                Ok(text) => self.seed_entry.insert(&text),
+                Err(error) => log::warn!("Failed to paste the seed: {error}"),
+            },
+            _ => return EventOutcome::Ignored,
         }
+
+        EventOutcome::Redraw
     }
 
-    fn cancel_set_tick_timer(&mut self) {
-        if self.timer_tick.is_some() {
-            let _ = self.timer_tick.as_ref().unwrap().cancel();
+    // the autosaved game is picked back up or thrown away, either way the file has done its job
+    fn handle_resume_key(&mut self, key: Keycode) -> EventOutcome {
+        match key {
+            Keycode::Return | Keycode::KpEnter => {
+                let Some(replay) = self.resumable.take() else {
+                    return EventOutcome::Ignored;
+                };
+                log::info!("Resumed the autosaved game with seed {}", replay.seed);
+                let frame_step = self.game.engine.frame_step;
+                let high_scores = self.game.engine.high_scores.take();
+                self.game = replay::resume(replay);
+                self.game.engine.frame_step = frame_step;
+                self.game.engine.high_scores = high_scores;
+                self.state = State::Playing;
+                // it might have been saved with another number of previews
+                self.layout = Layout::new(
+                    self.canvas.viewport(),
+                    self.game.engine.config.previews,
+                    self.settings.wide_panels,
+                );
+            }
+            Keycode::Escape | Keycode::Backspace => {
+                self.resumable = None;
+                log::info!("Declined the autosaved game");
+            }
+            _ => return EventOutcome::Ignored,
+        }
+
+        if let Some(autosave) = &self.autosave {
+            autosave.discard();
         }
+        EventOutcome::Redraw
     }
 
-    fn cancel_set_lockdown_timer(&mut self) {
-        if self.timer_lockdown.is_some() {
-            let _ = self.timer_lockdown.as_ref().unwrap().cancel();
+    // write the game in progress to the autosave; only a game alone can be picked up again
+    fn autosave_game(&mut self) {
+        let (Some(autosave), Some(replay)) = (self.autosave.as_mut(), &self.game.replay) else {
+            return;
+        };
+        if self.online.is_some() || self.state != State::Playing || self.game.is_game_over() {
+            return;
+        }
+
+        if let Err(error) = autosave.write(self.game.engine.config.mode.name(), replay) {
+            log::warn!(
+                "Failed to autosave to {}: {error}",
+                autosave.path().display()
+            );
         }
     }
 
-    fn set_tick_timer(&mut self) {
-        let is_soft_drop = self.state == State::SoftDropping;
-        self.cancel_set_tick_timer();
+    // how long the loop can wait for input before something needs updating or redrawing
+    fn wait_timeout(&self) -> Duration {
+        let due = match self.state {
+            State::Reviewing | State::SeedEntry => IDLE_WAIT,
+            State::Replaying => self
+                .replaying
+                .as_ref()
+                .and_then(Replaying::time_until_step)
+                .unwrap_or(IDLE_WAIT)
+                .min(IDLE_WAIT),
+            // the connection is only looked at in update
+            State::Connecting => ANIMATION_FRAME,
+            // the delay indicator counts up between frames
+            State::Spectating => ANIMATION_FRAME,
+            // a match moves on every step, whatever the local game is doing
+            State::Playing if self.online.as_ref().is_some_and(Online::is_running) => {
+                self.timestep.step
+            }
+            State::Playing if self.game.is_game_over() => match self.game_over_animation {
+                Some(animation) if !animation.is_complete() => GameOverAnimation::TICK_DURATION,
+                _ => IDLE_WAIT,
+            },
+            State::Playing => {
+                let mut timeout = self
+                    .game
+                    .time_until_update()
+                    .or(self.auto_pause.time_until_change())
+                    .unwrap_or(IDLE_WAIT)
+                    .min(IDLE_WAIT);
+
+                // the restart fill indicator grows every frame, a tetrimino that's forming or falling
+                // smoothly moves every frame
+                let is_falling = self.settings.smooth_fall
+                    && matches!(
+                        self.game.state,
+                        GameState::TickingDown | GameState::SoftDropping
+                    );
+                if self.quick_restart.is_held() || self.game.pending().is_some() || is_falling {
+                    timeout = timeout.min(ANIMATION_FRAME);
+                }
+                if let Some(until_move) = self.das.time_until_move() {
+                    timeout = timeout.min(until_move);
+                }
+
+                timeout
+            }
+        };
+
+        // the flash fades a bit every frame
+        let due = match self.lock_flash {
+            Some(_) => due.min(ANIMATION_FRAME),
+            None => due,
+        };
+
+        // the new level stays up until its time is over
+        let due = match self.level_up {
+            Some(_) => due.min(ANIMATION_FRAME),
+            None => due,
+        };
+
+        // the debug window shows timers that move on every step
+        let due = match self.debug_window {
+            Some(_) => due.min(ANIMATION_FRAME),
+            None => due,
+        };
 
-        let s = self.static_event_subsystem;
-        self.timer_tick = Some(
-            CancellableTimer::after(self.engine.drop_time(is_soft_drop), move |err| {
-                if err.is_err() {
-                    return;
+        // a recording needs frames coming even when nothing moves, or the gif skips the pauses
+        let due = match self.recording {
+            Some(_) => due.min(ANIMATION_FRAME),
+            None => due,
+        };
+
+        // the files are looked at every so often, and a popup has to go away in time
+        let due = match (&self.config_watch, &self.theme_watch) {
+            (None, None) => due,
+            _ => due.min(FileWatch::INTERVAL),
+        };
+        let due = match &self.popup {
+            Some(popup) => due.min(popup.left),
+            None => due,
+        };
+
+        // what's due only happens once the step covering it has run
+        self.timestep.time_until(due)
+    }
+
+    // one fixed step of the game (dt is the timestep's step): gravity and lock delay while playing,
+    // the animation once it's over; nothing moves while paused. returns whether anything changed that needs a redraw
+    fn update(&mut self, dt: Duration) -> bool {
+        let mut changed = false;
+
+        // the restart key has been held down long enough
+        if self.quick_restart.is_held() {
+            if self.quick_restart.is_confirmed(Instant::now()) {
+                self.restart();
+            }
+
+            // keep redrawing so the fill indicator grows
+            changed = true;
+        }
+
+        if let Some(flash) = self.lock_flash.as_mut() {
+            if !flash.advance(dt, &self.settings.lock_flash) {
+                self.lock_flash = None;
+            }
+            changed = true;
+        }
+
+        if let Some(level_up) = self.level_up.as_mut() {
+            if !level_up.advance(dt, self.settings.level_up) {
+                self.level_up = None;
+                changed = true;
+            }
+        }
+
+        changed |= self.reload_files(dt);
+        changed |= self.debug_window.is_some();
+
+        if self.recording.as_ref().is_some_and(Recording::is_over) {
+            log::info!("Recordings stop after {:?}", Recording::MAX_DURATION);
+            self.toggle_recording();
+        }
+        changed |= self.recording.is_some();
+
+        if let (State::Spectating, Some(spectating)) = (self.state, self.spectating.as_mut()) {
+            return changed | spectating.update(&mut self.game, dt);
+        }
+
+        if let (State::Replaying, Some(replaying)) = (self.state, self.replaying.as_mut()) {
+            if replaying.advance(dt) {
+                self.game.engine = replaying.engine().clone();
+                changed = true;
+            }
+            return changed;
+        }
+
+        if self.state == State::Connecting {
+            if let Some(online) = self.online.as_mut() {
+                changed |= online.update(&mut self.game, dt);
+                if online.opponent().is_some() {
+                    self.state = State::Playing;
                 }
-                s.push_custom_event(Tick).unwrap();
-            })
-            .unwrap(),
-        )
+            }
+            return changed;
+        }
+
+        if self.state != State::Playing {
+            return changed;
+        }
+
+        if self.game.is_game_over() {
+            return changed
+                | self
+                    .game_over_animation
+                    .as_mut()
+                    .is_some_and(|animation| animation.advance(dt));
+        }
+
+        // a direction held past the delay shifts the tetrimino on its own
+        if !self.game.is_paused() {
+            if let Some((direction, moves)) = self.das.advance(dt) {
+                for _ in 0..moves {
+                    self.apply_move(direction);
+                }
+                changed = true;
+            }
+        }
+
+        match self.online.as_mut() {
+            Some(online) => changed |= online.update(&mut self.game, dt),
+            None => {
+                let clock = self.game.play_time.as_secs();
+                changed |= self.auto_pause.advance(dt, &mut self.game);
+                changed |= self.game.update(dt);
+                // the time of a sprint or an ultra on the panel moved on a second
+                changed |= self.game.engine.config.mode != GameMode::Marathon
+                    && self.game.play_time.as_secs() != clock;
+            }
+        }
+        self.after_game_moved();
+
+        changed
     }
 
-    fn set_lockdown_timer(&mut self) {
-        println!("lck:${}", self.lockdown_timer_count);
-        if self.lockdown_timer_count > 15 {
+    // flash what locked, log what spawned and kick off the game over if that's where the game got to; only called while
+    // the game was still going
+    fn after_game_moved(&mut self) {
+        let locked = self.game.take_locked();
+        if locked.is_some() {
+            self.flash(locked);
+            if self.game.pieces.is_multiple_of(Autosave::EVERY) {
+                self.autosave_game();
+            }
+        }
+
+        for event in self.game.engine.take_events() {
+            if let Some(debug) = self.debug_window.as_mut() {
+                debug.record(event);
+            }
+
+            match event {
+                EngineEvent::Spawned { kind, source } => {
+                    log::debug!("Spawned {kind:?} from {source:?}")
+                }
+                EngineEvent::LinesCleared { .. } | EngineEvent::ModeComplete => {}
+                EngineEvent::LevelUp { level } if !self.settings.level_up.is_zero() => {
+                    self.level_up = Some(LevelUp::new(level, self.game.engine.gravity()));
+                }
+                EngineEvent::LevelUp { .. } => {}
+            }
+        }
+
+        if self.game.is_game_over() {
+            self.set_game_over();
+        }
+    }
+
+    // apply the config and theme files if they changed on disk; a file that doesn't parse is reported
+    // and the old values are kept. returns whether anything changed that needs a redraw
+    fn reload_files(&mut self, dt: Duration) -> bool {
+        let mut changed = false;
+
+        if let Some(popup) = self.popup.as_mut() {
+            if !popup.advance(dt) {
+                self.popup = None;
+                changed = true;
+            }
+        }
+
+        if let Some(text) = self.config_watch.as_mut().and_then(|watch| watch.poll(dt)) {
+            let result = text.and_then(|text| {
+                ConfigFile::parse(&text)?.apply(&mut self.settings, &mut self.key_bindings)
+            });
+            self.report_reload("CONFIG", result);
+            changed = true;
+        }
+
+        if let Some(text) = self.theme_watch.as_mut().and_then(|watch| watch.poll(dt)) {
+            let result = text
+                .and_then(|text| Theme::parse(&text))
+                .map(|theme| self.theme = theme);
+            self.report_reload("THEME", result);
+            changed = true;
+        }
+
+        changed
+    }
+
+    fn report_reload(&mut self, file: &str, result: Result<(), String>) {
+        let text = match result {
+            Ok(()) => {
+                log::info!("Applied the {} file", file.to_lowercase());
+                format!("{file} APPLIED")
+            }
+            Err(error) => {
+                log::warn!("Kept the old {}: {error}", file.to_lowercase());
+                format!("{file} ERROR: {error}")
+            }
+        };
+        self.popup = Some(Popup::new(text));
+    }
+
+    fn toggle_recording(&mut self) {
+        match self.recording.take() {
+            Some(recording) => recording.stop(),
+            None => match Recording::start() {
+                Ok(recording) => self.recording = Some(recording),
+                Err(error) => log::warn!("Failed to start recording: {error}"),
+            },
+        }
+    }
+
+    // throw away the current game and start a fresh one
+    fn restart(&mut self) {
+        self.game_over_animation = None;
+        self.lock_flash = None;
+        self.level_up = None;
+        self.quick_restart.reset();
+        self.das.reset();
+        self.review = None;
+        self.auto_pause = AutoPause::default();
+        self.state = State::Playing;
+        // the game that was autosaved is thrown away along with it
+        if let Some(autosave) = &self.autosave {
+            autosave.discard();
+        }
+
+        // every game gets a seed of its own so its replay can deal the same tetriminos again,
+        // unless one was typed in at the start
+        let seed = self.fixed_seed.unwrap_or_else(rand::random);
+        self.game.restart(seed);
+        log::info!("Started a new game with seed {seed}");
+    }
+
+    // throw away the current game and go back to the seed entry, where the next one is started from
+    fn quit_to_menu(&mut self) {
+        self.quick_restart.reset();
+        self.das.reset();
+        self.auto_pause = AutoPause::default();
+        self.pause_menu = PauseMenu::default();
+        self.state = State::SeedEntry;
+        if let Some(autosave) = &self.autosave {
+            autosave.discard();
+        }
+        log::info!("Quit the game for the menu");
+    }
+
+    // light up the cells of a tetrimino that just locked
+    fn flash(&mut self, locked: Option<Piece>) {
+        if self.settings.lock_flash.duration.is_zero() {
+            return;
+        }
+
+        self.lock_flash = locked
+            .and_then(|piece| self.game.engine.matrix.piece_cells(&piece))
+            .map(LockFlash::new);
+    }
+
+    // go through the replay of the game that was just lost
+    fn start_review(&mut self) {
+        let Some(replay) = self.game.replay.clone() else {
             return;
+        };
+        let scrubber = Scrubber::new(replay);
+        let finished = std::mem::replace(&mut self.game.engine, scrubber.engine().clone());
+        self.lock_flash = None;
+        self.level_up = None;
+
+        self.review = Some(Review::new(scrubber, finished));
+        self.state = State::Reviewing;
+    }
+
+    // back to the game that was there before the replay, which is the seed entry
+    fn leave_replay(&mut self) {
+        if let Some(replaying) = self.replaying.take() {
+            self.game.engine = replaying.own;
         }
+        self.state = State::SeedEntry;
+        self.layout = Layout::new(
+            self.canvas.viewport(),
+            self.game.engine.config.previews,
+            self.settings.wide_panels,
+        );
+    }
 
-        self.lockdown_timer_count += 1;
-        self.cancel_set_lockdown_timer();
+    fn navigate_review(&mut self, navigation: ReviewNavigation) {
+        let Some(review) = self.review.as_mut() else {
+            return;
+        };
+
+        if review.navigate(navigation) {
+            self.game.engine = review.scrubber.engine().clone();
+            return;
+        }
 
-        let s = self.static_event_subsystem;
-        self.timer_lockdown = Some(
-            CancellableTimer::after(Duration::from_millis(500), move |err| {
-                if err.is_err() {
-                    return;
+        // back to the game over screen the review was started from
+        if let Some(review) = self.review.take() {
+            self.game.engine = review.finished;
+        }
+        self.state = State::Playing;
+    }
+
+    // the game is lost, kick off the game over animation if it's turned on
+    fn set_game_over(&mut self) {
+        log::info!(
+            "Game over with score {} at level {} after {} steps",
+            self.game.engine.score,
+            self.game.engine.level,
+            self.game.replay.as_ref().map_or(0, |replay| replay.steps)
+        );
+
+        if self.settings.game_over_animation {
+            self.game_over_animation = Some(GameOverAnimation::new(Engine::MATRIX_HEIGHT));
+        }
+
+        // there's nothing left to pick back up
+        if let Some(autosave) = &self.autosave {
+            autosave.discard();
+        }
+
+        let mode = if self.online.is_some() {
+            "versus"
+        } else {
+            self.game.engine.config.mode.name()
+        };
+        let finished_at = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        let record = GameRecord::of(&self.game, mode, finished_at);
+
+        if let Some(path) = &self.stats_file {
+            match history::append(path, &record) {
+                Ok(()) => log::info!("Added the game to {}", path.display()),
+                Err(error) => log::warn!("Failed to add the game to {}: {error}", path.display()),
+            }
+        }
+
+        // off the main thread, so a slow disk (or a rotation) doesn't hold up the next game
+        if self.settings.metrics {
+            let game = GameMetrics::of(&record, self.game.top_out);
+            let path = self.metrics_file.clone();
+            thread::spawn(move || {
+                if let Err(error) = metrics::append(&path, &[game], metrics::MAX_BYTES) {
+                    log::warn!("Failed to add the game to {}: {error}", path.display());
                 }
-                s.push_custom_event(LockdownTick).unwrap();
-            })
-            .unwrap(),
-        )
+            });
+        }
+
+        // a match isn't played for the table, and neither is a sprint or an ultra, they're over before
+        // a marathon has got going
+        if self.online.is_none() && self.game.engine.config.mode == GameMode::Marathon {
+            self.record_high_score();
+        }
+
+        if let (Some(path), Some(replay)) = (&self.replay_file, &self.game.replay) {
+            match save::write(path, replay) {
+                Ok(()) => log::info!("Saved the replay to {}", path.display()),
+                Err(error) => {
+                    log::warn!("Failed to save the replay to {}: {error}", path.display())
+                }
+            }
+        }
     }
 
-    fn draw(&mut self) {
-        // Load the font
-        let path: &Path = Path::new("assets/NewAmsterdam-Regular.ttf");
-        let font = self
-            .ttf_context
-            .load_font(path, 512)
-            .expect("Failed to load font");
+    // a score good enough for the table goes in under the name of whoever is logged in
+    fn record_high_score(&mut self) {
+        let score = self.game.engine.score;
+        let Some(table) = self.game.engine.high_scores.as_mut() else {
+            return;
+        };
+        let Some(rank) = table.insert(&player_name(), score) else {
+            return;
+        };
 
-        self.canvas.set_draw_color(BACKGROUND_COLOR);
+        log::info!("Score {score} is number {} of the high scores", rank + 1);
+        if let Err(error) = table.save(&self.high_scores_file) {
+            log::warn!(
+                "Failed to save the high scores to {}: {error}",
+                self.high_scores_file.display()
+            );
+        }
+    }
+
+    fn render(&mut self) -> Result<(), InterfaceError> {
+        #[cfg(feature = "alloc-count")]
+        let allocations = tetris::alloc_count::allocations();
+
+        let overlay = self.settings.overlay;
+        self.canvas
+            .set_draw_color(overlay.map_or(self.theme.background, |overlay| overlay.chroma));
         self.canvas.clear();
         self.canvas.set_draw_color(Color::WHITE);
 
-        let viewport = self.canvas.viewport();
-
-        // the design is all based upon a 16x15 grid which is further divided into 4ths (see grid.png) -
-        // the system is based upon first positioning the container, then an inner rect relative to id
-
-        // the square into which we draw and the margin which can be either on the left/right or top/bottom (because the window is resizable)
-        // let ui_square = {
-        //     let Vector2 { x, y } = Vector2::from(viewport.size()).cast::<i32>().unwrap();
-
-        //     if x > y {
-        //         // landscape, we have top and bottom black margins
-        //         let midpoint = x / 2;
-        //         let left_edge = midpoint - (y / 2);
-        //         Rect::new(left_edge, 0, y as u32, y as u32)
-        //     } else {
-        //         // portrait, we have left and right black margins
-        //         let midpoint = y / 2;
-        //         let top_edge = midpoint - (x / 2);
-        //         Rect::new(0, top_edge, x as u32, x as u32)
-        //     }
-        // };
-        // canvas.draw_rect(ui_square).unwrap();
-
-        // the square into which we draw and the margin which can be either on the left/right or top/bottom (because the window is resizable)
-        let ui_square1 = SubRect::absolute(viewport, (1.0, 1.0), None);
-
-        let matrix_container = ui_square1.sub_rect((0.5, 1.0), None); // half of the width and full height, center alignment by default
-
-        let matrix1 = ui_square1
-            .sub_rect((0.5, 1.0), None) // half of the width and full height, center alignment by default
-            .sub_rect((7.0 / 8.0, 7.0 / 8.0), None); // 7/8ths of the width and 7/8ths of the height, center by default
-
-        // top right container for coming up tetrimino
-        let up_next1 = ui_square1
-            .sub_rect((0.25, 0.25), Some((Align::Far, Align::Near))) // top right container
-            .sub_rect((7.0 / 8.0, 7.0 / 8.0), Some((Align::Center, Align::Center))); // inside the top right container
-
-        // top left container for hold tetrimino
-        let hold1 = ui_square1
-            .sub_rect((0.25, 0.25), Some((Align::Near, Align::Near)))
-            .sub_rect((0.64, 0.64), None);
-
-        // bottom right where next tetriminos are displayed
-        let queue1 = ui_square1
-            .sub_rect((0.25, 0.75), Some((Align::Far, Align::Far)))
-            .sub_rect(
-                (5.0 / 10.0, 23.0 / 24.0),
-                Some((Align::Center, Align::Near)),
-            );
-
-        // bottom left score box
-        let score1 = ui_square1
-            .sub_rect((0.25, 11.0 / 16.0), Some((Align::Near, Align::Far)))
-            .sub_rect((7.0 / 8.0, 8.0 / 11.0), Some((Align::Center, Align::Near)));
+        let layout = self.layout;
 
-        self.canvas.set_draw_color(MATRIX_CONTAINER_COLOR);
-        self.canvas.fill_rect(Rect::from(matrix_container)).unwrap();
+        self.canvas.set_draw_color(self.theme.matrix_container);
+        draw_panel(&mut self.canvas, layout.matrix_container, overlay)?;
 
-        self.canvas.set_draw_color(MATRIX_COLOR);
+        self.canvas.set_draw_color(self.theme.matrix);
 
-        for subrect in [&matrix1, &up_next1, &hold1, &queue1, &score1] {
-            self.canvas.fill_rect(Rect::from(subrect)).unwrap();
+        for subrect in [
+            layout.matrix,
+            layout.up_next,
+            layout.hold,
+            layout.queue,
+            layout.score,
+        ] {
+            draw_panel(&mut self.canvas, subrect, overlay)?;
         }
 
+        // where the cursor would land, unless it's still forming or too close to landing for it to help
+        let engine = &self.game.engine;
+        let ghost = engine.ghost_cursor_info().filter(|_| {
+            self.game.pending().is_none()
+                && self
+                    .settings
+                    .shows_ghost(engine.drop_distance().unwrap_or(0))
+        });
+
         let mut cell_draw_ctx: CellDrawContext<
+            _,
             { Engine::MATRIX_WIDTH },
             { Engine::MATRIX_HEIGHT },
         > = CellDrawContext {
-            origin: matrix1.bottom_left(),
-            dims: matrix1.size(),
+            origin: layout.matrix.bottom_left(),
+            dims: layout.matrix.size(),
             canvas: &mut self.canvas,
-            matrix: &self.engine.matrix, // TODO: figure our how to pass the iter instead of the whole matrix
+            matrix: &self.game.engine.matrix, // TODO: figure our how to pass the iter instead of the whole matrix
+            connected: self.settings.connected_cells,
+            ghost,
         };
 
-        cell_draw_ctx.draw_matrix();
+        cell_draw_ctx.draw_matrix()?;
+
+        match (self.game.engine.cursor_info(), self.game.pending()) {
+            // the tetrimino that's up forms at the top of the matrix, fading in over the spawn delay
+            (Some((cursor_cells, cursor_color, _)), Some((_, formed))) => {
+                let color = cursor_color.screen_color();
+                let alpha = (formed * u8::MAX as f32) as u8;
+                cell_draw_ctx.canvas.set_blend_mode(BlendMode::Blend);
+                for coord in cursor_cells {
+                    cell_draw_ctx
+                        .draw_cell(coord, Color::RGBA(color.r, color.g, color.b, alpha))?;
+                }
+                cell_draw_ctx.canvas.set_blend_mode(BlendMode::None);
+            }
+            // between rows, as far down as it is on its way to the next one
+            (Some((cursor_cells, cursor_color, _)), None) if self.settings.smooth_fall => {
+                let rows = self.game.fall_progress();
+                cell_draw_ctx.draw_lowered_piece(cursor_cells, cursor_color, rows)?;
+            }
+            (Some((cursor_cells, cursor_color, _)), None) => {
+                cell_draw_ctx.draw_piece(cursor_cells, cursor_color)?;
+            }
+            (None, _) => {}
+        }
 
-        if let Some((cursor_cells, cursor_color, _)) = self.engine.cursor_info() {
-            for coord in cursor_cells {
-                cell_draw_ctx.try_draw_cell(coord, Some(cursor_color));
+        if let Some(flash) = self.lock_flash {
+            // blended on top of the cells, so they fade back to their own color
+            cell_draw_ctx.canvas.set_blend_mode(BlendMode::Blend);
+            for coord in flash.cells {
+                cell_draw_ctx.draw_cell(coord, flash.color(&self.settings.lock_flash))?;
+            }
+            cell_draw_ctx.canvas.set_blend_mode(BlendMode::None);
+        }
+
+        // gray rows of the game over animation go on top of everything in the matrix
+        if let Some(animation) = self.game_over_animation {
+            for y in 0..animation.filled_rows {
+                for x in 0..Engine::MATRIX_WIDTH {
+                    cell_draw_ctx.draw_cell(Coordinate::new(x, y), GAME_OVER_FILL_COLOR)?;
+                }
             }
         }
 
         let mut up_next_cell_draw_ctx: CellDrawContext<
+            _,
             { Engine::SINGLE_TETRIMINO_MATRIX_WIDTH },
             { Engine::SINGLE_TETRIMINO_MATRIX_HEIGHT },
         > = CellDrawContext {
-            origin: up_next1.bottom_left(),
-            dims: up_next1.size(),
+            origin: layout.up_next.bottom_left(),
+            dims: layout.up_next.size(),
             canvas: &mut self.canvas,
-            matrix: &self.engine.up_next_matrix,
+            matrix: &self.game.engine.up_next_matrix,
+            connected: self.settings.connected_cells,
+            ghost: None,
         };
 
-        up_next_cell_draw_ctx.draw_matrix();
-
-        let mut remaining_next_cell_draw_ctx: CellDrawContext<
-            { Engine::REMAINING_NEXT_MATRIX_WIDTH },
-            { Engine::REMAINING_NEXT_MATRIX_HEIGHT },
-        > = CellDrawContext {
-            origin: queue1.bottom_left(),
-            dims: queue1.size(),
-            canvas: &mut self.canvas,
-            matrix: &self.engine.queue_matrix,
-        };
+        up_next_cell_draw_ctx.draw_matrix()?;
+
+        // the queue panel only fits the rows with previews in them, the whole queue matrix would go up past it
+        let queue_rows =
+            self.game.engine.config.previews.min(Engine::MAX_PREVIEWS) * Engine::PREVIEW_ROWS;
+        if queue_rows > 0 {
+            let panel = layout.queue.size();
+            let mut remaining_next_cell_draw_ctx: CellDrawContext<
+                _,
+                { Engine::REMAINING_NEXT_MATRIX_WIDTH },
+                { Engine::REMAINING_NEXT_MATRIX_HEIGHT },
+            > = CellDrawContext {
+                origin: layout.queue.bottom_left(),
+                dims: Vector2::new(
+                    panel.x,
+                    panel.y * Engine::REMAINING_NEXT_MATRIX_HEIGHT as u32 / queue_rows as u32,
+                ),
+                canvas: &mut self.canvas,
+                matrix: &self.game.engine.queue_matrix,
+                connected: self.settings.connected_cells,
+                ghost: None,
+            };
 
-        remaining_next_cell_draw_ctx.draw_matrix();
+            remaining_next_cell_draw_ctx.draw_rows(queue_rows)?;
+
+            // a line across the queue where the next bag starts, only if it's within the previews
+            let previews = queue_rows / Engine::PREVIEW_ROWS;
+            let divider = self.game.engine.bag_divider();
+            if self.settings.bag_divider
+                && self.state != State::Spectating
+                && (1..=previews).contains(&divider)
+            {
+                let queue = Rect::from(layout.queue);
+                let above = queue.height() * (previews - divider + 1) as u32 / previews as u32;
+                let line = Rect::new(
+                    queue.x(),
+                    queue.bottom() - above as i32 - 1,
+                    queue.width(),
+                    (queue.height() / 200).max(2),
+                );
+                self.canvas.set_draw_color(PLACEHOLDER_3);
+                self.canvas
+                    .fill_rect(line)
+                    .map_err(InterfaceError::Render)?;
+            }
+        }
 
         let mut hold_cell_draw_ctx: CellDrawContext<
+            _,
             { Engine::SINGLE_TETRIMINO_MATRIX_WIDTH },
             { Engine::SINGLE_TETRIMINO_MATRIX_HEIGHT },
         > = CellDrawContext {
-            origin: hold1.bottom_left(),
-            dims: hold1.size(),
+            origin: layout.hold.bottom_left(),
+            dims: layout.hold.size(),
             canvas: &mut self.canvas,
-            matrix: &self.engine.hold_matrix,
+            matrix: &self.game.engine.hold_matrix,
+            connected: self.settings.connected_cells,
+            ghost: None,
         };
 
-        hold_cell_draw_ctx.draw_matrix();
+        hold_cell_draw_ctx.draw_matrix()?;
+
+        // in a match the opponent's game takes the place of the mini-map
+        let opponent = self.online.as_ref().and_then(Online::opponent);
+        if self.settings.mini_map || opponent.is_some() {
+            let shown = opponent.unwrap_or(&self.game);
+            let render_state = shown.engine.render_state();
+            // the ghost the same as on the main matrix, of whichever game is shown
+            let ghost = shown.engine.ghost_cursor_info().filter(|_| {
+                shown.pending().is_none()
+                    && self
+                        .settings
+                        .shows_ghost(shown.engine.drop_distance().unwrap_or(0))
+            });
+
+            self.canvas.set_draw_color(self.theme.matrix);
+            draw_panel(&mut self.canvas, layout.mini_map, overlay)?;
+
+            let mut mini_map_cell_draw_ctx: CellDrawContext<
+                _,
+                { Engine::MATRIX_WIDTH },
+                { Engine::MATRIX_HEIGHT },
+            > = CellDrawContext {
+                origin: layout.mini_map.bottom_left(),
+                dims: layout.mini_map.size(),
+                canvas: &mut self.canvas,
+                matrix: &render_state,
+                connected: self.settings.connected_cells,
+                ghost,
+            };
 
-        // up next text
-        let up_next_text = up_next1.sub_rect((0.5, 0.2), Some((Align::Center, Align::Near)));
+            mini_map_cell_draw_ctx.draw_matrix()?;
+        }
 
-        let mut text_draw_ctx: TextDrawContext = TextDrawContext {
+        // up next text
+        let mut text_draw_ctx: TextDrawContext<_> = TextDrawContext {
             canvas: &mut self.canvas,
-            font: &font,
+            text_cache: Some(&mut self.text_cache),
             text: "UP NEXT",
-            rect: up_next_text,
+            rect: layout.up_next_text,
         };
-        text_draw_ctx.draw_text();
+        text_draw_ctx.draw_text()?;
 
         // hold text
-        let hold_text = hold1.sub_rect((0.5, 0.25), Some((Align::Center, Align::Near)));
-
-        let mut text_draw_ctx: TextDrawContext = TextDrawContext {
+        let mut text_draw_ctx: TextDrawContext<_> = TextDrawContext {
             canvas: &mut self.canvas,
-            font: &font,
+            text_cache: Some(&mut self.text_cache),
             text: "HOLD",
-            rect: hold_text,
+            rect: layout.hold_text,
+        };
+        text_draw_ctx.draw_text()?;
+
+        // a sprint counts its lines down where the level goes and its time up where the score goes, an
+        // ultra counts its time down where the level goes
+        let mode = self.game.engine.config.mode;
+        let play_time = self.game.play_time;
+        let level = self.game.engine.level as u64;
+        let score = self.game.engine.score as u64;
+        let ((level_label, level_value), (score_label, score_value)) = match mode {
+            GameMode::Marathon => (
+                ("LEVEL", self.level_text.get(level)),
+                ("SCORE", self.score_text.get(score)),
+            ),
+            GameMode::Sprint { .. } => {
+                let left = mode.lines_left(self.game.engine.stats().lines());
+                (
+                    ("LINES", self.level_text.get(left.unwrap_or(0) as u64)),
+                    ("TIME", self.clock_text.get(play_time.as_secs())),
+                )
+            }
+            GameMode::Ultra { .. } => {
+                // counted up to the whole second, it reads 0:00 only once it's over
+                let left = mode.time_left(play_time).unwrap_or_default();
+                let seconds = left.as_secs() + u64::from(left.subsec_nanos() > 0);
+                (
+                    ("TIME", self.clock_text.get(seconds)),
+                    ("SCORE", self.score_text.get(score)),
+                )
+            }
         };
-        text_draw_ctx.draw_text();
-
-        let score_top = score1.sub_rect((1.0, 0.5), Some((Align::Center, Align::Near)));
-        let score_bottom = score1.sub_rect((1.0, 0.5), Some((Align::Center, Align::Far)));
 
         // level text
-        let level_text = score_top.sub_rect((0.5, 0.25), Some((Align::Center, Align::Near)));
-
-        let mut text_draw_ctx: TextDrawContext = TextDrawContext {
+        let mut text_draw_ctx: TextDrawContext<_> = TextDrawContext {
             canvas: &mut self.canvas,
-            font: &font,
-            text: "LEVEL",
-            rect: level_text,
+            text_cache: Some(&mut self.text_cache),
+            text: level_label,
+            rect: layout.level_label,
         };
-        text_draw_ctx.draw_text();
+        text_draw_ctx.draw_text()?;
 
         // level text
-        let level_text = score_top.sub_rect((0.8, 0.85), Some((Align::Center, Align::Far)));
-
-        let level: u8 = self.engine.level;
-
-        let mut text_draw_ctx: TextDrawContext = TextDrawContext {
+        let mut text_draw_ctx: TextDrawContext<_> = TextDrawContext {
             canvas: &mut self.canvas,
-            font: &font,
-            text: &format!("  {level}  "),
-            rect: level_text,
+            text_cache: Some(&mut self.text_cache),
+            text: level_value,
+            rect: layout.level_value,
         };
-        text_draw_ctx.draw_text();
+        text_draw_ctx.draw_text()?;
 
         // lines text
-        let lines_text = score_bottom.sub_rect((0.5, 0.25), Some((Align::Center, Align::Near)));
-
-        let mut text_draw_ctx: TextDrawContext = TextDrawContext {
+        let mut text_draw_ctx: TextDrawContext<_> = TextDrawContext {
             canvas: &mut self.canvas,
-            font: &font,
-            text: "SCORE",
-            rect: lines_text,
+            text_cache: Some(&mut self.text_cache),
+            text: score_label,
+            rect: layout.score_label,
         };
-        text_draw_ctx.draw_text();
+        text_draw_ctx.draw_text()?;
 
         // lines text
-        let lines_text = score_bottom.sub_rect((0.8, 0.85), Some((Align::Center, Align::Far)));
-
-        let score = self.engine.score;
-        let mut text_draw_ctx: TextDrawContext = TextDrawContext {
+        let mut text_draw_ctx: TextDrawContext<_> = TextDrawContext {
             canvas: &mut self.canvas,
-            font: &font,
-            text: &format!("  {score}  "),
-            rect: lines_text,
+            text_cache: Some(&mut self.text_cache),
+            text: score_value,
+            rect: layout.score_value,
         };
-        text_draw_ctx.draw_text();
+        text_draw_ctx.draw_text()?;
+
+        // under the score, the bonuses the next clear would keep going
+        let bonus = self
+            .bonus_text
+            .get(self.game.engine.combo(), self.game.engine.back_to_back);
+        if let Some(bonus) = bonus {
+            let mut text_draw_ctx: TextDrawContext<_> = TextDrawContext {
+                canvas: &mut self.canvas,
+                text_cache: Some(&mut self.text_cache),
+                text: bonus,
+                rect: layout.bonus_text,
+            };
+            text_draw_ctx.draw_text()?;
+        }
+
+        // a spectator only knows what the host's board looks like, not what's in its bag
+        if self.settings.bag_panel && self.state != State::Spectating {
+            // below the matrix, the tetriminos still left in the current bag
+            let mut text_draw_ctx: TextDrawContext<_> = TextDrawContext {
+                canvas: &mut self.canvas,
+                text_cache: Some(&mut self.text_cache),
+                text: self.bag_text.get(self.game.engine.remaining_bag()),
+                rect: layout.bag_text,
+            };
+            text_draw_ctx.draw_text()?;
+        }
+
+        // the review has its own text up there
+        if self.settings.piece_stats && self.state == State::Playing {
+            let strip = Rect::from(layout.piece_stats);
+            let stats = self.game.engine.stats();
+            let slots = PieceKind::ALL.len() as u32;
+
+            for (index, kind) in PieceKind::ALL.into_iter().enumerate() {
+                let slot = Rect::new(
+                    strip.x() + (strip.width() * index as u32 / slots) as i32,
+                    strip.y(),
+                    strip.width() / slots,
+                    strip.height(),
+                );
+                let icon = SubRect::of(slot, (0.5, 0.25), Some((Align::Near, Align::Center)));
+                draw_piece_icon(&mut self.canvas, icon, kind)?;
+
+                let mut text_draw_ctx: TextDrawContext<_> = TextDrawContext {
+                    canvas: &mut self.canvas,
+                    text_cache: Some(&mut self.text_cache),
+                    text: self.placed_texts[index].get(stats.placed(kind) as u64),
+                    rect: SubRect::of(slot, (0.4, 0.6), Some((Align::Far, Align::Center))),
+                };
+                text_draw_ctx.draw_text()?;
+            }
+        }
+
+        let is_animating = self
+            .game_over_animation
+            .is_some_and(|animation| !animation.is_complete());
+
+        if self.quick_restart.is_held() {
+            // restart fill indicator along the bottom of the matrix container
+            let progress = self.quick_restart.progress(Instant::now());
+            let indicator = layout
+                .matrix_container
+                .sub_rect((progress, 1.0 / 64.0), Some((Align::Near, Align::Far)));
+
+            self.canvas.set_draw_color(Color::WHITE);
+            self.canvas
+                .fill_rect(Rect::from(indicator))
+                .map_err(InterfaceError::Render)?;
+        }
+
+        if self.state.has_overlay(&self.game) && !is_animating {
+            // the board dimmed under the menu or message, the text reads better on it
+            draw_dim(
+                &mut self.canvas,
+                Rect::from(layout.matrix_container),
+                self.settings.dim_opacity,
+            )?;
+        }
+
+        if self.state == State::Playing && self.game.is_paused() {
+            // pause menu lines, stacked from the top of the matrix area; just the countdown while resuming
+            let countdown = self
+                .auto_pause
+                .seconds_left()
+                .map(|seconds| self.countdown_text.get(seconds));
+            let menu_lines: &[String] = match countdown {
+                Some(_) => &[],
+                None => {
+                    self.pause_menu_text
+                        .get(&self.pause_menu, &self.settings, &self.metrics_page)
+                }
+            };
+            let lines = countdown
+                .into_iter()
+                .chain(menu_lines.iter().map(String::as_str));
+            let menu_rect = Rect::from(layout.menu_area);
+
+            for (index, line) in lines.enumerate() {
+                let line_rect = PauseMenu::line_rect(menu_rect, index);
+
+                let mut text_draw_ctx: TextDrawContext<_> = TextDrawContext {
+                    canvas: &mut self.canvas,
+                    text_cache: Some(&mut self.text_cache),
+                    text: line,
+                    rect: SubRect::of(line_rect, (1.0, 0.8), None),
+                };
+                text_draw_ctx.draw_text()?;
+            }
+        }
+
+        if let (State::Spectating, Some(spectating)) = (self.state, &self.spectating) {
+            let mut text_draw_ctx: TextDrawContext<_> = TextDrawContext {
+                canvas: &mut self.canvas,
+                text_cache: Some(&mut self.text_cache),
+                text: &spectating.status(),
+                rect: layout.review_text,
+            };
+            text_draw_ctx.draw_text()?;
+        }
+
+        if let (State::Reviewing, Some(review)) = (self.state, &mut self.review) {
+            let mut text_draw_ctx: TextDrawContext<_> = TextDrawContext {
+                canvas: &mut self.canvas,
+                text_cache: Some(&mut self.text_cache),
+                text: review.label(),
+                rect: layout.review_text,
+            };
+            text_draw_ctx.draw_text()?;
+        }
+
+        if let (State::Replaying, Some(replaying)) = (self.state, &mut self.replaying) {
+            let mut text_draw_ctx: TextDrawContext<_> = TextDrawContext {
+                canvas: &mut self.canvas,
+                text_cache: Some(&mut self.text_cache),
+                text: replaying.label(),
+                rect: layout.review_text,
+            };
+            text_draw_ctx.draw_text()?;
+        }
+
+        if self.state == State::SeedEntry {
+            let entry_area = Rect::from(layout.entry_area);
+            let line_height = entry_area.height() / 4;
+            let seed = if self.seed_entry.text.is_empty() {
+                "RANDOM"
+            } else {
+                &self.seed_entry.text
+            };
 
-        if self.state == State::GameOver {
+            let lines = if self.resumable.is_some() {
+                [
+                    "RESUME PREVIOUS GAME?",
+                    "ENTER TO RESUME",
+                    "ESC TO DECLINE",
+                    "",
+                ]
+            } else {
+                [
+                    seed_entry::mode_line(self.game.engine.config.mode),
+                    "SEED",
+                    seed,
+                    "ENTER TO START",
+                ]
+            };
+
+            for (index, line) in lines.iter().enumerate() {
+                let line_rect = Rect::new(
+                    entry_area.x(),
+                    entry_area.y() + (index as u32 * line_height) as i32,
+                    entry_area.width(),
+                    line_height,
+                );
+
+                let mut text_draw_ctx: TextDrawContext<_> = TextDrawContext {
+                    canvas: &mut self.canvas,
+                    text_cache: Some(&mut self.text_cache),
+                    text: line,
+                    rect: SubRect::of(line_rect, (1.0, 0.8), None),
+                };
+                text_draw_ctx.draw_text()?;
+            }
+        }
+
+        let online_label = match &self.spectating {
+            Some(spectating) => spectating.label(),
+            None => self.online.as_ref().and_then(Online::label),
+        };
+        if let Some(label) = online_label.filter(|_| !is_animating) {
+            // waiting for the opponent or the host, how the match ended or why it was cut short
+            let mut text_draw_ctx: TextDrawContext<_> = TextDrawContext {
+                canvas: &mut self.canvas,
+                text_cache: Some(&mut self.text_cache),
+                text: &label,
+                rect: layout.game_over_text,
+            };
+            text_draw_ctx.draw_text()?;
+        } else if self.state == State::Playing && self.game.is_game_over() && !is_animating {
             // game over text
-            let game_over_text =
-                matrix_container.sub_rect((0.8, 0.1), Some((Align::Center, Align::Center)));
+            let mut text_draw_ctx: TextDrawContext<_> = TextDrawContext {
+                canvas: &mut self.canvas,
+                text_cache: Some(&mut self.text_cache),
+                text: game_over_text(&self.game, &mut self.sprint_time_text),
+                rect: layout.game_over_text,
+            };
+            text_draw_ctx.draw_text()?;
+
+            // the high scores under it, a line each
+            let lines = self
+                .game
+                .engine
+                .high_scores
+                .as_ref()
+                .map(HighScoreTable::lines)
+                .unwrap_or_default();
+            let area = Rect::from(layout.high_scores);
+            let line_height = area.height() / HighScoreTable::LEN as u32;
+            for (index, line) in lines.iter().enumerate() {
+                let line_rect = Rect::new(
+                    area.x(),
+                    area.y() + (index as u32 * line_height) as i32,
+                    area.width(),
+                    line_height,
+                );
+
+                let mut text_draw_ctx: TextDrawContext<_> = TextDrawContext {
+                    canvas: &mut self.canvas,
+                    text_cache: Some(&mut self.text_cache),
+                    text: line,
+                    rect: SubRect::of(line_rect, (0.8, 0.8), None),
+                };
+                text_draw_ctx.draw_text()?;
+            }
+        } else if let Some(level_up) = self
+            .level_up
+            .filter(|_| self.state == State::Playing && !self.game.is_paused() && !is_animating)
+        {
+            // the new level where the game over text would be, the gravity smaller underneath it
+            let area = Rect::from(layout.game_over_text);
+            let half = area.height() / 2;
+            for (index, (line, scale)) in level_up.lines().iter().zip([1.0, 0.6]).enumerate() {
+                let line_rect = Rect::new(
+                    area.x(),
+                    area.y() + (index as u32 * half) as i32,
+                    area.width(),
+                    half,
+                );
+
+                let mut text_draw_ctx: TextDrawContext<_> = TextDrawContext {
+                    canvas: &mut self.canvas,
+                    text_cache: Some(&mut self.text_cache),
+                    text: line,
+                    rect: SubRect::of(line_rect, (scale, scale), None),
+                };
+                text_draw_ctx.draw_text()?;
+            }
+        }
+
+        if let Some(popup) = &self.popup {
+            // on a backdrop of its own, it can be over anything
+            self.canvas.set_blend_mode(BlendMode::Blend);
+            self.canvas.set_draw_color(LOG_OVERLAY_COLOR);
+            self.canvas
+                .fill_rect(Rect::from(layout.review_text))
+                .map_err(InterfaceError::Render)?;
+            self.canvas.set_blend_mode(BlendMode::None);
+
+            let mut text_draw_ctx: TextDrawContext<_> = TextDrawContext {
+                canvas: &mut self.canvas,
+                text_cache: Some(&mut self.text_cache),
+                text: &popup.text,
+                rect: layout.review_text,
+            };
+            text_draw_ctx.draw_text()?;
+        }
 
-            let mut text_draw_ctx: TextDrawContext = TextDrawContext {
+        if self.log_overlay {
+            // the log on a darkened backdrop over the whole window, one line per row
+            let log_area = Rect::from(layout.ui_square);
+            self.canvas.set_blend_mode(BlendMode::Blend);
+            self.canvas.set_draw_color(LOG_OVERLAY_COLOR);
+            self.canvas
+                .fill_rect(log_area)
+                .map_err(InterfaceError::Render)?;
+            self.canvas.set_blend_mode(BlendMode::None);
+
+            let line_height = log_area.height() / logger::RECENT_LINES as u32;
+            for (index, line) in logger::recent_lines().iter().enumerate() {
+                let line_rect = Rect::new(
+                    log_area.x(),
+                    log_area.y() + (index as u32 * line_height) as i32,
+                    log_area.width(),
+                    line_height,
+                );
+
+                let mut text_draw_ctx: TextDrawContext<_> = TextDrawContext {
+                    canvas: &mut self.canvas,
+                    text_cache: Some(&mut self.text_cache),
+                    text: line,
+                    rect: SubRect::of(line_rect, (1.0, 0.8), Some((Align::Near, Align::Center))),
+                };
+                text_draw_ctx.draw_text()?;
+            }
+        }
+
+        if let Some(recording) = self.recording.as_mut() {
+            // the frame goes in as it is, without the indicator
+            if recording.wants_frame() {
+                let (width, height) = self.canvas.output_size().map_err(InterfaceError::Render)?;
+                let rgb = self
+                    .canvas
+                    .read_pixels(None, PixelFormatEnum::RGB24)
+                    .map_err(InterfaceError::Render)?;
+                recording.capture(rgb, width, height);
+            }
+
+            let indicator = Rect::from(SubRect::of(
+                Rect::from(layout.ui_square),
+                (0.08, 0.04),
+                Some((Align::Near, Align::Near)),
+            ));
+            self.canvas.set_draw_color(RECORD_COLOR);
+            self.canvas
+                .fill_rect(indicator)
+                .map_err(InterfaceError::Render)?;
+
+            let mut text_draw_ctx: TextDrawContext<_> = TextDrawContext {
                 canvas: &mut self.canvas,
-                font: &font,
-                text: "GAME OVER",
-                rect: game_over_text,
+                text_cache: Some(&mut self.text_cache),
+                text: "REC",
+                rect: SubRect::of(indicator, (0.9, 0.8), None),
             };
-            text_draw_ctx.draw_text();
+            text_draw_ctx.draw_text()?;
         }
 
         self.canvas.present();
+
+        if let Some(debug) = self.debug_window.as_mut() {
+            debug.render(&self.game.engine, &mut self.text_cache)?;
+        }
+
+        // a frame that shows the same as the last one shouldn't allocate at all
+        #[cfg(feature = "alloc-count")]
+        log::trace!(
+            "{} allocations in the frame",
+            tetris::alloc_count::allocations() - allocations
+        );
+
+        Ok(())
+    }
+}
+
+// the background of a panel, in overlay mode only its outline so the key color shows through
+fn draw_panel(
+    canvas: &mut Canvas<Window>,
+    panel: SubRect,
+    overlay: Option<Overlay>,
+) -> Result<(), InterfaceError> {
+    let rect = Rect::from(panel);
+    match overlay {
+        Some(_) => canvas.draw_rect(rect),
+        None => canvas.fill_rect(rect),
     }
+    .map_err(InterfaceError::Render)
+}
+
+// a dark layer over the rect, opacity 0 (not drawn at all) to 255 (black)
+fn draw_dim(canvas: &mut Canvas<Window>, rect: Rect, opacity: u8) -> Result<(), InterfaceError> {
+    if opacity == 0 {
+        return Ok(());
+    }
+
+    canvas.set_blend_mode(BlendMode::Blend);
+    canvas.set_draw_color(Color::RGBA(0x00, 0x00, 0x00, opacity));
+    let filled = canvas.fill_rect(rect).map_err(InterfaceError::Render);
+    canvas.set_blend_mode(BlendMode::None);
+    filled
+}
+
+// a tetrimino facing north, filling the four by two cells of the rect; every kind fits in rows 1 and 2
+// of its grid that way
+fn draw_piece_icon(
+    canvas: &mut Canvas<Window>,
+    icon: SubRect,
+    kind: PieceKind,
+) -> Result<(), InterfaceError> {
+    let rect = Rect::from(icon);
+    let (width, height) = ((rect.width() / 4).max(1), (rect.height() / 2).max(1));
+
+    canvas.set_draw_color(kind.color().screen_color());
+    for offset in kind.cells() {
+        let cell = Rect::new(
+            rect.x() + offset.x as i32 * width as i32,
+            rect.y() + (2 - offset.y as i32) * height as i32, // rows go up, the screen goes down
+            width,
+            height,
+        );
+        canvas.fill_rect(cell).map_err(InterfaceError::Render)?;
+    }
+    Ok(())
+}
+
+// the name a high score goes in under, there's no asking for one
+fn player_name() -> String {
+    env::var("USER")
+        .or_else(|_| env::var("USERNAME"))
+        .map(|name| name.to_uppercase())
+        .unwrap_or_else(|_| "PLAYER".to_string())
+}
+
+// a sprint that was finished shows the time it took, an ultra that ran out says so
+fn game_over_text<'a>(game: &GameController, sprint_time_text: &'a mut ClockText) -> &'a str {
+    match game.engine.config.mode {
+        GameMode::Sprint { .. } if game.completed => sprint_time_text.get(game.play_time.as_secs()),
+        GameMode::Ultra { .. } if game.completed => "TIME UP",
+        _ => "GAME OVER",
+    }
+}
+
+// the renderer for the window; vsync can only be chosen when a renderer is created
+fn build_canvas(window: Window, frame_rate: FrameRate) -> Result<Canvas<Window>, InterfaceError> {
+    let mut builder = window.into_canvas().accelerated();
+
+    if frame_rate.is_vsync() {
+        builder = builder.present_vsync();
+    }
+
+    builder
+        .build()
+        .map_err(|e| InterfaceError::WindowCreation(e.to_string()))
 }
@@ -0,0 +1,136 @@
+use std::time::Duration;
+
+use tetris::controller::{GameController, GameInput};
+use tetris::net::connection::{Connection, Pending};
+use tetris::net::spectate::Broadcast;
+use tetris::net::versus::{Outcome, Versus};
+use tetris::net::NetError;
+
+// how long the game waits on the opponent's inputs before giving up on the match
+const STALL_TIMEOUT: Duration = Duration::from_secs(10);
+
+// a match against someone over the network, from waiting for them to the end of it
+pub enum Online {
+    Connecting(Pending),
+    Playing {
+        connection: Connection,
+        versus: Box<Versus>,  // both games, by far the biggest part
+        stalled: Duration,    // since the last step that could run
+        broadcast: Broadcast, // the local game, for whoever is watching
+    },
+    Ended(String), // the match was cut short, why
+}
+
+impl Online {
+    // a local input goes to the match instead of straight to the game
+    pub fn input(&mut self, input: GameInput) {
+        if let Self::Playing { versus, .. } = self {
+            versus.input(input);
+        }
+    }
+
+    // one fixed step: connecting, then exchanging inputs and running the steps both sides are in for.
+    // `game` is the local game, replaced with the match's once connected. a network error ends the match.
+    // returns whether anything changed that needs a redraw
+    pub fn update(&mut self, game: &mut GameController, dt: Duration) -> bool {
+        match self.advance(game, dt) {
+            Ok(changed) => changed,
+            Err(error) => {
+                log::warn!("Match aborted: {error}");
+                *self = Self::Ended(error.to_string());
+                true
+            }
+        }
+    }
+
+    fn advance(&mut self, game: &mut GameController, dt: Duration) -> Result<bool, NetError> {
+        match self {
+            Self::Connecting(pending) => {
+                let Some(connection) = pending.poll() else {
+                    return Ok(false);
+                };
+                let mut connection = connection?;
+
+                let (versus, local) =
+                    Versus::start(connection.seed, game.engine.config, dt, connection.is_host);
+                *game = local;
+                log::info!("Started a match with seed {}", connection.seed);
+
+                *self = Self::Playing {
+                    broadcast: connection.broadcast(),
+                    connection,
+                    versus: Box::new(versus),
+                    stalled: Duration::ZERO,
+                };
+                Ok(true)
+            }
+            Self::Playing {
+                connection,
+                versus,
+                stalled,
+                broadcast,
+            } => {
+                // the end of the match is still shown to the spectators
+                broadcast.update(game, versus.step(), dt);
+                if versus.outcome().is_some() {
+                    return Ok(false);
+                }
+
+                if let Some(message) = versus.send_step() {
+                    connection.send(&message)?;
+                }
+                while let Some(message) = connection.try_receive()? {
+                    versus.receive(message)?;
+                }
+                let ran = versus.advance(game)?;
+                for message in versus.take_outgoing() {
+                    connection.send(&message)?;
+                }
+
+                if ran > 0 {
+                    *stalled = Duration::ZERO;
+                } else {
+                    *stalled += dt;
+                    if *stalled > STALL_TIMEOUT {
+                        return Err(NetError::Stalled);
+                    }
+                }
+
+                Ok(ran > 0)
+            }
+            Self::Ended(_) => Ok(false),
+        }
+    }
+
+    // steps still have to run, the loop can't sleep for long
+    pub fn is_running(&self) -> bool {
+        match self {
+            Self::Connecting(_) => true,
+            Self::Playing { versus, .. } => versus.outcome().is_none(),
+            Self::Ended(_) => false,
+        }
+    }
+
+    pub fn opponent(&self) -> Option<&GameController> {
+        match self {
+            Self::Playing { versus, .. } => Some(&versus.opponent),
+            Self::Connecting(_) | Self::Ended(_) => None,
+        }
+    }
+
+    // what to show over the matrix, None while the match is on
+    pub fn label(&self) -> Option<String> {
+        match self {
+            Self::Connecting(_) => Some("WAITING FOR OPPONENT".to_string()),
+            Self::Playing { versus, .. } => versus.outcome().map(|outcome| {
+                match outcome {
+                    Outcome::Won => "YOU WIN",
+                    Outcome::Lost => "YOU LOSE",
+                    Outcome::Draw => "DRAW",
+                }
+                .to_string()
+            }),
+            Self::Ended(reason) => Some(reason.clone()),
+        }
+    }
+}
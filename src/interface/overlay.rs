@@ -0,0 +1,74 @@
+use sdl2::pixels::Color;
+
+use super::render_traits::ScreenColor;
+use tetris::engine::color::TetriminoColor;
+use tetris::engine::piece_kind::PieceKind;
+
+// for compositing the board over a stream: the background in a solid color to key out, and panels only
+// outlined so it shows through them too. the game itself is drawn as always
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Overlay {
+    pub chroma: Color,
+    pub pinned: bool, // a borderless window of a fixed size, always on top
+}
+
+impl Default for Overlay {
+    fn default() -> Self {
+        Self {
+            chroma: Self::DEFAULT_CHROMA,
+            pinned: false,
+        }
+    }
+}
+
+impl Overlay {
+    pub const DEFAULT_CHROMA: Color = Color::RGB(0x00, 0xff, 0x00);
+    const MIN_DISTANCE: f32 = 64.0; // in rgb space, about as far as the tolerance of a keyer reaches
+
+    // the colors of cells which are close enough to the chroma color to be keyed out along with it
+    pub fn clashes(&self) -> Vec<TetriminoColor> {
+        PieceKind::ALL
+            .iter()
+            .map(PieceKind::color)
+            .chain([TetriminoColor::Gray])
+            .filter(|color| distance(color.screen_color(), self.chroma) < Self::MIN_DISTANCE)
+            .collect()
+    }
+}
+
+fn distance(a: Color, b: Color) -> f32 {
+    let channel = |a: u8, b: u8| (a as f32 - b as f32).powi(2);
+    (channel(a.r, b.r) + channel(a.g, b.g) + channel(a.b, b.b)).sqrt()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn green_screen_is_clear_of_the_cells() {
+        assert!(Overlay::default().clashes().is_empty());
+
+        let blue_screen = Overlay {
+            chroma: Color::RGB(0x00, 0x00, 0xff),
+            ..Overlay::default()
+        };
+        assert!(blue_screen.clashes().is_empty());
+    }
+
+    #[test]
+    fn colors_near_a_cell_clash() {
+        let red = TetriminoColor::Red.screen_color();
+        let overlay = Overlay {
+            chroma: Color::RGB(red.r, red.g + 20, red.b),
+            ..Overlay::default()
+        };
+        assert_eq!(overlay.clashes(), [TetriminoColor::Red]);
+
+        let gray = Overlay {
+            chroma: Color::RGB(0x99, 0x99, 0x99),
+            ..Overlay::default()
+        };
+        assert_eq!(gray.clashes(), [TetriminoColor::Gray]);
+    }
+}
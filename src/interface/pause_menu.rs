@@ -0,0 +1,425 @@
+use sdl2::keyboard::Keycode;
+use sdl2::rect::Rect;
+
+use super::settings::{Setting, Settings};
+
+// entries of the menu shown while the game is paused
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum PauseEntry {
+    Resume,
+    Restart,
+    Settings,
+    Metrics,
+    Quit,
+}
+
+impl PauseEntry {
+    pub const ALL: [Self; 5] = [
+        Self::Resume,
+        Self::Restart,
+        Self::Settings,
+        Self::Metrics,
+        Self::Quit,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Resume => "RESUME",
+            Self::Restart => "RESTART",
+            Self::Settings => "SETTINGS",
+            Self::Metrics => "METRICS",
+            Self::Quit => "QUIT TO MENU",
+        }
+    }
+}
+
+// what part of the pause menu is currently shown
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum PauseScreen {
+    Menu,
+    Confirm(PauseEntry), // restart and quit throw away the current game, so they need a second press
+    Settings,
+    Metrics, // trends from the metrics log, read when the page is opened
+}
+
+// keys that move around the menu; these are fixed and not part of the key bindings
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum PauseNavigation {
+    Up,
+    Down,
+    Select,
+    Back,
+}
+
+impl PauseNavigation {
+    pub fn try_from(key: Keycode) -> Option<Self> {
+        match key {
+            Keycode::Up => Some(Self::Up),
+            Keycode::Down => Some(Self::Down),
+            Keycode::Return => Some(Self::Select),
+            Keycode::Escape | Keycode::Backspace => Some(Self::Back),
+            _ => None,
+        }
+    }
+}
+
+// what the interface should do once the menu has been navigated
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum PauseOutcome {
+    Stay,   // still in the menu, the game underneath stays untouched
+    Resume, // through the countdown, see AutoPause::resume
+    Restart,
+    Quit,    // to the seed entry, the game is thrown away
+    Metrics, // the metrics page was opened, what it shows is up to the interface
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct PauseMenu {
+    pub screen: PauseScreen,
+    pub selected: usize, // index into PauseEntry::ALL or Setting::ALL, depending on the screen
+}
+
+impl Default for PauseMenu {
+    fn default() -> Self {
+        Self {
+            screen: PauseScreen::Menu,
+            selected: 0,
+        }
+    }
+}
+
+impl PauseMenu {
+    pub const MAX_LINES: usize = Setting::ALL.len(); // the settings screen shows the most lines
+
+    pub fn navigate(
+        &mut self,
+        navigation: PauseNavigation,
+        settings: &mut Settings,
+    ) -> PauseOutcome {
+        match (self.screen, navigation) {
+            (PauseScreen::Menu | PauseScreen::Settings, PauseNavigation::Up) => {
+                self.selected = (self.selected + self.entry_count() - 1) % self.entry_count();
+            }
+            (PauseScreen::Menu | PauseScreen::Settings, PauseNavigation::Down) => {
+                self.selected = (self.selected + 1) % self.entry_count();
+            }
+            (PauseScreen::Menu, PauseNavigation::Select) => match PauseEntry::ALL[self.selected] {
+                PauseEntry::Resume => return PauseOutcome::Resume,
+                PauseEntry::Settings => {
+                    self.screen = PauseScreen::Settings;
+                    self.selected = 0;
+                }
+                PauseEntry::Metrics => {
+                    self.screen = PauseScreen::Metrics;
+                    return PauseOutcome::Metrics;
+                }
+                entry => self.screen = PauseScreen::Confirm(entry),
+            },
+            (PauseScreen::Menu, PauseNavigation::Back) => return PauseOutcome::Resume,
+            (PauseScreen::Confirm(entry), PauseNavigation::Select) => {
+                return match entry {
+                    PauseEntry::Restart => PauseOutcome::Restart,
+                    _ => PauseOutcome::Quit,
+                };
+            }
+            (PauseScreen::Confirm(_), PauseNavigation::Back) => self.screen = PauseScreen::Menu,
+            (PauseScreen::Confirm(_), _) => {}
+            (PauseScreen::Settings, PauseNavigation::Select) => {
+                settings.toggle(Setting::ALL[self.selected]);
+            }
+            (PauseScreen::Settings, PauseNavigation::Back) => {
+                // go back to the menu with settings still selected
+                self.screen = PauseScreen::Menu;
+                self.selected = PauseEntry::ALL
+                    .iter()
+                    .position(|&entry| entry == PauseEntry::Settings)
+                    .unwrap();
+            }
+            (PauseScreen::Metrics, PauseNavigation::Back) => self.screen = PauseScreen::Menu,
+            (PauseScreen::Metrics, _) => {}
+        }
+
+        PauseOutcome::Stay
+    }
+
+    // a click on a line picks what's on it, like moving there and pressing enter; the confirmation and
+    // metrics screens have nothing to pick, a confirmation is only ever given from the keyboard
+    pub fn click(&mut self, line: usize, settings: &mut Settings) -> PauseOutcome {
+        if !self.has_entry(line) {
+            return PauseOutcome::Stay;
+        }
+
+        self.selected = line;
+        self.navigate(PauseNavigation::Select, settings)
+    }
+
+    // the mouse over a line highlights it; whether that changed anything
+    pub fn point(&mut self, line: usize) -> bool {
+        if !self.has_entry(line) || self.selected == line {
+            return false;
+        }
+
+        self.selected = line;
+        true
+    }
+
+    // where the line at index goes in the menu area, the lines are stacked from its top
+    pub fn line_rect(area: Rect, index: usize) -> Rect {
+        let line_height = area.height() / (Self::MAX_LINES as u32);
+        Rect::new(
+            area.x(),
+            area.y() + (index as u32 * line_height) as i32,
+            area.width(),
+            line_height,
+        )
+    }
+
+    // the line under the mouse at (x, y), if it's over the menu area
+    pub fn line_at(area: Rect, x: i32, y: i32) -> Option<usize> {
+        if !area.contains_point((x, y)) {
+            return None;
+        }
+
+        let line_height = (area.height() / (Self::MAX_LINES as u32)).max(1);
+        Some(((y - area.y()) as u32 / line_height) as usize)
+    }
+
+    // lines to draw for the current screen, along with which one is highlighted; the metrics page shows
+    // the lines it's given
+    pub fn lines(&self, settings: &Settings, metrics: &[String]) -> (Vec<String>, Option<usize>) {
+        match self.screen {
+            PauseScreen::Menu => (
+                PauseEntry::ALL
+                    .iter()
+                    .map(|entry| entry.label().to_string())
+                    .collect(),
+                Some(self.selected),
+            ),
+            PauseScreen::Confirm(entry) => (
+                vec![
+                    format!("{}?", entry.label()),
+                    "ENTER TO CONFIRM".to_string(),
+                ],
+                None,
+            ),
+            PauseScreen::Settings => (
+                Setting::ALL
+                    .iter()
+                    .map(|&setting| {
+                        let value = if settings.get(setting) { "ON" } else { "OFF" };
+                        format!("{} {value}", setting.label())
+                    })
+                    .collect(),
+                Some(self.selected),
+            ),
+            PauseScreen::Metrics => (metrics.to_vec(), None),
+        }
+    }
+
+    fn has_entry(&self, line: usize) -> bool {
+        matches!(self.screen, PauseScreen::Menu | PauseScreen::Settings)
+            && line < self.entry_count()
+    }
+
+    fn entry_count(&self) -> usize {
+        match self.screen {
+            PauseScreen::Settings => Setting::ALL.len(),
+            _ => PauseEntry::ALL.len(),
+        }
+    }
+}
+
+// the lines of the current screen with the highlighted one marked, only made again when the screen,
+// what's selected or a setting changes, or the metrics page is read again
+#[derive(Clone, Default, Debug)]
+pub struct PauseMenuText {
+    shown: Option<(PauseMenu, Settings)>, // what the lines were made from
+    lines: Vec<String>,
+}
+
+impl PauseMenuText {
+    pub fn get(&mut self, menu: &PauseMenu, settings: &Settings, metrics: &[String]) -> &[String] {
+        let metrics_changed = menu.screen == PauseScreen::Metrics && self.lines != metrics;
+        if self.shown != Some((*menu, *settings)) || metrics_changed {
+            let (lines, highlighted) = menu.lines(settings, metrics);
+            self.lines = lines;
+            if let Some(line) = highlighted.and_then(|index| self.lines.get_mut(index)) {
+                *line = format!("> {line} <");
+            }
+            self.shown = Some((*menu, *settings));
+        }
+
+        &self.lines
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn select(menu: &mut PauseMenu, settings: &mut Settings, entry: PauseEntry) -> PauseOutcome {
+        while PauseEntry::ALL[menu.selected] != entry {
+            menu.navigate(PauseNavigation::Down, settings);
+        }
+        menu.navigate(PauseNavigation::Select, settings)
+    }
+
+    #[test]
+    fn resume() {
+        let mut settings = Settings::default();
+        let mut menu = PauseMenu::default();
+        assert_eq!(
+            select(&mut menu, &mut settings, PauseEntry::Resume),
+            PauseOutcome::Resume
+        );
+
+        // backing out of the menu resumes as well
+        let mut menu = PauseMenu::default();
+        assert_eq!(
+            menu.navigate(PauseNavigation::Back, &mut settings),
+            PauseOutcome::Resume
+        );
+    }
+
+    #[test]
+    fn restart_needs_confirmation() {
+        let mut settings = Settings::default();
+        let mut menu = PauseMenu::default();
+
+        assert_eq!(
+            select(&mut menu, &mut settings, PauseEntry::Restart),
+            PauseOutcome::Stay
+        );
+        assert_eq!(menu.screen, PauseScreen::Confirm(PauseEntry::Restart));
+
+        // navigating up or down doesn't confirm
+        assert_eq!(
+            menu.navigate(PauseNavigation::Down, &mut settings),
+            PauseOutcome::Stay
+        );
+        assert_eq!(
+            menu.navigate(PauseNavigation::Select, &mut settings),
+            PauseOutcome::Restart
+        );
+    }
+
+    #[test]
+    fn declined_confirmation_returns_to_menu() {
+        let mut settings = Settings::default();
+        let mut menu = PauseMenu::default();
+
+        select(&mut menu, &mut settings, PauseEntry::Quit);
+        assert_eq!(
+            menu.navigate(PauseNavigation::Back, &mut settings),
+            PauseOutcome::Stay
+        );
+        assert_eq!(menu.screen, PauseScreen::Menu);
+    }
+
+    #[test]
+    fn quit_needs_confirmation() {
+        let mut settings = Settings::default();
+        let mut menu = PauseMenu::default();
+
+        assert_eq!(
+            select(&mut menu, &mut settings, PauseEntry::Quit),
+            PauseOutcome::Stay
+        );
+        assert_eq!(
+            menu.navigate(PauseNavigation::Select, &mut settings),
+            PauseOutcome::Quit
+        );
+    }
+
+    #[test]
+    fn settings_toggle_and_return_to_pause() {
+        let mut settings = Settings::default();
+        let mut menu = PauseMenu::default();
+
+        select(&mut menu, &mut settings, PauseEntry::Settings);
+        assert_eq!(menu.screen, PauseScreen::Settings);
+
+        menu.navigate(PauseNavigation::Down, &mut settings);
+        menu.navigate(PauseNavigation::Select, &mut settings);
+        assert!(settings.mini_map);
+
+        assert_eq!(
+            menu.navigate(PauseNavigation::Back, &mut settings),
+            PauseOutcome::Stay
+        );
+        assert_eq!(menu.screen, PauseScreen::Menu);
+        assert_eq!(PauseEntry::ALL[menu.selected], PauseEntry::Settings);
+    }
+
+    #[test]
+    fn lines_can_be_pointed_at_and_clicked() {
+        let mut settings = Settings::default();
+        let mut menu = PauseMenu::default();
+        let area = Rect::new(100, 200, 300, 10 * PauseMenu::MAX_LINES as u32);
+
+        assert_eq!(PauseMenu::line_at(area, 150, 200), Some(0));
+        assert_eq!(PauseMenu::line_at(area, 150, 235), Some(3));
+        assert_eq!(PauseMenu::line_at(area, 99, 235), None);
+        assert_eq!(PauseMenu::line_rect(area, 3), Rect::new(100, 230, 300, 10));
+
+        assert!(menu.point(1));
+        assert!(!menu.point(1)); // already highlighted
+        assert!(!menu.point(PauseEntry::ALL.len())); // under the last entry
+        assert_eq!(PauseEntry::ALL[menu.selected], PauseEntry::Restart);
+
+        // a click asks for the confirmation like enter does, which a click doesn't give
+        assert_eq!(menu.click(1, &mut settings), PauseOutcome::Stay);
+        assert_eq!(menu.screen, PauseScreen::Confirm(PauseEntry::Restart));
+        assert_eq!(menu.click(1, &mut settings), PauseOutcome::Stay);
+        assert_eq!(menu.screen, PauseScreen::Confirm(PauseEntry::Restart));
+
+        menu.navigate(PauseNavigation::Back, &mut settings);
+        assert_eq!(menu.click(0, &mut settings), PauseOutcome::Resume);
+    }
+
+    #[test]
+    fn metrics_page_shows_what_it_is_given() {
+        let mut settings = Settings::default();
+        let mut menu = PauseMenu::default();
+
+        assert_eq!(
+            select(&mut menu, &mut settings, PauseEntry::Metrics),
+            PauseOutcome::Metrics
+        );
+        let page = ["BEST SCORE 100".to_string()];
+        assert_eq!(menu.lines(&settings, &page), (page.to_vec(), None));
+
+        menu.navigate(PauseNavigation::Back, &mut settings);
+        assert_eq!(menu.screen, PauseScreen::Menu);
+        assert_eq!(PauseEntry::ALL[menu.selected], PauseEntry::Metrics);
+    }
+
+    #[test]
+    fn menu_text_marks_the_highlighted_line_and_follows_changes() {
+        let mut settings = Settings::default();
+        let mut menu = PauseMenu::default();
+        let mut text = PauseMenuText::default();
+
+        assert_eq!(
+            text.get(&menu, &settings, &[])[..2],
+            ["> RESUME <", "RESTART"]
+        );
+        menu.navigate(PauseNavigation::Down, &mut settings);
+        assert_eq!(
+            text.get(&menu, &settings, &[])[..2],
+            ["RESUME", "> RESTART <"]
+        );
+
+        // a setting toggled shows up right away
+        select(&mut menu, &mut settings, PauseEntry::Settings);
+        let before = text.get(&menu, &settings, &[])[0].clone();
+        menu.navigate(PauseNavigation::Select, &mut settings);
+        assert_ne!(text.get(&menu, &settings, &[])[0], before);
+
+        // so does a metrics page read again
+        menu.navigate(PauseNavigation::Back, &mut settings);
+        select(&mut menu, &mut settings, PauseEntry::Metrics);
+        assert_eq!(text.get(&menu, &settings, &["A".to_string()]), ["A"]);
+        assert_eq!(text.get(&menu, &settings, &["B".to_string()]), ["B"]);
+    }
+}
@@ -0,0 +1,101 @@
+use std::time::{Duration, Instant};
+
+// the restart key throws away the whole run, so it has to be either held down or double tapped
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub struct QuickRestart {
+    held_since: Option<Instant>, // when the key went down, None if it isn't held
+    last_press: Option<Instant>, // for detecting a double tap
+}
+
+impl QuickRestart {
+    pub const HOLD_DURATION: Duration = Duration::from_millis(500);
+    pub const DOUBLE_TAP_WINDOW: Duration = Duration::from_millis(300);
+
+    // the key went down; returns true if this press completes a double tap
+    pub fn press(&mut self, now: Instant) -> bool {
+        let is_double_tap = self
+            .last_press
+            .is_some_and(|last_press| now.duration_since(last_press) <= Self::DOUBLE_TAP_WINDOW);
+
+        if is_double_tap {
+            self.reset();
+            return true;
+        }
+
+        self.held_since = Some(now);
+        self.last_press = Some(now);
+        false
+    }
+
+    pub fn release(&mut self) {
+        self.held_since = None;
+    }
+
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+
+    pub fn is_held(&self) -> bool {
+        self.held_since.is_some()
+    }
+
+    // how far along the hold is, from 0 to 1, for drawing the fill indicator
+    pub fn progress(&self, now: Instant) -> f32 {
+        let Some(held_since) = self.held_since else {
+            return 0.0;
+        };
+
+        let held_for = now.duration_since(held_since).as_secs_f32();
+        (held_for / Self::HOLD_DURATION.as_secs_f32()).min(1.0)
+    }
+
+    pub fn is_confirmed(&self, now: Instant) -> bool {
+        self.progress(now) >= 1.0
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn hold_to_confirm() {
+        let start = Instant::now();
+        let mut restart = QuickRestart::default();
+
+        assert!(!restart.press(start));
+        assert!(restart.is_held());
+        assert!(!restart.is_confirmed(start + Duration::from_millis(250)));
+        assert!((restart.progress(start + Duration::from_millis(250)) - 0.5).abs() < 0.01);
+        assert!(restart.is_confirmed(start + Duration::from_millis(500)));
+        assert_eq!(restart.progress(start + Duration::from_secs(2)), 1.0);
+    }
+
+    #[test]
+    fn release_cancels_hold() {
+        let start = Instant::now();
+        let mut restart = QuickRestart::default();
+
+        restart.press(start);
+        restart.release();
+
+        assert!(!restart.is_held());
+        assert!(!restart.is_confirmed(start + Duration::from_secs(1)));
+    }
+
+    #[test]
+    fn double_tap_confirms() {
+        let start = Instant::now();
+        let mut restart = QuickRestart::default();
+
+        assert!(!restart.press(start));
+        restart.release();
+        assert!(restart.press(start + Duration::from_millis(200)));
+
+        // a slow second tap is just a new press
+        let mut restart = QuickRestart::default();
+        restart.press(start);
+        restart.release();
+        assert!(!restart.press(start + Duration::from_millis(400)));
+    }
+}
@@ -0,0 +1,121 @@
+use std::fs::{self, File};
+use std::io::{self, BufWriter};
+use std::path::Path;
+use std::sync::mpsc::{self, SyncSender, TrySendError};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+
+use super::gif::{Frame, GifWriter};
+
+const DIR: &str = "recordings";
+const SCALE: usize = 2; // frames are kept at half the window's size
+const QUEUED_FRAMES: usize = 8; // waiting for the encoder, any more are dropped
+const LAST_FRAME_DELAY: u16 = 100; // in hundredths of a second, so the end doesn't flash by
+const MIN_DELAY: u16 = 2; // shorter delays are slowed down by most viewers anyway
+
+// a captured frame on its way to the encoder: rgb24 pixels as read from the canvas
+struct Capture {
+    rgb: Vec<u8>,
+    width: usize,
+    height: usize,
+    at: Instant,
+}
+
+// the game as an animated gif in recordings/, started and stopped with RECORD_KEY. frames go to a
+// thread that encodes them into the file as they come, so at most a few of them are ever in memory
+pub struct Recording {
+    frames: SyncSender<Capture>,
+    started: Instant,
+    drawn: u64,
+}
+
+impl Recording {
+    pub const MAX_DURATION: Duration = Duration::from_secs(120); // stopped on its own after that
+
+    pub fn start() -> io::Result<Self> {
+        fs::create_dir_all(DIR)?;
+        let stamp = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        let path = Path::new(DIR).join(format!("tetris-{stamp}.gif"));
+        let file = File::create(&path)?;
+
+        let (frames, captures) = mpsc::sync_channel(QUEUED_FRAMES);
+        thread::spawn(move || {
+            if let Err(error) = encode(captures.into_iter(), BufWriter::new(file)) {
+                log::warn!("Failed to record {}: {error}", path.display());
+                return;
+            }
+            log::info!("Recorded {}", path.display());
+        });
+        log::info!("Recording");
+
+        Ok(Self {
+            frames,
+            started: Instant::now(),
+            drawn: 0,
+        })
+    }
+
+    // every other frame drawn is enough for a recording
+    pub fn wants_frame(&mut self) -> bool {
+        self.drawn += 1;
+        !self.drawn.is_multiple_of(2)
+    }
+
+    pub fn capture(&self, rgb: Vec<u8>, width: u32, height: u32) {
+        let capture = Capture {
+            rgb,
+            width: width as usize,
+            height: height as usize,
+            at: Instant::now(),
+        };
+        if let Err(TrySendError::Full(_)) = self.frames.try_send(capture) {
+            log::debug!("Dropped a frame, the recording is behind");
+        }
+    }
+
+    pub fn is_over(&self) -> bool {
+        self.started.elapsed() >= Self::MAX_DURATION
+    }
+
+    // the encoder finishes the file on its own once the frames stop coming
+    pub fn stop(self) {
+        log::info!("Stopped recording");
+    }
+}
+
+// every frame is written once the next one shows how long it was on screen. a resized window changes
+// the size of the frames, those don't fit in the gif and are left out
+fn encode(captures: impl Iterator<Item = Capture>, out: BufWriter<File>) -> io::Result<()> {
+    let mut out = Some(out);
+    let mut gif = None;
+    let mut previous: Option<(Frame, Instant)> = None;
+
+    for capture in captures {
+        let frame = Frame::downscaled(&capture.rgb, capture.width, capture.height, SCALE);
+        if let Some(out) = out.take() {
+            let size = (frame.width, frame.height);
+            gif = Some((GifWriter::new(out, size.0 as u16, size.1 as u16)?, size));
+        }
+        let Some((gif, size)) = gif.as_mut() else {
+            unreachable!("started with the first frame");
+        };
+        if (frame.width, frame.height) != *size {
+            continue;
+        }
+
+        if let Some((frame, at)) = previous.take() {
+            let delay = ((capture.at - at).as_millis() / 10).min(u16::MAX as u128) as u16;
+            gif.frame(&frame, delay.max(MIN_DELAY))?;
+        }
+        previous = Some((frame, capture.at));
+    }
+
+    if let (Some((mut gif, _)), Some((frame, _))) = (gif, previous) {
+        gif.frame(&frame, LAST_FRAME_DELAY)?;
+        gif.finish()?;
+    }
+    Ok(())
+}
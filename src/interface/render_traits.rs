@@ -4,6 +4,12 @@ use crate::engine::color::TetriminoColor;
 
 pub trait ScreenColor {
     fn screen_color(&self) -> SdlColor;
+
+    // the same at half the brightness, for the ghost of a tetrimino
+    fn ghost_color(&self) -> SdlColor {
+        let color = self.screen_color();
+        SdlColor::RGB(color.r / 2, color.g / 2, color.b / 2)
+    }
 }
 
 // we pull it out rather than putting it directly on the semantic color so this is a member of the interface and NOT the engine
@@ -17,6 +23,7 @@ impl ScreenColor for TetriminoColor {
             TetriminoColor::Blue => SdlColor::RGB(0x34, 0x65, 0xa4),
             TetriminoColor::Green => SdlColor::RGB(0x73, 0xd2, 0x16),
             TetriminoColor::Red => SdlColor::RGB(0xef, 0x29, 0x29),
+            TetriminoColor::Gray => SdlColor::RGB(0x88, 0x88, 0x88),
         }
     }
 }
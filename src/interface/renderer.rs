@@ -0,0 +1,100 @@
+use sdl2::pixels::Color;
+use sdl2::rect::{Point, Rect};
+use sdl2::render::{BlendMode, Canvas};
+use sdl2::surface::Surface;
+use sdl2::video::Window;
+
+// the few canvas calls the draw contexts make. the contexts are generic over it, so the sdl canvas gets
+// them monomorphized (no dyn while drawing cells) and tests get a recording to look at instead
+pub trait Renderer {
+    fn set_color(&mut self, color: Color);
+    fn set_blend_mode(&mut self, blend: BlendMode);
+    fn fill_rect(&mut self, rect: Rect) -> Result<(), String>;
+    fn draw_rect(&mut self, rect: Rect) -> Result<(), String>;
+    fn draw_line(&mut self, start: Point, end: Point) -> Result<(), String>;
+    // the surface becomes a texture stretched over the rect, this is how text gets on screen
+    fn copy_texture(&mut self, surface: &Surface, dst: Rect) -> Result<(), String>;
+}
+
+impl Renderer for Canvas<Window> {
+    fn set_color(&mut self, color: Color) {
+        Canvas::set_draw_color(self, color);
+    }
+
+    fn set_blend_mode(&mut self, blend: BlendMode) {
+        Canvas::set_blend_mode(self, blend);
+    }
+
+    fn fill_rect(&mut self, rect: Rect) -> Result<(), String> {
+        Canvas::fill_rect(self, rect)
+    }
+
+    fn draw_rect(&mut self, rect: Rect) -> Result<(), String> {
+        Canvas::draw_rect(self, rect)
+    }
+
+    fn draw_line(&mut self, start: Point, end: Point) -> Result<(), String> {
+        Canvas::draw_line(self, start, end)
+    }
+
+    fn copy_texture(&mut self, surface: &Surface, dst: Rect) -> Result<(), String> {
+        let texture_creator = self.texture_creator();
+        let texture = texture_creator
+            .create_texture_from_surface(surface)
+            .map_err(|e| e.to_string())?;
+
+        self.copy(&texture, None, Some(dst))
+    }
+}
+
+// every call made, in order, for tests to compare against
+#[cfg(test)]
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum DrawCall {
+    SetColor(Color),
+    SetBlendMode(BlendMode),
+    FillRect(Rect),
+    DrawRect(Rect),
+    DrawLine(Point, Point),
+    CopyTexture { size: (u32, u32), dst: Rect },
+}
+
+#[cfg(test)]
+#[derive(Default, Debug)]
+pub struct RecordingRenderer {
+    pub calls: Vec<DrawCall>,
+}
+
+#[cfg(test)]
+impl Renderer for RecordingRenderer {
+    fn set_color(&mut self, color: Color) {
+        self.calls.push(DrawCall::SetColor(color));
+    }
+
+    fn set_blend_mode(&mut self, blend: BlendMode) {
+        self.calls.push(DrawCall::SetBlendMode(blend));
+    }
+
+    fn fill_rect(&mut self, rect: Rect) -> Result<(), String> {
+        self.calls.push(DrawCall::FillRect(rect));
+        Ok(())
+    }
+
+    fn draw_rect(&mut self, rect: Rect) -> Result<(), String> {
+        self.calls.push(DrawCall::DrawRect(rect));
+        Ok(())
+    }
+
+    fn draw_line(&mut self, start: Point, end: Point) -> Result<(), String> {
+        self.calls.push(DrawCall::DrawLine(start, end));
+        Ok(())
+    }
+
+    fn copy_texture(&mut self, surface: &Surface, dst: Rect) -> Result<(), String> {
+        self.calls.push(DrawCall::CopyTexture {
+            size: surface.size(),
+            dst,
+        });
+        Ok(())
+    }
+}
@@ -0,0 +1,159 @@
+use std::time::Duration;
+
+use tetris::engine::recording::{HeadlessPlayback, HeadlessReplay};
+use tetris::engine::Engine;
+use tetris::replay::{Playback, Replay};
+use tetris::save::Saved;
+
+use super::text_draw::KeyedText;
+
+// a saved replay watched from the start, at the speed it was played at; unlike a review it only goes
+// forwards, there's nothing to jump around in
+pub struct Replaying {
+    source: Source,
+    elapsed: Duration,                  // since the last step, short of a whole one
+    pub own: Engine, // the engine of the game that's put back once the replay is left
+    label: KeyedText<(bool, u32, u32)>, // by whether it's over, then the step or tick out of how many
+}
+
+// a game's replay steps at the timestep it was recorded with; a headless one has no timing of its own,
+// so it ticks at the speed gravity would pull the tetrimino down at its level
+enum Source {
+    Game(Replay, Box<Playback>), // boxed, the engines in them make both large
+    Headless(HeadlessReplay, Box<HeadlessPlayback>),
+}
+
+impl Replaying {
+    pub fn new(saved: Saved, own: Engine) -> Self {
+        let source = match saved {
+            Saved::Game(replay) => {
+                let playback = Box::new(Playback::new(&replay));
+                Source::Game(replay, playback)
+            }
+            Saved::Headless(replay) => {
+                let playback = Box::new(HeadlessPlayback::new(&replay));
+                Source::Headless(replay, playback)
+            }
+        };
+        Self {
+            source,
+            elapsed: Duration::ZERO,
+            own,
+            label: KeyedText::default(),
+        }
+    }
+
+    pub fn engine(&self) -> &Engine {
+        match &self.source {
+            Source::Game(_, playback) => &playback.game.engine,
+            Source::Headless(_, playback) => &playback.engine,
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        match &self.source {
+            Source::Game(replay, playback) => playback.is_finished(replay),
+            Source::Headless(replay, playback) => playback.is_finished(replay),
+        }
+    }
+
+    fn step_time(&self) -> Duration {
+        match &self.source {
+            Source::Game(replay, _) => replay.step,
+            Source::Headless(_, playback) => playback.engine.drop_time(false),
+        }
+    }
+
+    // runs the steps that are due after another dt; whether any were
+    pub fn advance(&mut self, dt: Duration) -> bool {
+        self.elapsed += dt;
+        let mut stepped = false;
+        while self.elapsed >= self.step_time() && !self.is_finished() {
+            self.elapsed -= self.step_time();
+            match &mut self.source {
+                Source::Game(replay, playback) => playback.step(replay),
+                Source::Headless(replay, playback) => playback.step(replay),
+            }
+            stepped = true;
+        }
+
+        stepped
+    }
+
+    // None once it's over
+    pub fn time_until_step(&self) -> Option<Duration> {
+        if self.is_finished() {
+            return None;
+        }
+        Some(self.step_time().saturating_sub(self.elapsed))
+    }
+
+    pub fn label(&mut self) -> &str {
+        let (unit, at, of) = match &self.source {
+            Source::Game(replay, playback) => ("STEP", playback.step, replay.steps),
+            Source::Headless(replay, playback) => ("TICK", playback.ticks, replay.ticks()),
+        };
+        self.label
+            .get((self.is_finished(), at, of), |&(finished, at, of)| {
+                if finished {
+                    return "REPLAY OVER  ESC TO PLAY".to_string();
+                }
+                format!("REPLAY  {unit} {at}/{of}")
+            })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use tetris::controller::{GameController, GameInput};
+    use tetris::engine::config::Config;
+    use tetris::engine::headless::EngineInput;
+    use tetris::engine::move_kind::MoveKind;
+
+    #[test]
+    fn replays_play_at_the_speed_they_were_recorded() {
+        let step = Duration::from_millis(10);
+        let mut game = GameController::new(3, Config::default(), step);
+        game.apply(GameInput::HardDrop);
+        for _ in 0..50 {
+            game.update(step);
+        }
+        let replay = game.replay.clone().unwrap();
+
+        let mut replaying = Replaying::new(Saved::Game(replay), Engine::new());
+        assert_eq!(replaying.time_until_step(), Some(step));
+        assert!(!replaying.advance(step / 2));
+        assert_eq!(replaying.time_until_step(), Some(step / 2));
+        assert!(replaying.advance(step * 2)); // two and a half steps in, the half is left over
+        assert_eq!(replaying.label(), "REPLAY  STEP 2/50");
+
+        // however long the frame was, it stops at the end
+        assert!(replaying.advance(Duration::from_secs(10)));
+        assert!(replaying.is_finished());
+        assert_eq!(replaying.time_until_step(), None);
+        assert_eq!(replaying.engine().matrix.matrix, game.engine.matrix.matrix);
+        assert_eq!(replaying.engine().cursor(), game.engine.cursor());
+    }
+
+    #[test]
+    fn headless_replays_tick_at_the_speed_of_gravity() {
+        let mut engine = Engine::new_seeded(9);
+        engine.start_recording();
+        for _ in 0..3 {
+            engine.apply_input(EngineInput::Move(MoveKind::Left));
+            engine.tick();
+        }
+        let headless = engine.finish_recording();
+
+        let mut replaying = Replaying::new(Saved::Headless(headless), Engine::new());
+        let tick = replaying.engine().drop_time(false);
+        assert_eq!(replaying.time_until_step(), Some(tick));
+        assert!(replaying.advance(tick * 2));
+        assert_eq!(replaying.label(), "REPLAY  TICK 2/3");
+
+        assert!(replaying.advance(tick));
+        assert!(replaying.is_finished());
+        assert_eq!(replaying.engine().cursor(), engine.cursor());
+    }
+}
@@ -0,0 +1,72 @@
+use sdl2::keyboard::Keycode;
+
+use super::text_draw::KeyedText;
+use crate::engine::Engine;
+use tetris::replay::Scrubber;
+
+// looking back through the game that was just lost, started from the game over screen
+pub struct Review {
+    pub scrubber: Scrubber,
+    pub finished: Engine, // the engine as the game ended, put back once the review is over
+    label: KeyedText<(u32, u32, u32, u32)>, // by the piece and step it's at, out of how many
+}
+
+// keys that move through the review; these are fixed and not part of the key bindings
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ReviewNavigation {
+    StepBackward,
+    StepForward,
+    PreviousPiece,
+    NextPiece,
+    Exit,
+}
+
+impl ReviewNavigation {
+    pub const START_KEY: Keycode = Keycode::V; // on the game over screen
+
+    pub fn try_from(key: Keycode) -> Option<Self> {
+        match key {
+            Keycode::Left => Some(Self::StepBackward),
+            Keycode::Right => Some(Self::StepForward),
+            Keycode::Down => Some(Self::PreviousPiece),
+            Keycode::Up => Some(Self::NextPiece),
+            Keycode::Escape | Keycode::Backspace | Self::START_KEY => Some(Self::Exit),
+            _ => None,
+        }
+    }
+}
+
+impl Review {
+    pub fn new(scrubber: Scrubber, finished: Engine) -> Self {
+        Self {
+            scrubber,
+            finished,
+            label: KeyedText::default(),
+        }
+    }
+
+    // move through the replay; returns false once the review should be closed
+    pub fn navigate(&mut self, navigation: ReviewNavigation) -> bool {
+        match navigation {
+            ReviewNavigation::StepBackward => self.scrubber.step_backward(),
+            ReviewNavigation::StepForward => self.scrubber.step_forward(),
+            ReviewNavigation::PreviousPiece => self.scrubber.previous_piece(),
+            ReviewNavigation::NextPiece => self.scrubber.next_piece(),
+            ReviewNavigation::Exit => return false,
+        }
+
+        true
+    }
+
+    pub fn label(&mut self) -> &str {
+        let progress = (
+            self.scrubber.piece(),
+            self.scrubber.piece_count(),
+            self.scrubber.position(),
+            self.scrubber.end(),
+        );
+        self.label.get(progress, |(piece, pieces, step, steps)| {
+            format!("REVIEW  PIECE {piece}/{pieces}  STEP {step}/{steps}")
+        })
+    }
+}
@@ -0,0 +1,83 @@
+use tetris::engine::mode::GameMode;
+
+// the screen before the first game, where a seed can be typed or pasted in to play a known sequence
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct SeedEntry {
+    pub text: String, // only ever digits
+}
+
+impl SeedEntry {
+    pub const MAX_DIGITS: usize = 20; // as many as u64::MAX has
+
+    // typed or pasted text; anything that isn't a digit is left out
+    pub fn insert(&mut self, text: &str) {
+        for digit in text.chars().filter(char::is_ascii_digit) {
+            if self.text.len() < Self::MAX_DIGITS {
+                self.text.push(digit);
+            }
+        }
+    }
+
+    pub fn backspace(&mut self) {
+        self.text.pop();
+    }
+
+    // the seed to start with; whatever doesn't make a valid seed gets a random one
+    pub fn seed(&self, random: impl FnOnce() -> u64) -> u64 {
+        parse_seed(&self.text).unwrap_or_else(random)
+    }
+}
+
+// the mode the game is started in, picked on the same screen with left and right
+pub fn mode_line(mode: GameMode) -> &'static str {
+    match mode {
+        GameMode::Marathon => "< MARATHON >",
+        GameMode::Sprint { .. } => "< SPRINT >",
+        GameMode::Ultra { .. } => "< ULTRA >",
+    }
+}
+
+pub fn parse_seed(text: &str) -> Option<u64> {
+    text.trim().parse().ok()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn valid_seed_is_used() {
+        assert_eq!(parse_seed("12345"), Some(12345));
+        assert_eq!(parse_seed(" 42\n"), Some(42));
+
+        let mut entry = SeedEntry::default();
+        entry.insert("98765");
+        assert_eq!(entry.seed(|| 7), 98765);
+    }
+
+    #[test]
+    fn invalid_seed_falls_back_to_random() {
+        assert_eq!(parse_seed("abc"), None);
+        assert_eq!(parse_seed(""), None);
+        assert_eq!(parse_seed("99999999999999999999"), None); // larger than u64::MAX
+
+        let entry = SeedEntry {
+            text: "not a seed".to_string(),
+        };
+        assert_eq!(entry.seed(|| 7), 7);
+        assert_eq!(SeedEntry::default().seed(|| 7), 7);
+    }
+
+    #[test]
+    fn insert_keeps_digits_only() {
+        let mut entry = SeedEntry::default();
+        entry.insert("12-34 ab5");
+        assert_eq!(entry.text, "12345");
+
+        entry.backspace();
+        assert_eq!(entry.text, "1234");
+
+        entry.insert(&"9".repeat(30));
+        assert_eq!(entry.text.len(), SeedEntry::MAX_DIGITS);
+    }
+}
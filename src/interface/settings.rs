@@ -0,0 +1,158 @@
+use super::frame_limiter::FrameRate;
+use super::input::ControlPreset;
+use super::lock_flash::LockFlashStyle;
+use super::overlay::Overlay;
+use std::time::Duration;
+
+// user facing toggles for the interface, the engine knows nothing about these
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Settings {
+    pub game_over_animation: bool, // fill the matrix with gray row by row before showing the game over text
+    pub mini_map: bool,            // scaled down overview of the whole matrix below the score box
+    pub bag_panel: bool,           // which tetriminos are still left in the current 7-bag
+    pub bag_divider: bool,         // a line in the queue where the next 7-bag starts
+    pub piece_stats: bool,         // above the matrix, how many of each tetrimino were placed
+    pub connected_cells: bool, // draw each tetrimino as one shape instead of four outlined cells
+    pub smooth_fall: bool, // draw the falling tetrimino in between rows instead of jumping from row to row
+    pub auto_pause: bool,  // pause when the window loses focus
+    pub auto_resume: bool, // and resume after a countdown once it has focus again
+    pub metrics: bool,     // add a line about every finished game to the metrics log
+    pub frame_rate: FrameRate, // only read when the canvas is built, so it isn't in the settings screen
+    pub lock_flash: LockFlashStyle, // set from the command line, also not in the settings screen
+    pub level_up: Duration, // how long a new level is shown after reaching it, zero leaves it out
+    pub ghost_min_distance: usize, // the ghost is left out when the tetrimino is closer than this to landing
+    pub dim_opacity: u8, // how dark the board gets under a menu or message over it, 0 leaves it as it is
+    pub overlay: Option<Overlay>, // for streaming, from the command line; the window is built with it
+    pub wide_panels: bool, // spread the panels out on wide windows, from the command line as well
+    pub controls: ControlPreset, // which preset the key bindings came from, custom once keys are picked one by one
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            game_over_animation: true,
+            mini_map: false,
+            bag_panel: false,
+            bag_divider: false,
+            piece_stats: false,
+            connected_cells: false,
+            smooth_fall: true,
+            auto_pause: true,
+            auto_resume: false,
+            metrics: false,
+            frame_rate: FrameRate::Vsync,
+            lock_flash: LockFlashStyle::default(),
+            level_up: Duration::from_millis(1500),
+            ghost_min_distance: 0,
+            dim_opacity: 0x99,
+            overlay: None,
+            wide_panels: false,
+            controls: ControlPreset::Guideline,
+        }
+    }
+}
+
+// the individual toggles, so they can be listed and flipped from the settings screen
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Setting {
+    GameOverAnimation,
+    MiniMap,
+    BagPanel,
+    BagDivider,
+    PieceStats,
+    ConnectedCells,
+    SmoothFall,
+    AutoPause,
+    AutoResume,
+    Metrics,
+}
+
+impl Setting {
+    pub const ALL: [Self; 10] = [
+        Self::GameOverAnimation,
+        Self::MiniMap,
+        Self::BagPanel,
+        Self::BagDivider,
+        Self::PieceStats,
+        Self::ConnectedCells,
+        Self::SmoothFall,
+        Self::AutoPause,
+        Self::AutoResume,
+        Self::Metrics,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::GameOverAnimation => "ANIMATION",
+            Self::MiniMap => "MINI MAP",
+            Self::BagPanel => "BAG",
+            Self::BagDivider => "BAG DIVIDER",
+            Self::PieceStats => "STATISTICS",
+            Self::ConnectedCells => "CONNECTED",
+            Self::SmoothFall => "SMOOTH FALL",
+            Self::AutoPause => "AUTO PAUSE",
+            Self::AutoResume => "AUTO RESUME",
+            Self::Metrics => "METRICS",
+        }
+    }
+}
+
+impl Settings {
+    pub fn get(&self, setting: Setting) -> bool {
+        match setting {
+            Setting::GameOverAnimation => self.game_over_animation,
+            Setting::MiniMap => self.mini_map,
+            Setting::BagPanel => self.bag_panel,
+            Setting::BagDivider => self.bag_divider,
+            Setting::PieceStats => self.piece_stats,
+            Setting::ConnectedCells => self.connected_cells,
+            Setting::SmoothFall => self.smooth_fall,
+            Setting::AutoPause => self.auto_pause,
+            Setting::AutoResume => self.auto_resume,
+            Setting::Metrics => self.metrics,
+        }
+    }
+
+    // whether the ghost is worth showing for a tetrimino the given number of rows above where it lands;
+    // right above the stack it mostly just covers the tetrimino
+    pub fn shows_ghost(&self, drop_distance: usize) -> bool {
+        drop_distance >= self.ghost_min_distance
+    }
+
+    pub fn toggle(&mut self, setting: Setting) {
+        let value = self.toggle_of(setting);
+        *value = !*value;
+    }
+
+    fn toggle_of(&mut self, setting: Setting) -> &mut bool {
+        match setting {
+            Setting::GameOverAnimation => &mut self.game_over_animation,
+            Setting::MiniMap => &mut self.mini_map,
+            Setting::BagPanel => &mut self.bag_panel,
+            Setting::BagDivider => &mut self.bag_divider,
+            Setting::PieceStats => &mut self.piece_stats,
+            Setting::ConnectedCells => &mut self.connected_cells,
+            Setting::SmoothFall => &mut self.smooth_fall,
+            Setting::AutoPause => &mut self.auto_pause,
+            Setting::AutoResume => &mut self.auto_resume,
+            Setting::Metrics => &mut self.metrics,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn ghost_only_from_the_minimum_distance() {
+        let mut settings = Settings::default();
+        assert!(settings.shows_ghost(0));
+
+        settings.ghost_min_distance = 3;
+        assert!(!settings.shows_ghost(0));
+        assert!(!settings.shows_ghost(2));
+        assert!(settings.shows_ghost(3));
+        assert!(settings.shows_ghost(18));
+    }
+}
@@ -0,0 +1,69 @@
+use std::time::Duration;
+
+use tetris::controller::GameController;
+use tetris::net::connection::Pending;
+use tetris::net::spectate::Watcher;
+use tetris::net::NetError;
+
+// watching someone else's match, the window shows the host's game instead of one of its own
+pub enum Spectating {
+    Connecting(Pending<Watcher>),
+    Watching(Box<Watcher>),
+    Ended(String), // why there's nothing more to watch
+}
+
+impl Spectating {
+    // one fixed step: connecting, then taking in the frames. `game` only serves to draw the host's board
+    // with. returns whether anything changed that needs a redraw
+    pub fn update(&mut self, game: &mut GameController, dt: Duration) -> bool {
+        let result = match self {
+            Self::Connecting(pending) => match pending.poll() {
+                Some(Ok(watcher)) => {
+                    log::info!("Spectating");
+                    watcher.board.show(&mut game.engine);
+                    *self = Self::Watching(Box::new(watcher));
+                    Ok(())
+                }
+                Some(Err(error)) => Err(error),
+                None => return false,
+            },
+            Self::Watching(watcher) => watcher.update(dt).map(|changed| {
+                if changed {
+                    watcher.board.show(&mut game.engine);
+                }
+            }),
+            Self::Ended(_) => return false,
+        };
+
+        if let Err(error) = result {
+            log::warn!("Stopped spectating: {error}");
+            *self = Self::Ended(match error {
+                NetError::Disconnected => "Host disconnected".to_string(),
+                error => error.to_string(),
+            });
+        }
+
+        // the delay indicator keeps counting between frames
+        true
+    }
+
+    // what to show over the matrix, None while there's a game to watch
+    pub fn label(&self) -> Option<String> {
+        match self {
+            Self::Connecting(_) => Some("CONNECTING".to_string()),
+            Self::Watching(watcher) if watcher.board.game_over => Some("GAME OVER".to_string()),
+            Self::Watching(_) => None,
+            Self::Ended(reason) => Some(reason.clone()),
+        }
+    }
+
+    // above the matrix, along with how far behind the host's game the board could be
+    pub fn status(&self) -> String {
+        match self {
+            Self::Watching(watcher) => {
+                format!("SPECTATING  {:.1}S BEHIND", watcher.delay().as_secs_f32())
+            }
+            Self::Connecting(_) | Self::Ended(_) => "SPECTATING".to_string(),
+        }
+    }
+}
@@ -1,9 +1,59 @@
+use tetris::controller::GameController;
+
+// what the window is showing; while playing, the game has states of its own (see GameState)
 #[derive(Clone, Copy, PartialEq, Debug)]
 pub enum State {
-    Paused,
-    SoftDropping,
-    LockingDown,
-    LockedDown,
-    TickingDown,
-    GameOver,
+    SeedEntry,  // before the first game, a seed can be typed in
+    Connecting, // waiting for the opponent of a match over the network
+    Playing,
+    Reviewing,  // stepping through a replay of the game that was just lost
+    Replaying,  // watching a saved replay play out
+    Spectating, // watching someone else's match
+}
+
+impl State {
+    // whether a menu or a message is shown over the board, which then gets dimmed under it. a review, a
+    // replay or a match being watched is all about the board, their labels are out of its way
+    pub fn has_overlay(self, game: &GameController) -> bool {
+        match self {
+            Self::SeedEntry | Self::Connecting => true,
+            Self::Playing => game.is_paused() || game.is_game_over(),
+            Self::Reviewing | Self::Replaying | Self::Spectating => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::time::Duration;
+    use tetris::controller::GameInput;
+    use tetris::engine::config::Config;
+
+    #[test]
+    fn menus_and_messages_dim_the_board() {
+        let step = Duration::from_millis(10);
+        let mut game = GameController::new(3, Config::default(), step);
+
+        assert!(State::SeedEntry.has_overlay(&game));
+        assert!(State::Connecting.has_overlay(&game));
+        assert!(!State::Playing.has_overlay(&game));
+        assert!(!State::Reviewing.has_overlay(&game));
+        assert!(!State::Replaying.has_overlay(&game));
+        assert!(!State::Spectating.has_overlay(&game));
+
+        // the pause menu, with its settings and metrics pages
+        game.apply(GameInput::Pause);
+        assert!(State::Playing.has_overlay(&game));
+        game.apply(GameInput::Resume);
+        assert!(!State::Playing.has_overlay(&game));
+
+        // the game over text
+        while !game.is_game_over() {
+            game.apply(GameInput::HardDrop);
+            game.update(step);
+        }
+        assert!(State::Playing.has_overlay(&game));
+        assert!(!State::Reviewing.has_overlay(&game));
+    }
 }
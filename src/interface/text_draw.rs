@@ -1,34 +1,300 @@
-use sdl2::render::TextureQuery;
+use std::collections::HashMap;
+
+use sdl2::surface::Surface;
 use sdl2::ttf::Font;
-use sdl2::{pixels::Color, rect::Rect, render::Canvas, video::Window};
+use sdl2::{pixels::Color, rect::Rect};
 
+use super::error::InterfaceError;
+use super::renderer::Renderer;
 use super::sub_rect::SubRect;
+use tetris::engine::piece_kind::PieceKind;
+
+// whatever was made from a text, kept by what the text says; looking up a text that's already in
+// there doesn't allocate
+pub struct TextMap<T> {
+    entries: HashMap<String, T>,
+}
+
+impl<T> Default for TextMap<T> {
+    fn default() -> Self {
+        Self {
+            entries: HashMap::new(),
+        }
+    }
+}
+
+impl<T> TextMap<T> {
+    const MAX_TEXTS: usize = 256; // a score that keeps going up would pile up texts forever otherwise
+
+    pub fn get_or_try_insert<E>(
+        &mut self,
+        text: &str,
+        make: impl FnOnce() -> Result<T, E>,
+    ) -> Result<&T, E> {
+        if !self.entries.contains_key(text) {
+            if self.entries.len() >= Self::MAX_TEXTS {
+                self.entries.clear();
+            }
+            self.entries.insert(text.to_string(), make()?);
+        }
+
+        Ok(&self.entries[text])
+    }
+}
+
+// text rendered once, so labels and numbers that stay the same from frame to frame aren't rendered (or
+// allocated for) again
+pub struct TextCache<'ttf> {
+    font: Font<'ttf, 'static>,
+    surfaces: TextMap<Surface<'static>>,
+}
+
+impl<'ttf> TextCache<'ttf> {
+    pub fn new(font: Font<'ttf, 'static>) -> Self {
+        Self {
+            font,
+            surfaces: TextMap::default(),
+        }
+    }
+
+    pub fn surface(&mut self, text: &str) -> Result<&Surface<'static>, InterfaceError> {
+        let font = &self.font;
+        self.surfaces.get_or_try_insert(text, || {
+            font.render(text)
+                .blended(Color::WHITE)
+                .map_err(|e| InterfaceError::Render(e.to_string()))
+        })
+    }
+}
+
+// a number as text with some space on either side, only formatted again when it changes and always
+// into the same buffer
+#[derive(Clone, Debug)]
+pub struct NumberText {
+    value: Option<u64>,
+    padding: usize,
+    label: &'static str, // in front of the number, inside the padding
+    text: String,
+}
+
+impl NumberText {
+    pub fn new(padding: usize) -> Self {
+        Self::labeled("", padding)
+    }
+
+    pub fn labeled(label: &'static str, padding: usize) -> Self {
+        Self {
+            value: None,
+            padding,
+            label,
+            text: String::with_capacity(20 + label.len() + 2 * padding), // room for u64::MAX, it never grows
+        }
+    }
+
+    pub fn get(&mut self, value: u64) -> &str {
+        if self.value != Some(value) {
+            self.value = Some(value);
+            self.text.clear();
+            self.text.extend(std::iter::repeat_n(' ', self.padding));
+            self.text.push_str(self.label);
+            write_number(&mut self.text, value);
+            self.text.extend(std::iter::repeat_n(' ', self.padding));
+        }
+
+        &self.text
+    }
+}
+
+// a time as m:ss, the same as NumberText but for the clock of a sprint or an ultra
+#[derive(Clone, Debug)]
+pub struct ClockText {
+    seconds: Option<u64>,
+    padding: usize,
+    label: &'static str, // in front of the time, inside the padding
+    text: String,
+}
+
+impl ClockText {
+    pub fn new(padding: usize) -> Self {
+        Self::labeled("", padding)
+    }
+
+    pub fn labeled(label: &'static str, padding: usize) -> Self {
+        Self {
+            seconds: None,
+            padding,
+            label,
+            text: String::with_capacity(24 + label.len() + 2 * padding),
+        }
+    }
+
+    pub fn get(&mut self, seconds: u64) -> &str {
+        if self.seconds != Some(seconds) {
+            self.seconds = Some(seconds);
+            self.text.clear();
+            self.text.extend(std::iter::repeat_n(' ', self.padding));
+            self.text.push_str(self.label);
+            write_clock(&mut self.text, seconds);
+            self.text.extend(std::iter::repeat_n(' ', self.padding));
+        }
+
+        &self.text
+    }
+}
+
+// the tetriminos left in the bag by their letters, made again only when one is taken out of it (or a
+// new bag is opened) and always into the same buffer
+#[derive(Clone, Debug)]
+pub struct BagText {
+    kinds: Vec<PieceKind>, // what the text was made from
+    text: String,
+}
+
+impl Default for BagText {
+    fn default() -> Self {
+        let mut text = String::with_capacity(Self::PREFIX.len() + 2 * PieceKind::ALL.len());
+        text.push_str(Self::PREFIX);
+        Self {
+            kinds: Vec::with_capacity(PieceKind::ALL.len()),
+            text,
+        }
+    }
+}
+
+impl BagText {
+    const PREFIX: &'static str = "BAG  ";
+
+    pub fn get(&mut self, remaining: &[PieceKind]) -> &str {
+        if self.kinds != remaining {
+            self.kinds.clear();
+            self.kinds.extend_from_slice(remaining);
+            self.text.truncate(Self::PREFIX.len());
+            for (index, kind) in remaining.iter().enumerate() {
+                if index > 0 {
+                    self.text.push(' ');
+                }
+                self.text.push(kind.color().letter());
+            }
+        }
+
+        &self.text
+    }
+}
+
+// "B2B" and "COMBO n" for the bonuses that are going on, made again only when either of them changes
+#[derive(Clone, Debug)]
+pub struct BonusText {
+    bonuses: Option<(i32, bool)>, // the combo and back-to-back the text was made from
+    text: String,
+}
+
+impl Default for BonusText {
+    fn default() -> Self {
+        Self {
+            bonuses: None,
+            text: String::with_capacity("B2B  COMBO ".len() + 20),
+        }
+    }
+}
+
+impl BonusText {
+    // None while neither is going on
+    pub fn get(&mut self, combo: i32, back_to_back: bool) -> Option<&str> {
+        if combo <= 0 && !back_to_back {
+            return None;
+        }
+
+        if self.bonuses != Some((combo, back_to_back)) {
+            self.bonuses = Some((combo, back_to_back));
+            self.text.clear();
+            if back_to_back {
+                self.text.push_str("B2B");
+            }
+            if combo > 0 {
+                if back_to_back {
+                    self.text.push_str("  ");
+                }
+                self.text.push_str("COMBO ");
+                write_number(&mut self.text, combo as u64);
+            }
+        }
+
+        Some(&self.text)
+    }
+}
+
+// text made from a few values, like how far into a replay it is, only made again when they change;
+// making it may allocate, drawing the same one again doesn't
+#[derive(Clone, Debug)]
+pub struct KeyedText<K> {
+    key: Option<K>, // what the text was made from
+    text: String,
+}
+
+impl<K> Default for KeyedText<K> {
+    fn default() -> Self {
+        Self {
+            key: None,
+            text: String::new(),
+        }
+    }
+}
+
+impl<K: PartialEq> KeyedText<K> {
+    pub fn get(&mut self, key: K, make: impl FnOnce(&K) -> String) -> &str {
+        if self.key.as_ref() != Some(&key) {
+            self.text = make(&key);
+            self.key = Some(key);
+        }
+
+        &self.text
+    }
+}
+
+// m:ss, the minutes going on past an hour
+pub fn write_clock(text: &mut String, seconds: u64) {
+    write_number(text, seconds / 60);
+    text.push(':');
+    if seconds % 60 < 10 {
+        text.push('0');
+    }
+    write_number(text, seconds % 60);
+}
+
+// the digits of the number pushed onto the text, without the formatting machinery of format!
+pub fn write_number(text: &mut String, mut value: u64) {
+    let mut digits = [0; 20];
+    let mut start = digits.len();
+    loop {
+        start -= 1;
+        digits[start] = b'0' + (value % 10) as u8;
+        value /= 10;
+        if value == 0 {
+            break;
+        }
+    }
+
+    text.extend(digits[start..].iter().map(|&digit| digit as char));
+}
 
 // we need a lifetime because we have a mutable reference
-pub struct TextDrawContext<'canvas, 'canvas1> {
-    pub font: &'canvas Font<'canvas, 'canvas1>,
-    pub canvas: &'canvas mut Canvas<Window>,
+pub struct TextDrawContext<'canvas, 'ttf, R: Renderer> {
+    pub text_cache: Option<&'canvas mut TextCache<'ttf>>, // without one the text is left out
+    pub canvas: &'canvas mut R,
     pub text: &'canvas str,
     pub rect: SubRect,
 }
 
-impl TextDrawContext<'_, '_> {
-    pub fn draw_text(&mut self) {
-        let texture_creator = self.canvas.texture_creator();
+impl<R: Renderer> TextDrawContext<'_, '_, R> {
+    pub fn draw_text(&mut self) -> Result<(), InterfaceError> {
+        let Some(text_cache) = self.text_cache.as_deref_mut() else {
+            return Ok(());
+        };
 
-        // render a surface, and convert it to a texture bound to the canvas
-        let surface = self
-            .font
-            .render(self.text)
-            .blended(Color::WHITE)
-            .map_err(|e| e.to_string())
-            .expect("Failed to create surface");
-        let texture = texture_creator
-            .create_texture_from_surface(&surface)
-            .map_err(|e| e.to_string())
-            .expect("Failed to create texture");
+        // the surface rendered by the cache, the renderer turns it into a texture bound to the canvas
+        let surface = text_cache.surface(self.text)?;
 
-        let TextureQuery { width, height, .. } = texture.query();
+        let (width, height) = surface.size();
 
         let container = SubRect::absolute(
             Rect::from(self.rect),
@@ -37,7 +303,196 @@ impl TextDrawContext<'_, '_> {
         );
 
         self.canvas
-            .copy(&texture, None, Some(Rect::from(container)))
-            .expect("Failed to copy to canvas");
+            .copy_texture(surface, Rect::from(container))
+            .map_err(InterfaceError::Render)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn numbers_are_written_out() {
+        for value in [0, 7, 10, 1234, u64::MAX] {
+            let mut text = String::new();
+            write_number(&mut text, value);
+            assert_eq!(text, value.to_string());
+        }
+
+        let mut score = NumberText::new(2);
+        assert_eq!(score.get(0), "  0  ");
+        assert_eq!(score.get(120), "  120  ");
+        assert_eq!(score.get(u64::MAX), format!("  {}  ", u64::MAX));
+
+        let mut countdown = NumberText::labeled("RESUMING IN ", 0);
+        assert_eq!(countdown.get(3), "RESUMING IN 3");
+    }
+
+    #[test]
+    fn clocks_are_minutes_and_seconds() {
+        let mut clock = ClockText::new(1);
+        assert_eq!(clock.get(0), " 0:00 ");
+        assert_eq!(clock.get(65), " 1:05 ");
+        assert_eq!(clock.get(120), " 2:00 ");
+        assert_eq!(clock.get(3725), " 62:05 ");
+
+        let mut sprint = ClockText::labeled("SPRINT ", 0);
+        assert_eq!(sprint.get(83), "SPRINT 1:23");
+    }
+
+    #[test]
+    fn the_bag_is_written_out_by_letter() {
+        let mut bag = BagText::default();
+        assert_eq!(bag.get(&[]), "BAG  ");
+        assert_eq!(bag.get(&PieceKind::ALL), "BAG  O I T L J S Z");
+        assert_eq!(bag.get(&[PieceKind::S, PieceKind::I]), "BAG  S I");
+    }
+
+    #[test]
+    fn bonuses_are_only_shown_while_going_on() {
+        let mut bonus = BonusText::default();
+        assert_eq!(bonus.get(0, false), None);
+        assert_eq!(bonus.get(-1, false), None);
+        assert_eq!(bonus.get(0, true), Some("B2B"));
+        assert_eq!(bonus.get(3, false), Some("COMBO 3"));
+        assert_eq!(bonus.get(12, true), Some("B2B  COMBO 12"));
+    }
+
+    #[test]
+    fn keyed_texts_are_made_again_once_the_key_changes() {
+        let mut text = KeyedText::default();
+        let mut made = 0;
+        for step in [1, 1, 2] {
+            text.get(step, |step| {
+                made += 1;
+                format!("STEP {step}")
+            });
+        }
+        assert_eq!(made, 2);
+        assert_eq!(text.get(2, |_| unreachable!()), "STEP 2");
+    }
+
+    #[test]
+    fn texts_are_only_made_once() {
+        let mut map = TextMap::default();
+        let mut made = 0;
+        for text in ["SCORE", "LEVEL", "SCORE"] {
+            map.get_or_try_insert(text, || {
+                made += 1;
+                Ok::<_, ()>(text.len())
+            })
+            .unwrap();
+        }
+        assert_eq!(made, 2);
+
+        // a failure isn't kept, the next frame tries again
+        assert_eq!(map.get_or_try_insert("HOLD", || Err(())), Err(()));
+        assert_eq!(map.get_or_try_insert("HOLD", || Ok::<_, ()>(4)), Ok(&4));
+    }
+
+    // everything a frame that changes nothing does with text: the same numbers and labels again. the
+    // surfaces stand in for rendered text, rendering it needs sdl_ttf
+    #[cfg(feature = "alloc-count")]
+    #[test]
+    fn a_frame_without_changes_does_not_allocate() {
+        use crate::interface::pause_menu::{PauseMenu, PauseMenuText};
+        use crate::interface::renderer::RecordingRenderer;
+        use crate::interface::replaying::Replaying;
+        use crate::interface::settings::Settings;
+        use std::time::Duration;
+        use tetris::alloc_count::allocations;
+        use tetris::controller::GameController;
+        use tetris::engine::{config::Config, Engine};
+        use tetris::save::Saved;
+
+        let mut surfaces = TextMap::default();
+        let (mut score, mut level) = (NumberText::new(2), NumberText::new(2));
+
+        let mut frame = |score_value, level_value| {
+            let texts = ["SCORE", "LEVEL", "HOLD", "UP NEXT"];
+            for text in texts
+                .into_iter()
+                .chain([score.get(score_value), level.get(level_value)])
+            {
+                surfaces
+                    .get_or_try_insert(text, || Ok::<_, ()>(text.len()))
+                    .unwrap();
+            }
+        };
+
+        frame(100, 1);
+        let before = allocations();
+        frame(100, 1);
+        frame(100, 1);
+        assert_eq!(allocations() - before, 0);
+
+        // the bag panel drawn over and over while the bag stays the same, and once a tetrimino is taken
+        // out of it; without a text cache the text is left out, same as without a font
+        let mut renderer = RecordingRenderer::default();
+        let mut bag = BagText::default();
+        let mut draw_bag = |remaining: &[PieceKind]| {
+            let mut text_draw_ctx: TextDrawContext<_> = TextDrawContext {
+                canvas: &mut renderer,
+                text_cache: None,
+                text: bag.get(remaining),
+                rect: SubRect::absolute(Rect::new(0, 0, 200, 20), (1.0, 1.0), None),
+            };
+            text_draw_ctx.draw_text().unwrap();
+        };
+        draw_bag(&PieceKind::ALL);
+        let before = allocations();
+        draw_bag(&PieceKind::ALL);
+        draw_bag(&PieceKind::ALL[1..]);
+        assert_eq!(allocations() - before, 0);
+        assert!(renderer.calls.is_empty());
+
+        // the bonuses shown again, and once the combo goes up
+        let mut bonus = BonusText::default();
+        bonus.get(2, true);
+        let before = allocations();
+        bonus.get(2, true);
+        bonus.get(3, true);
+        assert_eq!(allocations() - before, 0);
+
+        // the time of a finished sprint, the same every frame of the game over
+        let mut sprint = ClockText::labeled("SPRINT ", 0);
+        sprint.get(83);
+        let before = allocations();
+        sprint.get(83);
+        sprint.get(u64::MAX);
+        assert_eq!(allocations() - before, 0);
+
+        // a new score is rendered once, its number is formatted without allocating
+        let mut number = NumberText::new(2);
+        number.get(1);
+        let before = allocations();
+        number.get(u64::MAX);
+        assert_eq!(allocations() - before, 0);
+
+        // the pause menu and the countdown in its place, drawn again while nothing is picked
+        let (menu, settings) = (PauseMenu::default(), Settings::default());
+        let mut menu_text = PauseMenuText::default();
+        let mut countdown = NumberText::labeled("RESUMING IN ", 0);
+        menu_text.get(&menu, &settings, &[]);
+        countdown.get(3);
+        let before = allocations();
+        menu_text.get(&menu, &settings, &[]);
+        countdown.get(3);
+        countdown.get(2);
+        assert_eq!(allocations() - before, 0);
+
+        // the label of a replay being watched, while it's between steps
+        let step = Duration::from_millis(10);
+        let mut game = GameController::new(3, Config::default(), step);
+        game.update(step);
+        let saved = Saved::Game(game.replay.clone().unwrap());
+        let mut replaying = Replaying::new(saved, Engine::new_seeded(3));
+        replaying.label();
+        let before = allocations();
+        replaying.label();
+        replaying.advance(step / 2);
+        replaying.label();
+        assert_eq!(allocations() - before, 0);
     }
 }
@@ -0,0 +1,73 @@
+use sdl2::pixels::Color;
+use serde::Deserialize;
+
+use super::lock_flash::LockFlashStyle;
+
+// the colors around the game: the window background and the panels the matrices are drawn on. the cells
+// keep their own colors, so tetriminos look the same whatever the theme
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Theme {
+    pub background: Color,
+    pub matrix: Color, // also the up next, hold, queue, score and mini-map panels
+    pub matrix_container: Color,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            background: Color::RGB(0x10, 0x10, 0x18),
+            matrix: Color::RGB(0x66, 0x77, 0x77),
+            matrix_container: Color::RGB(0x22, 0x22, 0x22),
+        }
+    }
+}
+
+// a theme file as written, every color as RRGGBB hex; colors left out stay the default ones
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+struct ThemeFile {
+    background: Option<String>,
+    matrix: Option<String>,
+    matrix_container: Option<String>,
+}
+
+impl Theme {
+    // the theme in a json theme file; the error says what's wrong with it
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let file: ThemeFile = serde_json::from_str(text).map_err(|error| error.to_string())?;
+        let default = Self::default();
+        let color = |name: &str, value: Option<String>, default: Color| match value {
+            Some(value) => {
+                LockFlashStyle::parse_color(&value).ok_or(format!("{name}: '{value}' isn't RRGGBB"))
+            }
+            None => Ok(default),
+        };
+
+        Ok(Self {
+            background: color("background", file.background, default.background)?,
+            matrix: color("matrix", file.matrix, default.matrix)?,
+            matrix_container: color(
+                "matrix_container",
+                file.matrix_container,
+                default.matrix_container,
+            )?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_theme() {
+        let theme = Theme::parse(r##"{ "background": "#000000", "matrix": "405060" }"##).unwrap();
+        assert_eq!(theme.background, Color::BLACK);
+        assert_eq!(theme.matrix, Color::RGB(0x40, 0x50, 0x60));
+        assert_eq!(theme.matrix_container, Theme::default().matrix_container);
+
+        assert_eq!(Theme::parse("{}"), Ok(Theme::default()));
+        assert!(Theme::parse(r#"{ "matrix": "green" }"#).is_err());
+        assert!(Theme::parse(r#"{ "matrics": "405060" }"#).is_err());
+    }
+}
@@ -0,0 +1,23 @@
+#![allow(dead_code)]
+#![feature(generic_const_exprs, array_chunks, new_range_api)]
+
+// the engine is a library so frontends other than the sdl binary (e.g. the browser) can use it
+#[cfg(feature = "alloc-count")]
+pub mod alloc_count;
+pub mod bot;
+pub mod cli;
+pub mod controller;
+pub mod engine;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+pub mod fuzz;
+pub mod history;
+pub mod logger;
+pub mod metrics;
+pub mod net;
+pub mod replay;
+pub mod save;
+pub mod sim;
+pub mod timestep;
+#[cfg(feature = "web")]
+pub mod web;
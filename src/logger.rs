@@ -0,0 +1,82 @@
+// a small logger for the log crate: lines go to stderr and the most recent ones are kept around so the
+// interface can show them in game. the level comes from RUST_LOG (error, warn, info, debug, trace or off),
+// without it debug builds log info and release builds only warnings
+use log::{LevelFilter, Log, Metadata, Record};
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+pub const RECENT_LINES: usize = 20;
+
+struct Logger;
+
+static LOGGER: Logger = Logger;
+static ECHO: AtomicBool = AtomicBool::new(true); // whether lines go to stderr too, the terminal interface turns it off
+static RECENT: Mutex<VecDeque<String>> = Mutex::new(VecDeque::new());
+
+impl Log for Logger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= log::max_level()
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        let line = format!("{} {}: {}", record.level(), record.target(), record.args());
+        if ECHO.load(Ordering::Relaxed) {
+            eprintln!("{line}");
+        }
+
+        let mut recent = RECENT.lock().unwrap();
+        if recent.len() == RECENT_LINES {
+            recent.pop_front();
+        }
+        recent.push_back(line);
+    }
+
+    fn flush(&self) {}
+}
+
+// the level RUST_LOG asks for, or the default one for the build
+pub fn level_from(value: Option<&str>) -> LevelFilter {
+    let default = if cfg!(debug_assertions) {
+        LevelFilter::Info
+    } else {
+        LevelFilter::Warn
+    };
+
+    value
+        .and_then(|value| value.trim().parse().ok())
+        .unwrap_or(default)
+}
+
+// install the logger; only the first call does anything
+pub fn init(echo: bool) {
+    ECHO.store(echo, Ordering::Relaxed);
+
+    if log::set_logger(&LOGGER).is_ok() {
+        log::set_max_level(level_from(std::env::var("RUST_LOG").ok().as_deref()));
+    }
+}
+
+// the last RECENT_LINES lines that were logged, oldest first
+pub fn recent_lines() -> Vec<String> {
+    RECENT.lock().unwrap().iter().cloned().collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn level_from_rust_log() {
+        assert_eq!(level_from(Some("debug")), LevelFilter::Debug);
+        assert_eq!(level_from(Some("WARN")), LevelFilter::Warn);
+        assert_eq!(level_from(Some("off")), LevelFilter::Off);
+
+        // nonsense falls back to the default, which depends on the build
+        assert_eq!(level_from(Some("loud")), level_from(None));
+    }
+}
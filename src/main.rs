@@ -1,15 +1,230 @@
 #![allow(dead_code)]
 #![feature(generic_const_exprs, array_chunks, new_range_api)]
 
+#[cfg(not(feature = "tui"))]
+use engine::config::HoldMode;
+#[cfg(not(feature = "tui"))]
+use engine::mode::GameMode;
+#[cfg(not(feature = "tui"))]
 use engine::Engine;
-use interface::Interface;
+#[cfg(not(feature = "tui"))]
+use interface::{
+    assets::AssetDirs, frame_limiter::FrameRate, input::ControlPreset, lock_flash::LockFlashStyle,
+    overlay::Overlay, Interface, InterfaceError, Settings,
+};
+#[cfg(not(feature = "tui"))]
+use std::path::{Path, PathBuf};
+#[cfg(not(feature = "tui"))]
+use std::process::ExitCode;
+#[cfg(not(feature = "tui"))]
+use tetris::{cli, metrics, net::connection::Pending, net::spectate::Watcher, save};
+use tetris::{engine, logger};
 
-mod engine;
+#[cfg(not(feature = "tui"))]
 mod interface;
+#[cfg(feature = "tui")]
+mod tui;
 
-fn main() {
-    let engine = Engine::new();
+#[cfg(feature = "alloc-count")]
+#[global_allocator]
+static ALLOCATOR: tetris::alloc_count::CountingAllocator = tetris::alloc_count::CountingAllocator;
+
+#[cfg(not(feature = "tui"))]
+fn main() -> ExitCode {
+    logger::init(true);
+    let mut engine = Engine::new();
+
+    let mut settings = Settings::default();
+    let args: Vec<String> = std::env::args().collect();
+    let online =
+        match configure(&args, &mut engine, &mut settings).and_then(|()| online_from(&args)) {
+            Ok(online) => online,
+            Err(error) => {
+                eprintln!("{error}");
+                return ExitCode::FAILURE;
+            }
+        };
+
+    // `--spectate <host>:<port>` watches the match of a host instead
+    let spectating =
+        cli::value(&args, "--spectate").map(|address| Watcher::pending(address.to_string()));
+
+    // `--config <file>` and `--theme <file>` are json files applied again whenever they're saved
+    let config = cli::value(&args, "--config").map(PathBuf::from);
+    let theme = cli::value(&args, "--theme").map(PathBuf::from);
+
+    // `--stats-csv <file>` gets a row for every finished game
+    let stats_file = cli::value(&args, "--stats-csv").map(PathBuf::from);
+
+    // `--save-replay <file>` gets the replay of every finished game, the last one wins
+    let replay_file = cli::value(&args, "--save-replay").map(PathBuf::from);
+
+    // `--watch-replay <file>` plays a saved replay back instead of starting with the seed entry, a game's
+    // or one recorded from the headless engine
+    let watched =
+        match cli::value(&args, "--watch-replay").map(|path| save::read_any(Path::new(path))) {
+            Some(Ok(replay)) => Some(replay),
+            Some(Err(error)) => {
+                eprintln!("{error}");
+                return ExitCode::FAILURE;
+            }
+            None => None,
+        };
+
+    // `--assets <dir>` is looked in first for optional assets, before the assets next to the executable
+    // and in the working directory
+    let assets = AssetDirs::new(cli::value(&args, "--assets").map(PathBuf::from));
+
+    // the font the interface loads borrows it, so it's kept here for as long as the interface is around
+    let ttf_context = match sdl2::ttf::init() {
+        Ok(ttf_context) => ttf_context,
+        Err(error) => {
+            eprintln!("{}", InterfaceError::SdlInit(error.to_string()));
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let interface = Interface::with_settings(&ttf_context, engine, settings, assets);
+    let result = interface.and_then(|mut interface| {
+        // `--debug-window` shows the engine's internals next to the game
+        if cli::flag(&args, "--debug-window") {
+            interface.open_debug_window()?;
+        }
+        if let Some(path) = config {
+            interface.watch_config(path);
+        }
+        if let Some(path) = theme {
+            interface.watch_theme(path);
+        }
+        interface.stats_file = stats_file;
+        interface.replay_file = replay_file;
+        // only a game alone is autosaved, a match can't be picked back up
+        if online.is_none() && spectating.is_none() && watched.is_none() {
+            interface.enable_autosave(metrics::data_dir().join(save::AUTOSAVE_FILE));
+        }
+        if let Some(pending) = online {
+            interface.go_online(pending);
+        }
+        if let Some(pending) = spectating {
+            interface.spectate(pending);
+        }
+        if let Some(replay) = watched {
+            interface.watch_replay(replay);
+        }
+        interface.run()
+    });
+    if let Err(error) = result {
+        // straight to stderr, the log might be turned off
+        eprintln!("{error}");
+        return ExitCode::FAILURE;
+    }
+
+    ExitCode::SUCCESS
+}
+
+// the engine config and settings from the command line; a value that doesn't parse is an error naming
+// the argument
+#[cfg(not(feature = "tui"))]
+fn configure(args: &[String], engine: &mut Engine, settings: &mut Settings) -> Result<(), String> {
+    // `--frame-rate vsync|uncapped|<fps>`, vsync by default
+    if let Some(frame_rate) = cli::parsed_with(args, "--frame-rate", FrameRate::parse)? {
+        settings.frame_rate = frame_rate;
+    }
+
+    // `--previews <n>` tetriminos in the queue, up to 6
+    if let Some(previews) = cli::parsed(args, "--previews")? {
+        engine.config.previews = previews;
+    }
+
+    // `--clear-delay-ms <ms>` full lines stay before the stack drops, 0 by default
+    if let Some(ms) = cli::parsed(args, "--clear-delay-ms")? {
+        engine.config.clear_delay = std::time::Duration::from_millis(ms);
+    }
+
+    // `--spawn-delay-ms <ms>` a new tetrimino forms above the matrix before it's in play, 0 by default
+    if let Some(ms) = cli::parsed(args, "--spawn-delay-ms")? {
+        engine.config.spawn_delay = std::time::Duration::from_millis(ms);
+    }
 
-    let mut interface = Interface::new(engine);
-    drop(interface.run());
+    // `--soft-drop-repress` makes soft drop a fresh press for every tetrimino
+    engine.config.soft_drop_repress = cli::flag(args, "--soft-drop-repress");
+
+    // `--hold-undo` lets holding again right away take the hold back
+    engine.config.hold_undo = cli::flag(args, "--hold-undo");
+
+    // `--no-first-hold` keeps the first tetrimino of a game from being held
+    engine.config.first_hold = !cli::flag(args, "--no-first-hold");
+
+    // `--hold-queue` holds up to three tetriminos that come back out in the order they went in
+    if cli::flag(args, "--hold-queue") {
+        engine.config.hold_mode = HoldMode::Queue;
+    }
+
+    // `--mode marathon|sprint|ultra` is the mode the seed entry starts on; sprint is 40 lines, ultra two
+    // minutes
+    if let Some(mode) = cli::parsed_with(args, "--mode", GameMode::parse)? {
+        engine.config.mode = mode;
+    }
+
+    // `--frame-step` stops gravity and locks, F10 lets the game go on to the next of either
+    engine.frame_step = cli::flag(args, "--frame-step");
+
+    // `--wide-panels` spreads the panels out on windows wider than they're high
+    settings.wide_panels = cli::flag(args, "--wide-panels");
+
+    // `--controls guideline|classic|wasd` picks a whole set of key bindings
+    let controls = cli::parsed_with(args, "--controls", |value| {
+        ControlPreset::parse(value).filter(|preset| preset.bindings().is_some())
+    })?;
+    if let Some(controls) = controls {
+        settings.controls = controls;
+    }
+
+    // `--lock-flash-color RRGGBB` and `--lock-flash-ms <ms>`, 0 turns the flash off
+    if let Some(color) = cli::parsed_with(args, "--lock-flash-color", LockFlashStyle::parse_color)?
+    {
+        settings.lock_flash.color = color;
+    }
+    if let Some(ms) = cli::parsed(args, "--lock-flash-ms")? {
+        settings.lock_flash.duration = std::time::Duration::from_millis(ms);
+    }
+
+    // `--level-up-ms <ms>` for how long a new level is shown, 0 doesn't show it
+    if let Some(ms) = cli::parsed(args, "--level-up-ms")? {
+        settings.level_up = std::time::Duration::from_millis(ms);
+    }
+
+    // `--overlay` keys out the background for streaming, in green or `--overlay-color RRGGBB`;
+    // `--overlay-pinned` makes the window borderless, always on top and of a fixed size
+    if cli::flag(args, "--overlay") || cli::flag(args, "--overlay-pinned") {
+        let mut overlay = Overlay {
+            pinned: cli::flag(args, "--overlay-pinned"),
+            ..Overlay::default()
+        };
+        if let Some(chroma) =
+            cli::parsed_with(args, "--overlay-color", LockFlashStyle::parse_color)?
+        {
+            overlay.chroma = chroma;
+        }
+        settings.overlay = Some(overlay);
+    }
+
+    Ok(())
+}
+
+#[cfg(not(feature = "tui"))]
+fn online_from(args: &[String]) -> Result<Option<Pending>, String> {
+    // `--host <port>` waits there for an opponent, `--connect <host>:<port>` plays against one that's waiting
+    Ok(match cli::parsed::<u16>(args, "--host")? {
+        Some(port) => Some(Pending::host(port, rand::random())),
+        None => cli::value(args, "--connect").map(|address| Pending::connect(address.to_string())),
+    })
+}
+
+#[cfg(feature = "tui")]
+fn main() {
+    // stderr would end up all over the terminal interface, so the log is only kept in memory
+    logger::init(false);
+    let mut tui = tui::Tui::new(rand::random(), engine::config::Config::default());
+    tui.run().expect("Failed to run the terminal interface");
 }
@@ -0,0 +1,270 @@
+// an opt-in log of how every game went, one json object per line, for players looking at their play
+// over weeks. it lives in the data directory and is rotated once it gets big; the log before the last
+// rotation is kept next to it and read back along with it
+use crate::controller::TopOut;
+use crate::history::GameRecord;
+use crate::net::versus::garbage_for;
+use serde::{Deserialize, Serialize};
+use std::env;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+pub const FILE_NAME: &str = "metrics.jsonl";
+pub const MAX_BYTES: u64 = 1024 * 1024; // rotated before appending once it's this big
+
+const WEEK_SECS: u64 = 7 * 24 * 60 * 60;
+
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct GameMetrics {
+    pub timestamp: u64, // unix time in seconds of the game over
+    pub mode: String,
+    pub duration_s: f64,
+    pub score: u32,
+    pub lines: u32,
+    pub tetris_rate: f64,    // share of the cleared lines that went in tetrises
+    pub tspins: Option<u32>, // null in logs from before the engine detected them
+    pub pps: f64,
+    pub apm: f64, // lines of garbage the clears are worth per minute, whether or not anyone got them
+    pub finesse_faults: Option<u32>, // null while nothing counts them
+    pub topout: Option<String>, // null for a game that didn't top out
+}
+
+impl GameMetrics {
+    pub fn of(record: &GameRecord, top_out: Option<TopOut>) -> Self {
+        let stats = &record.stats;
+        let cleared = stats.singles + stats.doubles * 2 + stats.triples * 3 + stats.tetrises * 4;
+        let attack = garbage_for(2) as u32 * stats.doubles
+            + garbage_for(3) as u32 * stats.triples
+            + garbage_for(4) as u32 * stats.tetrises;
+        let minutes = record.duration.as_secs_f64() / 60.0;
+
+        Self {
+            timestamp: record.finished_at,
+            mode: record.mode.to_string(),
+            duration_s: record.duration.as_secs_f64(),
+            score: record.score,
+            lines: record.lines,
+            tetris_rate: ratio(stats.tetrises as f64 * 4.0, cleared as f64),
+            tspins: Some(stats.tspins),
+            pps: record.pps(),
+            apm: ratio(attack as f64, minutes),
+            finesse_faults: None,
+            topout: top_out.map(|top_out| {
+                match top_out {
+                    TopOut::LockOut => "lock_out",
+                    TopOut::Garbage => "garbage",
+                }
+                .to_string()
+            }),
+        }
+    }
+}
+
+fn ratio(part: f64, whole: f64) -> f64 {
+    if whole == 0.0 {
+        return 0.0;
+    }
+    part / whole
+}
+
+// $XDG_DATA_HOME/tetris, or ~/.local/share/tetris without it; %APPDATA%\tetris on windows
+pub fn data_dir() -> PathBuf {
+    let base = env::var_os("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("APPDATA").map(PathBuf::from))
+        .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(".local/share")))
+        .unwrap_or_default();
+    base.join("tetris")
+}
+
+// where the log goes once it's rotated, replacing the one rotated before it
+pub fn rotated(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".1");
+    path.with_file_name(name)
+}
+
+// adds the games to the log in one go, rotating it first if it has grown past max_bytes
+pub fn append(path: &Path, games: &[GameMetrics], max_bytes: u64) -> io::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    if fs::metadata(path).is_ok_and(|metadata| metadata.len() >= max_bytes) {
+        fs::rename(path, rotated(path))?;
+    }
+
+    let mut lines = String::new();
+    for game in games {
+        lines += &serde_json::to_string(game).map_err(io::Error::other)?;
+        lines.push('\n');
+    }
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?
+        .write_all(lines.as_bytes())
+}
+
+// the games in the rotated log and the current one, oldest first; lines that don't parse are skipped
+pub fn read(path: &Path) -> Vec<GameMetrics> {
+    [rotated(path), path.to_path_buf()]
+        .iter()
+        .filter_map(|path| fs::read_to_string(path).ok())
+        .flat_map(|text| {
+            text.lines()
+                .filter_map(|line| serde_json::from_str(line).ok())
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+// what the metrics page shows: averages over the last week and the bests of all time
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct Trends {
+    pub games_this_week: usize,
+    pub average_score: f64, // these four are over the last week
+    pub average_pps: f64,
+    pub average_apm: f64,
+    pub average_tetris_rate: f64,
+    pub best_score: u32,
+    pub best_pps: f64,
+    pub most_lines: u32,
+}
+
+impl Trends {
+    pub fn of(games: &[GameMetrics], now: u64) -> Self {
+        let week: Vec<&GameMetrics> = games
+            .iter()
+            .filter(|game| now.saturating_sub(game.timestamp) < WEEK_SECS)
+            .collect();
+        let average = |value: fn(&GameMetrics) -> f64| {
+            ratio(week.iter().map(|game| value(game)).sum(), week.len() as f64)
+        };
+
+        Self {
+            games_this_week: week.len(),
+            average_score: average(|game| game.score as f64),
+            average_pps: average(|game| game.pps),
+            average_apm: average(|game| game.apm),
+            average_tetris_rate: average(|game| game.tetris_rate),
+            best_score: games.iter().map(|game| game.score).max().unwrap_or(0),
+            best_pps: games.iter().map(|game| game.pps).fold(0.0, f64::max),
+            most_lines: games.iter().map(|game| game.lines).max().unwrap_or(0),
+        }
+    }
+
+    pub fn lines(&self) -> Vec<String> {
+        vec![
+            format!("{} GAMES IN 7 DAYS", self.games_this_week),
+            format!("AVG SCORE {:.0}", self.average_score),
+            format!("AVG PPS {:.2}", self.average_pps),
+            format!("AVG APM {:.1}", self.average_apm),
+            format!("TETRIS RATE {:.0}%", self.average_tetris_rate * 100.0),
+            format!("BEST SCORE {}", self.best_score),
+            format!("BEST PPS {:.2}", self.best_pps),
+            format!("MOST LINES {}", self.most_lines),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::engine::stats::Stats;
+    use std::time::Duration;
+
+    fn game(timestamp: u64, score: u32, pps: f64) -> GameMetrics {
+        GameMetrics {
+            timestamp,
+            mode: "marathon".to_string(),
+            duration_s: 60.0,
+            score,
+            lines: score / 100,
+            tetris_rate: 0.5,
+            tspins: None,
+            pps,
+            apm: 10.0,
+            finesse_faults: None,
+            topout: Some("lock_out".to_string()),
+        }
+    }
+
+    #[test]
+    fn a_game_record_is_summed_up() {
+        let stats = Stats {
+            singles: 2,
+            doubles: 1,
+            tetrises: 2,
+            ..Stats::default()
+        };
+        let record = GameRecord {
+            finished_at: 1_700_000_000,
+            mode: "marathon",
+            score: 4000,
+            level: 2,
+            lines: 12,
+            duration: Duration::from_secs(120),
+            pieces: 60,
+            stats,
+        };
+
+        let metrics = GameMetrics::of(&record, Some(TopOut::Garbage));
+        assert_eq!(metrics.tetris_rate, 8.0 / 12.0);
+        assert_eq!(metrics.pps, 0.5);
+        assert_eq!(metrics.apm, 4.5); // a double and two tetrises are 9 lines of garbage in 2 minutes
+        assert_eq!(metrics.topout.as_deref(), Some("garbage"));
+
+        let line = serde_json::to_string(&metrics).unwrap();
+        assert!(line.contains(r#""tspins":0"#));
+        assert_eq!(serde_json::from_str::<GameMetrics>(&line).unwrap(), metrics);
+    }
+
+    #[test]
+    fn the_log_is_appended_to_and_rotated() {
+        let path = env::temp_dir()
+            .join(format!("tetris-metrics-{}", std::process::id()))
+            .join(FILE_NAME);
+        let _ = fs::remove_dir_all(path.parent().unwrap());
+
+        append(&path, &[game(1, 100, 1.0), game(2, 200, 1.0)], 1024).unwrap();
+        let written = fs::read_to_string(&path).unwrap();
+        assert_eq!(written.lines().count(), 2);
+        assert!(!rotated(&path).exists());
+
+        // past the limit, the next append starts a new file and the old one is kept
+        let max_bytes = written.len() as u64;
+        append(&path, &[game(3, 300, 1.0)], max_bytes).unwrap();
+        assert_eq!(fs::read_to_string(rotated(&path)).unwrap(), written);
+        assert_eq!(fs::read_to_string(&path).unwrap().lines().count(), 1);
+
+        // both are read back, oldest first, and broken lines don't take the rest with them
+        fs::write(&path, fs::read_to_string(&path).unwrap() + "{ not json\n").unwrap();
+        let timestamps: Vec<u64> = read(&path).iter().map(|game| game.timestamp).collect();
+        assert_eq!(timestamps, [1, 2, 3]);
+
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn trends_average_the_last_week_and_keep_the_bests() {
+        let now = 100 * WEEK_SECS;
+        let games = [
+            game(now - WEEK_SECS - 1, 9000, 3.0), // too old for the averages, still the best
+            game(now - 60, 1000, 1.0),
+            game(now - 30, 3000, 2.0),
+        ];
+
+        let trends = Trends::of(&games, now);
+        assert_eq!(trends.games_this_week, 2);
+        assert_eq!(trends.average_score, 2000.0);
+        assert_eq!(trends.average_pps, 1.5);
+        assert_eq!(trends.best_score, 9000);
+        assert_eq!(trends.best_pps, 3.0);
+        assert_eq!(trends.most_lines, 90);
+        assert_eq!(trends.lines()[0], "2 GAMES IN 7 DAYS");
+
+        // nothing played yet
+        assert_eq!(Trends::of(&[], now), Trends::default());
+    }
+}
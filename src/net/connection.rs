@@ -0,0 +1,221 @@
+// the tcp side of a match: agreeing on the protocol version and seed, then messages in both directions.
+// reading happens on a thread of its own, so the game loop only ever looks at what has come in so far
+use super::message::{read_message, write_message, Message, PROTOCOL_VERSION};
+use super::spectate::Broadcast;
+use super::NetError;
+use std::net::{TcpListener, TcpStream};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::thread;
+use std::time::Duration;
+
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+pub struct Connection {
+    pub is_host: bool,
+    pub seed: u64, // both games of the match are dealt from it
+    stream: TcpStream,
+    incoming: Receiver<Result<Message, NetError>>,
+    spectators: Option<Receiver<TcpStream>>, // the host's, as they connect
+}
+
+// who connected to the host
+enum Arrival {
+    Player(Connection),
+    Spectator(TcpStream),
+}
+
+impl Connection {
+    // the host's side of the handshake on a stream that was just accepted; the host picks the seed
+    pub fn host(stream: TcpStream, seed: u64) -> Result<Self, NetError> {
+        match Self::welcome(stream, seed)? {
+            Arrival::Player(connection) => Ok(connection),
+            Arrival::Spectator(_) => Err(NetError::Unexpected(Message::Join { spectator: true })),
+        }
+    }
+
+    fn welcome(stream: TcpStream, seed: u64) -> Result<Arrival, NetError> {
+        let mut stream = prepare(stream)?;
+        hello(&mut stream)?;
+
+        match read_message(&mut stream)? {
+            Message::Join { spectator: true } => Ok(Arrival::Spectator(stream)),
+            Message::Join { spectator: false } => {
+                write_message(&mut stream, &Message::Seed(seed))?;
+                Ok(Arrival::Player(Self::start(stream, true, seed)?))
+            }
+            message => Err(NetError::Unexpected(message)),
+        }
+    }
+
+    // the other side, connected to a host
+    pub fn guest(stream: TcpStream) -> Result<Self, NetError> {
+        let mut stream = prepare(stream)?;
+        hello(&mut stream)?;
+        write_message(&mut stream, &Message::Join { spectator: false })?;
+        let seed = match read_message(&mut stream)? {
+            Message::Seed(seed) => seed,
+            message => return Err(NetError::Unexpected(message)),
+        };
+
+        Self::start(stream, false, seed)
+    }
+
+    fn start(stream: TcpStream, is_host: bool, seed: u64) -> Result<Self, NetError> {
+        let incoming = read_in_background(&stream)?;
+
+        Ok(Self {
+            is_host,
+            seed,
+            stream,
+            incoming,
+            spectators: None,
+        })
+    }
+
+    // sends the local game to the spectators who connected to the host, the guest never has any
+    pub fn broadcast(&mut self) -> Broadcast {
+        Broadcast::new(self.spectators.take())
+    }
+
+    pub fn send(&mut self, message: &Message) -> Result<(), NetError> {
+        Ok(write_message(&mut self.stream, message)?)
+    }
+
+    // the next message that came in, None if there's nothing new
+    pub fn try_receive(&self) -> Result<Option<Message>, NetError> {
+        match self.incoming.try_recv() {
+            Ok(message) => message.map(Some),
+            Err(TryRecvError::Empty) => Ok(None),
+            Err(TryRecvError::Disconnected) => Err(NetError::Disconnected),
+        }
+    }
+}
+
+impl Drop for Connection {
+    // lets the reading thread (and the other side) know right away
+    fn drop(&mut self) {
+        let _ = self.stream.shutdown(std::net::Shutdown::Both);
+    }
+}
+
+// everything that comes in on the stream, as it does
+pub(super) fn read_in_background(
+    stream: &TcpStream,
+) -> Result<Receiver<Result<Message, NetError>>, NetError> {
+    stream.set_read_timeout(None)?;
+
+    let mut reader = stream.try_clone()?;
+    let (sender, incoming) = mpsc::channel();
+    thread::spawn(move || loop {
+        let message = read_message(&mut reader).map_err(NetError::from);
+        let failed = message.is_err();
+
+        // nobody's listening anymore or the stream broke, either way that's it
+        if sender.send(message).is_err() || failed {
+            return;
+        }
+    });
+
+    Ok(incoming)
+}
+
+pub(super) fn prepare(stream: TcpStream) -> Result<TcpStream, NetError> {
+    // inputs are tiny and late ones hold up both games, so they go out right away
+    stream.set_nodelay(true)?;
+    stream.set_read_timeout(Some(HANDSHAKE_TIMEOUT))?;
+
+    Ok(stream)
+}
+
+pub(super) fn hello(stream: &mut TcpStream) -> Result<(), NetError> {
+    write_message(
+        stream,
+        &Message::Hello {
+            version: PROTOCOL_VERSION,
+        },
+    )?;
+
+    match read_message(stream)? {
+        Message::Hello { version } if version == PROTOCOL_VERSION => Ok(()),
+        Message::Hello { version } => Err(NetError::Version {
+            ours: PROTOCOL_VERSION,
+            theirs: version,
+        }),
+        message => Err(NetError::Unexpected(message)),
+    }
+}
+
+// a connection (or a spectator's, see spectate) being set up in the background, so the window keeps going
+// while it waits
+pub struct Pending<T = Connection> {
+    result: Receiver<Result<T, NetError>>,
+}
+
+impl Pending {
+    // wait for an opponent to connect on the port; spectators can connect there as well, before and
+    // during the match
+    pub fn host(port: u16, seed: u64) -> Self {
+        let (sender, result) = mpsc::channel();
+        thread::spawn(move || {
+            let listener = match TcpListener::bind(("0.0.0.0", port)) {
+                Ok(listener) => listener,
+                Err(error) => {
+                    let _ = sender.send(Err(error.into()));
+                    return;
+                }
+            };
+            let (spectator_sender, spectators) = mpsc::channel();
+            let mut spectators = Some(spectators);
+
+            for stream in listener.incoming() {
+                let Ok(stream) = stream else {
+                    continue;
+                };
+                let address = stream.peer_addr();
+
+                match Connection::welcome(stream, seed) {
+                    Ok(Arrival::Player(mut connection)) if spectators.is_some() => {
+                        log::info!("Opponent connected from {address:?}");
+                        connection.spectators = spectators.take();
+                        let _ = sender.send(Ok(connection));
+                    }
+                    Ok(Arrival::Player(_)) => log::info!("Turned away a second opponent"),
+                    Ok(Arrival::Spectator(stream)) => {
+                        // nobody is taking spectators anymore, the match is over
+                        if spectator_sender.send(stream).is_err() {
+                            return;
+                        }
+                    }
+                    Err(error) => log::warn!("Failed to greet {address:?}: {error}"),
+                }
+            }
+        });
+
+        Self { result }
+    }
+
+    // connect to a host at `address` (host:port)
+    pub fn connect(address: String) -> Self {
+        Self::spawn(move || Connection::guest(TcpStream::connect(address)?))
+    }
+}
+
+impl<T: Send + 'static> Pending<T> {
+    pub(super) fn spawn(connect: impl FnOnce() -> Result<T, NetError> + Send + 'static) -> Self {
+        let (sender, result) = mpsc::channel();
+        thread::spawn(move || {
+            let _ = sender.send(connect());
+        });
+
+        Self { result }
+    }
+
+    // the connection once it's there, None while it's still being set up
+    pub fn poll(&self) -> Option<Result<T, NetError>> {
+        match self.result.try_recv() {
+            Ok(result) => Some(result),
+            Err(TryRecvError::Empty) => None,
+            Err(TryRecvError::Disconnected) => Some(Err(NetError::Disconnected)),
+        }
+    }
+}
@@ -0,0 +1,122 @@
+// what the two sides of a match say to each other, and how it's framed on the stream: a big endian u32
+// with the length, then that many bytes of json
+use super::spectate::Frame;
+use crate::controller::GameInput;
+use serde::{Deserialize, Serialize};
+use std::io::{self, Read, Write};
+
+pub const PROTOCOL_VERSION: u32 = 2;
+const MAX_LEN: u32 = 64 * 1024; // far more than any message needs, so garbage on the stream is caught early
+
+// steps are the fixed update steps of the games (see timestep), counted from the start of the match
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub enum Message {
+    Hello { version: u32 },   // the first thing both sides send
+    Join { spectator: bool }, // from whoever connected to the host, after the hellos
+    Seed(u64),                // from the host to its opponent, both games are dealt from it
+    Inputs { step: u32, inputs: Vec<GameInput> }, // everything pressed for a step, sent for every step even if empty
+    GarbageSent { step: u32, lines: usize }, // what the sender's game sent over after that step
+    BoardChecksum { step: u32, checksum: u64 }, // of the sender's game after that step, see versus::checksum
+    GameOver { step: u32 },                     // the sender's game ended with that step
+    Frame(Frame), // from the host to spectators, what changed since the last one
+}
+
+impl Message {
+    // the step a report about the sender's own game is for, None for the other messages
+    pub fn report_step(&self) -> Option<u32> {
+        match self {
+            Self::GarbageSent { step, .. }
+            | Self::BoardChecksum { step, .. }
+            | Self::GameOver { step } => Some(*step),
+            Self::Hello { .. }
+            | Self::Join { .. }
+            | Self::Seed(_)
+            | Self::Inputs { .. }
+            | Self::Frame(_) => None,
+        }
+    }
+}
+
+pub fn write_message(writer: &mut impl Write, message: &Message) -> io::Result<()> {
+    let body = serde_json::to_vec(message)?;
+    let len = u32::try_from(body.len())
+        .ok()
+        .filter(|&len| len <= MAX_LEN)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "message too long"))?;
+
+    writer.write_all(&len.to_be_bytes())?;
+    writer.write_all(&body)?;
+    writer.flush()
+}
+
+// blocks until a whole message is in
+pub fn read_message(reader: &mut impl Read) -> io::Result<Message> {
+    let mut len = [0; 4];
+    reader.read_exact(&mut len)?;
+
+    let len = u32::from_be_bytes(len);
+    if len > MAX_LEN {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("message of {len} bytes"),
+        ));
+    }
+
+    let mut body = vec![0; len as usize];
+    reader.read_exact(&mut body)?;
+
+    Ok(serde_json::from_slice(&body)?)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn messages_survive_the_framing() {
+        let messages = [
+            Message::Hello {
+                version: PROTOCOL_VERSION,
+            },
+            Message::Join { spectator: true },
+            Message::Seed(u64::MAX),
+            Message::Inputs {
+                step: 7,
+                inputs: vec![GameInput::Rotate, GameInput::HardDrop],
+            },
+            Message::Inputs {
+                step: 8,
+                inputs: Vec::new(),
+            },
+            Message::GarbageSent { step: 9, lines: 4 },
+            Message::BoardChecksum {
+                step: 120,
+                checksum: 0xdead_beef,
+            },
+            Message::GameOver { step: 300 },
+        ];
+
+        let mut stream = Vec::new();
+        for message in &messages {
+            write_message(&mut stream, message).unwrap();
+        }
+
+        let mut reader = stream.as_slice();
+        for message in &messages {
+            assert_eq!(&read_message(&mut reader).unwrap(), message);
+        }
+
+        // the stream ending, as when the other side goes away
+        let error = read_message(&mut reader).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn oversized_length_is_rejected() {
+        let mut stream = (MAX_LEN + 1).to_be_bytes().to_vec();
+        stream.extend([b'{'; 16]);
+
+        let error = read_message(&mut stream.as_slice()).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    }
+}
@@ -0,0 +1,49 @@
+// online versus: two players each run both games, their own and the opponent's, from the same seed and
+// the inputs they send each other, so only inputs (and a few checks) ever go over the wire
+pub mod connection;
+pub mod message;
+pub mod spectate;
+pub mod versus;
+
+use message::Message;
+use std::{fmt, io};
+
+// what ends a match early
+#[derive(Debug)]
+pub enum NetError {
+    Io(io::Error),
+    Version { ours: u32, theirs: u32 }, // both sides have to run the same game to stay in sync
+    Unexpected(Message),                // a message that doesn't belong where it came in
+    Desync { step: u32 },               // the opponent's game went somewhere ours didn't
+    Stalled,                            // nothing came from the opponent for too long
+    Disconnected,
+}
+
+impl fmt::Display for NetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(error) => write!(f, "Network error: {error}"),
+            Self::Version { ours, theirs } => {
+                write!(f, "Opponent runs protocol version {theirs}, this is {ours}")
+            }
+            Self::Unexpected(message) => write!(f, "Unexpected message: {message:?}"),
+            Self::Desync { step } => write!(f, "Games out of sync at step {step}"),
+            Self::Stalled => write!(f, "Opponent stopped responding"),
+            Self::Disconnected => write!(f, "Opponent disconnected"),
+        }
+    }
+}
+
+impl std::error::Error for NetError {}
+
+impl From<io::Error> for NetError {
+    fn from(error: io::Error) -> Self {
+        match error.kind() {
+            io::ErrorKind::UnexpectedEof
+            | io::ErrorKind::ConnectionReset
+            | io::ErrorKind::ConnectionAborted
+            | io::ErrorKind::BrokenPipe => Self::Disconnected,
+            _ => Self::Io(error),
+        }
+    }
+}
@@ -0,0 +1,267 @@
+// watching a match without playing in it: the host sends what its game looks like to whoever connected
+// as a spectator, a few times a second and only the cells that changed since the last time
+use super::connection::{hello, prepare, read_in_background, Pending};
+use super::message::{write_message, Message};
+use super::NetError;
+use crate::controller::GameController;
+use crate::engine::matrix::{CellChange, Matrix};
+use crate::engine::Engine;
+use serde::{Deserialize, Serialize};
+use std::net::TcpStream;
+use std::sync::mpsc::{Receiver, TryRecvError};
+use std::time::Duration;
+
+pub const FRAME_INTERVAL: Duration = Duration::from_millis(100); // spectators don't need every step
+const WRITE_TIMEOUT: Duration = Duration::from_millis(50); // a spectator that can't keep up is let go
+
+// everything a spectator is shown of a game
+#[derive(Clone)]
+pub struct Board {
+    pub matrix: Matrix<{ Engine::MATRIX_WIDTH }, { Engine::MATRIX_HEIGHT }>, // with the falling tetrimino
+    pub up_next: Matrix<
+        { Engine::SINGLE_TETRIMINO_MATRIX_WIDTH },
+        { Engine::SINGLE_TETRIMINO_MATRIX_HEIGHT },
+    >,
+    pub hold: Matrix<
+        { Engine::SINGLE_TETRIMINO_MATRIX_WIDTH },
+        { Engine::SINGLE_TETRIMINO_MATRIX_HEIGHT },
+    >,
+    pub queue:
+        Matrix<{ Engine::REMAINING_NEXT_MATRIX_WIDTH }, { Engine::REMAINING_NEXT_MATRIX_HEIGHT }>,
+    pub score: u32,
+    pub level: u8,
+    pub game_over: bool,
+}
+
+// the changes from one board to the next, see Board::diff
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct Frame {
+    pub step: u32, // of the match the board is from
+    pub matrix: Vec<CellChange>,
+    pub up_next: Vec<CellChange>,
+    pub hold: Vec<CellChange>,
+    pub queue: Vec<CellChange>,
+    pub score: u32,
+    pub level: u8,
+    pub game_over: bool,
+}
+
+impl Board {
+    // nothing shown yet, the first frame a spectator gets fills it in
+    pub fn blank() -> Self {
+        Self {
+            matrix: Matrix::blank(),
+            up_next: Matrix::blank(),
+            hold: Matrix::blank(),
+            queue: Matrix::blank(),
+            score: 0,
+            level: 1,
+            game_over: false,
+        }
+    }
+
+    pub fn of(game: &GameController) -> Self {
+        let engine = &game.engine;
+
+        Self {
+            matrix: engine.render_state(),
+            up_next: engine.up_next_matrix.clone(),
+            hold: engine.hold_matrix.clone(),
+            queue: engine.queue_matrix.clone(),
+            score: engine.score,
+            level: engine.level,
+            game_over: game.is_game_over(),
+        }
+    }
+
+    pub fn diff(&self, newer: &Self, step: u32) -> Frame {
+        Frame {
+            step,
+            matrix: self.matrix.diff(&newer.matrix),
+            up_next: self.up_next.diff(&newer.up_next),
+            hold: self.hold.diff(&newer.hold),
+            queue: self.queue.diff(&newer.queue),
+            score: newer.score,
+            level: newer.level,
+            game_over: newer.game_over,
+        }
+    }
+
+    pub fn apply(&mut self, frame: &Frame) {
+        self.matrix.apply_changes(&frame.matrix);
+        self.up_next.apply_changes(&frame.up_next);
+        self.hold.apply_changes(&frame.hold);
+        self.queue.apply_changes(&frame.queue);
+        self.score = frame.score;
+        self.level = frame.level;
+        self.game_over = frame.game_over;
+    }
+
+    // put the board into an engine so it can be drawn like any game; the engine is good for nothing else
+    // after that, its cursor is gone and the falling tetrimino is part of its matrix
+    pub fn show(&self, engine: &mut Engine) {
+        engine.reset();
+        engine.matrix = self.matrix.clone();
+        engine.up_next_matrix = self.up_next.clone();
+        engine.hold_matrix = self.hold.clone();
+        engine.queue_matrix = self.queue.clone();
+        engine.score = self.score;
+        engine.level = self.level;
+    }
+}
+
+struct Spectator {
+    stream: TcpStream,
+    seen: Board, // what the frames sent so far add up to
+}
+
+// the host's side: its game, sent to every spectator
+#[derive(Default)]
+pub struct Broadcast {
+    joining: Option<Receiver<TcpStream>>,
+    spectators: Vec<Spectator>,
+    since_frame: Duration,
+}
+
+impl Broadcast {
+    pub fn new(joining: Option<Receiver<TcpStream>>) -> Self {
+        Self {
+            joining,
+            ..Self::default()
+        }
+    }
+
+    pub fn spectators(&self) -> usize {
+        self.spectators.len()
+    }
+
+    // once every update step, with the game to show and the step it's at; every FRAME_INTERVAL the
+    // spectators get a frame, new ones the whole board
+    pub fn update(&mut self, game: &GameController, step: u32, dt: Duration) {
+        if let Some(joining) = &self.joining {
+            while let Ok(stream) = joining.try_recv() {
+                if let Err(error) = stream.set_write_timeout(Some(WRITE_TIMEOUT)) {
+                    log::warn!("Failed to take a spectator: {error}");
+                    continue;
+                }
+
+                log::info!("A spectator joined");
+                self.spectators.push(Spectator {
+                    stream,
+                    seen: Board::blank(),
+                });
+            }
+        }
+
+        self.since_frame += dt;
+        if self.since_frame < FRAME_INTERVAL || self.spectators.is_empty() {
+            return;
+        }
+        self.since_frame = Duration::ZERO;
+
+        let board = Board::of(game);
+        self.spectators.retain_mut(|spectator| {
+            let frame = spectator.seen.diff(&board, step);
+            match write_message(&mut spectator.stream, &Message::Frame(frame)) {
+                Ok(()) => {
+                    spectator.seen = board.clone();
+                    true
+                }
+                Err(error) => {
+                    log::info!("A spectator left: {error}");
+                    false
+                }
+            }
+        });
+    }
+}
+
+// the spectator's side: the host's board, as the frames come in
+pub struct Watcher {
+    pub board: Board,
+    pub step: u32, // of the last frame
+    since_frame: Duration,
+    incoming: Receiver<Result<Message, NetError>>,
+    _stream: TcpStream, // the reading happens on a clone, this one keeps the connection open
+}
+
+impl Watcher {
+    pub fn connect(stream: TcpStream) -> Result<Self, NetError> {
+        let mut stream = prepare(stream)?;
+        hello(&mut stream)?;
+        write_message(&mut stream, &Message::Join { spectator: true })?;
+
+        Ok(Self {
+            board: Board::blank(),
+            step: 0,
+            since_frame: Duration::ZERO,
+            incoming: read_in_background(&stream)?,
+            _stream: stream,
+        })
+    }
+
+    // watch the host at `address` (host:port), connecting in the background
+    pub fn pending(address: String) -> Pending<Self> {
+        Pending::spawn(move || Self::connect(TcpStream::connect(address)?))
+    }
+
+    // take in the frames that came; returns whether the board changed
+    pub fn update(&mut self, dt: Duration) -> Result<bool, NetError> {
+        self.since_frame += dt;
+        let mut changed = false;
+
+        loop {
+            match self.incoming.try_recv() {
+                Ok(Ok(Message::Frame(frame))) => {
+                    self.board.apply(&frame);
+                    self.step = frame.step;
+                    self.since_frame = Duration::ZERO;
+                    changed = true;
+                }
+                Ok(Ok(message)) => return Err(NetError::Unexpected(message)),
+                Ok(Err(error)) => return Err(error),
+                Err(TryRecvError::Empty) => return Ok(changed),
+                Err(TryRecvError::Disconnected) => return Err(NetError::Disconnected),
+            }
+        }
+    }
+
+    // how long ago the last frame came, what's shown is at least this much behind the game
+    pub fn delay(&self) -> Duration {
+        self.since_frame
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::controller::GameInput;
+    use crate::engine::config::Config;
+
+    #[test]
+    fn frames_add_up_to_the_board() {
+        let dt = Duration::from_millis(10);
+        let mut game = GameController::new(9, Config::default(), dt);
+        let mut seen = Board::blank();
+
+        for step in 0..300 {
+            if step % 20 == 0 {
+                game.apply(GameInput::HardDrop);
+            }
+            game.update(dt);
+
+            let board = Board::of(&game);
+            let frame = seen.diff(&board, step);
+            seen.apply(&frame);
+        }
+
+        let board = Board::of(&game);
+        assert_eq!(seen.matrix.to_string(), board.matrix.to_string());
+        assert_eq!(seen.queue.to_string(), board.queue.to_string());
+        assert_eq!(seen.score, board.score);
+
+        // nothing changed, nothing to send
+        let frame = seen.diff(&board, 300);
+        assert!(frame.matrix.is_empty() && frame.up_next.is_empty() && frame.hold.is_empty());
+    }
+}
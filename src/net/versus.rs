@@ -0,0 +1,362 @@
+// a match in lockstep: inputs are sent INPUT_DELAY steps ahead of when they're applied, and a step only
+// runs once both sides' inputs for it are in. both games run on both sides, so lines cleared on one
+// side turn into garbage on the other without anyone having to say so; what is said (garbage, board
+// checksums, game overs) is only checked against what the opponent's game did here
+use super::message::Message;
+use super::NetError;
+use crate::controller::{GameController, GameInput};
+use crate::engine::{config::Config, event::EngineEvent};
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+pub const INPUT_DELAY: u32 = 6; // 50ms at 120 updates a second, time for the inputs to get across
+pub const CHECKSUM_INTERVAL: u32 = 60; // steps between board checksums
+const MAX_REPORT_LAG: u32 = 1200; // steps a report can be overdue before the games count as out of sync
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Outcome {
+    Won,
+    Lost,
+    Draw, // both topped out on the same step
+}
+
+pub struct Versus {
+    pub opponent: GameController, // the opponent's game, run here from its inputs
+    step: u32,                    // the next step the games run
+    next_input_step: u32,         // local inputs gathered now are applied at this step
+    pending: Vec<GameInput>,      // local inputs since the last step was sent
+    local_inputs: BTreeMap<u32, Vec<GameInput>>,
+    remote_inputs: BTreeMap<u32, Vec<GameInput>>,
+    predicted: Vec<Message>, // reports the opponent is still to send, going by its game here
+    early: Vec<Message>,     // reports from the opponent for steps that haven't run here yet
+    outgoing: Vec<Message>,  // reports about the local game, to be sent
+    outcome: Option<Outcome>,
+    dt: Duration, // game time of a step
+}
+
+impl Versus {
+    // both games of a match; the local one is handed back to be played (through input())
+    pub fn start(seed: u64, config: Config, dt: Duration, is_host: bool) -> (Self, GameController) {
+        let mut local = GameController::new(seed, config, dt);
+        let mut opponent = GameController::new(seed, config, dt);
+
+        // the host's game always gets its holes from the first garbage seed, on both sides
+        let (host_holes, guest_holes) = (seed.wrapping_add(1), seed.wrapping_add(2));
+        let (local_holes, remote_holes) = if is_host {
+            (host_holes, guest_holes)
+        } else {
+            (guest_holes, host_holes)
+        };
+        local.engine.seed_garbage(local_holes);
+        opponent.engine.seed_garbage(remote_holes);
+
+        // nobody can press anything in time for the very first steps
+        let empty: BTreeMap<u32, Vec<GameInput>> =
+            (0..INPUT_DELAY).map(|step| (step, Vec::new())).collect();
+
+        let versus = Self {
+            opponent,
+            step: 0,
+            next_input_step: INPUT_DELAY,
+            pending: Vec::new(),
+            local_inputs: empty.clone(),
+            remote_inputs: empty,
+            predicted: Vec::new(),
+            early: Vec::new(),
+            outgoing: Vec::new(),
+            outcome: None,
+            dt,
+        };
+
+        (versus, local)
+    }
+
+    pub fn step(&self) -> u32 {
+        self.step
+    }
+
+    pub fn outcome(&self) -> Option<Outcome> {
+        self.outcome
+    }
+
+    // a local input, it goes out with the next step sent and is applied INPUT_DELAY steps later
+    pub fn input(&mut self, input: GameInput) {
+        if self.outcome.is_none() {
+            self.pending.push(input);
+        }
+    }
+
+    // the local inputs for the next step, to be sent once per update step; None while the opponent is
+    // too far behind for another one, the local game waits for it then
+    pub fn send_step(&mut self) -> Option<Message> {
+        if self.outcome.is_some() || self.next_input_step > self.step + INPUT_DELAY {
+            return None;
+        }
+
+        let step = self.next_input_step;
+        let inputs = std::mem::take(&mut self.pending);
+        self.local_inputs.insert(step, inputs.clone());
+        self.next_input_step += 1;
+
+        Some(Message::Inputs { step, inputs })
+    }
+
+    pub fn receive(&mut self, message: Message) -> Result<(), NetError> {
+        if let Message::Inputs { step, inputs } = message {
+            if step < self.step || self.remote_inputs.contains_key(&step) {
+                return Err(NetError::Unexpected(Message::Inputs { step, inputs }));
+            }
+
+            self.remote_inputs.insert(step, inputs);
+            return Ok(());
+        }
+
+        let Some(step) = message.report_step() else {
+            return Err(NetError::Unexpected(message));
+        };
+
+        if step >= self.step {
+            self.early.push(message);
+            return Ok(());
+        }
+
+        self.check_report(message)
+    }
+
+    // the reports about the local game since this was last asked
+    pub fn take_outgoing(&mut self) -> Vec<Message> {
+        std::mem::take(&mut self.outgoing)
+    }
+
+    // run every step both sides' inputs are in for; returns how many ran. the engine events of the local
+    // game are used up here
+    pub fn advance(&mut self, local: &mut GameController) -> Result<u32, NetError> {
+        let mut ran = 0;
+
+        while self.outcome.is_none() {
+            let step = self.step;
+            if !self.local_inputs.contains_key(&step) || !self.remote_inputs.contains_key(&step) {
+                break;
+            }
+            let local_inputs = self.local_inputs.remove(&step).unwrap_or_default();
+            let remote_inputs = self.remote_inputs.remove(&step).unwrap_or_default();
+
+            for input in local_inputs {
+                local.apply(input);
+            }
+            for input in remote_inputs {
+                self.opponent.apply(input);
+            }
+            local.update(self.dt);
+            self.opponent.update(self.dt);
+
+            // both clears are counted before either side's garbage goes in
+            let sent_by_local = garbage_sent(local);
+            let sent_by_opponent = garbage_sent(&mut self.opponent);
+            for lines in sent_by_local {
+                let hole = self.opponent.engine.garbage_hole();
+                self.opponent.receive_garbage(lines, hole);
+                self.outgoing.push(Message::GarbageSent { step, lines });
+            }
+            for lines in sent_by_opponent {
+                let hole = local.engine.garbage_hole();
+                local.receive_garbage(lines, hole);
+                self.predicted.push(Message::GarbageSent { step, lines });
+            }
+
+            if step.is_multiple_of(CHECKSUM_INTERVAL) {
+                self.outgoing.push(Message::BoardChecksum {
+                    step,
+                    checksum: checksum(local),
+                });
+                self.predicted.push(Message::BoardChecksum {
+                    step,
+                    checksum: checksum(&self.opponent),
+                });
+            }
+
+            self.outcome = match (local.is_game_over(), self.opponent.is_game_over()) {
+                (true, true) => Some(Outcome::Draw),
+                (true, false) => Some(Outcome::Lost),
+                (false, true) => Some(Outcome::Won),
+                (false, false) => None,
+            };
+            if local.is_game_over() {
+                self.outgoing.push(Message::GameOver { step });
+            }
+            if self.opponent.is_game_over() {
+                self.predicted.push(Message::GameOver { step });
+            }
+
+            self.step += 1;
+            ran += 1;
+
+            for report in std::mem::take(&mut self.early) {
+                match report.report_step() {
+                    Some(step) if step < self.step => self.check_report(report)?,
+                    _ => self.early.push(report),
+                }
+            }
+        }
+
+        // a report that should have come long ago means the opponent's game didn't do what it did here
+        if let Some(step) = self
+            .predicted
+            .iter()
+            .filter_map(Message::report_step)
+            .find(|&step| step + MAX_REPORT_LAG < self.step)
+        {
+            return Err(NetError::Desync { step });
+        }
+
+        Ok(ran)
+    }
+
+    // a report about a step that ran here has to be one the opponent's game here predicted
+    fn check_report(&mut self, report: Message) -> Result<(), NetError> {
+        match self
+            .predicted
+            .iter()
+            .position(|predicted| *predicted == report)
+        {
+            Some(index) => {
+                self.predicted.remove(index);
+                Ok(())
+            }
+            None => Err(NetError::Desync {
+                step: report.report_step().unwrap_or(self.step),
+            }),
+        }
+    }
+}
+
+// lines of garbage for each line clear the game made since it was last asked: 2 for 1, 3 for 2, 4 for 4
+fn garbage_sent(game: &mut GameController) -> Vec<usize> {
+    game.engine
+        .take_events()
+        .into_iter()
+        .filter_map(|event| match event {
+            EngineEvent::LinesCleared { lines, .. } => Some(lines),
+            EngineEvent::Spawned { .. }
+            | EngineEvent::LevelUp { .. }
+            | EngineEvent::ModeComplete => None,
+        })
+        .map(garbage_for)
+        .filter(|&lines| lines > 0)
+        .collect()
+}
+
+// lines of garbage a clear of that many lines sends
+pub fn garbage_for(lines: usize) -> usize {
+    match lines {
+        0 | 1 => 0,
+        2 => 1,
+        3 => 2,
+        _ => 4,
+    }
+}
+
+// a fingerprint of what can be seen of a game; the same on every machine and build (fnv-1a), unlike the
+// hashers of std
+pub fn checksum(game: &GameController) -> u64 {
+    let engine = &game.engine;
+    let state = format!(
+        "{}\n{}\n{}\n{} {} {:?}",
+        engine.render_state(),
+        engine.up_next_matrix,
+        engine.hold_matrix,
+        engine.score,
+        engine.level,
+        game.state,
+    );
+
+    state.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x0000_0100_0000_01b3)
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const DT: Duration = Duration::from_micros(8_333);
+
+    // the host's side and the guest's, handing their messages straight to each other
+    fn pair() -> ((Versus, GameController), (Versus, GameController)) {
+        (
+            Versus::start(3, Config::default(), DT, true),
+            Versus::start(3, Config::default(), DT, false),
+        )
+    }
+
+    fn exchange(from: &mut Versus, to: &mut Versus) {
+        if let Some(message) = from.send_step() {
+            to.receive(message).unwrap();
+        }
+        for message in from.take_outgoing() {
+            to.receive(message).unwrap();
+        }
+    }
+
+    #[test]
+    fn steps_wait_for_the_opponents_inputs() {
+        let ((mut host, mut host_game), (mut guest, _)) = pair();
+
+        // the first steps need no inputs from anyone, after that the host is stuck without the guest's
+        for _ in 0..INPUT_DELAY + 3 {
+            host.send_step();
+            host.advance(&mut host_game).unwrap();
+        }
+        assert_eq!(host.step(), INPUT_DELAY);
+        assert!(host.send_step().is_none(), "ran too far ahead");
+
+        let message = guest.send_step().unwrap();
+        host.receive(message).unwrap();
+        assert_eq!(host.advance(&mut host_game).unwrap(), 1);
+    }
+
+    #[test]
+    fn both_sides_run_the_same_two_games() {
+        let ((mut host, mut host_game), (mut guest, mut guest_game)) = pair();
+
+        for tick in 0..2000u32 {
+            // hard drops on either side at different rates, so lines get cleared and garbage sent
+            if tick % 40 == 0 {
+                host.input(GameInput::HardDrop);
+            }
+            if tick % 55 == 0 {
+                guest.input(GameInput::MoveLeft);
+                guest.input(GameInput::HardDrop);
+            }
+
+            exchange(&mut host, &mut guest);
+            exchange(&mut guest, &mut host);
+            host.advance(&mut host_game).unwrap();
+            guest.advance(&mut guest_game).unwrap();
+
+            if host.outcome().is_some() && guest.outcome().is_some() {
+                break;
+            }
+        }
+
+        assert_eq!(checksum(&host_game), checksum(&guest.opponent));
+        assert_eq!(checksum(&guest_game), checksum(&host.opponent));
+        assert_eq!(host.step(), guest.step());
+        assert!(host.outcome().is_some());
+    }
+
+    #[test]
+    fn a_wrong_checksum_is_a_desync() {
+        let ((mut host, mut host_game), (mut guest, mut guest_game)) = pair();
+
+        exchange(&mut guest, &mut host);
+        host.advance(&mut host_game).unwrap();
+        guest.advance(&mut guest_game).unwrap();
+
+        let result = host.receive(Message::BoardChecksum {
+            step: 0,
+            checksum: checksum(&guest_game) ^ 1,
+        });
+        assert!(matches!(result, Err(NetError::Desync { step: 0 })));
+    }
+}
@@ -0,0 +1,294 @@
+// recorded games: the seed a game was dealt from plus every input along with the update step it came in
+// at; played back on the same fixed timestep (see timestep) that gives the very same game again
+use crate::controller::{GameController, GameInput};
+use crate::engine::{config::Config, Engine};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub struct ReplayEvent {
+    pub step: u32, // applied right before this update step ran
+    pub input: GameInput,
+}
+
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct Replay {
+    pub seed: u64,
+    pub config: Config,
+    pub step: Duration, // game time of a single update step
+    pub steps: u32,     // how many update steps the game ran for
+    pub events: Vec<ReplayEvent>,
+}
+
+impl Replay {
+    pub fn new(seed: u64, config: Config, step: Duration) -> Self {
+        Self {
+            seed,
+            config,
+            step,
+            steps: 0,
+            events: Vec::new(),
+        }
+    }
+
+    // an input applied before the update step that's about to run
+    pub fn record(&mut self, input: GameInput) {
+        self.events.push(ReplayEvent {
+            step: self.steps,
+            input,
+        });
+    }
+
+    // an update step has run
+    pub fn record_step(&mut self) {
+        self.steps += 1;
+    }
+}
+
+// a replayed game at some step
+#[derive(Clone)]
+pub struct Playback {
+    pub game: GameController,
+    pub step: u32,     // update steps run so far
+    next_event: usize, // index of the first event that hasn't been applied yet
+}
+
+impl Playback {
+    pub fn new(replay: &Replay) -> Self {
+        Self {
+            game: GameController::for_replay(replay),
+            step: 0,
+            next_event: 0,
+        }
+    }
+
+    pub fn is_finished(&self, replay: &Replay) -> bool {
+        self.game.is_game_over() || self.step >= replay.steps
+    }
+
+    // run the next update step, with the inputs recorded right before it
+    pub fn step(&mut self, replay: &Replay) {
+        while let Some(event) = replay
+            .events
+            .get(self.next_event)
+            .filter(|event| event.step == self.step)
+        {
+            self.game.apply(event.input);
+            self.next_event += 1;
+        }
+
+        self.game.update(replay.step);
+        self.step += 1;
+    }
+}
+
+// the game a replay was recorded from, as far as it got and recording on from there; e.g. an autosaved
+// game picked back up
+pub fn resume(replay: Replay) -> GameController {
+    let mut playback = Playback::new(&replay);
+    while !playback.is_finished(&replay) {
+        playback.step(&replay);
+    }
+    // inputs that came in after the last step ran
+    for event in &replay.events[playback.next_event..] {
+        playback.game.apply(event.input);
+    }
+
+    let mut game = playback.game;
+    game.replay = Some(replay);
+    game
+}
+
+// reviewing a replay: jump to any step or piece of it, backwards as well as forwards; states along the
+// way are kept every so often, so going back only replays from the closest one instead of the start
+pub struct Scrubber {
+    replay: Replay,
+    current: Playback,
+    snapshots: Vec<Playback>, // the state every SNAPSHOT_INTERVAL steps
+    piece_steps: Vec<u32>,    // the step by which n tetriminos have locked, starting with 0 at 0
+    end: u32,                 // the last step there is
+}
+
+impl Scrubber {
+    pub const SNAPSHOT_INTERVAL: u32 = 120; // a second of play at the default timestep
+
+    pub fn new(replay: Replay) -> Self {
+        let mut playback = Playback::new(&replay);
+        let mut snapshots = Vec::new();
+        let mut piece_steps = vec![0];
+
+        loop {
+            if playback.step.is_multiple_of(Self::SNAPSHOT_INTERVAL) {
+                snapshots.push(playback.clone());
+            }
+
+            if playback.is_finished(&replay) {
+                break;
+            }
+
+            playback.step(&replay);
+            if playback.game.pieces as usize == piece_steps.len() {
+                piece_steps.push(playback.step);
+            }
+        }
+
+        Self {
+            current: snapshots[0].clone(),
+            end: playback.step,
+            replay,
+            snapshots,
+            piece_steps,
+        }
+    }
+
+    pub fn engine(&self) -> &Engine {
+        &self.current.game.engine
+    }
+
+    pub fn position(&self) -> u32 {
+        self.current.step
+    }
+
+    pub fn end(&self) -> u32 {
+        self.end
+    }
+
+    // tetriminos locked by the current step
+    pub fn piece(&self) -> u32 {
+        self.current.game.pieces
+    }
+
+    pub fn piece_count(&self) -> u32 {
+        self.piece_steps.len() as u32 - 1
+    }
+
+    pub fn seek(&mut self, step: u32) {
+        let step = step.min(self.end);
+
+        // start over from the closest snapshot unless the current state is already closer
+        let snapshot = &self.snapshots[(step / Self::SNAPSHOT_INTERVAL) as usize];
+        if step < self.current.step || snapshot.step > self.current.step {
+            self.current = snapshot.clone();
+        }
+
+        while self.current.step < step {
+            self.current.step(&self.replay);
+        }
+    }
+
+    pub fn step_forward(&mut self) {
+        self.seek(self.current.step + 1);
+    }
+
+    pub fn step_backward(&mut self) {
+        self.seek(self.current.step.saturating_sub(1));
+    }
+
+    // the moment the given number of tetriminos have locked
+    pub fn seek_piece(&mut self, piece: u32) {
+        let piece = piece.min(self.piece_count());
+        self.seek(self.piece_steps[piece as usize]);
+    }
+
+    pub fn next_piece(&mut self) {
+        if self.current.game.pieces < self.piece_count() {
+            self.seek_piece(self.current.game.pieces + 1);
+        } else {
+            self.seek(self.end);
+        }
+    }
+
+    // back to where the current tetrimino came in, or to the one before if that's where we are
+    pub fn previous_piece(&mut self) {
+        let start = self.piece_steps[self.current.game.pieces as usize];
+
+        if self.current.step > start {
+            self.seek(start);
+        } else {
+            self.seek_piece(self.current.game.pieces.saturating_sub(1));
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::timestep::FixedTimestep;
+
+    // a game with a hard drop every half a second, moved around a bit and with some soft dropping
+    fn replay() -> Replay {
+        let mut replay = Replay::new(9, Config::default(), FixedTimestep::default().step);
+
+        for step in 0..3000 {
+            let input = match step % 60 {
+                5 if step % 120 == 5 => Some(GameInput::MoveLeft),
+                5 => Some(GameInput::MoveRight),
+                10 if step % 180 == 10 => Some(GameInput::Rotate),
+                20 => Some(GameInput::SoftDropStart),
+                30 => Some(GameInput::SoftDropStop),
+                59 => Some(GameInput::HardDrop),
+                _ => None,
+            };
+            if let Some(input) = input {
+                replay.record(input);
+            }
+            replay.record_step();
+        }
+
+        replay
+    }
+
+    #[test]
+    fn seeking_a_piece_gives_the_board_at_that_point() {
+        let replay = replay();
+
+        // play it straight through once, keeping the board each time a tetrimino has locked
+        let mut playback = Playback::new(&replay);
+        let mut boards = vec![playback.game.engine.matrix.to_string()];
+        while !playback.is_finished(&replay) {
+            playback.step(&replay);
+            if playback.game.pieces as usize == boards.len() {
+                boards.push(playback.game.engine.matrix.to_string());
+            }
+        }
+
+        let mut scrubber = Scrubber::new(replay);
+        assert_eq!(scrubber.piece_count() as usize, boards.len() - 1);
+        assert!(boards.len() > 10);
+
+        // backwards from the end, so every seek has to go back to a snapshot
+        scrubber.seek(scrubber.end());
+        for piece in (0..boards.len()).rev() {
+            scrubber.seek_piece(piece as u32);
+            assert_eq!(scrubber.piece(), piece as u32);
+            assert_eq!(scrubber.engine().matrix.to_string(), boards[piece]);
+        }
+
+        scrubber.next_piece();
+        scrubber.next_piece();
+        assert_eq!(scrubber.engine().matrix.to_string(), boards[2]);
+        scrubber.previous_piece();
+        assert_eq!(scrubber.engine().matrix.to_string(), boards[1]);
+    }
+
+    #[test]
+    fn stepping_back_and_forth() {
+        let mut scrubber = Scrubber::new(replay());
+
+        scrubber.seek(301);
+        let cursor = scrubber.engine().cursor();
+        let board = scrubber.engine().matrix.to_string();
+
+        scrubber.step_backward();
+        scrubber.step_backward();
+        assert_eq!(scrubber.position(), 299);
+        scrubber.step_forward();
+        scrubber.step_forward();
+
+        assert_eq!(scrubber.engine().cursor(), cursor);
+        assert_eq!(scrubber.engine().matrix.to_string(), board);
+
+        scrubber.seek(u32::MAX);
+        assert_eq!(scrubber.position(), scrubber.end());
+    }
+}
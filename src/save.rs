@@ -0,0 +1,459 @@
+// replays written to a file as json. a replay is all it takes to get a game back, so it's what a game
+// is saved as too. files carry the version of the format they were written in; older ones are brought
+// up to date on load one version at a time, newer ones are refused instead of half understood. the
+// headless engine's replays are kept the same way, marked as such, see HEADLESS_KIND
+use crate::engine::recording::HeadlessReplay;
+use crate::replay::Replay;
+use serde::Serialize;
+use serde_json::Value;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+pub const FORMAT_VERSION: u32 = 7;
+pub const AUTOSAVE_FILE: &str = "autosave.json";
+// the kind a headless replay is marked with; game replays have none. both share the format version, the
+// migrations only touch the config that both have
+const HEADLESS_KIND: &str = "headless";
+
+// MIGRATIONS[n] takes a file of version n + 1 to version n + 2
+const MIGRATIONS: [fn(&mut Value); FORMAT_VERSION as usize - 1] =
+    [v1_to_v2, v2_to_v3, v3_to_v4, v4_to_v5, v5_to_v6, v6_to_v7];
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum LoadError {
+    Io(String),
+    Format(String),          // not json, or not a replay once migrated
+    NoVersion,               // json, but without the version of its format
+    TooNew { version: u32 }, // written by a newer build than this one
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(error) => write!(f, "Failed to read the replay: {error}"),
+            Self::Format(error) => write!(f, "Not a replay: {error}"),
+            Self::NoVersion => write!(f, "Not a replay: the format version is missing"),
+            Self::TooNew { version } => write!(
+                f,
+                "The replay is in format version {version}, this build only reads up to {FORMAT_VERSION}"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for LoadError {}
+
+pub fn to_json(replay: &Replay) -> String {
+    let mut value = serde_json::to_value(replay).expect("Failed to serialize the replay");
+    value["version"] = FORMAT_VERSION.into();
+    value.to_string()
+}
+
+pub fn from_json(text: &str) -> Result<Replay, LoadError> {
+    serde_json::from_value(migrated(text)?).map_err(|error| LoadError::Format(error.to_string()))
+}
+
+// the json of a file brought up to the current version
+fn migrated(text: &str) -> Result<Value, LoadError> {
+    let mut value: Value =
+        serde_json::from_str(text).map_err(|error| LoadError::Format(error.to_string()))?;
+
+    let version = value
+        .get("version")
+        .and_then(Value::as_u64)
+        .and_then(|version| u32::try_from(version).ok())
+        .filter(|&version| version > 0)
+        .ok_or(LoadError::NoVersion)?;
+    if version > FORMAT_VERSION {
+        return Err(LoadError::TooNew { version });
+    }
+
+    for migration in &MIGRATIONS[version as usize - 1..] {
+        migration(&mut value);
+    }
+    Ok(value)
+}
+
+pub fn headless_to_json(replay: &HeadlessReplay) -> String {
+    let mut value = serde_json::to_value(replay).expect("Failed to serialize the replay");
+    value["version"] = FORMAT_VERSION.into();
+    value["kind"] = HEADLESS_KIND.into();
+    value.to_string()
+}
+
+pub fn headless_from_json(text: &str) -> Result<HeadlessReplay, LoadError> {
+    match saved_from_json(text)? {
+        Saved::Headless(replay) => Ok(replay),
+        Saved::Game(_) => Err(LoadError::Format(
+            "a game replay, not a headless one".to_string(),
+        )),
+    }
+}
+
+// a replay file of either kind
+#[derive(Clone, PartialEq, Debug)]
+pub enum Saved {
+    Game(Replay),
+    Headless(HeadlessReplay),
+}
+
+impl Saved {
+    pub fn seed(&self) -> u64 {
+        match self {
+            Self::Game(replay) => replay.seed,
+            Self::Headless(replay) => replay.seed,
+        }
+    }
+}
+
+fn saved_from_json(text: &str) -> Result<Saved, LoadError> {
+    let value = migrated(text)?;
+    let saved = if value.get("kind").and_then(Value::as_str) == Some(HEADLESS_KIND) {
+        serde_json::from_value(value).map(Saved::Headless)
+    } else {
+        serde_json::from_value(value).map(Saved::Game)
+    };
+    saved.map_err(|error| LoadError::Format(error.to_string()))
+}
+
+pub fn write(path: &Path, replay: &Replay) -> std::io::Result<()> {
+    fs::write(path, to_json(replay))
+}
+
+pub fn write_headless(path: &Path, replay: &HeadlessReplay) -> std::io::Result<()> {
+    fs::write(path, headless_to_json(replay))
+}
+
+pub fn read(path: &Path) -> Result<Replay, LoadError> {
+    let text = fs::read_to_string(path).map_err(|error| LoadError::Io(error.to_string()))?;
+    from_json(&text)
+}
+
+// a replay file of whichever kind it turns out to be; for watching either
+pub fn read_any(path: &Path) -> Result<Saved, LoadError> {
+    let text = fs::read_to_string(path).map_err(|error| LoadError::Io(error.to_string()))?;
+    saved_from_json(&text)
+}
+
+// a replay file with the mode of the game next to the replay; what the game looked like and how long
+// it went on for both come from playing the replay back
+#[derive(Serialize)]
+struct Autosaved<'a> {
+    version: u32,
+    mode: &'a str,
+    #[serde(flatten)]
+    replay: &'a Replay,
+}
+
+// the game in progress, kept on disk so a crash or a kill doesn't lose it. a write goes to a temporary
+// file first that's then renamed over the autosave, so there's never half of one to find
+pub struct Autosave {
+    path: PathBuf,
+    buffer: Vec<u8>, // kept between writes, the replay only ever grows
+}
+
+impl Autosave {
+    pub const EVERY: u32 = 10; // tetriminos locked between writes
+
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            buffer: Vec::new(),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn write(&mut self, mode: &str, replay: &Replay) -> io::Result<()> {
+        self.buffer.clear();
+        let autosaved = Autosaved {
+            version: FORMAT_VERSION,
+            mode,
+            replay,
+        };
+        serde_json::to_writer(&mut self.buffer, &autosaved).map_err(io::Error::other)?;
+
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let temporary = self.path.with_extension("tmp");
+        fs::write(&temporary, &self.buffer)?;
+        fs::rename(&temporary, &self.path)
+    }
+
+    // the mode and replay of the game that was left, if there's one; one that can't be read is deleted
+    pub fn load(&self) -> Option<(String, Replay)> {
+        let text = fs::read_to_string(&self.path).ok()?;
+        let loaded = migrated(&text).and_then(|value| {
+            let mode = value
+                .get("mode")
+                .and_then(Value::as_str)
+                .ok_or(LoadError::Format("the mode is missing".to_string()))?
+                .to_string();
+            let replay = serde_json::from_value(value)
+                .map_err(|error| LoadError::Format(error.to_string()))?;
+            Ok((mode, replay))
+        });
+
+        match loaded {
+            Ok(loaded) => Some(loaded),
+            Err(error) => {
+                log::warn!("Discarded the autosave {}: {error}", self.path.display());
+                self.discard();
+                None
+            }
+        }
+    }
+
+    // once the game was resumed or declined, or it's over
+    pub fn discard(&self) {
+        match fs::remove_file(&self.path) {
+            Ok(()) => {}
+            Err(error) if error.kind() == io::ErrorKind::NotFound => {}
+            Err(error) => log::warn!("Failed to delete {}: {error}", self.path.display()),
+        }
+    }
+}
+
+// version 2 added the clear delay and hold undo to the config, both off unless they're asked for
+fn v1_to_v2(value: &mut Value) {
+    if let Some(config) = value.get_mut("config").and_then(Value::as_object_mut) {
+        config.insert(
+            "clear_delay".to_string(),
+            serde_json::json!({ "secs": 0, "nanos": 0 }),
+        );
+        config.insert("hold_undo".to_string(), false.into());
+    }
+}
+
+// version 3 added what holding the first tetrimino and holding with something on hold do, as they always
+// did before
+fn v2_to_v3(value: &mut Value) {
+    if let Some(config) = value.get_mut("config").and_then(Value::as_object_mut) {
+        config.insert("first_hold".to_string(), true.into());
+        config.insert("hold_mode".to_string(), "Swap".into());
+    }
+}
+
+// version 4 added the spawn delay, none for the files from before it
+fn v3_to_v4(value: &mut Value) {
+    if let Some(config) = value.get_mut("config").and_then(Value::as_object_mut) {
+        config.insert(
+            "spawn_delay".to_string(),
+            serde_json::json!({ "secs": 0, "nanos": 0 }),
+        );
+    }
+}
+
+// version 5 added having to press soft drop again for every tetrimino, which files from before didn't
+fn v4_to_v5(value: &mut Value) {
+    if let Some(config) = value.get_mut("config").and_then(Value::as_object_mut) {
+        config.insert("soft_drop_repress".to_string(), false.into());
+    }
+}
+
+// version 6 added turning the combo and back-to-back bonuses off; files from before were scored without
+// either
+fn v5_to_v6(value: &mut Value) {
+    if let Some(config) = value.get_mut("config").and_then(Value::as_object_mut) {
+        config.insert("combo".to_string(), false.into());
+        config.insert("back_to_back".to_string(), false.into());
+    }
+}
+
+// version 7 added the game mode; every game before it was a marathon
+fn v6_to_v7(value: &mut Value) {
+    if let Some(config) = value.get_mut("config").and_then(Value::as_object_mut) {
+        config.insert("mode".to_string(), "Marathon".into());
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::controller::{GameController, GameInput};
+    use crate::engine::config::{Config, HoldMode};
+    use crate::engine::headless::EngineInput;
+    use crate::engine::mode::GameMode;
+    use crate::engine::Engine;
+    use crate::replay::{self, Playback};
+    use std::env;
+    use std::time::Duration;
+
+    fn autosave(name: &str) -> Autosave {
+        let dir = env::temp_dir().join(format!("tetris-autosave-{}-{name}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        Autosave::new(dir.join(AUTOSAVE_FILE))
+    }
+
+    const V1_FIXTURE: &str = include_str!("../tests/fixtures/replay_v1.json");
+
+    fn recorded_game() -> Replay {
+        let step = Duration::from_millis(10);
+        let mut game = GameController::new(
+            4,
+            Config {
+                hold_undo: true,
+                clear_delay: Duration::from_millis(250),
+                ..Config::default()
+            },
+            step,
+        );
+        for input in [GameInput::MoveLeft, GameInput::Hold, GameInput::HardDrop] {
+            game.apply(input);
+            for _ in 0..20 {
+                game.update(step);
+            }
+        }
+        game.replay.unwrap()
+    }
+
+    #[test]
+    fn replays_survive_a_round_trip() {
+        let replay = recorded_game();
+        assert_eq!(from_json(&to_json(&replay)), Ok(replay));
+    }
+
+    #[test]
+    fn headless_replays_are_told_apart_from_game_ones() {
+        let mut engine = Engine::new_seeded(5);
+        engine.start_recording();
+        engine.tick();
+        engine.apply_input(EngineInput::HardDrop);
+        engine.tick();
+        let headless = engine.finish_recording();
+
+        let json = headless_to_json(&headless);
+        assert_eq!(headless_from_json(&json), Ok(headless.clone()));
+        assert_eq!(saved_from_json(&json), Ok(Saved::Headless(headless)));
+
+        let replay = recorded_game();
+        assert_eq!(
+            saved_from_json(&to_json(&replay)),
+            Ok(Saved::Game(replay.clone()))
+        );
+        assert!(matches!(
+            headless_from_json(&to_json(&replay)),
+            Err(LoadError::Format(_))
+        ));
+
+        // and they're versioned like the rest
+        let mut value: Value = serde_json::from_str(&json).unwrap();
+        value["version"] = (FORMAT_VERSION + 1).into();
+        assert_eq!(
+            headless_from_json(&value.to_string()),
+            Err(LoadError::TooNew {
+                version: FORMAT_VERSION + 1
+            })
+        );
+    }
+
+    #[test]
+    fn an_autosaved_game_resumes_where_it_was() {
+        let mut autosave = autosave("resume");
+        let replay = recorded_game();
+        let step = replay.step;
+        autosave.write("marathon", &replay).unwrap();
+        autosave.write("marathon", &replay).unwrap(); // over the last one
+        assert!(!autosave.path().with_extension("tmp").exists());
+
+        let (mode, loaded) = autosave.load().unwrap();
+        assert_eq!(mode, "marathon");
+        assert_eq!(loaded, replay);
+
+        let mut played = Playback::new(&replay);
+        while !played.is_finished(&replay) {
+            played.step(&replay);
+        }
+        let mut resumed = replay::resume(loaded);
+        assert_eq!(resumed.pieces, played.game.pieces);
+        assert_eq!(
+            resumed.engine.matrix.matrix,
+            played.game.engine.matrix.matrix
+        );
+        assert_eq!(resumed.engine.cursor(), played.game.engine.cursor());
+
+        // and it goes on recording from there
+        resumed.apply(GameInput::HardDrop);
+        resumed.update(step);
+        let replay_after = resumed.replay.as_ref().unwrap();
+        assert_eq!(replay_after.steps, replay.steps + 1);
+        assert_eq!(replay_after.events.len(), replay.events.len() + 1);
+
+        autosave.discard();
+        fs::remove_dir_all(autosave.path().parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn declined_and_corrupt_autosaves_are_deleted() {
+        let mut autosave = autosave("decline");
+        assert_eq!(autosave.load(), None); // nothing saved yet
+
+        autosave.write("marathon", &recorded_game()).unwrap();
+        autosave.discard();
+        assert!(!autosave.path().exists());
+        assert_eq!(autosave.load(), None);
+
+        for corrupt in [
+            "{ \"version\": 2, \"mode\"",
+            r#"{ "version": 2, "seed": 1 }"#,
+        ] {
+            fs::write(autosave.path(), corrupt).unwrap();
+            assert_eq!(autosave.load(), None);
+            assert!(!autosave.path().exists());
+        }
+
+        fs::remove_dir_all(autosave.path().parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn version_1_files_are_migrated() {
+        let replay = from_json(V1_FIXTURE).unwrap();
+        assert_eq!(replay.config.clear_delay, Duration::ZERO);
+        assert!(!replay.config.hold_undo);
+        assert_eq!(replay.config.previews, 3); // what the file had is kept
+        assert!(replay.config.first_hold);
+        assert_eq!(replay.config.hold_mode, HoldMode::Swap);
+        assert_eq!(replay.config.spawn_delay, Duration::ZERO);
+        assert!(!replay.config.soft_drop_repress);
+        assert!(!replay.config.combo && !replay.config.back_to_back);
+        assert_eq!(replay.config.mode, GameMode::Marathon);
+
+        // and it still plays back
+        let mut playback = Playback::new(&replay);
+        while !playback.is_finished(&replay) {
+            playback.step(&replay);
+        }
+        assert_eq!(playback.game.pieces, 5);
+    }
+
+    #[test]
+    fn newer_and_unversioned_files_are_refused() {
+        let mut value: Value = serde_json::from_str(&to_json(&recorded_game())).unwrap();
+
+        value["version"] = (FORMAT_VERSION + 1).into();
+        let error = from_json(&value.to_string()).unwrap_err();
+        assert_eq!(
+            error,
+            LoadError::TooNew {
+                version: FORMAT_VERSION + 1
+            }
+        );
+        assert!(error.to_string().contains("only reads up to 7"));
+
+        for version in [Value::Null, 0.into(), "2".into()] {
+            value["version"] = version;
+            assert_eq!(from_json(&value.to_string()), Err(LoadError::NoVersion));
+        }
+
+        assert!(matches!(from_json("{ not json"), Err(LoadError::Format(_))));
+        assert!(matches!(
+            from_json(r#"{ "version": 3, "seed": 1 }"#),
+            Err(LoadError::Format(_))
+        ));
+    }
+}
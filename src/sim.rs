@@ -0,0 +1,225 @@
+// headless simulation: plays a whole game without a window, driven either by the bot or by a script,
+// and sums it up at the end; this is what the tetris-sim binary runs
+
+use crate::bot;
+use crate::engine::{move_kind::MoveKind, Engine};
+use serde::Serialize;
+use std::time::Instant;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum SimMode {
+    Marathon, // until the game is lost or the piece limit is reached
+    Sprint,   // until SPRINT_LINES lines are cleared
+}
+
+impl SimMode {
+    pub const SPRINT_LINES: u32 = 40;
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "marathon" => Some(Self::Marathon),
+            "sprint" => Some(Self::Sprint),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Marathon => "marathon",
+            Self::Sprint => "sprint",
+        }
+    }
+}
+
+// one action per script line; the headless simulation has no gravity, so tetriminos only lock on a hard drop
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ScriptAction {
+    MoveLeft,
+    MoveRight,
+    Rotate,
+    SoftDrop,
+    HardDrop,
+    Hold,
+}
+
+impl ScriptAction {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "left" => Some(Self::MoveLeft),
+            "right" => Some(Self::MoveRight),
+            "rotate" => Some(Self::Rotate),
+            "soft" => Some(Self::SoftDrop),
+            "drop" => Some(Self::HardDrop),
+            "hold" => Some(Self::Hold),
+            _ => None,
+        }
+    }
+}
+
+// a script is one action per line; blank lines and lines starting with # are skipped
+pub fn parse_script(source: &str) -> Result<Vec<ScriptAction>, String> {
+    source
+        .lines()
+        .enumerate()
+        .map(|(index, line)| (index, line.trim()))
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+        .map(|(index, line)| {
+            ScriptAction::parse(line)
+                .ok_or_else(|| format!("Unknown action on line {}: {line}", index + 1))
+        })
+        .collect()
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum Driver {
+    Bot,
+    Script(Vec<ScriptAction>),
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct SimOptions {
+    pub seed: u64,
+    pub mode: SimMode,
+    pub driver: Driver,
+    pub max_pieces: u32, // so a bot that never loses still finishes
+}
+
+impl SimOptions {
+    pub const DEFAULT_MAX_PIECES: u32 = 1000;
+}
+
+#[derive(Clone, Copy, PartialEq, Debug, Default, Serialize)]
+pub struct EventCounts {
+    pub pieces: u32, // locked tetriminos
+    pub holds: u32,
+    pub singles: u32,
+    pub doubles: u32,
+    pub triples: u32,
+    pub tetrises: u32,
+}
+
+impl EventCounts {
+    pub fn lines(&self) -> u32 {
+        self.singles + self.doubles * 2 + self.triples * 3 + self.tetrises * 4
+    }
+}
+
+#[derive(Clone, PartialEq, Debug, Serialize)]
+pub struct SimSummary {
+    pub seed: u64,
+    pub mode: &'static str,
+    pub board: Vec<String>, // the final matrix from the top row down, see the Display of Matrix
+    pub score: u32,
+    pub level: u8,
+    pub lines: u32,
+    pub game_over: bool,
+    pub duration_ms: u64, // wall clock time the simulation took
+    pub events: EventCounts,
+}
+
+impl SimSummary {
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("Failed to serialize the summary")
+    }
+}
+
+pub fn run(options: &SimOptions) -> SimSummary {
+    let started = Instant::now();
+    let mut engine = Engine::new_seeded(options.seed);
+    let mut events = EventCounts::default();
+    let mut script = match &options.driver {
+        Driver::Bot => None,
+        Driver::Script(actions) => Some(actions.iter()),
+    };
+    let mut game_over = false;
+
+    engine.create_top_cursor(None);
+
+    while events.pieces < options.max_pieces && !is_mode_complete(options.mode, &events) {
+        let action = match script.as_mut() {
+            Some(actions) => match actions.next() {
+                Some(&action) => action,
+                None => break, // the script ran out
+            },
+            None => {
+                // with nowhere good to go it's dropped where it is, which is game over soon enough
+                if let Some(placement) = bot::best_placement(&engine) {
+                    bot::apply_placement(&mut engine, placement);
+                }
+                ScriptAction::HardDrop
+            }
+        };
+
+        match action {
+            ScriptAction::MoveLeft => engine.move_cursor(MoveKind::Left),
+            ScriptAction::MoveRight => engine.move_cursor(MoveKind::Right),
+            ScriptAction::Rotate => {
+                if let Some(rotation) = engine.next_cursor_rotation() {
+                    engine.rotate_and_adjust_cursor(rotation);
+                }
+            }
+            ScriptAction::SoftDrop => {
+                if engine.ticked_down_cursor().is_some() {
+                    engine.try_tick_down();
+                }
+            }
+            ScriptAction::Hold => {
+                engine.try_hold();
+                events.holds += 1;
+            }
+            ScriptAction::HardDrop => {
+                if !lock(&mut engine, &mut events) {
+                    game_over = true;
+                    break;
+                }
+            }
+        }
+    }
+
+    SimSummary {
+        seed: options.seed,
+        mode: options.mode.name(),
+        board: engine
+            .matrix
+            .to_string()
+            .lines()
+            .map(String::from)
+            .collect(),
+        score: engine.score,
+        level: engine.level,
+        lines: events.lines(),
+        game_over,
+        duration_ms: started.elapsed().as_millis() as u64,
+        events,
+    }
+}
+
+fn is_mode_complete(mode: SimMode, events: &EventCounts) -> bool {
+    match mode {
+        SimMode::Marathon => false,
+        SimMode::Sprint => events.lines() >= SimMode::SPRINT_LINES,
+    }
+}
+
+// hard drop and lock the cursor, then spawn the next one; false if that's game over
+fn lock(engine: &mut Engine, events: &mut EventCounts) -> bool {
+    engine.hard_drop();
+    if engine.try_place_cursor() != Ok(true) {
+        return false;
+    }
+    events.pieces += 1;
+
+    let mut cleared = 0;
+    engine.line_clear(engine.placed_tspin(), |lines| cleared = lines.len());
+
+    match cleared {
+        1 => events.singles += 1,
+        2 => events.doubles += 1,
+        3 => events.triples += 1,
+        4 => events.tetrises += 1,
+        _ => {}
+    }
+
+    engine.create_top_cursor(None);
+    true
+}
@@ -0,0 +1,131 @@
+// fixed timestep: frame time is accumulated and handed out in equal steps, so the game logic runs the
+// same way whether the frames come at 60, 144 or however many per second
+use std::time::Duration;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct FixedTimestep {
+    pub step: Duration,    // how much game time each update covers
+    accumulated: Duration, // frame time that hasn't been handed out as a step yet
+}
+
+impl FixedTimestep {
+    pub const UPDATE_RATE: u32 = 120; // updates per second
+    pub const MAX_STEPS: u32 = 30; // after a long stall (e.g. the window being dragged) the rest is dropped
+
+    pub fn new(update_rate: u32) -> Self {
+        Self {
+            step: Duration::from_secs(1) / update_rate,
+            accumulated: Duration::ZERO,
+        }
+    }
+
+    // add the time a frame took; returns how many steps are now due
+    pub fn accumulate(&mut self, dt: Duration) -> u32 {
+        self.accumulated += dt;
+
+        let mut steps = 0;
+        while self.accumulated >= self.step {
+            self.accumulated -= self.step;
+            steps += 1;
+        }
+
+        if steps > Self::MAX_STEPS {
+            self.accumulated = Duration::ZERO;
+            steps = Self::MAX_STEPS;
+        }
+
+        steps
+    }
+
+    // how far into the next step we are, from 0 to 1; for drawing in between two updates
+    pub fn alpha(&self) -> f32 {
+        self.accumulated.as_secs_f32() / self.step.as_secs_f32()
+    }
+
+    // frame time still needed before the update that covers `due` of game time has run;
+    // there's always at least one more step to wait for
+    pub fn time_until(&self, due: Duration) -> Duration {
+        let steps = due.as_nanos().div_ceil(self.step.as_nanos()).max(1) as u32;
+
+        (self.step * steps).saturating_sub(self.accumulated)
+    }
+}
+
+impl Default for FixedTimestep {
+    fn default() -> Self {
+        Self::new(Self::UPDATE_RATE)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::engine::{advance::Advance, move_kind::MoveKind, Engine};
+
+    #[test]
+    fn accumulate_hands_out_whole_steps() {
+        let mut timestep = FixedTimestep::new(100); // 10ms steps
+
+        assert_eq!(timestep.accumulate(Duration::from_millis(25)), 2);
+        assert!((timestep.alpha() - 0.5).abs() < 0.001);
+        assert_eq!(timestep.accumulate(Duration::from_millis(5)), 1);
+        assert_eq!(timestep.accumulate(Duration::from_millis(9)), 0);
+
+        assert_eq!(
+            timestep.time_until(Duration::ZERO),
+            Duration::from_millis(1)
+        );
+        assert_eq!(
+            timestep.time_until(Duration::from_millis(15)),
+            Duration::from_millis(11)
+        );
+
+        // a stall doesn't get caught up all at once
+        assert_eq!(
+            timestep.accumulate(Duration::from_secs(10)),
+            FixedTimestep::MAX_STEPS
+        );
+        assert_eq!(timestep.alpha(), 0.0);
+    }
+
+    const STEPS: u32 = 3000; // 25 seconds of game time
+
+    // plays a game at the given frame rate; the inputs are keyed to update steps, not frames
+    fn play(frame_rate: u32) -> Engine {
+        let mut engine = Engine::new_seeded(3);
+        engine.create_top_cursor(None);
+
+        let mut timestep = FixedTimestep::default();
+        let frame = Duration::from_secs(1) / frame_rate;
+        let mut step_index = 0;
+
+        while step_index < STEPS {
+            for _ in 0..timestep.accumulate(frame).min(STEPS - step_index) {
+                match step_index % 90 {
+                    10 => engine.move_cursor(MoveKind::Left),
+                    20 => engine.move_cursor(MoveKind::Left),
+                    40 => engine.move_cursor(MoveKind::Right),
+                    _ => {}
+                }
+
+                if engine.advance(timestep.step, step_index % 200 > 150) == Advance::Locked {
+                    engine.line_clear(engine.placed_tspin(), |_| ());
+                }
+                step_index += 1;
+            }
+        }
+
+        engine
+    }
+
+    #[test]
+    fn same_inputs_same_game_at_any_frame_rate() {
+        let at_60 = play(60);
+        let at_144 = play(144);
+
+        assert!(at_60.matrix.to_string().contains(|c| c != '.' && c != '\n'));
+        assert_eq!(at_60.matrix.to_string(), at_144.matrix.to_string());
+        assert_eq!(at_60.cursor(), at_144.cursor());
+        assert_eq!(at_60.score, at_144.score);
+    }
+}
@@ -0,0 +1,321 @@
+// terminal frontend, so the engine can be played without a window (e.g. over ssh)
+// it shares no code with the sdl interface, only the engine and the game inputs of the library
+
+use crate::engine::{color::TetriminoColor, config::Config, matrix::Matrix, Engine};
+use crossterm::{
+    cursor,
+    event::{self, Event, KeyCode, KeyEvent, KeyEventKind},
+    execute, queue,
+    style::{Color, Stylize},
+    terminal::{self, ClearType, EnterAlternateScreen, LeaveAlternateScreen},
+};
+use std::io::{self, Write};
+use std::time::{Duration, Instant};
+use tetris::controller::{GameController, GameInput, GameState};
+use tetris::timestep::FixedTimestep;
+
+const FRAME_DURATION: Duration = Duration::from_millis(16);
+// terminals don't report key releases, so soft drop is let go once the key stops repeating for this long
+const SOFT_DROP_RELEASE: Duration = Duration::from_millis(100);
+const EMPTY_CELL: &str = " .";
+const FULL_CELL: &str = "██";
+
+// what a key does: an input for the game, or something around the game
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum Action {
+    Game(GameInput),
+    TogglePause, // pause or resume, whichever it isn't
+    Restart,     // a new game once this one is over
+    Quit,
+}
+
+// the same game the window plays, rules and all; only the keys and the drawing are the terminal's own
+pub struct Tui {
+    pub game: GameController,
+    timestep: FixedTimestep,
+    last_update: Instant,
+    soft_drop_until: Option<Instant>, // while soft drop counts as held, see SOFT_DROP_RELEASE
+}
+
+impl Tui {
+    pub fn new(seed: u64, config: Config) -> Self {
+        let timestep = FixedTimestep::default();
+        Self {
+            game: GameController::new(seed, config, timestep.step),
+            timestep,
+            last_update: Instant::now(),
+            soft_drop_until: None,
+        }
+    }
+
+    pub fn run(&mut self) -> io::Result<()> {
+        let mut stdout = io::stdout();
+
+        terminal::enable_raw_mode()?;
+        execute!(stdout, EnterAlternateScreen, cursor::Hide)?;
+
+        let result = self.game_loop(&mut stdout);
+
+        // always give the terminal back, even if the game loop failed
+        execute!(stdout, cursor::Show, LeaveAlternateScreen)?;
+        terminal::disable_raw_mode()?;
+
+        result
+    }
+
+    fn game_loop(&mut self, stdout: &mut impl Write) -> io::Result<()> {
+        loop {
+            self.draw(stdout)?;
+
+            // wait for input for at most a frame, then let gravity do its thing
+            if event::poll(FRAME_DURATION)? {
+                if let Event::Key(KeyEvent {
+                    code,
+                    kind: KeyEventKind::Press,
+                    ..
+                }) = event::read()?
+                {
+                    match action_from(code) {
+                        Some(Action::Quit) => return Ok(()),
+                        Some(action) => self.handle_action(action, Instant::now()),
+                        None => {}
+                    }
+                }
+            }
+
+            self.update(Instant::now());
+        }
+    }
+
+    // the game's fixed steps for the time that has passed
+    fn update(&mut self, now: Instant) {
+        if self.soft_drop_until.is_some_and(|until| now >= until) {
+            self.soft_drop_until = None;
+            self.game.apply(GameInput::SoftDropStop);
+        }
+
+        let steps = self
+            .timestep
+            .accumulate(now.duration_since(self.last_update));
+        self.last_update = now;
+        for _ in 0..steps {
+            self.game.update(self.timestep.step);
+        }
+    }
+
+    fn handle_action(&mut self, action: Action, now: Instant) {
+        match action {
+            Action::Game(input) => {
+                if input == GameInput::SoftDropStart {
+                    self.soft_drop_until = Some(now + SOFT_DROP_RELEASE);
+                }
+                self.game.apply(input);
+            }
+            Action::TogglePause if self.game.is_paused() => self.game.apply(GameInput::Resume),
+            Action::TogglePause => self.game.apply(GameInput::Pause),
+            Action::Restart if self.game.is_game_over() => {
+                self.game.restart(rand::random());
+                self.soft_drop_until = None;
+            }
+            Action::Restart | Action::Quit => {}
+        }
+    }
+
+    fn draw(&self, stdout: &mut impl Write) -> io::Result<()> {
+        queue!(
+            stdout,
+            cursor::MoveTo(0, 0),
+            terminal::Clear(ClearType::All)
+        )?;
+
+        for (row, line) in render(&self.game.engine, self.game.state)
+            .iter()
+            .enumerate()
+        {
+            queue!(stdout, cursor::MoveTo(0, row as u16))?;
+            write!(stdout, "{line}")?;
+        }
+
+        stdout.flush()
+    }
+}
+
+// terminal keys to the game inputs every frontend shares
+fn action_from(code: KeyCode) -> Option<Action> {
+    Some(match code {
+        KeyCode::Left => Action::Game(GameInput::MoveLeft),
+        KeyCode::Right => Action::Game(GameInput::MoveRight),
+        KeyCode::Up => Action::Game(GameInput::Rotate),
+        KeyCode::Char('z') => Action::Game(GameInput::RotateCounterclockwise),
+        KeyCode::Down => Action::Game(GameInput::SoftDropStart),
+        KeyCode::Char(' ') => Action::Game(GameInput::HardDrop),
+        KeyCode::Char('c') => Action::Game(GameInput::Hold),
+        KeyCode::Char('p') | KeyCode::Char('1') => Action::TogglePause,
+        KeyCode::Enter => Action::Restart,
+        KeyCode::Esc | KeyCode::Char('q') => Action::Quit,
+        _ => return None,
+    })
+}
+
+// the terminal counterpart of the sdl ScreenColor
+trait TerminalColor {
+    fn terminal_color(&self) -> Color;
+}
+
+impl TerminalColor for TetriminoColor {
+    fn terminal_color(&self) -> Color {
+        match self {
+            TetriminoColor::Yellow => Color::Yellow,
+            TetriminoColor::Cyan => Color::Cyan,
+            TetriminoColor::Purple => Color::Magenta,
+            TetriminoColor::Orange => Color::Rgb {
+                r: 0xf5,
+                g: 0x79,
+                b: 0x00,
+            },
+            TetriminoColor::Blue => Color::Blue,
+            TetriminoColor::Green => Color::Green,
+            TetriminoColor::Red => Color::Red,
+            TetriminoColor::Gray => Color::DarkGrey,
+        }
+    }
+}
+
+fn render_cell(cell: Option<TetriminoColor>) -> String {
+    match cell {
+        Some(color) => FULL_CELL.with(color.terminal_color()).to_string(),
+        None => EMPTY_CELL.to_string(),
+    }
+}
+
+// rows of a matrix from the top down, since the terminal draws top to bottom
+fn render_rows<const WIDTH: usize, const HEIGHT: usize>(
+    matrix: &Matrix<WIDTH, HEIGHT>,
+) -> Vec<String>
+where
+    [usize; WIDTH * HEIGHT]:,
+{
+    matrix
+        .matrix
+        .chunks(WIDTH)
+        .rev()
+        .map(|row| row.iter().map(|&cell| render_cell(cell)).collect())
+        .collect()
+}
+
+// the whole frame as lines of text: the matrix in a frame with hold, up next and the score next to it
+pub fn render(engine: &Engine, state: GameState) -> Vec<String> {
+    let mut side = vec!["HOLD".to_string()];
+    side.extend(render_rows(&engine.hold_matrix));
+    side.push("UP NEXT".to_string());
+    side.extend(render_rows(&engine.up_next_matrix));
+    side.push(format!("LEVEL {}", engine.level));
+    side.push(format!("SCORE {}", engine.score));
+    side.push(
+        match state {
+            GameState::Paused => "PAUSED",
+            GameState::GameOver => "GAME OVER",
+            _ => "",
+        }
+        .to_string(),
+    );
+
+    let horizontal_border = "─".repeat(Engine::MATRIX_WIDTH * 2);
+    let mut lines = vec![format!("┌{horizontal_border}┐")];
+
+    for (row, matrix_row) in render_rows(&engine.render_state()).into_iter().enumerate() {
+        let side_text = side.get(row).map(String::as_str).unwrap_or("");
+        lines.push(format!("│{matrix_row}│  {side_text}"));
+    }
+
+    lines.push(format!("└{horizontal_border}┘"));
+    lines
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::engine::Coordinate;
+
+    // drop the ansi color codes so only the visible characters remain
+    fn strip_ansi(line: &str) -> String {
+        let mut visible = String::new();
+        let mut in_escape = false;
+
+        for character in line.chars() {
+            match character {
+                '\x1b' => in_escape = true,
+                'm' if in_escape => in_escape = false,
+                _ if !in_escape => visible.push(character),
+                _ => {}
+            }
+        }
+
+        visible
+    }
+
+    #[test]
+    fn keys_map_to_game_inputs() {
+        assert_eq!(
+            action_from(KeyCode::Char('z')),
+            Some(Action::Game(GameInput::RotateCounterclockwise))
+        );
+        assert_eq!(action_from(KeyCode::Char('q')), Some(Action::Quit));
+        assert_eq!(action_from(KeyCode::Char('x')), None);
+    }
+
+    #[test]
+    fn soft_drop_is_let_go_once_the_key_stops_repeating() {
+        let start = Instant::now();
+        let mut tui = Tui::new(3, Config::default());
+        tui.last_update = start;
+
+        tui.handle_action(Action::Game(GameInput::SoftDropStart), start);
+        tui.update(start + SOFT_DROP_RELEASE / 2);
+        assert_eq!(tui.game.state, GameState::SoftDropping);
+
+        tui.update(start + SOFT_DROP_RELEASE);
+        assert_eq!(tui.game.state, GameState::TickingDown);
+    }
+
+    #[test]
+    fn a_restart_is_a_whole_new_game() {
+        let mut tui = Tui::new(3, Config::default());
+        tui.handle_action(Action::Game(GameInput::Hold), Instant::now());
+        tui.handle_action(Action::Restart, Instant::now()); // not over yet
+        assert!(tui.game.engine.held().is_some());
+
+        while !tui.game.is_game_over() {
+            tui.handle_action(Action::Game(GameInput::HardDrop), Instant::now());
+            tui.game.update(tui.timestep.step);
+        }
+        tui.handle_action(Action::Restart, Instant::now());
+        assert!(!tui.game.is_game_over());
+        assert_eq!(tui.game.engine.held(), None);
+        assert_eq!(tui.game.pieces, 0);
+    }
+
+    #[test]
+    fn renders_known_board() {
+        let mut engine = Engine::new();
+        engine.matrix[Coordinate::new(0, 0)] = Some(TetriminoColor::Red);
+        engine.matrix[Coordinate::new(9, 19)] = Some(TetriminoColor::Blue);
+        engine.score = 42;
+
+        let lines: Vec<String> = render(&engine, GameState::Paused)
+            .iter()
+            .map(|line| strip_ansi(line))
+            .collect();
+
+        // a border on top and bottom with the 20 matrix rows in between
+        assert_eq!(lines.len(), Engine::MATRIX_HEIGHT + 2);
+
+        let top_row = &lines[1];
+        let bottom_row = &lines[Engine::MATRIX_HEIGHT];
+        assert!(top_row.starts_with(&format!("│{}██│", EMPTY_CELL.repeat(9))));
+        assert!(bottom_row.starts_with(&format!("│██{}│", EMPTY_CELL.repeat(9))));
+
+        assert!(lines.iter().any(|line| line.ends_with("SCORE 42")));
+        assert!(lines.iter().any(|line| line.ends_with("PAUSED")));
+    }
+}
@@ -0,0 +1,191 @@
+// browser frontend: a js friendly wrapper around the engine, compiled to wasm with wasm-bindgen;
+// the drawing and keyboard handling live in web/main.js
+
+use crate::controller::{GameController, GameInput};
+use crate::engine::{color::TetriminoColor, config::Config, Engine};
+use crate::timestep::FixedTimestep;
+use std::time::Duration;
+use wasm_bindgen::prelude::*;
+
+// actions js can apply, they cross the wasm boundary as plain numbers
+#[wasm_bindgen]
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Action {
+    MoveLeft = 0,
+    MoveRight = 1,
+    Rotate = 2,
+    SoftDrop = 3, // the key went down, SoftDropStop once it's back up
+    HardDrop = 4,
+    Hold = 5,
+    Pause = 6, // pauses, or resumes when paused
+    Restart = 7,
+    RotateCounterclockwise = 8,
+    SoftDropStop = 9,
+}
+
+// the same game the window plays, rules and all, driven by the frames of the page
+#[wasm_bindgen]
+pub struct WebGame {
+    game: GameController,
+    seed: u32,
+    timestep: FixedTimestep,
+}
+
+#[wasm_bindgen]
+impl WebGame {
+    // a seed rather than os randomness, so the same seed deals the same game
+    #[wasm_bindgen(constructor)]
+    pub fn new(seed: u32) -> Self {
+        let timestep = FixedTimestep::default();
+        Self {
+            game: GameController::new(seed as u64, Config::default(), timestep.step),
+            seed,
+            timestep,
+        }
+    }
+
+    pub fn apply(&mut self, action: Action) {
+        let input = match action {
+            Action::Restart => {
+                *self = Self::new(self.seed);
+                return;
+            }
+            Action::Pause if self.game.is_paused() => GameInput::Resume,
+            Action::Pause => GameInput::Pause,
+            Action::MoveLeft => GameInput::MoveLeft,
+            Action::MoveRight => GameInput::MoveRight,
+            Action::Rotate => GameInput::Rotate,
+            Action::RotateCounterclockwise => GameInput::RotateCounterclockwise,
+            Action::SoftDrop => GameInput::SoftDropStart,
+            Action::SoftDropStop => GameInput::SoftDropStop,
+            Action::HardDrop => GameInput::HardDrop,
+            Action::Hold => GameInput::Hold,
+        };
+
+        self.game.apply(input);
+    }
+
+    // let the game run for the given amount of milliseconds, in the fixed steps the other frontends use;
+    // a tab that was in the background doesn't catch up on all of it at once
+    pub fn advance(&mut self, dt_ms: f64) {
+        let dt = Duration::from_secs_f64(dt_ms.max(0.0) / 1000.0);
+        for _ in 0..self.timestep.accumulate(dt) {
+            self.game.update(self.timestep.step);
+        }
+    }
+
+    // the visible matrix with the cursor on top, one byte per cell, row by row starting at the bottom;
+    // see TetriminoColor::cell_code for what the numbers stand for, web/main.js has the matching palette
+    pub fn render_model(&self) -> Vec<u8> {
+        self.game
+            .engine
+            .render_state()
+            .matrix
+            .iter()
+            .map(|&cell| TetriminoColor::cell_code(cell))
+            .collect()
+    }
+
+    pub fn width(&self) -> usize {
+        Engine::MATRIX_WIDTH
+    }
+
+    pub fn height(&self) -> usize {
+        Engine::MATRIX_HEIGHT
+    }
+
+    pub fn score(&self) -> u32 {
+        self.game.engine.score
+    }
+
+    pub fn level(&self) -> u8 {
+        self.game.engine.level
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.game.is_paused()
+    }
+
+    pub fn is_game_over(&self) -> bool {
+        self.game.is_game_over()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::controller::GameState;
+    #[cfg(target_arch = "wasm32")]
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    fn occupied(game: &WebGame) -> usize {
+        game.render_model()
+            .iter()
+            .filter(|&&cell| cell != 0)
+            .count()
+    }
+
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    #[cfg_attr(not(target_arch = "wasm32"), test)]
+    fn hard_drop_locks_and_spawns() {
+        let mut game = WebGame::new(7);
+        assert_eq!(game.render_model().len(), game.width() * game.height());
+
+        game.apply(Action::HardDrop);
+
+        // the dropped tetrimino is on the matrix and the next one hasn't fallen in yet
+        assert_eq!(occupied(&game), 4);
+        assert!(!game.is_game_over());
+    }
+
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    #[cfg_attr(not(target_arch = "wasm32"), test)]
+    fn advance_applies_gravity_and_lockdown() {
+        let mut game = WebGame::new(7);
+
+        // long enough to fall all the way down and lock, a few times over
+        for _ in 0..2000 {
+            game.advance(16.0);
+        }
+        assert!(occupied(&game) >= 8);
+
+        // nothing moves while paused
+        game.apply(Action::Pause);
+        let model = game.render_model();
+        game.advance(10_000.0);
+        assert_eq!(game.render_model(), model);
+    }
+
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    #[cfg_attr(not(target_arch = "wasm32"), test)]
+    fn soft_drop_lasts_until_the_key_is_up() {
+        let mut game = WebGame::new(7);
+        game.apply(Action::SoftDrop);
+        game.advance(100.0);
+        assert_eq!(game.game.state, GameState::SoftDropping);
+
+        game.apply(Action::SoftDropStop);
+        assert_eq!(game.game.state, GameState::TickingDown);
+    }
+
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    #[cfg_attr(not(target_arch = "wasm32"), test)]
+    fn same_seed_same_game() {
+        let mut first = WebGame::new(42);
+        let mut second = WebGame::new(42);
+
+        for action in [
+            Action::MoveLeft,
+            Action::HardDrop,
+            Action::Rotate,
+            Action::HardDrop,
+            Action::RotateCounterclockwise,
+            Action::HardDrop,
+        ] {
+            first.apply(action);
+            second.apply(action);
+        }
+
+        assert_eq!(first.render_model(), second.render_model());
+    }
+}
@@ -0,0 +1,29 @@
+#![cfg(feature = "alloc-count")]
+#![allow(incomplete_features)]
+#![feature(generic_const_exprs)]
+
+use tetris::alloc_count::{allocations, CountingAllocator};
+use tetris::controller::GameController;
+use tetris::engine::config::Config;
+use tetris::timestep::FixedTimestep;
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+#[test]
+fn a_falling_tetrimino_does_not_allocate() {
+    let step = FixedTimestep::default().step;
+    let mut game = GameController::new(5, Config::default(), step);
+    game.update(step);
+    game.engine.take_events();
+
+    // a second of falling: steps that move nothing and the one that ticks it down a row
+    let before = allocations();
+    for _ in 0..120 {
+        game.update(step);
+        game.engine.take_events();
+        game.time_until_update();
+    }
+    assert_eq!(allocations() - before, 0);
+    assert_eq!(game.pieces, 0);
+}
@@ -0,0 +1,427 @@
+#![allow(incomplete_features)]
+#![feature(generic_const_exprs)]
+
+use std::time::Duration;
+use tetris::controller::{GameController, GameInput, GameState};
+use tetris::engine::color::TetriminoColor;
+use tetris::engine::piece_kind::PieceKind;
+use tetris::engine::{config::Config, mode::GameMode, Coordinate, Engine};
+use tetris::timestep::FixedTimestep;
+
+fn step() -> Duration {
+    FixedTimestep::default().step
+}
+
+fn game() -> GameController {
+    GameController::new(5, Config::default(), step())
+}
+
+// let the given amount of game time pass in fixed steps
+fn run(game: &mut GameController, time: Duration) {
+    let steps = time.as_nanos().div_ceil(step().as_nanos());
+    for _ in 0..steps {
+        game.update(step());
+    }
+}
+
+fn occupied(game: &GameController) -> usize {
+    game.engine
+        .matrix
+        .to_string()
+        .chars()
+        .filter(|&c| c != '.' && c != '\n')
+        .count()
+}
+
+// soft drop the tetrimino until it rests on the floor and the lock delay has started
+fn soft_drop_onto_floor(game: &mut GameController) {
+    game.apply(GameInput::SoftDropStart);
+    for _ in 0..1000 {
+        if game.state == GameState::LockingDown {
+            return;
+        }
+        game.update(step());
+    }
+
+    panic!("never landed, still {:?}", game.state);
+}
+
+#[test]
+fn soft_drop_into_lockdown_then_rotate_resets_the_lock_delay() {
+    let mut game = game();
+
+    soft_drop_onto_floor(&mut game);
+    run(&mut game, Engine::LOCK_DELAY - Duration::from_millis(100));
+    assert_eq!(game.state, GameState::LockingDown);
+    assert_eq!(game.pieces, 0);
+
+    game.apply(GameInput::SoftDropStop);
+    game.apply(GameInput::Rotate);
+    assert_eq!(game.engine.lock_elapsed, Some(Duration::ZERO));
+    assert_eq!(game.engine.lock_resets, 1);
+
+    // without the reset it would have locked by now
+    run(&mut game, Duration::from_millis(300));
+    assert_eq!(game.pieces, 0);
+
+    run(&mut game, Engine::LOCK_DELAY);
+    assert_eq!(game.pieces, 1);
+    assert_eq!(occupied(&game), 4);
+}
+
+#[test]
+fn lock_resets_run_out_and_the_tetrimino_locks() {
+    let mut game = game();
+    soft_drop_onto_floor(&mut game);
+    game.apply(GameInput::SoftDropStop);
+
+    // shuffled back and forth well within the lock delay, it still locks once the resets are used up
+    let mut moves = 0;
+    while game.pieces == 0 {
+        assert!(moves < 100, "never locked");
+        assert!(game.engine.lock_resets <= Engine::MAX_LOCK_RESETS);
+
+        let resets = game.engine.lock_resets;
+        game.apply(if moves % 2 == 0 {
+            GameInput::MoveLeft
+        } else {
+            GameInput::MoveRight
+        });
+        moves += 1;
+
+        if resets == Engine::MAX_LOCK_RESETS {
+            assert_eq!(game.engine.lock_resets, resets);
+        }
+        run(&mut game, Duration::from_millis(100));
+    }
+
+    let per_delay = (Engine::LOCK_DELAY.as_millis() / 100) as u32;
+    assert!(moves > Engine::MAX_LOCK_RESETS);
+    assert!(moves <= Engine::MAX_LOCK_RESETS + per_delay);
+    assert_eq!(occupied(&game), 4);
+}
+
+#[test]
+fn hard_drop_during_lockdown_locks_right_away() {
+    let mut game = game();
+    soft_drop_onto_floor(&mut game);
+    let cursor = game.engine.cursor();
+
+    game.apply(GameInput::HardDrop);
+    assert_eq!(game.state, GameState::LockedDown);
+    assert_eq!(game.pieces, 1);
+    assert_eq!(game.take_locked(), cursor);
+    assert_eq!(game.take_locked(), None);
+
+    // the line clear happens on the next update, then the next tetrimino falls
+    game.update(step());
+    assert_eq!(game.state, GameState::TickingDown);
+    assert_eq!(occupied(&game), 4);
+    assert_ne!(game.engine.cursor(), cursor);
+}
+
+#[test]
+fn clear_delay_holds_the_full_lines_back() {
+    let config = Config {
+        clear_delay: Duration::from_millis(200),
+        ..Config::default()
+    };
+    let mut game = GameController::new(5, config, step());
+
+    // a bottom row with room for a flat i in the middle
+    game.engine.create_top_cursor(Some(PieceKind::I));
+    for x in (0..3).chain(7..Engine::MATRIX_WIDTH) {
+        game.engine.matrix[Coordinate::new(x, 0)] = Some(TetriminoColor::Gray);
+    }
+    game.apply(GameInput::HardDrop);
+    assert!(game.engine.matrix.is_line_full(0));
+
+    // the stack hangs there, the next tetrimino waits for it
+    run(&mut game, Duration::from_millis(150));
+    assert_eq!(game.state, GameState::LockedDown);
+    assert!(game.engine.matrix.is_line_full(0));
+    let cursor = game.engine.cursor();
+    game.apply(GameInput::MoveLeft);
+    assert_eq!(game.engine.cursor(), cursor);
+
+    run(&mut game, Duration::from_millis(100));
+    assert_eq!(game.state, GameState::TickingDown);
+    assert_eq!(occupied(&game), 0);
+}
+
+#[test]
+fn pause_during_lockdown_stops_the_lock_delay() {
+    let mut game = game();
+    soft_drop_onto_floor(&mut game);
+    game.apply(GameInput::SoftDropStop);
+    let lock_elapsed = game.engine.lock_elapsed;
+
+    game.apply(GameInput::Pause);
+    run(&mut game, Duration::from_secs(10));
+    assert_eq!(game.state, GameState::Paused);
+    assert_eq!(game.engine.lock_elapsed, lock_elapsed);
+    assert_eq!(game.pieces, 0);
+
+    // inputs don't get through while paused
+    let cursor = game.engine.cursor();
+    game.apply(GameInput::MoveLeft);
+    game.apply(GameInput::HardDrop);
+    assert_eq!(game.engine.cursor(), cursor);
+    assert_eq!(game.pieces, 0);
+
+    game.apply(GameInput::Resume);
+    assert_eq!(game.state, GameState::LockingDown);
+    run(&mut game, Engine::LOCK_DELAY);
+    assert_eq!(game.pieces, 1);
+}
+
+#[test]
+fn resume_only_gives_what_was_left_of_the_lock_delay() {
+    let mut game = game();
+    soft_drop_onto_floor(&mut game);
+    game.apply(GameInput::SoftDropStop);
+    run(&mut game, Duration::from_millis(300));
+
+    game.apply(GameInput::Pause);
+    run(&mut game, Duration::from_secs(10));
+    game.apply(GameInput::Resume);
+
+    // 300ms in, give or take the step it took to get there
+    let remaining = game.engine.lock_delay_remaining().unwrap();
+    assert!(
+        remaining <= Duration::from_millis(200) && remaining > Duration::from_millis(190),
+        "{remaining:?}"
+    );
+    assert_eq!(game.time_until_update(), Some(remaining));
+
+    run(&mut game, remaining - step());
+    assert_eq!(game.pieces, 0);
+    run(&mut game, step());
+    assert_eq!(game.pieces, 1);
+}
+
+#[test]
+fn during_the_spawn_delay_the_next_tetrimino_is_pending() {
+    let spawn_delay = Duration::from_millis(200);
+    let mut game = GameController::new(
+        5,
+        Config {
+            spawn_delay,
+            ..Config::default()
+        },
+        step(),
+    );
+    assert_eq!(game.pending(), None); // the first one is in play right away
+
+    game.apply(GameInput::HardDrop);
+    game.update(step());
+    assert_eq!(game.state, GameState::Spawning);
+    let (pending, formed) = game.pending().unwrap();
+    assert_eq!(game.engine.cursor(), Some(pending));
+    assert_eq!(formed, 0.0);
+
+    // nothing moves it, not even gravity, but it can be paused
+    run(&mut game, Duration::from_millis(100));
+    for input in [
+        GameInput::MoveLeft,
+        GameInput::Rotate,
+        GameInput::HardDrop,
+        GameInput::Hold,
+    ] {
+        game.apply(input);
+    }
+    assert_eq!(game.engine.cursor(), Some(pending));
+    assert_eq!(game.pieces, 1);
+    assert!(game.pending().unwrap().1 >= 0.5);
+
+    game.apply(GameInput::Pause);
+    run(&mut game, Duration::from_secs(1));
+    game.apply(GameInput::Resume);
+    assert_eq!(game.state, GameState::Spawning);
+
+    // then it's in play
+    let remaining = game.time_until_update().unwrap();
+    run(&mut game, remaining);
+    assert_eq!(game.state, GameState::TickingDown);
+    assert_eq!(game.pending(), None);
+    game.apply(GameInput::MoveLeft);
+    assert_ne!(game.engine.cursor(), Some(pending));
+}
+
+#[test]
+fn a_held_soft_drop_has_to_be_pressed_again_for_the_next_tetrimino() {
+    for soft_drop_repress in [false, true] {
+        let mut game = GameController::new(
+            5,
+            Config {
+                soft_drop_repress,
+                ..Config::default()
+            },
+            step(),
+        );
+
+        // held down until the tetrimino locks, the key repeating all along
+        game.apply(GameInput::SoftDropStart);
+        while game.pieces == 0 {
+            game.update(step());
+            game.apply(GameInput::SoftDropStart);
+        }
+        game.update(step());
+        game.apply(GameInput::SoftDropStart);
+        assert_eq!(
+            game.state == GameState::SoftDropping,
+            !soft_drop_repress,
+            "repress {soft_drop_repress}"
+        );
+
+        // let go and pressed again, it soft drops either way
+        game.apply(GameInput::SoftDropStop);
+        game.apply(GameInput::SoftDropStart);
+        assert_eq!(game.state, GameState::SoftDropping);
+    }
+}
+
+#[test]
+fn frame_step_only_falls_when_stepped() {
+    let mut game = game();
+    game.engine.frame_step = true;
+    let spawned = game.engine.cursor().unwrap().position;
+
+    run(&mut game, Duration::from_secs(10));
+    assert_eq!(game.engine.cursor().unwrap().position, spawned);
+    assert_eq!(game.time_until_update(), None);
+
+    // one row down, however long gravity would have taken
+    game.engine.step();
+    game.update(step());
+    assert_eq!(game.engine.cursor().unwrap().position.y, spawned.y - 1);
+    run(&mut game, Duration::from_secs(10));
+    assert_eq!(game.engine.cursor().unwrap().position.y, spawned.y - 1);
+
+    // down to the floor a row at a time, then the lock is a step of its own
+    for _ in 0..100 {
+        if game.state == GameState::LockingDown {
+            break;
+        }
+        game.engine.step();
+        game.update(step());
+    }
+    assert_eq!(game.state, GameState::LockingDown);
+    assert_eq!(game.pieces, 0);
+    game.engine.step();
+    game.update(step());
+    assert_eq!(game.pieces, 1);
+
+    // and it stays on for the next game
+    game.restart(6);
+    assert!(game.engine.frame_step);
+}
+
+#[test]
+fn restart_after_game_over() {
+    let mut game = game();
+
+    // stacking everything in the middle ends the game soon enough
+    for _ in 0..100 {
+        if game.is_game_over() {
+            break;
+        }
+        game.apply(GameInput::HardDrop);
+        game.update(step());
+    }
+    assert!(game.is_game_over());
+
+    // nothing moves once it's over
+    let board = game.engine.matrix.to_string();
+    run(&mut game, Duration::from_secs(1));
+    game.apply(GameInput::HardDrop);
+    assert_eq!(game.engine.matrix.to_string(), board);
+
+    let steps = game.replay.as_ref().unwrap().steps;
+    run(&mut game, Duration::from_secs(1));
+    assert_eq!(game.replay.as_ref().unwrap().steps, steps);
+
+    game.restart(6);
+    assert_eq!(game.state, GameState::TickingDown);
+    assert_eq!(game.pieces, 0);
+    assert_eq!(occupied(&game), 0);
+    assert!(game.engine.cursor().is_some());
+
+    let replay = game.replay.as_ref().unwrap();
+    assert_eq!(replay.seed, 6);
+    assert!(replay.events.is_empty());
+}
+
+#[test]
+fn soft_dropping_scores_a_point_for_every_row() {
+    let mut game = game();
+    while game.engine.cursor().is_none() {
+        game.update(step());
+    }
+    let top = game.engine.cursor().unwrap().position.y;
+
+    game.apply(GameInput::SoftDropStart);
+    while game.engine.cursor().unwrap().position.y > top - 5 {
+        game.update(step());
+    }
+    assert_eq!(game.engine.score, 5);
+
+    // down to the floor, and nothing more for resting on it
+    soft_drop_onto_floor(&mut game);
+    let rows = top - game.engine.cursor().unwrap().position.y;
+    assert_eq!(game.engine.score, rows as u32);
+    game.update(step());
+    assert_eq!(game.engine.score, rows as u32);
+}
+
+#[test]
+fn a_sprint_is_over_once_its_lines_are_cleared() {
+    let config = Config {
+        mode: GameMode::Sprint { lines_goal: 2 },
+        ..Config::default()
+    };
+    let mut game = GameController::new(5, config, step());
+
+    // the first line leaves one to go, the second is the last
+    for lines_left in [1, 0] {
+        game.engine.create_top_cursor(Some(PieceKind::I));
+        for x in (0..3).chain(7..Engine::MATRIX_WIDTH) {
+            game.engine.matrix[Coordinate::new(x, 0)] = Some(TetriminoColor::Gray);
+        }
+        game.apply(GameInput::HardDrop);
+        game.update(step());
+        assert_eq!(
+            config.mode.lines_left(game.engine.stats().lines()),
+            Some(lines_left)
+        );
+    }
+    assert!(game.is_game_over());
+    assert!(game.completed);
+    assert_eq!(game.top_out, None);
+}
+
+#[test]
+fn an_ultra_is_over_when_its_time_is_up() {
+    let config = Config {
+        mode: GameMode::Ultra {
+            duration: Duration::from_secs(2),
+        },
+        ..Config::default()
+    };
+    let mut game = GameController::new(5, config, step());
+    run(&mut game, Duration::from_secs(1));
+
+    // paused time doesn't count
+    game.apply(GameInput::Pause);
+    run(&mut game, Duration::from_secs(5));
+    game.apply(GameInput::Resume);
+    assert!(!game.is_game_over());
+    assert!(game.time_until_update().unwrap() <= Duration::from_secs(1));
+
+    run(&mut game, Duration::from_secs(1));
+    assert!(game.is_game_over());
+    assert!(game.completed);
+    // on the step that went past it
+    assert!(game.play_time >= Duration::from_secs(2));
+    assert!(game.play_time < Duration::from_secs(2) + step());
+}
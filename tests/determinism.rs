@@ -0,0 +1,86 @@
+#![allow(incomplete_features)]
+#![feature(generic_const_exprs)]
+
+use std::collections::VecDeque;
+use std::time::Duration;
+use tetris::bot;
+use tetris::controller::{GameController, GameInput};
+use tetris::engine::config::Config;
+use tetris::engine::piece_rotation::Rotation;
+use tetris::timestep::FixedTimestep;
+
+const SEED: u64 = 11;
+const STEPS: u32 = 12_000;
+const TURN: u32 = 150; // steps each tetrimino gets, it's hard dropped by the end of them
+
+// the bot's placement for the cursor as inputs, to be played one every few steps
+fn plan(game: &GameController) -> VecDeque<GameInput> {
+    let (Some(cursor), Some(placement)) = (game.engine.cursor(), bot::best_placement(&game.engine))
+    else {
+        return VecDeque::new();
+    };
+
+    let index = |rotation| Rotation::ALL.iter().position(|&r| r == rotation).unwrap();
+    let rotations = (index(placement.rotation) + 4 - index(cursor.rotation)) % 4;
+    let shift = match placement.shift {
+        shift if shift < 0 => GameInput::MoveLeft,
+        _ => GameInput::MoveRight,
+    };
+
+    std::iter::repeat_n(GameInput::Rotate, rotations)
+        .chain(std::iter::repeat_n(shift, placement.shift.unsigned_abs()))
+        .collect()
+}
+
+// the board and score every time a tetrimino locked, with frames delivered at `frame_rate`. the inputs are
+// keyed to update steps like in a replay, never to frames
+fn play(frame_rate: u32) -> Vec<(u64, u32)> {
+    let mut timestep = FixedTimestep::default();
+    let mut game = GameController::new(SEED, Config::default(), timestep.step);
+    let frame = Duration::from_secs(1) / frame_rate;
+    let mut planned = VecDeque::new();
+    let mut hashes = Vec::new();
+    let mut step = 0;
+
+    while step < STEPS && !game.is_game_over() {
+        for _ in 0..timestep.accumulate(frame).min(STEPS - step) {
+            let turn = step / TURN;
+            let input = match step % TURN {
+                5 if turn % 7 == 3 => Some(GameInput::Hold),
+                10 => {
+                    planned = plan(&game);
+                    None
+                }
+                moment @ 15..=90 if moment.is_multiple_of(5) => planned.pop_front(),
+                100 => Some(GameInput::SoftDropStart),
+                110 => Some(GameInput::SoftDropStop),
+                120 => Some(GameInput::HardDrop),
+                _ => None,
+            };
+            if let Some(input) = input {
+                game.apply(input);
+            }
+            game.update(timestep.step);
+
+            if game.take_locked().is_some() {
+                hashes.push((game.engine.matrix.content_hash(), game.engine.score));
+            }
+            step += 1;
+        }
+    }
+
+    hashes
+}
+
+#[test]
+fn same_board_after_every_lock_at_any_frame_rate() {
+    let at_60 = play(60);
+    assert!(at_60.len() > 60, "only {} tetriminos locked", at_60.len());
+    assert!(at_60.last().unwrap().1 > 0);
+
+    // the boards really change from one lock to the next, the hash isn't stuck on something
+    assert!(at_60.windows(2).all(|pair| pair[0].0 != pair[1].0));
+
+    assert_eq!(play(30), at_60);
+    assert_eq!(play(144), at_60);
+}
@@ -0,0 +1,57 @@
+// crashes the fuzzer found (see src/fuzz.rs for how the bytes are read), minimized and written down so
+// they stay fixed, plus the checked in corpus so it keeps running clean without cargo fuzz
+use std::fs;
+use std::path::Path;
+use tetris::fuzz;
+
+#[test]
+fn corpus_runs_clean() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("fuzz/corpus/engine");
+    let mut count = 0;
+
+    for entry in fs::read_dir(dir).unwrap() {
+        let path = entry.unwrap().path();
+        if path.is_file() {
+            fuzz::run(&fs::read(path).unwrap());
+            count += 1;
+        }
+    }
+
+    assert!(count > 0, "the corpus is empty");
+}
+
+// seed 156: hard drop, hold, hard drop, three left, hard drop, four right, hard drop. the last one
+// completes the bottom row, so its line clear is still due when the next input comes in
+const BOTTOM_ROW_COMPLETED: [u8; 13] = [156, 85, 150, 85, 160, 176, 224, 37, 1, 81, 97, 129, 69];
+
+fn completed_then(input: u8) -> Vec<u8> {
+    let mut data = BOTTOM_ROW_COMPLETED.to_vec();
+    data.push(input);
+    data
+}
+
+// pausing swallowed the line clear, the full row stayed on the board for the rest of the game
+#[test]
+fn pause_right_after_a_hard_drop_clears_the_lines() {
+    fuzz::run(&completed_then(0x47));
+}
+
+// a resume without a pause went straight to ticking down, past the line clear
+#[test]
+fn resume_while_not_paused_keeps_the_line_clear() {
+    fuzz::run(&completed_then(0x48));
+}
+
+// so did starting a soft drop
+#[test]
+fn soft_drop_right_after_a_hard_drop_clears_the_lines() {
+    fuzz::run(&completed_then(0x43));
+}
+
+// losing with a full row on the board isn't a leftover line clear, the game just stopped there
+#[test]
+fn game_over_keeps_the_board_as_it_was() {
+    let mut data = vec![5];
+    data.extend([0x55; 40]);
+    fuzz::run(&data);
+}
@@ -0,0 +1,732 @@
+#![allow(incomplete_features)]
+#![feature(generic_const_exprs)]
+
+// golden boards: each test writes a board down as text (top row first, see Matrix's Display), does one
+// documented thing to it and compares the result against the board it should give. these pin down the
+// geometry so the matrix can be reworked underneath without anything changing
+
+use tetris::engine::{
+    matrix::Matrix, move_kind::MoveKind, piece_kind::PieceKind, piece_rotation::Rotation,
+    tspin::TSpinKind, Engine,
+};
+
+const EMPTY_ROW: &str = "..........";
+
+// a full size board from its bottom rows, everything above them is empty
+fn board(rows: &str) -> Matrix<{ Engine::MATRIX_WIDTH }, { Engine::MATRIX_HEIGHT }> {
+    padded(rows).parse().unwrap()
+}
+
+fn padded(rows: &str) -> String {
+    let rows: Vec<&str> = rows.trim().lines().map(str::trim).collect();
+    let mut lines = vec![EMPTY_ROW; Engine::MATRIX_HEIGHT - rows.len()];
+    lines.extend(rows);
+
+    lines.join("\n")
+}
+
+fn assert_board<const WIDTH: usize, const HEIGHT: usize>(
+    operation: &str,
+    actual: &Matrix<WIDTH, HEIGHT>,
+    expected: &str,
+) where
+    [usize; WIDTH * HEIGHT]:,
+{
+    let expected: Matrix<WIDTH, HEIGHT> = expected.parse().unwrap();
+    let (actual, expected) = (actual.to_string(), expected.to_string());
+
+    assert!(
+        actual == expected,
+        "{operation}\n\nexpected:\n{expected}\n\nactual:\n{actual}\n"
+    );
+}
+
+// the bottom rows of a full size board, as in board()
+fn assert_bottom(
+    operation: &str,
+    actual: &Matrix<{ Engine::MATRIX_WIDTH }, { Engine::MATRIX_HEIGHT }>,
+    expected: &str,
+) {
+    assert_board(operation, actual, &padded(expected));
+}
+
+// a small board and the lines cleared from it
+fn cleared(rows: &str, lines: &[usize]) -> Matrix<4, 5> {
+    let mut matrix: Matrix<4, 5> = rows.parse().unwrap();
+    matrix.clear_lines(lines);
+    matrix
+}
+
+// an engine on the given board with a tetrimino of the given kind brought down into view
+fn engine(rows: &str, kind: PieceKind, rows_down: usize) -> Engine {
+    let mut engine = Engine::new_seeded(1);
+    engine.matrix = board(rows);
+    engine.create_top_cursor(Some(kind));
+    for _ in 0..rows_down {
+        engine.try_tick_down();
+    }
+
+    engine
+}
+
+fn rotate(engine: &mut Engine) {
+    let rotation = engine.next_cursor_rotation().unwrap();
+    engine.rotate_and_adjust_cursor(rotation);
+}
+
+fn move_all_the_way(engine: &mut Engine, kind: MoveKind) {
+    for _ in 0..Engine::MATRIX_WIDTH {
+        engine.move_cursor(kind);
+    }
+}
+
+#[test]
+fn parse_and_display_round_trip() {
+    let text = "....\n.T..\nTTT.\nGGG.\nIIII";
+    let matrix: Matrix<4, 5> = text.parse().unwrap();
+
+    assert_eq!(matrix.to_string(), text);
+    assert!("....\n....".parse::<Matrix<4, 5>>().is_err());
+    assert!("....\n....\n....\n....\n...x"
+        .parse::<Matrix<4, 5>>()
+        .is_err());
+    assert!("....\n....\n....\n....\n....."
+        .parse::<Matrix<4, 5>>()
+        .is_err());
+}
+
+#[test]
+fn clear_bottom_row() {
+    let matrix = cleared(
+        "
+        ....
+        ....
+        .T..
+        TTTZ
+        IIII",
+        &[0],
+    );
+
+    assert_board(
+        "clear row 0",
+        &matrix,
+        "
+        ....
+        ....
+        ....
+        .T..
+        TTTZ",
+    );
+}
+
+#[test]
+fn clear_top_row() {
+    let matrix = cleared(
+        "
+        OOOO
+        J...
+        JJJ.
+        ..SS
+        .SS.",
+        &[4],
+    );
+
+    assert_board(
+        "clear row 4",
+        &matrix,
+        "
+        ....
+        J...
+        JJJ.
+        ..SS
+        .SS.",
+    );
+}
+
+#[test]
+fn clear_top_and_bottom_rows() {
+    let matrix = cleared(
+        "
+        IIII
+        .L..
+        .L..
+        .LL.
+        ZZZZ",
+        &[0, 4],
+    );
+
+    assert_board(
+        "clear rows 0 and 4",
+        &matrix,
+        "
+        ....
+        ....
+        .L..
+        .L..
+        .LL.",
+    );
+}
+
+#[test]
+fn clear_adjacent_middle_rows() {
+    let matrix = cleared(
+        "
+        ..O.
+        IIII
+        JJJJ
+        .S..
+        SS..",
+        &[2, 3],
+    );
+
+    assert_board(
+        "clear rows 2 and 3",
+        &matrix,
+        "
+        ....
+        ....
+        ..O.
+        .S..
+        SS..",
+    );
+}
+
+#[test]
+fn clear_rows_with_a_gap_between() {
+    let matrix = cleared(
+        "
+        ...T
+        IIII
+        .Z..
+        LLLL
+        J...",
+        &[1, 3],
+    );
+
+    assert_board(
+        "clear rows 1 and 3",
+        &matrix,
+        "
+        ....
+        ....
+        ...T
+        .Z..
+        J...",
+    );
+}
+
+#[test]
+fn clear_four_rows() {
+    let matrix = cleared(
+        "
+        .O..
+        IIII
+        IIII
+        IIII
+        IIII",
+        &[0, 1, 2, 3],
+    );
+
+    assert_board(
+        "clear rows 0 to 3",
+        &matrix,
+        "
+        ....
+        ....
+        ....
+        ....
+        .O..",
+    );
+}
+
+#[test]
+fn clear_every_row() {
+    let matrix = cleared(
+        "
+        OOOO
+        IIII
+        TTTT
+        SSSS
+        ZZZZ",
+        &[0, 1, 2, 3, 4],
+    );
+
+    assert_board(
+        "clear rows 0 to 4",
+        &matrix,
+        "
+        ....
+        ....
+        ....
+        ....
+        ....",
+    );
+}
+
+#[test]
+fn full_lines_skip_rows_with_a_hole() {
+    let matrix: Matrix<4, 5> = "
+        ....
+        IIII
+        TT.T
+        LLLL
+        GGG."
+        .parse()
+        .unwrap();
+
+    assert_eq!(matrix.full_lines(), [1, 3]);
+}
+
+// there's no cascade gravity: what's above a cleared line moves down by exactly as many lines as were
+// cleared below it, cells left hanging over a hole stay hanging
+#[test]
+fn clear_leaves_floating_cells_hanging() {
+    let mut engine = Engine::new_seeded(1);
+    engine.matrix = board(
+        "
+        .TTT......
+        ZZZZZZZZZZ
+        .I.I.I.I.I",
+    );
+    engine.line_clear(TSpinKind::None, |_| ());
+
+    assert_bottom(
+        "line clear",
+        &engine.matrix,
+        "
+        .TTT......
+        .I.I.I.I.I",
+    );
+    assert!(engine.matrix.full_lines().is_empty());
+}
+
+#[test]
+fn hard_drop_into_a_well_clears_the_lines_it_completes() {
+    let mut engine = engine(
+        "
+        GGGGGGGGG.
+        GGGGGGGGG.
+        GGGGGGGGG.
+        GGGGGGGGG.
+        GGGGG.GGG.",
+        PieceKind::I,
+        0,
+    );
+    rotate(&mut engine);
+    move_all_the_way(&mut engine, MoveKind::Right);
+    engine.hard_drop();
+    assert!(engine.lock_cursor());
+    engine.line_clear(TSpinKind::None, |_| ());
+
+    assert_bottom(
+        "hard drop a vertical I into the right well and clear",
+        &engine.matrix,
+        "
+        GGGGGGGGG.
+        GGGGG.GGGI",
+    );
+}
+
+#[test]
+fn garbage_on_an_empty_board() {
+    let mut matrix = board("");
+    assert!(matrix.insert_garbage(1, 0));
+
+    assert_bottom(
+        "one line of garbage, hole in column 0",
+        &matrix,
+        ".GGGGGGGGG",
+    );
+}
+
+#[test]
+fn garbage_pushes_the_stack_up() {
+    let mut matrix = board(
+        "
+        ..T.......
+        .TTT..OO..
+        LLL...OO.I",
+    );
+    assert!(matrix.insert_garbage(2, 4));
+
+    assert_bottom(
+        "two lines of garbage, hole in column 4",
+        &matrix,
+        "
+        ..T.......
+        .TTT..OO..
+        LLL...OO.I
+        GGGG.GGGGG
+        GGGG.GGGGG",
+    );
+}
+
+#[test]
+fn garbage_hole_in_the_last_column() {
+    let mut matrix = board("ZZ........");
+    assert!(matrix.insert_garbage(1, 9));
+
+    assert_bottom(
+        "one line of garbage, hole in column 9",
+        &matrix,
+        "
+        ZZ........
+        GGGGGGGGG.",
+    );
+}
+
+#[test]
+fn garbage_over_the_top() {
+    let mut matrix: Matrix<4, 5> = "
+        .O..
+        .O..
+        IIII
+        TTT.
+        .T.."
+        .parse()
+        .unwrap();
+
+    assert!(!matrix.insert_garbage(2, 1));
+    assert_board(
+        "two lines of garbage on a board that's nearly full",
+        &matrix,
+        "
+        IIII
+        TTT.
+        .T..
+        G.GG
+        G.GG",
+    );
+}
+
+#[test]
+fn garbage_hole_filled_clears_it() {
+    let mut engine = engine("", PieceKind::I, 0);
+    engine.matrix.insert_garbage(1, 3);
+    rotate(&mut engine);
+    move_all_the_way(&mut engine, MoveKind::Left);
+    for _ in 0..3 {
+        engine.move_cursor(MoveKind::Right);
+    }
+    engine.hard_drop();
+    assert!(engine.lock_cursor());
+    engine.line_clear(TSpinKind::None, |_| ());
+
+    assert_bottom(
+        "vertical I dropped into the garbage hole and cleared",
+        &engine.matrix,
+        "
+        ...I......
+        ...I......
+        ...I......",
+    );
+}
+
+#[test]
+fn garbage_lifts_an_overlapping_cursor() {
+    let mut engine = engine(
+        "
+        ..........
+        ZZZZ..ZZZZ",
+        PieceKind::O,
+        0,
+    );
+    engine.hard_drop();
+    assert!(engine.add_garbage(1, 0));
+
+    assert_bottom(
+        "garbage under an O that landed in a gap",
+        &engine.render_state(),
+        "
+        ....OO....
+        ZZZZOOZZZZ
+        .GGGGGGGGG",
+    );
+}
+
+#[test]
+fn garbage_leaves_a_clear_cursor_in_place() {
+    let mut engine = engine("", PieceKind::T, 6);
+    let before = engine.cursor().unwrap().position;
+    assert!(engine.add_garbage(3, 9));
+
+    assert_eq!(engine.cursor().unwrap().position, before);
+    assert_bottom(
+        "garbage well below a falling T",
+        &engine.render_state(),
+        "
+        ....T.....
+        ...TTT....
+        ..........
+        ..........
+        ..........
+        ..........
+        ..........
+        ..........
+        ..........
+        ..........
+        ..........
+        ..........
+        ..........
+        GGGGGGGGG.
+        GGGGGGGGG.
+        GGGGGGGGG.",
+    );
+}
+
+// rotated up in the air, where there's room for every rotation, then dropped
+fn t_dropped_after(rotations: usize) -> Engine {
+    let mut engine = engine("", PieceKind::T, 10);
+    for _ in 0..rotations {
+        rotate(&mut engine);
+    }
+    engine.hard_drop();
+
+    engine
+}
+
+#[test]
+fn t_rotates_clockwise_in_open_space() {
+    assert_bottom(
+        "T rotated to east and dropped",
+        &t_dropped_after(1).render_state(),
+        "
+        ....T.....
+        ....TT....
+        ....T.....",
+    );
+    assert_bottom(
+        "T rotated to south and dropped",
+        &t_dropped_after(2).render_state(),
+        "
+        ...TTT....
+        ....T.....",
+    );
+    assert_bottom(
+        "T rotated to west and dropped",
+        &t_dropped_after(3).render_state(),
+        "
+        ....T.....
+        ...TT.....
+        ....T.....",
+    );
+    assert_bottom(
+        "T rotated back to north and dropped",
+        &t_dropped_after(4).render_state(),
+        "
+        ....T.....
+        ...TTT....",
+    );
+}
+
+#[test]
+fn t_on_the_floor_kicks_up_to_rotate() {
+    let mut engine = t_dropped_after(0);
+    rotate(&mut engine);
+
+    // east reaches a row further down than north does, the third kick makes room up and to the left
+    assert_eq!(engine.cursor().unwrap().rotation, Rotation::E);
+    assert_bottom(
+        "T on the floor rotated",
+        &engine.render_state(),
+        "
+        ...T......
+        ...TT.....
+        ...T......",
+    );
+}
+
+#[test]
+fn i_rotates_to_vertical() {
+    let mut engine = engine("", PieceKind::I, 10);
+    rotate(&mut engine);
+    assert_eq!(engine.cursor().unwrap().rotation, Rotation::E);
+    engine.hard_drop();
+
+    assert_bottom(
+        "I rotated to east and dropped",
+        &engine.render_state(),
+        "
+        .....I....
+        .....I....
+        .....I....
+        .....I....",
+    );
+}
+
+#[test]
+fn i_against_the_left_wall_kicks_right() {
+    let mut engine = engine("", PieceKind::I, 10);
+    rotate(&mut engine);
+    rotate(&mut engine);
+    rotate(&mut engine);
+    assert_eq!(engine.cursor().unwrap().rotation, Rotation::W);
+    move_all_the_way(&mut engine, MoveKind::Left);
+    engine.hard_drop();
+
+    // the horizontal I would stick out of the matrix by a column, the second kick moves it back in
+    rotate(&mut engine);
+    assert_eq!(engine.cursor().unwrap().rotation, Rotation::N);
+    assert_bottom(
+        "vertical I at the left wall rotated",
+        &engine.render_state(),
+        "
+        IIII......
+        ..........
+        ..........",
+    );
+}
+
+#[test]
+fn i_against_the_right_wall_kicks_left() {
+    let mut engine = engine("", PieceKind::I, 10);
+    rotate(&mut engine);
+    move_all_the_way(&mut engine, MoveKind::Right);
+    engine.hard_drop();
+
+    rotate(&mut engine);
+    assert_eq!(engine.cursor().unwrap().rotation, Rotation::S);
+    assert_bottom(
+        "vertical I at the right wall rotated",
+        &engine.render_state(),
+        "
+        ......IIII
+        ..........",
+    );
+}
+
+#[test]
+fn i_next_to_the_stack_kicks_away_from_it() {
+    let mut engine = engine(
+        "
+        ......G...
+        ......G...
+        ......G...
+        ......G...",
+        PieceKind::I,
+        0,
+    );
+    rotate(&mut engine);
+    engine.hard_drop();
+
+    rotate(&mut engine);
+    assert_eq!(engine.cursor().unwrap().rotation, Rotation::S);
+    assert_bottom(
+        "vertical I next to a column rotated",
+        &engine.render_state(),
+        "
+        ......G...
+        ......G...
+        ..IIIIG...
+        ......G...",
+    );
+}
+
+#[test]
+fn o_looks_the_same_in_every_rotation() {
+    let mut engine = engine("", PieceKind::O, 0);
+    engine.hard_drop();
+    let before = engine.render_state().to_string();
+
+    for _ in 0..4 {
+        rotate(&mut engine);
+        assert_eq!(engine.render_state().to_string(), before);
+    }
+    assert_bottom(
+        "O rotated",
+        &engine.render_state(),
+        "
+        ....OO....
+        ....OO....",
+    );
+}
+
+#[test]
+fn j_at_the_right_wall_rotates_when_it_fits() {
+    let mut engine = engine("", PieceKind::J, 10);
+    move_all_the_way(&mut engine, MoveKind::Right);
+    rotate(&mut engine);
+    engine.hard_drop();
+
+    assert_bottom(
+        "J at the right wall rotated to east",
+        &engine.render_state(),
+        "
+        ........JJ
+        ........J.
+        ........J.",
+    );
+}
+
+#[test]
+fn hard_drop_lands_on_the_stack() {
+    let mut engine = engine(
+        "
+        ...L......
+        ...L......
+        ...LL.....",
+        PieceKind::S,
+        0,
+    );
+    engine.hard_drop();
+
+    assert_bottom(
+        "S hard dropped onto an L",
+        &engine.render_state(),
+        "
+        ....SS....
+        ...SS.....
+        ...L......
+        ...L......
+        ...LL.....",
+    );
+}
+
+#[test]
+fn sonic_drop_stops_on_an_overhang() {
+    let mut engine = engine(
+        "
+        ..ZZ......
+        ...ZZ.....
+        ..........
+        ..........",
+        PieceKind::Z,
+        0,
+    );
+    engine.move_cursor(MoveKind::Left);
+    assert!(engine.sonic_drop());
+
+    assert_bottom(
+        "Z sonic dropped onto a Z with room beneath",
+        &engine.render_state(),
+        "
+        ..ZZ......
+        ...ZZ.....
+        ..ZZ......
+        ...ZZ.....
+        ..........
+        ..........",
+    );
+}
+
+#[test]
+fn moving_into_the_stack_does_nothing() {
+    let mut engine = engine(
+        "
+        ..........
+        GG........
+        GG........",
+        PieceKind::L,
+        0,
+    );
+    engine.hard_drop();
+    move_all_the_way(&mut engine, MoveKind::Left);
+
+    assert_bottom(
+        "L on the floor moved left into a block",
+        &engine.render_state(),
+        "
+        GG..L.....
+        GGLLL.....",
+    );
+}
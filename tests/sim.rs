@@ -0,0 +1,80 @@
+use tetris::sim::{self, Driver, ScriptAction, SimMode, SimOptions};
+
+fn bot_options(seed: u64) -> SimOptions {
+    SimOptions {
+        seed,
+        mode: SimMode::Marathon,
+        driver: Driver::Bot,
+        max_pieces: 100,
+    }
+}
+
+#[test]
+fn bot_clears_lines() {
+    let summary = sim::run(&bot_options(3));
+
+    assert!(!summary.game_over);
+    assert_eq!(summary.events.pieces, 100);
+    assert!(summary.lines > 0);
+    assert_eq!(summary.board.len(), 20);
+}
+
+#[test]
+fn same_seed_same_summary() {
+    let first = sim::run(&bot_options(11));
+    let second = sim::run(&bot_options(11));
+
+    assert_eq!(first.board, second.board);
+    assert_eq!(first.events, second.events);
+    assert_eq!(first.score, second.score);
+}
+
+#[test]
+fn sprint_stops_at_the_line_goal() {
+    let summary = sim::run(&SimOptions {
+        mode: SimMode::Sprint,
+        max_pieces: 1000,
+        ..bot_options(5)
+    });
+
+    assert!(summary.lines >= SimMode::SPRINT_LINES);
+    assert!(summary.lines < SimMode::SPRINT_LINES + 4);
+}
+
+#[test]
+fn script_runs_to_its_end() {
+    let actions =
+        sim::parse_script("# stack two on the left\nleft\nleft\nleft\ndrop\n\nhold\ndrop\n")
+            .unwrap();
+    assert_eq!(actions[0], ScriptAction::MoveLeft);
+
+    let summary = sim::run(&SimOptions {
+        seed: 1,
+        mode: SimMode::Marathon,
+        driver: Driver::Script(actions),
+        max_pieces: 1000,
+    });
+
+    assert_eq!(summary.events.pieces, 2);
+    assert_eq!(summary.events.holds, 1);
+    assert!(!summary.game_over);
+
+    let occupied: usize = summary
+        .board
+        .iter()
+        .map(|row| row.chars().filter(|&cell| cell != '.').count())
+        .sum();
+    assert_eq!(occupied, 8);
+
+    let json = summary.to_json();
+    assert!(json.contains("\"mode\": \"marathon\""));
+    assert!(json.contains("\"pieces\": 2"));
+}
+
+#[test]
+fn unknown_script_action() {
+    assert_eq!(
+        sim::parse_script("left\njump\n"),
+        Err("Unknown action on line 2: jump".to_string())
+    );
+}
@@ -0,0 +1,197 @@
+#![allow(incomplete_features)]
+#![feature(generic_const_exprs)]
+
+// a short match between two sides in this process, over a real tcp connection on localhost
+use std::net::{Ipv4Addr, TcpListener, TcpStream};
+use std::thread;
+use std::time::{Duration, Instant};
+use tetris::controller::{GameController, GameInput};
+use tetris::engine::config::Config;
+use tetris::net::connection::{Connection, Pending};
+use tetris::net::spectate::{Board, Watcher, FRAME_INTERVAL};
+use tetris::net::versus::{checksum, Outcome, Versus};
+use tetris::net::NetError;
+use tetris::timestep::FixedTimestep;
+
+struct Side {
+    connection: Connection,
+    versus: Versus,
+    game: GameController,
+}
+
+impl Side {
+    fn new(connection: Connection) -> Self {
+        let (versus, game) = Versus::start(
+            connection.seed,
+            Config::default(),
+            FixedTimestep::default().step,
+            connection.is_host,
+        );
+
+        Self {
+            connection,
+            versus,
+            game,
+        }
+    }
+
+    // what the game loop does each update step: send, take in what came, run what can be run
+    fn tick(&mut self) -> Result<(), NetError> {
+        if let Some(message) = self.versus.send_step() {
+            self.connection.send(&message)?;
+        }
+        while let Some(message) = self.connection.try_receive()? {
+            self.versus.receive(message)?;
+        }
+        self.versus.advance(&mut self.game)?;
+        for message in self.versus.take_outgoing() {
+            self.connection.send(&message)?;
+        }
+
+        Ok(())
+    }
+
+    // keeps going until the match is over, the scripts drop pieces fast enough for someone to top out
+    fn play(mut self, script: impl Fn(u32) -> Vec<GameInput>) -> Result<Self, NetError> {
+        let deadline = Instant::now() + Duration::from_secs(30);
+        let mut last_scripted = None;
+
+        while self.versus.outcome().is_none() {
+            assert!(Instant::now() < deadline, "the match got stuck");
+
+            let step = self.versus.step();
+            if last_scripted != Some(step) {
+                for input in script(step) {
+                    self.versus.input(input);
+                }
+                last_scripted = Some(step);
+            }
+
+            self.tick()?;
+            thread::sleep(Duration::from_micros(200));
+        }
+
+        Ok(self)
+    }
+}
+
+fn host_script(step: u32) -> Vec<GameInput> {
+    match step % 50 {
+        0 => vec![GameInput::Rotate, GameInput::HardDrop],
+        25 => vec![
+            GameInput::MoveRight,
+            GameInput::MoveRight,
+            GameInput::HardDrop,
+        ],
+        _ => Vec::new(),
+    }
+}
+
+fn guest_script(step: u32) -> Vec<GameInput> {
+    match step % 35 {
+        0 => vec![GameInput::MoveLeft, GameInput::HardDrop],
+        17 => vec![GameInput::Hold],
+        _ => Vec::new(),
+    }
+}
+
+#[test]
+fn both_sides_agree_on_a_scripted_match() {
+    let listener = TcpListener::bind(("127.0.0.1", 0)).unwrap();
+    let address = listener.local_addr().unwrap();
+
+    let guest = thread::spawn(move || {
+        let connection = Connection::guest(TcpStream::connect(address).unwrap()).unwrap();
+        Side::new(connection).play(guest_script)
+    });
+
+    let (stream, _) = listener.accept().unwrap();
+    let host = Side::new(Connection::host(stream, 11).unwrap())
+        .play(host_script)
+        .unwrap();
+    let guest = guest.join().unwrap().unwrap();
+
+    // both stopped on the step the match ended, with the same two games
+    assert_eq!(guest.connection.seed, 11);
+    assert_eq!(host.versus.step(), guest.versus.step());
+    assert_eq!(checksum(&host.game), checksum(&guest.versus.opponent));
+    assert_eq!(checksum(&guest.game), checksum(&host.versus.opponent));
+
+    let outcomes = (host.versus.outcome(), guest.versus.outcome());
+    match outcomes {
+        (Some(Outcome::Won), Some(outcome)) => assert_eq!(outcome, Outcome::Lost),
+        (Some(Outcome::Lost), Some(outcome)) => assert_eq!(outcome, Outcome::Won),
+        (Some(Outcome::Draw), Some(outcome)) => assert_eq!(outcome, Outcome::Draw),
+        _ => panic!("the sides disagree on how it ended: {outcomes:?}"),
+    }
+}
+
+// a spectator of the host, who joined before the opponent did, sees the host's board after a burst of
+// frames the same as the host does
+#[test]
+fn a_spectator_sees_the_hosts_board() {
+    // a port that was free a moment ago
+    let port = TcpListener::bind((Ipv4Addr::LOCALHOST, 0))
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port();
+    let pending = Pending::host(port, 4);
+
+    // the host binds in the background, it may not be listening yet
+    let connect = || {
+        let deadline = Instant::now() + Duration::from_secs(10);
+        loop {
+            match TcpStream::connect((Ipv4Addr::LOCALHOST, port)) {
+                Ok(stream) => return stream,
+                Err(error) => assert!(Instant::now() < deadline, "no host: {error}"),
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+    };
+    let mut watcher = Watcher::connect(connect()).unwrap();
+    let _guest = Connection::guest(connect()).unwrap();
+
+    let deadline = Instant::now() + Duration::from_secs(10);
+    let mut host = loop {
+        if let Some(host) = pending.poll() {
+            break host.unwrap();
+        }
+        assert!(Instant::now() < deadline, "the opponent never got through");
+        thread::sleep(Duration::from_millis(10));
+    };
+    let mut broadcast = host.broadcast();
+
+    let step = FixedTimestep::default().step;
+    let mut game = GameController::new(host.seed, Config::default(), step);
+    let mut steps = 0;
+    for _ in 0..1000 {
+        match steps % 30 {
+            0 => game.apply(GameInput::HardDrop),
+            10 => game.apply(GameInput::MoveLeft),
+            20 => game.apply(GameInput::Hold),
+            _ => {}
+        }
+        game.update(step);
+        broadcast.update(&game, steps, step);
+        steps += 1;
+    }
+    // a frame goes out for the last step for sure
+    broadcast.update(&game, steps, FRAME_INTERVAL);
+    assert_eq!(broadcast.spectators(), 1);
+
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while watcher.step != steps {
+        assert!(Instant::now() < deadline, "the last frame never came");
+        watcher.update(Duration::ZERO).unwrap();
+        thread::sleep(Duration::from_millis(1));
+    }
+
+    let board = Board::of(&game);
+    assert_eq!(watcher.board.matrix.to_string(), board.matrix.to_string());
+    assert_eq!(watcher.board.up_next.to_string(), board.up_next.to_string());
+    assert_eq!(watcher.board.hold.to_string(), board.hold.to_string());
+    assert_eq!(watcher.board.queue.to_string(), board.queue.to_string());
+    assert_eq!(watcher.board.score, board.score);
+    assert_eq!(watcher.board.game_over, board.game_over);
+}
@@ -0,0 +1,74 @@
+// build the bindings first: wasm-pack build --target web --out-dir web/pkg -- --features web
+import init, { WebGame, Action } from "./pkg/tetris.js";
+
+// indexed by the cell codes of WebGame.render_model, 0 is an empty cell
+const PALETTE = [null, "#ffeb3b", "#00bcd4", "#9c27b0", "#f57900", "#2196f3", "#4caf50", "#f44336", "#888888"];
+
+const KEYS = {
+  ArrowLeft: Action.MoveLeft,
+  ArrowRight: Action.MoveRight,
+  ArrowUp: Action.Rotate,
+  z: Action.RotateCounterclockwise,
+  ArrowDown: Action.SoftDrop,
+  " ": Action.HardDrop,
+  c: Action.Hold,
+  p: Action.Pause,
+  r: Action.Restart,
+};
+
+await init();
+
+const game = new WebGame(Math.floor(Math.random() * 0xffffffff));
+const canvas = document.getElementById("matrix");
+const context = canvas.getContext("2d");
+const cellSize = canvas.width / game.width();
+
+document.addEventListener("keydown", (event) => {
+  const action = KEYS[event.key];
+  if (action === undefined) return;
+
+  event.preventDefault();
+  game.apply(action);
+});
+
+// soft drop lasts for as long as the key is held
+document.addEventListener("keyup", (event) => {
+  if (event.key !== "ArrowDown") return;
+
+  event.preventDefault();
+  game.apply(Action.SoftDropStop);
+});
+
+function draw() {
+  const cells = game.render_model();
+  const width = game.width();
+  const height = game.height();
+
+  context.clearRect(0, 0, canvas.width, canvas.height);
+
+  for (let i = 0; i < cells.length; i++) {
+    if (cells[i] === 0) continue;
+
+    // the model starts at the bottom row, the canvas at the top
+    const x = i % width;
+    const y = height - 1 - Math.floor(i / width);
+    context.fillStyle = PALETTE[cells[i]];
+    context.fillRect(x * cellSize + 1, y * cellSize + 1, cellSize - 2, cellSize - 2);
+  }
+
+  document.getElementById("level").textContent = `LEVEL ${game.level()}`;
+  document.getElementById("score").textContent = `SCORE ${game.score()}`;
+  document.getElementById("status").textContent = game.is_game_over() ? "GAME OVER" : game.is_paused() ? "PAUSED" : "";
+}
+
+let last = performance.now();
+
+function frame(now) {
+  game.advance(now - last);
+  last = now;
+
+  draw();
+  requestAnimationFrame(frame);
+}
+
+requestAnimationFrame(frame);